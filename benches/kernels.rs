@@ -0,0 +1,63 @@
+//! Compare DataPoint iterators with slice based series functions
+//!
+//! Run with: cargo bench
+//!
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::{Criterion, black_box};
+use timeseries::{TimeSeries, DataPoint};
+
+
+const SIZE: i64 = 100_000;
+
+fn series(offset: i64) -> TimeSeries {
+    let index = (0..SIZE).map(|i| 2 * i + offset).collect();
+    let values = (0..SIZE).map(|i| (i as f64).sin()).collect();
+    TimeSeries::new(index, values)
+}
+
+fn bench_diff(c: &mut Criterion) {
+    let ts = series(0);
+    c.bench_function("diff datapoints", move |b| b.iter(|| {
+        black_box(ts.iter().zip(ts.iter().skip(1))
+            .map(|(prev, dp)| DataPoint::new(dp.timestamp, dp.value - prev.value))
+            .collect::<TimeSeries>())
+    }));
+    let ts = series(0);
+    c.bench_function("diff slices", move |b| b.iter(|| black_box(ts.diff())));
+}
+
+fn bench_merge(c: &mut Criterion) {
+    let (ts1, ts2) = (series(0), series(1));
+    c.bench_function("merge datapoints", move |b| b.iter(|| {
+        let mut datapoints: Vec<DataPoint> = ts1.iter().chain(ts2.iter()).collect();
+        datapoints.sort_by_key(|dp| dp.timestamp);
+        datapoints.dedup_by_key(|dp| dp.timestamp);
+        black_box(TimeSeries::from_datapoints(datapoints))
+    }));
+    let (ts1, ts2) = (series(0), series(1));
+    c.bench_function("merge slices", move |b| b.iter(|| black_box(ts1.merge(&ts2))));
+}
+
+fn bench_at(c: &mut Criterion) {
+    let ts = series(0);
+    c.bench_function("at datapoints", move |b| b.iter(|| {
+        black_box(ts.iter().take_while(|dp| dp.timestamp <= SIZE).last().map_or(0.0, |dp| dp.value))
+    }));
+    let ts = series(0);
+    c.bench_function("at slices", move |b| b.iter(|| black_box(ts.at(SIZE))));
+}
+
+fn bench_mean(c: &mut Criterion) {
+    let ts = series(0);
+    c.bench_function("mean datapoints", move |b| b.iter(|| {
+        black_box(ts.iter().map(|dp| dp.value).sum::<f64>() / ts.len() as f64)
+    }));
+    let ts = series(0);
+    c.bench_function("mean slices", move |b| b.iter(|| black_box(ts.mean())));
+}
+
+criterion_group!(benches, bench_diff, bench_merge, bench_at, bench_mean);
+criterion_main!(benches);
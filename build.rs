@@ -0,0 +1,17 @@
+// Generates C header for the `ffi` module
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    cbindgen::generate(&crate_dir)
+        .expect("Unable to generate C bindings")
+        .write_to_file(std::path::Path::new(&out_dir).join("timeseries.h"));
+}
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use timeseries::io::binary;
+
+
+fuzz_target!(|data: &[u8]| {
+    // Corrupted file should be reported as an error and never panic or allocate the declared length
+    let _ = binary::read_from(data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use timeseries::io::csv;
+
+
+fuzz_target!(|data: &[u8]| {
+    // Malformed input should be reported as an error and never panic
+    let _ = csv::read_frame_from_reader(data);
+});
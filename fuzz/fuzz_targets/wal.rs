@@ -0,0 +1,25 @@
+#![no_main]
+use std::env;
+use std::fs;
+use std::process;
+
+use libfuzzer_sys::fuzz_target;
+
+use timeseries::io::wal::{self, WriteAheadLog};
+
+
+fuzz_target!(|data: &[u8]| {
+    // Snapshot and log are only read from files, so the input is stored in both of them.
+    // Corrupted files should be reported as an error and never panic.
+    let dir = env::temp_dir();
+    let log_path = dir.join(format!("timeseries_fuzz_{}.wal", process::id()));
+    let snapshot_path = dir.join(format!("timeseries_fuzz_{}.tss", process::id()));
+    let (log_path, snapshot_path) = (log_path.to_str().unwrap(), snapshot_path.to_str().unwrap());
+    fs::write(snapshot_path, data).unwrap();
+    let _ = wal::read_snapshot(snapshot_path);
+    fs::write(log_path, data).unwrap();
+    let _ = fs::remove_file(snapshot_path);
+    if let Ok(mut log) = WriteAheadLog::open(log_path, snapshot_path) {
+        let _ = log.recover_set();
+    }
+});
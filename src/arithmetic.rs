@@ -0,0 +1,306 @@
+//! Point-wise arithmetic between series
+//!
+//! Values are paired by timestamp. The result has the index of the left series,
+//! and data points without value at the same timestamp in the right series get NaN.
+//! Operators (`+`, `-`, `*`, `/`) use `NanPolicy::Propagate`.
+//!
+//! Operators with a scalar apply it to every value. Assign variants (`+=`, `-=`, `*=`, `/=`)
+//! and the `*_assign_scalar` methods change the series in place without allocating.
+//!
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+
+use crate::TimeSeries;
+use crate::nan::{NanError, NanPolicy};
+
+
+impl TimeSeries {
+
+    /// Combine values of both series with the given function.
+    /// With `NanPolicy::Skip` data points where any of the values is NaN are removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::nan::NanPolicy;
+    ///
+    /// let a = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, f64::NAN]);
+    /// let b = TimeSeries::new(vec![1, 2, 3], vec![10.0, 20.0, 30.0]);
+    /// let sum = a.combine(&b, NanPolicy::Skip, |x, y| x + y).unwrap();
+    /// assert_eq!(sum, TimeSeries::new(vec![1, 2], vec![11.0, 22.0]));
+    /// assert!(a.combine(&b, NanPolicy::Error, |x, y| x + y).is_err());
+    /// ```
+    pub fn combine<F>(&self, other: &TimeSeries, policy: NanPolicy, f: F) -> Result<TimeSeries, NanError>
+    where
+        F: Fn(f64, f64) -> f64 {
+
+        let other = &*other.in_resolution(self.index.resolution);
+        let same_index = self.index == other.index;
+        let pairs: Vec<(f64, f64)> = (0..self.len())
+            .map(|i| {
+                let b = if same_index {
+                    other.values[i]
+                } else {
+                    other.index.values.binary_search(&self.index[i]).map_or(f64::NAN, |pos| other.values[pos])
+                };
+                (self.values[i], b)
+            })
+            .collect();
+        let positions: Vec<usize> = match policy {
+            NanPolicy::Propagate => (0..self.len()).collect(),
+            NanPolicy::Skip => (0..self.len()).filter(|&i| !pairs[i].0.is_nan() && !pairs[i].1.is_nan()).collect(),
+            NanPolicy::Error => {
+                if let Some(position) = pairs.iter().position(|(a, b)| a.is_nan() || b.is_nan()) {
+                    return Err(NanError { position });
+                }
+                (0..self.len()).collect()
+            },
+        };
+        let mut output = self.select(&positions);
+        output.values = positions.iter().map(|&i| f(pairs[i].0, pairs[i].1)).collect();
+        Ok(output.logged(self, "combine", || vec![("other_len", other.len().to_string()), ("policy", format!("{:?}", policy))]))
+    }
+
+    /// Apply the function to every value in place.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let mut ts = TimeSeries::new(vec![1, 2], vec![1.0, -2.0]);
+    /// ts.apply_inplace(f64::abs);
+    /// assert_eq!(ts.values, vec![1.0, 2.0]);
+    /// ```
+    pub fn apply_inplace<F: Fn(f64) -> f64>(&mut self, f: F) {
+        self.values.iter_mut().for_each(|v| *v = f(*v));
+        self.log_inplace("apply_inplace", Vec::new);
+    }
+
+    /// Add the scalar to every value in place.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let mut ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// ts.add_assign_scalar(10.0);
+    /// assert_eq!(ts.values, vec![11.0, 12.0]);
+    /// ```
+    pub fn add_assign_scalar(&mut self, x: f64) {
+        self.assign_scalar("add_assign_scalar", x, |v| v + x);
+    }
+
+    /// Multiply every value by the scalar in place.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let mut ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// ts.mul_assign_scalar(10.0);
+    /// assert_eq!(ts.values, vec![10.0, 20.0]);
+    /// ```
+    pub fn mul_assign_scalar(&mut self, x: f64) {
+        self.assign_scalar("mul_assign_scalar", x, |v| v * x);
+    }
+
+    /// Apply the function to every value in place.
+    fn assign_scalar<F: Fn(f64) -> f64>(&mut self, name: &str, x: f64, f: F) {
+        self.values.iter_mut().for_each(|v| *v = f(*v));
+        self.log_inplace(name, || vec![("x", x.to_string())]);
+    }
+
+    /// Copy of the series with the function applied to every value
+    fn broadcast<F: Fn(f64) -> f64>(&self, name: &str, x: f64, f: F) -> TimeSeries {
+        let mut output = self.clone();
+        output.values.iter_mut().for_each(|v| *v = f(*v));
+        output.logged(self, name, || vec![("x", x.to_string())])
+    }
+
+    fn propagate<F: Fn(f64, f64) -> f64>(&self, other: &TimeSeries, f: F) -> TimeSeries {
+        self.combine(other, NanPolicy::Propagate, f).expect("Propagate policy never fails")
+    }
+}
+
+impl Add for &TimeSeries {
+    type Output = TimeSeries;
+
+    /// Point-wise sum
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let a = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// let b = TimeSeries::new(vec![2, 3], vec![5.0, 6.0]);
+    /// let sum = &a + &b;
+    /// assert!(sum.values[0].is_nan());
+    /// assert_eq!(sum.values[1], 7.0);
+    /// ```
+    fn add(self, other: &TimeSeries) -> TimeSeries {
+        self.propagate(other, |a, b| a + b)
+    }
+}
+
+impl Sub for &TimeSeries {
+    type Output = TimeSeries;
+
+    fn sub(self, other: &TimeSeries) -> TimeSeries {
+        self.propagate(other, |a, b| a - b)
+    }
+}
+
+impl Mul for &TimeSeries {
+    type Output = TimeSeries;
+
+    fn mul(self, other: &TimeSeries) -> TimeSeries {
+        self.propagate(other, |a, b| a * b)
+    }
+}
+
+impl Div for &TimeSeries {
+    type Output = TimeSeries;
+
+    fn div(self, other: &TimeSeries) -> TimeSeries {
+        self.propagate(other, |a, b| a / b)
+    }
+}
+
+impl Add<f64> for &TimeSeries {
+    type Output = TimeSeries;
+
+    /// Add the scalar to every value
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// assert_eq!((&ts + 1.0).values, vec![2.0, 3.0]);
+    /// ```
+    fn add(self, x: f64) -> TimeSeries {
+        self.broadcast("add_scalar", x, |v| v + x)
+    }
+}
+
+impl Sub<f64> for &TimeSeries {
+    type Output = TimeSeries;
+
+    fn sub(self, x: f64) -> TimeSeries {
+        self.broadcast("sub_scalar", x, |v| v - x)
+    }
+}
+
+impl Mul<f64> for &TimeSeries {
+    type Output = TimeSeries;
+
+    fn mul(self, x: f64) -> TimeSeries {
+        self.broadcast("mul_scalar", x, |v| v * x)
+    }
+}
+
+impl Div<f64> for &TimeSeries {
+    type Output = TimeSeries;
+
+    fn div(self, x: f64) -> TimeSeries {
+        self.broadcast("div_scalar", x, |v| v / x)
+    }
+}
+
+impl AddAssign<f64> for TimeSeries {
+
+    /// Add the scalar to every value in place
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let mut ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// ts += 1.0;
+    /// ts *= 2.0;
+    /// assert_eq!(ts.values, vec![4.0, 6.0]);
+    /// ```
+    fn add_assign(&mut self, x: f64) {
+        self.add_assign_scalar(x);
+    }
+}
+
+impl SubAssign<f64> for TimeSeries {
+
+    fn sub_assign(&mut self, x: f64) {
+        self.assign_scalar("sub_assign_scalar", x, |v| v - x);
+    }
+}
+
+impl MulAssign<f64> for TimeSeries {
+
+    fn mul_assign(&mut self, x: f64) {
+        self.mul_assign_scalar(x);
+    }
+}
+
+impl DivAssign<f64> for TimeSeries {
+
+    fn div_assign(&mut self, x: f64) {
+        self.assign_scalar("div_assign_scalar", x, |v| v / x);
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operators() {
+        let a = TimeSeries::new(vec![1, 2], vec![6.0, 8.0]);
+        let b = TimeSeries::new(vec![1, 2], vec![2.0, 4.0]);
+        assert_eq!((&a - &b).values, vec![4.0, 4.0]);
+        assert_eq!((&a * &b).values, vec![12.0, 32.0]);
+        assert_eq!((&a / &b).values, vec![3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_scalar_operators() {
+        let ts = TimeSeries::new(vec![1, 2], vec![6.0, f64::NAN]).with_provenance();
+        assert_eq!((&ts - 1.0).values[0], 5.0);
+        assert_eq!((&ts * 2.0).values[0], 12.0);
+        assert!((&ts / 2.0).values[1].is_nan());
+        let mut output = ts.clone();
+        output -= 2.0;
+        output /= 4.0;
+        assert_eq!(output.values[0], 1.0);
+        assert_eq!(output.index, ts.index);
+        let operations = output.provenance.unwrap().operations;
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].name, "sub_assign_scalar");
+        assert_eq!(operations[0].params, vec![("x".to_string(), "2".to_string())]);
+        assert_eq!(operations[1].name, "div_assign_scalar");
+    }
+
+    #[test]
+    fn test_missing_timestamp() {
+        let a = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        let b = TimeSeries::new(vec![1, 3], vec![1.0, 1.0]);
+        assert_eq!(a.combine(&b, NanPolicy::Skip, |x, y| x - y).unwrap().index.values, vec![1, 3]);
+        assert_eq!(a.combine(&b, NanPolicy::Error, |x, y| x - y), Err(NanError { position: 1 }));
+    }
+
+    #[test]
+    fn test_skip_keeps_quality() {
+        let mut a = TimeSeries::new(vec![1, 2], vec![f64::NAN, 2.0]);
+        a.quality = Some(vec![1, 2]);
+        let output = a.combine(&a, NanPolicy::Skip, |x, y| x + y).unwrap();
+        assert_eq!(output.quality, Some(vec![2]));
+    }
+}
@@ -0,0 +1,109 @@
+//! Conversion to and from ndarray
+//!
+//! Series is converted into 1-D array of values.
+//! Frame is converted into 2-D array with row for each timestamp and column for each series.
+//!
+
+use ndarray::{Array1, Array2, ArrayView2, Axis};
+
+use crate::TimeSeries;
+use crate::frame::TimeSeriesFrame;
+
+
+impl TimeSeries {
+
+    /// Copy values into array
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// assert_eq!(ts.to_ndarray().sum(), 3.0);
+    /// ```
+    pub fn to_ndarray(&self) -> Array1<f64> {
+        Array1::from(self.values.clone())
+    }
+
+    /// Create series from index and array of values
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndarray::array;
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::from_ndarray(vec![1, 2], array![1.0, 2.0]);
+    /// assert_eq!(ts, TimeSeries::new(vec![1, 2], vec![1.0, 2.0]));
+    /// ```
+    pub fn from_ndarray(index: Vec<i64>, values: Array1<f64>) -> TimeSeries {
+        TimeSeries::new(index, values.to_vec())
+    }
+}
+
+impl TimeSeriesFrame {
+
+    /// Copy values into array with shape (rows, columns)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndarray::array;
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let frame = TimeSeriesFrame::new(vec![1, 2])
+    ///     .with_column("a", vec![1.0, 2.0])
+    ///     .with_column("b", vec![3.0, 4.0]);
+    /// assert_eq!(frame.to_ndarray(), array![[1.0, 3.0], [2.0, 4.0]]);
+    /// ```
+    pub fn to_ndarray(&self) -> Array2<f64> {
+        let mut array = Array2::zeros((self.len(), self.width()));
+        for (mut col, values) in array.axis_iter_mut(Axis(1)).zip(&self.columns) {
+            col.assign(&Array1::from(values.clone()));
+        }
+        array
+    }
+
+    /// Create frame from array with shape (rows, columns).
+    /// Array should have row for each timestamp and column for each name.
+    /// Extra rows or columns are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndarray::array;
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let frame = TimeSeriesFrame::from_ndarray(vec![1, 2], &["a", "b"], array![[1.0, 3.0], [2.0, 4.0]].view());
+    /// assert_eq!(frame.columns, vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+    /// ```
+    pub fn from_ndarray(index: Vec<i64>, names: &[&str], values: ArrayView2<'_, f64>) -> TimeSeriesFrame {
+        let rows = index.len();
+        values.axis_iter(Axis(1)).zip(names)
+            .fold(TimeSeriesFrame::new(index), |frame, (col, name)| {
+                frame.with_column(name, col.iter().take(rows).cloned().collect())
+            })
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frame = TimeSeriesFrame::new(vec![1, 2, 3])
+            .with_column("x", vec![1.0, 2.0, 3.0])
+            .with_column("y", vec![4.0, f64::NAN, 6.0]);
+        let array = frame.to_ndarray();
+        assert_eq!(array.dim(), (3, 2));
+        let restored = TimeSeriesFrame::from_ndarray(frame.index.values.clone(), &["x", "y"], array.view());
+        assert_eq!(restored.columns[0], frame.columns[0]);
+        assert!(restored.columns[1][1].is_nan());
+    }
+}
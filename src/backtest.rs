@@ -0,0 +1,217 @@
+//! Rolling origin evaluation of forecasting models
+//!
+//! Model is fitted to the first data points of the series (history) and its forecast is compared
+//! with the next data points. Then the origin moves forward by the step and the history grows,
+//! until there are not enough data points left for the full horizon.
+//! Forecasted values are compared with the actual values by position, not by timestamp.
+//!
+
+use crate::TimeSeries;
+use crate::forecast::{Forecaster, Prediction};
+use crate::metrics;
+use crate::progress::{Progress, Reporter};
+
+
+/// Forecast errors
+///   * mae - Mean absolute error
+///   * rmse - Root mean squared error
+///   * mape - Mean absolute percentage error. Data points with zero actual value are skipped
+///   * smape - Symmetric mean absolute percentage error (between 0 and 200)
+///   * pinball - Mean pinball loss of the interval bounds (quantiles of the prediction interval)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scores {
+    pub mae: f64,
+    pub rmse: f64,
+    pub mape: f64,
+    pub smape: f64,
+    pub pinball: f64,
+}
+
+/// Scores of the single forecast
+///   * origin - Timestamp of the last data point in the history
+///   * scores - Errors of the forecasted data points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fold {
+    pub origin: i64,
+    pub scores: Scores,
+}
+
+/// Result of the backtest
+///   * folds - Scores of each forecast
+///   * overall - Scores of all forecasted data points together
+///   * failed - Number of folds where the model couldn't be fitted
+#[derive(Clone, Debug, PartialEq)]
+pub struct BacktestReport {
+    pub folds: Vec<Fold>,
+    pub overall: Scores,
+    pub failed: usize,
+}
+
+/// Forecasted data points with actual values
+#[derive(Default)]
+struct Points {
+    /// Actual and forecasted value
+    pairs: Vec<(f64, f64)>,
+    pinball: Vec<f64>,
+}
+
+
+/// Evaluate forecaster with expanding window. The first fold uses `initial` data points as history
+/// and each forecast has `horizon` data points. Actual values which are NaN are skipped.
+/// Returns report without folds (and NaN overall scores) if horizon or step is 0
+/// or the series is too short for a single fold.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::backtest;
+/// use timeseries::forecast::arima::Order;
+///
+/// let ts = TimeSeries::new((0..20).collect(), (0..20).map(|i| i as f64).collect());
+/// let report = backtest::rolling_origin(&ts, &Order { p: 0, d: 1, q: 0 }, 10, 2, 4);
+/// assert_eq!(report.folds.len(), 3);
+/// assert_eq!(report.folds[0].origin, 9);
+/// assert_eq!(report.failed, 0);
+/// assert_eq!(report.overall.mae, 1.5);
+/// ```
+pub fn rolling_origin<F: Forecaster>(ts: &TimeSeries, forecaster: &F, initial: usize, horizon: usize, step: usize) -> BacktestReport {
+    rolling_origin_with_progress(ts, forecaster, initial, horizon, step, &|_, _| ())
+}
+
+/// Evaluate forecaster with expanding window (see `rolling_origin`) and report the number of evaluated folds.
+///
+/// # Example
+///
+/// ```
+/// use std::cell::Cell;
+/// use timeseries::TimeSeries;
+/// use timeseries::backtest;
+/// use timeseries::forecast::arima::Order;
+///
+/// let ts = TimeSeries::new((0..20).collect(), (0..20).map(|i| i as f64).collect());
+/// let last = Cell::new((0, None));
+/// let order = Order { p: 0, d: 1, q: 0 };
+/// backtest::rolling_origin_with_progress(&ts, &order, 10, 2, 4, &|done, total| last.set((done, total)));
+/// assert_eq!(last.get(), (3, Some(3)));
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(ts, forecaster, progress), fields(len = ts.len())))]
+pub fn rolling_origin_with_progress<F>(ts: &TimeSeries, forecaster: &F, initial: usize, horizon: usize, step: usize,
+                                       progress: Progress) -> BacktestReport
+where
+    F: Forecaster {
+
+    let mut folds = vec![];
+    let mut all = Points::default();
+    let mut failed = 0;
+    let first = initial.max(1);
+    let total = if horizon > 0 && step > 0 && first + horizon <= ts.len() { (ts.len() - horizon - first) / step + 1 } else { 0 };
+    let mut reporter = Reporter::new(progress, Some(total as u64));
+    if horizon > 0 && step > 0 {
+        let mut origin = first;
+        while origin + horizon <= ts.len() {
+            match forecaster.forecast(&ts.head(origin), horizon) {
+                Some(prediction) => {
+                    let points = Points::of(&ts.values[origin..origin + horizon], &prediction);
+                    folds.push(Fold { origin: ts.index[origin - 1], scores: points.scores() });
+                    all.pairs.extend(points.pairs);
+                    all.pinball.extend(points.pinball);
+                },
+                None => failed += 1,
+            }
+            reporter.update((folds.len() + failed) as u64);
+            origin += step;
+        }
+    }
+    reporter.finish();
+    BacktestReport { folds, overall: all.scores(), failed }
+}
+
+impl Points {
+
+    fn of(actual: &[f64], prediction: &Prediction) -> Points {
+        // Interval bounds are the quantiles at both tails
+        let tau = (1.0 - prediction.level) / 2.0;
+        let mut points = Points::default();
+        for (i, &y) in actual.iter().enumerate().filter(|(_, y)| !y.is_nan()) {
+            let at = |ts: &TimeSeries| ts.values.get(i).cloned().unwrap_or(f64::NAN);
+            points.pairs.push((y, at(&prediction.mean)));
+            points.pinball.push((pinball_loss(y, at(&prediction.lower), tau) + pinball_loss(y, at(&prediction.upper), 1.0 - tau)) / 2.0);
+        }
+        points
+    }
+
+    fn scores(&self) -> Scores {
+        let or_nan = |v: Option<f64>| v.unwrap_or(f64::NAN);
+        Scores {
+            mae: or_nan(metrics::mean_absolute_error(&self.pairs)),
+            rmse: or_nan(metrics::mean_squared_error(&self.pairs)).sqrt(),
+            mape: or_nan(metrics::mean_absolute_percentage_error(&self.pairs)),
+            smape: or_nan(metrics::symmetric_mean_absolute_percentage_error(&self.pairs)),
+            pinball: if self.pinball.is_empty() { f64::NAN } else { self.pinball.iter().sum::<f64>() / self.pinball.len() as f64 },
+        }
+    }
+}
+
+/// Loss of the quantile q forecast
+fn pinball_loss(actual: f64, forecast: f64, q: f64) -> f64 {
+    if actual >= forecast { q * (actual - forecast) } else { (1.0 - q) * (forecast - actual) }
+}
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Forecasts the last value with interval +-1
+    fn naive(history: &TimeSeries, h: usize) -> Option<Prediction> {
+        let last = history.last()?;
+        let index: Vec<i64> = (1..=h as i64).map(|k| last.timestamp + k).collect();
+        Some(Prediction {
+            mean: TimeSeries::new(index.clone(), vec![last.value; h]),
+            lower: TimeSeries::new(index.clone(), vec![last.value - 1.0; h]),
+            upper: TimeSeries::new(index, vec![last.value + 1.0; h]),
+            level: 0.8,
+        })
+    }
+
+    #[test]
+    fn test_scores() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![2.0, 4.0, 0.0]);
+        let report = rolling_origin(&ts, &naive, 1, 2, 1);
+        assert_eq!(report.folds.len(), 1);
+        let scores = report.overall;
+        assert_eq!(scores.mae, 2.0);
+        assert_eq!(scores.rmse, 2.0);
+        // Zero actual value is skipped
+        assert_eq!(scores.mape, 50.0);
+        assert_approx_eq!(scores.smape, (400.0 / 6.0 + 200.0) / 2.0);
+        // Actual values above and below the interval: (0.1 * 3 + 0.9 * 1) / 2 for both
+        assert_approx_eq!(scores.pinball, 0.6);
+    }
+
+    #[test]
+    fn test_failed_folds() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, f64::NAN, 4.0]);
+        let fails = |_: &TimeSeries, _: usize| -> Option<Prediction> { None };
+        let report = rolling_origin(&ts, &fails, 2, 1, 1);
+        assert_eq!(report.failed, 2);
+        assert!(report.folds.is_empty());
+        assert!(report.overall.mae.is_nan());
+        // NaN actual value is skipped
+        let report = rolling_origin(&ts, &naive, 1, 2, 1);
+        assert_eq!(report.folds[1].scores.mae, 2.0);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        assert!(rolling_origin(&ts, &naive, 1, 0, 1).folds.is_empty());
+        assert!(rolling_origin(&ts, &naive, 1, 1, 0).folds.is_empty());
+        assert!(rolling_origin(&ts, &naive, 3, 1, 1).folds.is_empty());
+        assert_eq!(rolling_origin(&ts, &naive, 0, 1, 1).folds[0].origin, 1);
+    }
+}
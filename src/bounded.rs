@@ -0,0 +1,214 @@
+//! Fixed memory series for live data
+//!
+//! BoundedTimeSeries keeps only the most recent data points.
+//! The oldest points are evicted when the series is longer then the max length
+//! or when it spans more then the max time span (retention).
+//!
+
+use std::collections::VecDeque;
+
+use crate::{TimeSeries, DataPoint, ToSeries};
+use crate::extrapolation::Extrapolation;
+
+
+/// Ring buffer with the most recent data points
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundedTimeSeries {
+    max_len: Option<usize>,
+    max_span: Option<i64>,
+    index: VecDeque<i64>,
+    values: VecDeque<f64>,
+}
+
+impl BoundedTimeSeries {
+
+    /// Keep at most max_len data points
+    pub fn with_max_len(max_len: usize) -> BoundedTimeSeries {
+        BoundedTimeSeries { max_len: Some(max_len), max_span: None, index: VecDeque::new(), values: VecDeque::new() }
+    }
+
+    /// Keep data points not older then max_span milliseconds before the last data point
+    pub fn with_max_span(max_span: i64) -> BoundedTimeSeries {
+        BoundedTimeSeries { max_len: None, max_span: Some(max_span), index: VecDeque::new(), values: VecDeque::new() }
+    }
+
+    /// Add time span limit to the length limited series
+    pub fn max_span(mut self, max_span: i64) -> BoundedTimeSeries {
+        self.max_span = Some(max_span);
+        self.evict();
+        self
+    }
+
+    /// Append data point and evict the oldest points if needed.
+    /// Data point is ignored (and false is returned) if it is not after the last data point.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    /// use timeseries::bounded::BoundedTimeSeries;
+    ///
+    /// let mut ts = BoundedTimeSeries::with_max_span(10);
+    /// for t in &[0, 5, 10, 15] {
+    ///     ts.push(DataPoint::new(*t, 1.0));
+    /// }
+    /// assert_eq!(ts.first().unwrap().timestamp, 5);
+    /// assert!(!ts.push(DataPoint::new(12, 1.0)));
+    /// ```
+    pub fn push(&mut self, dp: DataPoint) -> bool {
+        if self.index.back().is_some_and(|&last| dp.timestamp <= last) {
+            return false;
+        }
+        self.index.push_back(dp.timestamp);
+        self.values.push_back(dp.value);
+        self.evict();
+        true
+    }
+
+    /// Drop data points older then the timestamp
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    /// use timeseries::bounded::BoundedTimeSeries;
+    ///
+    /// let mut ts = BoundedTimeSeries::with_max_len(10);
+    /// for t in 0..5 {
+    ///     ts.push(DataPoint::new(t, 1.0));
+    /// }
+    /// ts.truncate_before(3);
+    /// assert_eq!(ts.len(), 2);
+    /// ```
+    pub fn truncate_before(&mut self, timestamp: i64) {
+        while self.index.front().is_some_and(|&first| first < timestamp) {
+            self.index.pop_front();
+            self.values.pop_front();
+        }
+    }
+
+    fn evict(&mut self) {
+        if let Some(max_len) = self.max_len {
+            while self.index.len() > max_len {
+                self.index.pop_front();
+                self.values.pop_front();
+            }
+        }
+        if let (Some(max_span), Some(&last)) = (self.max_span, self.index.back()) {
+            self.truncate_before(last.saturating_sub(max_span));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Return nth element of the series
+    pub fn nth(&self, pos: usize) -> Option<DataPoint> {
+        if pos < self.len() {
+            Some(DataPoint::new(self.index[pos], self.values[pos]))
+        } else {
+            None
+        }
+    }
+
+    pub fn first(&self) -> Option<DataPoint> {
+        self.nth(0)
+    }
+
+    pub fn last(&self) -> Option<DataPoint> {
+        self.len().checked_sub(1).and_then(|pos| self.nth(pos))
+    }
+
+    /// Value at the given timestamp (see `TimeSeries::at_with`)
+    pub fn at_with(&self, timestamp: i64, policy: Extrapolation) -> Option<f64> {
+        let pos = self.index.partition_point(|&ts| ts <= timestamp);
+        if pos > 0 && (pos < self.len() || self.index[pos-1] == timestamp) {
+            Some(self.values[pos-1])
+        } else {
+            // Outside of the range only the edge points are needed
+            let mut positions: Vec<usize> = (0..self.len().min(2))
+                .chain(self.len().saturating_sub(2)..self.len())
+                .collect();
+            positions.dedup();
+            let index = positions.iter().map(|&i| self.index[i]).collect();
+            let values = positions.iter().map(|&i| self.values[i]).collect();
+            TimeSeries::new(index, values).at_with(timestamp, policy)
+        }
+    }
+
+    /// Iterate over data points from the oldest to the newest
+    pub fn iter(&self) -> impl Iterator<Item = DataPoint> + '_ {
+        self.index.iter().zip(&self.values).map(|(&t, &v)| DataPoint::new(t, v))
+    }
+}
+
+impl ToSeries for BoundedTimeSeries {
+    /// Copy current data points into TimeSeries.
+    /// All TimeSeries functions can be used on the copy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{DataPoint, ToSeries};
+    /// use timeseries::bounded::BoundedTimeSeries;
+    ///
+    /// let mut ts = BoundedTimeSeries::with_max_len(2);
+    /// for t in 0..5 {
+    ///     ts.push(DataPoint::new(t, t as f64));
+    /// }
+    /// assert_eq!(ts.to_series().mean(), Some(3.5));
+    /// ```
+    fn to_series(&self) -> TimeSeries {
+        TimeSeries::new(self.index.iter().cloned().collect(), self.values.iter().cloned().collect())
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_len() {
+        let mut ts = BoundedTimeSeries::with_max_len(3);
+        (0..10).for_each(|t| { ts.push(DataPoint::new(t, t as f64)); });
+        assert_eq!(ts.len(), 3);
+        assert_eq!(ts.first(), Some(DataPoint::new(7, 7.0)));
+        assert_eq!(ts.last(), Some(DataPoint::new(9, 9.0)));
+    }
+
+    #[test]
+    fn test_both_limits() {
+        let mut ts = BoundedTimeSeries::with_max_len(5).max_span(100);
+        ts.push(DataPoint::new(0, 1.0));
+        ts.push(DataPoint::new(50, 2.0));
+        ts.push(DataPoint::new(200, 3.0));
+        assert_eq!(ts.iter().map(|dp| dp.timestamp).collect::<Vec<i64>>(), vec![200]);
+        assert_eq!(ts.at_with(250, Extrapolation::NearestValue), Some(3.0));
+    }
+
+    #[test]
+    fn test_extrapolation_uses_edges() {
+        let mut ts = BoundedTimeSeries::with_max_len(10);
+        (0..5).for_each(|t| { ts.push(DataPoint::new(t, t as f64)); });
+        assert_eq!(ts.at_with(2, Extrapolation::Error), Some(2.0));
+        assert_eq!(ts.at_with(6, Extrapolation::Linear), Some(6.0));
+        assert_eq!(ts.at_with(-2, Extrapolation::Linear), Some(-2.0));
+    }
+
+    #[test]
+    fn test_empty() {
+        let ts = BoundedTimeSeries::with_max_len(0);
+        assert!(ts.is_empty());
+        assert_eq!(ts.last(), None);
+        assert!(ts.to_series().is_empty());
+    }
+}
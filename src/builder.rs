@@ -0,0 +1,317 @@
+//! Construction of series with explicit validation rules
+//!
+//! `TimeSeries::new` and `TimeSeries::from_datapoints` silently pad or truncate the input.
+//! The builder makes each of these decisions explicit. By default any problem is reported as error.
+//!
+
+use core::error::Error;
+use core::fmt;
+
+use crate::{TimeSeries, DataPoint};
+use crate::index::Resolution;
+pub use crate::nan::NanPolicy;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// What to do with data points which have the same timestamp
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnDuplicate {
+    Error,
+    First,
+    Last,
+    Mean,
+}
+
+/// What to do if timestamps are not in increasing order
+///   * Sort - Sort data points by timestamp
+///   * Truncate - Keep only data points before the first decreasing timestamp
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnUnsorted {
+    Error,
+    Sort,
+    Truncate,
+}
+
+/// What to do if index and values have different lengths
+///   * Pad - Add NaN values for missing timestamps. Extra values are dropped
+///   * Truncate - Use the length of the shorter vector
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnLengthMismatch {
+    Error,
+    Pad,
+    Truncate,
+}
+
+/// What to do if timestamps look like different unit then the expected resolution
+/// (see `Resolution::infer`)
+///   * Error - Report the first such timestamp as error
+///   * Convert - Convert timestamp from the detected unit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnUnitMismatch {
+    Error,
+    Convert,
+}
+
+/// Problems found in the input data
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    LengthMismatch { index: usize, values: usize },
+    Unsorted { position: usize },
+    Duplicate { timestamp: i64 },
+    NaN { position: usize },
+    UnitMismatch { position: usize, detected: Resolution },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::LengthMismatch { index, values } =>
+                write!(f, "Index has {} elements, but there are {} values", index, values),
+            BuildError::Unsorted { position } => write!(f, "Timestamp at position {} is not increasing", position),
+            BuildError::Duplicate { timestamp } => write!(f, "Duplicated timestamp {}", timestamp),
+            BuildError::NaN { position } => write!(f, "NaN value at position {}", position),
+            BuildError::UnitMismatch { position, detected } =>
+                write!(f, "Timestamp at position {} looks like {:?} units", position, detected),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// Builder with validation policies
+#[derive(Clone, Debug)]
+pub struct TimeSeriesBuilder {
+    on_duplicate: OnDuplicate,
+    on_unsorted: OnUnsorted,
+    on_length_mismatch: OnLengthMismatch,
+    nan_policy: NanPolicy,
+    resolution: Option<Resolution>,
+    on_unit_mismatch: OnUnitMismatch,
+}
+
+impl Default for TimeSeriesBuilder {
+    fn default() -> TimeSeriesBuilder {
+        TimeSeriesBuilder::new()
+    }
+}
+
+impl TimeSeriesBuilder {
+
+    /// Create builder which reports every problem as error and keeps NaN values
+    pub fn new() -> TimeSeriesBuilder {
+        TimeSeriesBuilder {
+            on_duplicate: OnDuplicate::Error,
+            on_unsorted: OnUnsorted::Error,
+            on_length_mismatch: OnLengthMismatch::Error,
+            nan_policy: NanPolicy::Propagate,
+            resolution: None,
+            on_unit_mismatch: OnUnitMismatch::Error,
+        }
+    }
+
+    pub fn on_duplicate(mut self, policy: OnDuplicate) -> Self {
+        self.on_duplicate = policy;
+        self
+    }
+
+    pub fn on_unsorted(mut self, policy: OnUnsorted) -> Self {
+        self.on_unsorted = policy;
+        self
+    }
+
+    pub fn on_length_mismatch(mut self, policy: OnLengthMismatch) -> Self {
+        self.on_length_mismatch = policy;
+        self
+    }
+
+    /// NaN values in the input: kept (Propagate), removed (Skip) or reported as error (Error)
+    pub fn nan_policy(mut self, policy: NanPolicy) -> Self {
+        self.nan_policy = policy;
+        self
+    }
+
+    /// Expected unit of the input timestamps. Timestamps are checked only if the resolution is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::builder::{TimeSeriesBuilder, OnUnitMismatch};
+    /// use timeseries::index::Resolution;
+    ///
+    /// // The second timestamp is in seconds
+    /// let index = vec![1_600_000_000_000, 1_600_000_060];
+    /// let builder = TimeSeriesBuilder::new().resolution(Resolution::Millisecond);
+    /// assert!(builder.build(index.clone(), vec![1.0, 2.0]).is_err());
+    /// let ts = builder.on_unit_mismatch(OnUnitMismatch::Convert).build(index, vec![1.0, 2.0]).unwrap();
+    /// assert_eq!(ts.index.values, vec![1_600_000_000_000, 1_600_000_060_000]);
+    /// ```
+    pub fn resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = Some(resolution);
+        self
+    }
+
+    pub fn on_unit_mismatch(mut self, policy: OnUnitMismatch) -> Self {
+        self.on_unit_mismatch = policy;
+        self
+    }
+
+    /// Build series from index and values.
+    /// Policies are applied in order: length mismatch, unit mismatch, NaN, unsorted, duplicates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::builder::{TimeSeriesBuilder, OnDuplicate, OnUnsorted};
+    ///
+    /// let ts = TimeSeriesBuilder::new()
+    ///     .on_unsorted(OnUnsorted::Sort)
+    ///     .on_duplicate(OnDuplicate::Mean)
+    ///     .build(vec![3, 1, 2, 1], vec![3.0, 1.0, 2.0, 2.0])
+    ///     .unwrap();
+    /// assert_eq!(ts, TimeSeries::new(vec![1, 2, 3], vec![1.5, 2.0, 3.0]));
+    ///
+    /// let error = TimeSeriesBuilder::new().build(vec![2, 1], vec![1.0, 2.0]);
+    /// assert!(error.is_err());
+    /// ```
+    pub fn build(&self, mut index: Vec<i64>, mut values: Vec<f64>) -> Result<TimeSeries, BuildError> {
+        if index.len() != values.len() {
+            match self.on_length_mismatch {
+                OnLengthMismatch::Error =>
+                    return Err(BuildError::LengthMismatch { index: index.len(), values: values.len() }),
+                OnLengthMismatch::Pad => values.resize(index.len(), f64::NAN),
+                OnLengthMismatch::Truncate => {
+                    let size = index.len().min(values.len());
+                    index.truncate(size);
+                    values.truncate(size);
+                },
+            }
+        }
+
+        if let Some(resolution) = self.resolution {
+            for (position, t) in index.iter_mut().enumerate() {
+                let detected = Resolution::infer(*t);
+                if detected != resolution {
+                    match self.on_unit_mismatch {
+                        OnUnitMismatch::Error => return Err(BuildError::UnitMismatch { position, detected }),
+                        OnUnitMismatch::Convert => *t = detected.convert(*t, resolution),
+                    }
+                }
+            }
+        }
+
+        let mut points: Vec<(i64, f64)> = index.into_iter().zip(values).collect();
+        match self.nan_policy {
+            NanPolicy::Propagate => (),
+            NanPolicy::Skip => points.retain(|p| !p.1.is_nan()),
+            NanPolicy::Error => {
+                if let Some(position) = points.iter().position(|p| p.1.is_nan()) {
+                    return Err(BuildError::NaN { position });
+                }
+            },
+        }
+
+        if let Some(position) = (1..points.len()).find(|&i| points[i].0 < points[i-1].0) {
+            match self.on_unsorted {
+                OnUnsorted::Error => return Err(BuildError::Unsorted { position }),
+                OnUnsorted::Sort => points.sort_by_key(|p| p.0),
+                OnUnsorted::Truncate => points.truncate(position),
+            }
+        }
+
+        let mut index: Vec<i64> = Vec::with_capacity(points.len());
+        let mut values: Vec<f64> = Vec::with_capacity(points.len());
+        let mut start = 0;
+        while start < points.len() {
+            let timestamp = points[start].0;
+            let end = start + points[start..].iter().take_while(|p| p.0 == timestamp).count();
+            let group = &points[start..end];
+            let value = if group.len() == 1 {
+                group[0].1
+            } else {
+                match self.on_duplicate {
+                    OnDuplicate::Error => return Err(BuildError::Duplicate { timestamp }),
+                    OnDuplicate::First => group[0].1,
+                    OnDuplicate::Last => group[group.len()-1].1,
+                    OnDuplicate::Mean => group.iter().map(|p| p.1).sum::<f64>() / group.len() as f64,
+                }
+            };
+            index.push(timestamp);
+            values.push(value);
+            start = end;
+        }
+        let mut ts = TimeSeries::new(index, values);
+        ts.index.resolution = self.resolution.unwrap_or_default();
+        Ok(ts)
+    }
+
+    /// Build series from data points
+    pub fn build_from_datapoints(&self, datapoints: Vec<DataPoint>) -> Result<TimeSeries, BuildError> {
+        let index = datapoints.iter().map(|dp| dp.timestamp).collect();
+        let values = datapoints.iter().map(|dp| dp.value).collect();
+        self.build(index, values)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_length_mismatch() {
+        let builder = TimeSeriesBuilder::new();
+        assert_eq!(builder.build(vec![1, 2], vec![1.0]),
+                   Err(BuildError::LengthMismatch { index: 2, values: 1 }));
+        let ts = builder.clone().on_length_mismatch(OnLengthMismatch::Truncate).build(vec![1, 2], vec![1.0]).unwrap();
+        assert_eq!(ts.len(), 1);
+        let ts = builder.on_length_mismatch(OnLengthMismatch::Pad).build(vec![1, 2], vec![1.0]).unwrap();
+        assert!(ts.values[1].is_nan());
+    }
+
+    #[test]
+    fn test_unit_mismatch() {
+        let builder = TimeSeriesBuilder::new().resolution(Resolution::Nanosecond);
+        assert_eq!(builder.build(vec![1_600_000_000_000], vec![1.0]),
+                   Err(BuildError::UnitMismatch { position: 0, detected: Resolution::Millisecond }));
+        let ts = builder.on_unit_mismatch(OnUnitMismatch::Convert).build(vec![1_600_000_000], vec![1.0]).unwrap();
+        assert_eq!(ts.index.values, vec![1_600_000_000_000_000_000]);
+        assert_eq!(ts.index.resolution, Resolution::Nanosecond);
+    }
+
+    #[test]
+    fn test_nan_policy() {
+        let builder = TimeSeriesBuilder::new();
+        let index = vec![1, 2, 3];
+        let values = vec![1.0, f64::NAN, 3.0];
+        assert_eq!(builder.clone().nan_policy(NanPolicy::Error).build(index.clone(), values.clone()),
+                   Err(BuildError::NaN { position: 1 }));
+        let ts = builder.nan_policy(NanPolicy::Skip).build(index, values).unwrap();
+        assert_eq!(ts.index.values, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_truncate_unsorted() {
+        let ts = TimeSeriesBuilder::new()
+            .on_unsorted(OnUnsorted::Truncate)
+            .build(vec![1, 2, 5, 3], vec![1.0, 2.0, 5.0, 3.0])
+            .unwrap();
+        assert_eq!(ts.index.values, vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let builder = TimeSeriesBuilder::new();
+        let index = vec![1, 1, 2];
+        let values = vec![1.0, 2.0, 3.0];
+        assert_eq!(builder.build(index.clone(), values.clone()), Err(BuildError::Duplicate { timestamp: 1 }));
+        let first = builder.clone().on_duplicate(OnDuplicate::First).build(index.clone(), values.clone()).unwrap();
+        assert_eq!(first.values, vec![1.0, 3.0]);
+        let last = builder.on_duplicate(OnDuplicate::Last).build(index, values).unwrap();
+        assert_eq!(last.values, vec![2.0, 3.0]);
+    }
+}
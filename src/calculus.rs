@@ -0,0 +1,192 @@
+//! Integration and rate of change of series values over time
+//!
+
+use crate::TimeSeries;
+
+
+impl TimeSeries {
+
+    /// Integral of the series over time in seconds, calculated with the trapezoidal rule.
+    /// For example integral of power in watts gives energy in joules.
+    /// Data points with the same timestamp don't add any area.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 2000, 3000], vec![1.0, 3.0, 3.0]);
+    /// assert_eq!(ts.integral(), 7.0);
+    /// ```
+    pub fn integral(&self) -> f64 {
+        self.index.values.windows(2).zip(self.values.windows(2))
+            .filter(|(t, _)| t[1] != t[0])
+            .map(|(t, v)| (v[0] + v[1]) / 2.0 * (t[1] - t[0]) as f64 / 1000.0)
+            .sum()
+    }
+
+    /// Rate of increase of a monotonic counter per given interval (in milliseconds).
+    /// Drop in the value is treated as counter reset: the counter restarted from 0,
+    /// so the increase is equal to the new value.
+    /// The rate is assigned to the later data point of each pair, so the output is 1 point shorter.
+    /// Pairs of data points with the same timestamp are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// // Requests counter sampled every 10 seconds, reset after the third sample
+    /// let ts = TimeSeries::new(vec![0, 10_000, 20_000, 30_000], vec![100.0, 160.0, 220.0, 30.0]);
+    /// let per_second = ts.rate(1000);
+    /// assert_eq!(per_second.index.values, vec![10_000, 20_000, 30_000]);
+    /// assert_eq!(per_second.values, vec![6.0, 6.0, 3.0]);
+    /// ```
+    pub fn rate(&self, per_interval: i64) -> TimeSeries {
+        // Rate is not defined between data points with the same timestamp.
+        // Pair i is stored at position i-1 of the diff.
+        let pairs: Vec<usize> = (1..self.len()).filter(|&i| self.index[i] != self.index[i-1]).collect();
+        let mut output = self.diff().select(&pairs.iter().map(|&i| i - 1).collect::<Vec<usize>>());
+        output.values = pairs.iter()
+            .map(|&i| {
+                let delta = self.values[i] - self.values[i-1];
+                let increase = if delta < 0.0 { self.values[i] } else { delta };
+                increase * per_interval as f64 / (self.index[i] - self.index[i-1]) as f64
+            })
+            .collect();
+        output.provenance = None;
+        output.logged(self, "rate", || vec![("per_interval", per_interval.to_string())])
+    }
+
+    /// Change of value per second, taking the actual time between data points into account.
+    /// The derivative is assigned to the later data point of each pair, so the output is 1 point shorter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1000, 3000], vec![1.0, 3.0, 4.0]);
+    /// assert_eq!(ts.diff().values, vec![2.0, 1.0]);
+    /// assert_eq!(ts.derivative().values, vec![2.0, 0.5]);
+    /// ```
+    pub fn derivative(&self) -> TimeSeries {
+        let per_second = self.index.resolution.per_second() as f64;
+        let mut output = self.diff();
+        output.values = (1..self.len())
+            .map(|i| (self.values[i] - self.values[i-1]) * per_second / (self.index[i] - self.index[i-1]) as f64)
+            .collect();
+        output.provenance = None;
+        output.logged(self, "derivative", Vec::new)
+    }
+
+    /// Second derivative per second², calculated from 3 consecutive data points with
+    /// divided differences, so it is correct also for irregular sampling.
+    /// It is assigned to the middle data point, so the output is 2 points shorter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// // value = t², where t is in seconds
+    /// let ts = TimeSeries::new(vec![0, 1000, 3000, 4000], vec![0.0, 1.0, 9.0, 16.0]);
+    /// let acceleration = ts.second_derivative();
+    /// assert_eq!(acceleration.index.values, vec![1000, 3000]);
+    /// assert_eq!(acceleration.values, vec![2.0, 2.0]);
+    /// ```
+    pub fn second_derivative(&self) -> TimeSeries {
+        if self.len() < 3 {
+            return TimeSeries::empty();
+        }
+        let per_second = self.index.resolution.per_second() as f64;
+        let slope = |i: usize| (self.values[i] - self.values[i-1]) * per_second / (self.index[i] - self.index[i-1]) as f64;
+        let positions: Vec<usize> = (1..self.len() - 1).collect();
+        let mut output = self.select(&positions);
+        output.values = positions.iter()
+            .map(|&i| 2.0 * (slope(i + 1) - slope(i)) * per_second / (self.index[i+1] - self.index[i-1]) as f64)
+            .collect();
+        output.logged(self, "second_derivative", Vec::new)
+    }
+
+    /// Timestamps where the counter value drops (counter resets)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![5.0, 7.0, 1.0, 2.0]);
+    /// assert_eq!(ts.counter_resets(), vec![3]);
+    /// ```
+    pub fn counter_resets(&self) -> Vec<i64> {
+        (1..self.len())
+            .filter(|&i| self.values[i] < self.values[i-1])
+            .map(|i| self.index[i])
+            .collect()
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integral_short_series() {
+        assert_eq!(TimeSeries::empty().integral(), 0.0);
+        assert_eq!(TimeSeries::new(vec![0], vec![5.0]).integral(), 0.0);
+    }
+
+    #[test]
+    fn test_irregular_sampling() {
+        // Constant value integrates to value * duration, regardless of sampling
+        let ts = TimeSeries::new(vec![0, 100, 5000, 10_000], vec![2.0; 4]);
+        assert_eq!(ts.integral(), 20.0);
+        assert_eq!(ts.time_weighted_mean(), Some(2.0));
+    }
+
+    #[test]
+    fn test_same_timestamps() {
+        let ts = TimeSeries::new(vec![0, 1000, 1000, 2000], vec![2.0, 2.0, 4.0, 4.0]);
+        assert_eq!(ts.integral(), 6.0);
+        assert_eq!(ts.time_weighted_mean(), Some(3.0));
+        let ts = TimeSeries::new(vec![5, 5], vec![1.0, 3.0]);
+        assert_eq!(ts.integral(), 0.0);
+        assert_eq!(ts.time_weighted_mean(), Some(2.0));
+    }
+
+    #[test]
+    fn test_rate_short_series() {
+        assert!(TimeSeries::new(vec![1], vec![1.0]).rate(1000).is_empty());
+        assert!(TimeSeries::empty().counter_resets().is_empty());
+    }
+
+    #[test]
+    fn test_derivative_resolution() {
+        let ts = TimeSeries::new(vec![0, 500, 2000], vec![0.0, 1.0, 4.0]);
+        let ns = ts.to_resolution(crate::index::Resolution::Nanosecond);
+        assert_eq!(ns.derivative().values, vec![2.0, 2.0]);
+        assert_eq!(ns.second_derivative().values, vec![0.0]);
+        assert!(TimeSeries::new(vec![0, 1], vec![0.0, 1.0]).second_derivative().is_empty());
+        assert!(TimeSeries::empty().derivative().is_empty());
+    }
+
+    #[test]
+    fn test_rate_same_timestamps() {
+        let ts = TimeSeries::new(vec![0, 1000, 1000, 2000], vec![0.0, 5.0, 6.0, 8.0]).with_quality(vec![0, 1, 2, 0]);
+        let rate = ts.rate(1000);
+        assert_eq!(rate.index.values, vec![1000, 2000]);
+        assert_eq!(rate.values, vec![5.0, 2.0]);
+        assert_eq!(rate.quality, Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_rate_per_minute() {
+        let ts = TimeSeries::new(vec![0, 30_000], vec![0.0, 5.0]);
+        assert_eq!(ts.rate(60_000).values, vec![10.0]);
+    }
+}
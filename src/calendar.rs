@@ -0,0 +1,291 @@
+//! Business day calendar and calendar features
+//!
+//! Business days are Monday to Friday (UTC), excluding holidays.
+//! Calendar is used to build indexes which skip weekends and holidays,
+//! e.g. for daily financial data.
+//!
+//! Calendar features (hour of day, day of week, ...) of the index are used for grouping
+//! and as model covariates. They are calculated in UTC. Use `DateTimeIndex::to_local`
+//! to get features in the other timezone. Timestamps out of the datetime range have no features.
+//!
+
+use std::collections::HashSet;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, Timelike};
+
+use crate::TimeSeries;
+use crate::index::{DateTimeIndex, Resolution};
+use crate::frequency::DAY;
+
+
+/// Calendar with weekends and list of holidays
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Calendar {
+    /// Holidays as number of days since the epoch
+    holidays: HashSet<i64>,
+}
+
+impl Calendar {
+
+    /// Calendar without holidays. Only weekends are skipped
+    pub fn new() -> Calendar {
+        Calendar { holidays: HashSet::new() }
+    }
+
+    /// Add holidays to the calendar
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use timeseries::calendar::Calendar;
+    ///
+    /// let christmas = NaiveDate::from_ymd_opt(2020, 12, 25).unwrap();
+    /// let calendar = Calendar::new().with_holidays(&[christmas]);
+    /// // 2020-12-25 00:00:00
+    /// assert!(!calendar.is_business_day(1_608_854_400_000));
+    /// ```
+    pub fn with_holidays(mut self, dates: &[NaiveDate]) -> Calendar {
+        let days = dates.iter()
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis().div_euclid(DAY));
+        self.holidays.extend(days);
+        self
+    }
+
+    /// Check if the day containing the timestamp is a business day
+    pub fn is_business_day(&self, timestamp: i64) -> bool {
+        let day = timestamp.div_euclid(DAY);
+        // 1970-01-01 was Thursday, so weekday (Monday = 0) is shifted by 3
+        let weekday = (day + 3).rem_euclid(7);
+        weekday < 5 && !self.holidays.contains(&day)
+    }
+
+    /// Move timestamp by n business days. The time of day is kept.
+    /// If the timestamp is not on a business day, then the first step goes
+    /// to the nearest business day in the given direction.
+    /// Returns None if the timestamp gets out of range. The search for the next business day
+    /// is bounded by a week plus 2 days per holiday, which covers any run of holidays.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::calendar::Calendar;
+    ///
+    /// // Friday 2020-02-14 00:00:00
+    /// let friday = 1_581_638_400_000;
+    /// // Monday 2020-02-17 00:00:00
+    /// assert_eq!(Calendar::new().add_business_days(friday, 1), Some(1_581_897_600_000));
+    /// ```
+    pub fn add_business_days(&self, timestamp: i64, n: i64) -> Option<i64> {
+        let step = n.signum() * DAY;
+        let max_days = 7 + 2 * self.holidays.len();
+        let mut ts = timestamp;
+        for _ in 0..n.unsigned_abs() {
+            ts = ts.checked_add(step)?;
+            let mut days = 1;
+            while !self.is_business_day(ts) {
+                if days >= max_days {
+                    return None;
+                }
+                ts = ts.checked_add(step)?;
+                days += 1;
+            }
+        }
+        Some(ts)
+    }
+}
+
+
+impl DateTimeIndex {
+
+    /// Create index with the start of each business day between start and end (inclusive).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    /// use timeseries::calendar::Calendar;
+    ///
+    /// // Thursday 2020-02-13 to Tuesday 2020-02-18
+    /// let index = DateTimeIndex::date_range_business(1_581_552_000_000, 1_581_984_000_000, &Calendar::new());
+    /// assert_eq!(index.len(), 4);
+    /// ```
+    pub fn date_range_business(start: i64, end: i64, calendar: &Calendar) -> DateTimeIndex {
+        let first = (start + DAY - 1).div_euclid(DAY) * DAY;
+        let values = (0..)
+            .map(|n| first + n * DAY)
+            .take_while(|&ts| ts <= end)
+            .filter(|&ts| calendar.is_business_day(ts))
+            .collect();
+        DateTimeIndex::new(values)
+    }
+
+    /// Index with timestamps moved by the timezone offset, so calendar features are in local time.
+    /// The result doesn't represent the same moments in time, so use it only for calendar features.
+    /// Timestamps are saturated at the limits of i64.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::FixedOffset;
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// // 2020-02-14 23:30:00 UTC
+    /// let index = DateTimeIndex::new(vec![1_581_723_000_000]);
+    /// let local = index.to_local(FixedOffset::east_opt(3600).unwrap());
+    /// assert_eq!(index.hour_of_day(), vec![Some(23)]);
+    /// assert_eq!(local.hour_of_day(), vec![Some(0)]);
+    /// assert_eq!(local.day_of_month(), vec![Some(15)]);
+    /// ```
+    pub fn to_local(&self, offset: FixedOffset) -> DateTimeIndex {
+        let shift = Resolution::Second.convert(offset.local_minus_utc() as i64, self.resolution);
+        let mut index = self.clone();
+        index.values.iter_mut().for_each(|t| *t = t.saturating_add(shift));
+        index
+    }
+
+    /// Hour of the day (0-23). None for timestamps out of the datetime range.
+    pub fn hour_of_day(&self) -> Vec<Option<u32>> {
+        self.datetimes().map(|dt| dt.map(|dt| dt.hour())).collect()
+    }
+
+    /// Day of the week. Monday is 0 and Sunday is 6. None for timestamps out of the datetime range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// // Friday 2020-02-14, Saturday 2020-02-15 and Monday 2020-02-17
+    /// let index = DateTimeIndex::new(vec![1_581_638_400_000, 1_581_724_800_000, 1_581_897_600_000]);
+    /// assert_eq!(index.day_of_week(), vec![Some(4), Some(5), Some(0)]);
+    /// assert_eq!(index.is_weekend(), vec![Some(false), Some(true), Some(false)]);
+    /// ```
+    pub fn day_of_week(&self) -> Vec<Option<u32>> {
+        self.datetimes().map(|dt| dt.map(|dt| dt.weekday().num_days_from_monday())).collect()
+    }
+
+    /// Day of the month (1-31). None for timestamps out of the datetime range.
+    pub fn day_of_month(&self) -> Vec<Option<u32>> {
+        self.datetimes().map(|dt| dt.map(|dt| dt.day())).collect()
+    }
+
+    /// Month (1-12). None for timestamps out of the datetime range.
+    pub fn month(&self) -> Vec<Option<u32>> {
+        self.datetimes().map(|dt| dt.map(|dt| dt.month())).collect()
+    }
+
+    /// Saturday or Sunday. None for timestamps out of the datetime range.
+    pub fn is_weekend(&self) -> Vec<Option<bool>> {
+        self.day_of_week().into_iter().map(|d| d.map(|d| d >= 5)).collect()
+    }
+
+    /// Timestamps as UTC datetimes. None for timestamps out of the datetime range.
+    fn datetimes(&self) -> impl Iterator<Item = Option<NaiveDateTime>> + '_ {
+        self.values.iter().map(move |&t| {
+            let micros = self.resolution.convert(t, Resolution::Microsecond);
+            DateTime::from_timestamp_micros(micros).map(|dt| dt.naive_utc())
+        })
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Convert series to business day frequency.
+    /// The new series has data point at the start of each business day in the series range.
+    /// Value is the last known value at that time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::calendar::Calendar;
+    ///
+    /// // Friday 2020-02-14 and Tuesday 2020-02-18
+    /// let ts = TimeSeries::new(vec![1_581_638_400_000, 1_581_984_000_000], vec![1.0, 2.0]);
+    /// let daily = ts.asfreq_business_days(&Calendar::new());
+    /// assert_eq!(daily.values, vec![1.0, 1.0, 2.0]);
+    /// ```
+    pub fn asfreq_business_days(&self, calendar: &Calendar) -> TimeSeries {
+        if self.is_empty() {
+            return TimeSeries::empty();
+        }
+        let resolution = self.index.resolution;
+        let (first, last) = (self.index[0], self.index[self.len()-1]);
+        // Days are calculated in milliseconds, so the start is rounded up and the end down
+        let start = resolution.convert(first, Resolution::Millisecond);
+        let start = if Resolution::Millisecond.convert(start, resolution) < first { start + 1 } else { start };
+        let end = resolution.convert(last, Resolution::Millisecond);
+        let index = DateTimeIndex::date_range_business(start, end, calendar).to_resolution(resolution);
+        let values = index.iter().map(|&ts| self.at(ts)).collect();
+        self.derived(index.values, values).logged(self, "asfreq_business_days", Vec::new)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monday 2020-02-10 00:00:00
+    const MONDAY: i64 = 1_581_292_800_000;
+
+    #[test]
+    fn test_weekend() {
+        let calendar = Calendar::new();
+        assert!(calendar.is_business_day(MONDAY + 4 * DAY + DAY - 1));
+        assert!(!calendar.is_business_day(MONDAY + 5 * DAY));
+        assert!(!calendar.is_business_day(MONDAY - 1));
+    }
+
+    #[test]
+    fn test_holidays_skipped() {
+        let holiday = NaiveDate::from_ymd_opt(2020, 2, 11).unwrap();
+        let calendar = Calendar::new().with_holidays(&[holiday]);
+        let index = DateTimeIndex::date_range_business(MONDAY, MONDAY + 7 * DAY, &calendar);
+        assert_eq!(index.values, vec![MONDAY, MONDAY + 2 * DAY, MONDAY + 3 * DAY, MONDAY + 4 * DAY, MONDAY + 7 * DAY]);
+        assert_eq!(calendar.add_business_days(MONDAY, 1), Some(MONDAY + 2 * DAY));
+    }
+
+    #[test]
+    fn test_calendar_features() {
+        // 2020-02-10 13:00:00
+        let index = DateTimeIndex::new(vec![MONDAY + 13 * 3_600_000]).to_resolution(Resolution::Second);
+        assert_eq!(index.hour_of_day(), vec![Some(13)]);
+        assert_eq!(index.day_of_week(), vec![Some(0)]);
+        assert_eq!(index.day_of_month(), vec![Some(10)]);
+        assert_eq!(index.month(), vec![Some(2)]);
+        let local = index.to_local(FixedOffset::west_opt(14 * 3600).unwrap());
+        assert_eq!(local.hour_of_day(), vec![Some(23)]);
+        assert_eq!(local.day_of_week(), vec![Some(6)]);
+        assert_eq!(local.is_weekend(), vec![Some(true)]);
+    }
+
+    #[test]
+    fn test_features_out_of_datetime_range() {
+        let index = DateTimeIndex::new(vec![i64::MIN, 0, i64::MAX]).to_local(FixedOffset::east_opt(3600).unwrap());
+        assert_eq!(index.values, vec![i64::MIN + 3_600_000, 3_600_000, i64::MAX]);
+        assert_eq!(index.hour_of_day(), vec![None, Some(1), None]);
+        assert_eq!(index.day_of_week(), vec![None, Some(3), None]);
+        assert_eq!(index.is_weekend(), vec![None, Some(false), None]);
+    }
+
+    #[test]
+    fn test_backward_steps() {
+        let calendar = Calendar::new();
+        assert_eq!(calendar.add_business_days(MONDAY, -1), Some(MONDAY - 3 * DAY));
+        assert_eq!(calendar.add_business_days(MONDAY, 0), Some(MONDAY));
+    }
+
+    #[test]
+    fn test_long_holidays() {
+        let first = NaiveDate::from_ymd_opt(2020, 2, 10).unwrap();
+        let holidays: Vec<NaiveDate> = first.iter_days().take(200).filter(|d| d.weekday().number_from_monday() <= 5).collect();
+        let calendar = Calendar::new().with_holidays(&holidays);
+        assert_eq!(calendar.add_business_days(MONDAY - 3 * DAY, 1), Some(MONDAY + 200 * DAY));
+        assert_eq!(calendar.add_business_days(i64::MAX - DAY, 5), None);
+    }
+}
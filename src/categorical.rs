@@ -0,0 +1,192 @@
+//! Series with categorical (string) values
+//!
+//! Logs and state machines produce values like "idle", "running" or "error".
+//! Each distinct value is stored once, and data points keep only its code.
+//!
+
+use std::collections::HashMap;
+
+use crate::TimeSeries;
+use crate::frame::TimeSeriesFrame;
+use crate::index::DateTimeIndex;
+
+
+/// Series of categories
+///   * index - Timestamps of the data points
+///   * categories - Distinct values in the order of the first occurrence
+///   * codes - Position of the data point value in categories
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoricalTimeSeries {
+    pub index: DateTimeIndex,
+    pub categories: Vec<String>,
+    pub codes: Vec<u32>,
+}
+
+/// Consecutive data points with the same category
+///   * start - Timestamp of the first data point
+///   * end - Timestamp of the last data point
+///   * category - Category of the run
+///   * count - Number of data points
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryRun<'a> {
+    pub start: i64,
+    pub end: i64,
+    pub category: &'a str,
+    pub count: usize,
+}
+
+
+impl CategoricalTimeSeries {
+
+    /// Create series from values. Index and values should have the same length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::categorical::CategoricalTimeSeries;
+    ///
+    /// let ts = CategoricalTimeSeries::new(vec![1, 2, 3], &["idle", "running", "idle"]);
+    /// assert_eq!(ts.categories, vec!["idle", "running"]);
+    /// assert_eq!(ts.codes, vec![0, 1, 0]);
+    /// assert_eq!(ts.value(2), Some("idle"));
+    /// ```
+    pub fn new<S: AsRef<str>>(index: Vec<i64>, values: &[S]) -> CategoricalTimeSeries {
+        let size = index.len().min(values.len());
+        let mut lookup: HashMap<&str, u32> = HashMap::new();
+        let mut categories = vec![];
+        let codes = values[..size].iter()
+            .map(|v| {
+                let v = v.as_ref();
+                *lookup.entry(v).or_insert_with(|| {
+                    categories.push(v.to_owned());
+                    categories.len() as u32 - 1
+                })
+            })
+            .collect();
+        CategoricalTimeSeries { index: DateTimeIndex::new(index[..size].to_vec()), categories, codes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Category of the nth data point
+    pub fn value(&self, pos: usize) -> Option<&str> {
+        self.codes.get(pos).map(|&code| self.categories[code as usize].as_str())
+    }
+
+    /// Number of data points in each category, sorted from the most frequent.
+    /// Categories with the same count are in the order of the first occurrence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::categorical::CategoricalTimeSeries;
+    ///
+    /// let ts = CategoricalTimeSeries::new(vec![1, 2, 3], &["on", "off", "off"]);
+    /// assert_eq!(ts.value_counts(), vec![("off", 2), ("on", 1)]);
+    /// ```
+    pub fn value_counts(&self) -> Vec<(&str, usize)> {
+        let mut counts = vec![0; self.categories.len()];
+        self.codes.iter().for_each(|&code| counts[code as usize] += 1);
+        let mut output: Vec<(&str, usize)> = self.categories.iter()
+            .map(|c| c.as_str())
+            .zip(counts)
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        output.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        output
+    }
+
+    /// Split series into runs of the same category
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::categorical::CategoricalTimeSeries;
+    ///
+    /// let ts = CategoricalTimeSeries::new(vec![1, 2, 3], &["on", "on", "off"]);
+    /// let runs = ts.runs();
+    /// assert_eq!((runs[0].category, runs[0].start, runs[0].end), ("on", 1, 2));
+    /// assert_eq!(runs[1].count, 1);
+    /// ```
+    pub fn runs(&self) -> Vec<CategoryRun<'_>> {
+        let mut runs = vec![];
+        let mut start = 0;
+        while start < self.len() {
+            let code = self.codes[start];
+            let end = start + self.codes[start..].iter().take_while(|&&c| c == code).count();
+            runs.push(CategoryRun {
+                start: self.index[start],
+                end: self.index[end-1],
+                category: &self.categories[code as usize],
+                count: end - start,
+            });
+            start = end;
+        }
+        runs
+    }
+
+    /// Series with 1.0 where the value is equal to the given category and 0.0 elsewhere
+    pub fn indicator(&self, category: &str) -> TimeSeries {
+        let code = self.categories.iter().position(|c| c == category).map(|pos| pos as u32);
+        let values = self.codes.iter().map(|&c| if Some(c) == code { 1.0 } else { 0.0 }).collect();
+        TimeSeries::new(self.index.values.clone(), values)
+    }
+
+    /// One column per category with 1.0 where the data point has this category and 0.0 elsewhere
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::categorical::CategoricalTimeSeries;
+    ///
+    /// let ts = CategoricalTimeSeries::new(vec![1, 2, 3], &["a", "b", "a"]);
+    /// let frame = ts.one_hot();
+    /// assert_eq!(frame.names, vec!["a", "b"]);
+    /// assert_eq!(frame.columns[1], vec![0.0, 1.0, 0.0]);
+    /// ```
+    pub fn one_hot(&self) -> TimeSeriesFrame {
+        self.categories.iter().fold(TimeSeriesFrame::new(self.index.values.clone()), |frame, category| {
+            let values = self.indicator(category).values;
+            frame.with_column(category, values)
+        })
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owned_values() {
+        let values = vec!["x".to_string(), "y".to_string()];
+        let ts = CategoricalTimeSeries::new(vec![1, 2, 3], &values);
+        assert_eq!(ts.len(), 2);
+        assert_eq!(ts.value(2), None);
+    }
+
+    #[test]
+    fn test_indicator_unknown_category() {
+        let ts = CategoricalTimeSeries::new(vec![1, 2], &["a", "b"]);
+        assert_eq!(ts.indicator("b").values, vec![0.0, 1.0]);
+        assert_eq!(ts.indicator("c").values, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_empty() {
+        let ts = CategoricalTimeSeries::new::<&str>(vec![], &[]);
+        assert!(ts.is_empty());
+        assert!(ts.runs().is_empty());
+        assert!(ts.value_counts().is_empty());
+        assert_eq!(ts.one_hot().width(), 0);
+    }
+}
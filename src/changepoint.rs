@@ -0,0 +1,200 @@
+//! Changepoint detection
+//!
+//! Finds timestamps where statistical properties of the series change.
+//! Segments are scored with a cost function and each changepoint adds a penalty.
+//! Good default for the penalty is `ln(n)` multiplied by the expected noise variance.
+//!
+
+use crate::TimeSeries;
+
+
+/// Cost of the segment
+///   * Mean - Change in mean. Sum of squared errors from the segment mean
+///   * Variance - Change in mean and variance. Normal distribution log likelihood
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cost {
+    Mean,
+    Variance,
+}
+
+/// Prefix sums which allow to calculate segment cost in O(1)
+struct SegmentCost {
+    cost: Cost,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+}
+
+impl SegmentCost {
+
+    fn new(values: &[f64], cost: Cost) -> SegmentCost {
+        let mut sum = vec![0.0; values.len() + 1];
+        let mut sum_sq = vec![0.0; values.len() + 1];
+        for (i, v) in values.iter().enumerate() {
+            sum[i+1] = sum[i] + v;
+            sum_sq[i+1] = sum_sq[i] + v * v;
+        }
+        SegmentCost { cost, sum, sum_sq }
+    }
+
+    /// Cost of values in range [start, end)
+    fn cost(&self, start: usize, end: usize) -> f64 {
+        let n = (end - start) as f64;
+        let s = self.sum[end] - self.sum[start];
+        let sse = (self.sum_sq[end] - self.sum_sq[start] - s * s / n).max(0.0);
+        match self.cost {
+            Cost::Mean => sse,
+            Cost::Variance => n * (sse / n).max(f64::EPSILON).ln(),
+        }
+    }
+
+    fn min_size(&self) -> usize {
+        match self.cost {
+            Cost::Mean => 1,
+            Cost::Variance => 2,
+        }
+    }
+}
+
+
+/// Detect changepoints with Pruned Exact Linear Time (PELT) method.
+/// Returns timestamps of the first data point of each new segment.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::changepoint::{pelt, Cost};
+///
+/// let values = vec![1.0, 1.1, 0.9, 1.0, 5.0, 5.1, 4.9, 5.0];
+/// let ts = TimeSeries::new((0..8).collect(), values);
+/// assert_eq!(pelt(&ts, Cost::Mean, 1.0), vec![4]);
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(ts), fields(len = ts.len())))]
+pub fn pelt(ts: &TimeSeries, cost: Cost, penalty: f64) -> Vec<i64> {
+    let n = ts.len();
+    let segment = SegmentCost::new(&ts.values, cost);
+    let min_size = segment.min_size();
+    if n < 2 * min_size {
+        return vec![];
+    }
+    // best[t] - optimal cost of values [0, t), last[t] - start of the last segment
+    let mut best = vec![f64::INFINITY; n + 1];
+    let mut last = vec![0; n + 1];
+    best[0] = -penalty;
+    let mut candidates: Vec<usize> = vec![0];
+    for t in min_size..=n {
+        let mut costs = Vec::with_capacity(candidates.len());
+        for &s in &candidates {
+            let c = if t - s >= min_size { best[s] + segment.cost(s, t) } else { f64::INFINITY };
+            costs.push(c);
+            if c + penalty < best[t] {
+                best[t] = c + penalty;
+                last[t] = s;
+            }
+        }
+        let limit = best[t];
+        let mut pruned: Vec<usize> = candidates.iter().zip(&costs)
+            .filter(|(_, &c)| c.is_infinite() || c <= limit)
+            .map(|(&s, _)| s)
+            .collect();
+        pruned.push(t + 1 - min_size);
+        candidates = pruned;
+    }
+    let mut positions = vec![];
+    let mut t = n;
+    while last[t] > 0 {
+        positions.push(last[t]);
+        t = last[t];
+    }
+    positions.iter().rev().map(|&p| ts.index[p]).collect()
+}
+
+/// Detect changepoints with binary segmentation.
+/// The series is recursively split at the point which reduces cost the most,
+/// as long as the reduction is bigger then the penalty.
+/// Returns timestamps of the first data point of each new segment.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::changepoint::{binary_segmentation, Cost};
+///
+/// let values = vec![1.0, 1.1, 0.9, 1.0, 5.0, 5.1, 4.9, 5.0, 1.0, 1.1];
+/// let ts = TimeSeries::new((0..10).collect(), values);
+/// assert_eq!(binary_segmentation(&ts, Cost::Mean, 1.0), vec![4, 8]);
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(ts), fields(len = ts.len())))]
+pub fn binary_segmentation(ts: &TimeSeries, cost: Cost, penalty: f64) -> Vec<i64> {
+    let segment = SegmentCost::new(&ts.values, cost);
+    let mut positions = vec![];
+    let mut stack = vec![(0, ts.len())];
+    while let Some((start, end)) = stack.pop() {
+        if let Some(split) = best_split(&segment, start, end, penalty) {
+            positions.push(split);
+            stack.push((start, split));
+            stack.push((split, end));
+        }
+    }
+    positions.sort_unstable();
+    positions.iter().map(|&p| ts.index[p]).collect()
+}
+
+fn best_split(segment: &SegmentCost, start: usize, end: usize, penalty: f64) -> Option<usize> {
+    let min_size = segment.min_size();
+    if end - start < 2 * min_size {
+        return None;
+    }
+    let total = segment.cost(start, end);
+    (start + min_size..=end - min_size)
+        .map(|s| (s, total - segment.cost(start, s) - segment.cost(s, end)))
+        .filter(|&(_, gain)| gain > penalty)
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(s, _)| s)
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variance_change() -> TimeSeries {
+        let values = (0..40).map(|i| {
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            if i < 20 { 0.1 * sign } else { 5.0 * sign }
+        }).collect();
+        TimeSeries::new((0..40).map(|i| i * 1000).collect(), values)
+    }
+
+    #[test]
+    fn test_constant_series() {
+        let ts = TimeSeries::new((0..10).collect(), vec![2.0; 10]);
+        assert!(pelt(&ts, Cost::Mean, 1.0).is_empty());
+        assert!(binary_segmentation(&ts, Cost::Mean, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_empty_series() {
+        let ts = TimeSeries::empty();
+        assert!(pelt(&ts, Cost::Variance, 1.0).is_empty());
+        assert!(binary_segmentation(&ts, Cost::Variance, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_variance_change() {
+        let ts = variance_change();
+        assert_eq!(pelt(&ts, Cost::Variance, 10.0), vec![20_000]);
+        assert_eq!(binary_segmentation(&ts, Cost::Variance, 10.0), vec![20_000]);
+    }
+
+    #[test]
+    fn test_multiple_mean_changes() {
+        let values = (0..30).map(|i| if (10..20).contains(&i) { 3.0 } else { 0.0 }).collect();
+        let ts = TimeSeries::new((0..30).collect(), values);
+        assert_eq!(pelt(&ts, Cost::Mean, 2.0), vec![10, 20]);
+        assert_eq!(binary_segmentation(&ts, Cost::Mean, 2.0), vec![10, 20]);
+    }
+}
@@ -0,0 +1,224 @@
+//! Grouping similar series
+//!
+//!   * k-means - clusters feature vectors (e.g. values of equal length profiles or extracted features)
+//!   * k-medoids - clusters series directly with any distance from the `distance` module.
+//!     Each cluster is represented by one of its series (medoid)
+//!
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::TimeSeries;
+use crate::distance::Distance;
+
+
+/// Limit of refinement steps. Both algorithms usually converge much faster
+const MAX_ITERATIONS: usize = 100;
+
+
+/// Result of k-means clustering
+///   * assignments - Cluster of each feature vector
+///   * centroids - Mean feature vector of each cluster
+///   * inertia - Sum of squared distances between vectors and their centroids
+#[derive(Clone, Debug, PartialEq)]
+pub struct KMeans {
+    pub assignments: Vec<usize>,
+    pub centroids: Vec<Vec<f64>>,
+    pub inertia: f64,
+}
+
+/// Result of k-medoids clustering
+///   * assignments - Cluster of each series
+///   * medoids - Position of the series which represents each cluster
+///   * cost - Sum of distances between series and their medoids
+#[derive(Clone, Debug, PartialEq)]
+pub struct KMedoids {
+    pub assignments: Vec<usize>,
+    pub medoids: Vec<usize>,
+    pub cost: f64,
+}
+
+
+/// Cluster feature vectors with k-means (k-means++ initialization).
+/// All vectors should have the same length.
+/// The same seed always gives the same clusters.
+/// Returns empty result if k is 0 or there are no vectors. k larger then the number of vectors is reduced.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::cluster;
+///
+/// let features = vec![vec![0.0, 0.0], vec![10.0, 10.0], vec![0.5, 0.0], vec![10.0, 9.5]];
+/// let result = cluster::kmeans(&features, 2, 42);
+/// assert_eq!(result.assignments[0], result.assignments[2]);
+/// assert_eq!(result.assignments[1], result.assignments[3]);
+/// assert_ne!(result.assignments[0], result.assignments[1]);
+/// ```
+pub fn kmeans(features: &[Vec<f64>], k: usize, seed: u64) -> KMeans {
+    let k = k.min(features.len());
+    if k == 0 {
+        return KMeans { assignments: vec![], centroids: vec![], inertia: 0.0 };
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut centroids = vec![features[rng.gen_range(0, features.len())].clone()];
+    while centroids.len() < k {
+        let weights: Vec<f64> = features.iter().map(|f| nearest(f, &centroids).1).collect();
+        let total: f64 = weights.iter().sum();
+        let next = if total > 0.0 {
+            let mut target = rng.gen::<f64>() * total;
+            weights.iter().position(|&w| { target -= w; target < 0.0 }).unwrap_or(features.len() - 1)
+        } else {
+            rng.gen_range(0, features.len())
+        };
+        centroids.push(features[next].clone());
+    }
+
+    let mut assignments: Vec<usize> = vec![];
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<usize> = features.iter().map(|f| nearest(f, &centroids).0).collect();
+        if next == assignments {
+            break;
+        }
+        assignments = next;
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&Vec<f64>> = features.iter().zip(&assignments)
+                .filter(|(_, &a)| a == c)
+                .map(|(f, _)| f)
+                .collect();
+            // Empty cluster keeps its previous centroid
+            if !members.is_empty() {
+                *centroid = (0..centroid.len())
+                    .map(|i| members.iter().map(|f| f[i]).sum::<f64>() / members.len() as f64)
+                    .collect();
+            }
+        }
+    }
+    let inertia = features.iter().zip(&assignments).map(|(f, &c)| squared_distance(f, &centroids[c])).sum();
+    KMeans { assignments, centroids, inertia }
+}
+
+/// Cluster series with k-medoids. Initial medoids are selected greedily (BUILD step of PAM),
+/// so the result is deterministic. Pairs of series without distance (e.g. Euclidean for different
+/// lengths) are treated as infinitely far apart.
+/// Returns empty result if k is 0 or there are no series. k larger then the number of series is reduced.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::cluster;
+/// use timeseries::distance::Distance;
+///
+/// let profiles = vec![
+///     TimeSeries::new(vec![0, 1, 2, 3], vec![0.0, 5.0, 0.0, 0.0]),
+///     TimeSeries::new(vec![0, 1, 2, 3], vec![9.0, 9.0, 9.0, 9.0]),
+///     TimeSeries::new(vec![0, 1, 2, 3], vec![0.0, 0.0, 5.0, 0.0]),
+///     TimeSeries::new(vec![0, 1, 2], vec![9.0, 8.0, 9.0]),
+/// ];
+/// let result = cluster::kmedoids(&profiles, 2, Distance::Dtw(None));
+/// assert_eq!(result.assignments[0], result.assignments[2]);
+/// assert_eq!(result.assignments[1], result.assignments[3]);
+/// assert_ne!(result.assignments[0], result.assignments[1]);
+/// ```
+pub fn kmedoids(series: &[TimeSeries], k: usize, distance: Distance) -> KMedoids {
+    let n = series.len();
+    let k = k.min(n);
+    if k == 0 {
+        return KMedoids { assignments: vec![], medoids: vec![], cost: 0.0 };
+    }
+    let mut dist = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let d = distance.between(&series[i], &series[j]).unwrap_or(f64::INFINITY);
+            dist[i][j] = d;
+            dist[j][i] = d;
+        }
+    }
+    let cost_with = |medoids: &[usize]| -> f64 {
+        (0..n).map(|i| medoids.iter().map(|&m| dist[i][m]).fold(f64::INFINITY, f64::min)).sum()
+    };
+
+    let mut medoids: Vec<usize> = vec![];
+    while medoids.len() < k {
+        let next = (0..n).filter(|i| !medoids.contains(i))
+            .map(|i| {
+                let mut candidate = medoids.clone();
+                candidate.push(i);
+                (i, cost_with(&candidate))
+            })
+            .fold((n, f64::INFINITY), |best, c| if c.1 < best.1 || best.0 == n { c } else { best })
+            .0;
+        medoids.push(next);
+    }
+
+    let assign = |medoids: &[usize]| -> Vec<usize> {
+        (0..n).map(|i| {
+            (0..medoids.len()).fold(0, |best, c| if dist[i][medoids[c]] < dist[i][medoids[best]] { c } else { best })
+        }).collect()
+    };
+    let mut assignments = assign(&medoids);
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<usize> = (0..k).map(|c| {
+            let members: Vec<usize> = (0..n).filter(|&i| assignments[i] == c).collect();
+            members.iter().cloned()
+                .map(|m| (m, members.iter().map(|&i| dist[i][m]).sum::<f64>()))
+                .fold((medoids[c], f64::INFINITY), |best, m| if m.1 < best.1 { m } else { best })
+                .0
+        }).collect();
+        if next == medoids {
+            break;
+        }
+        medoids = next;
+        assignments = assign(&medoids);
+    }
+    let cost = (0..n).map(|i| dist[i][medoids[assignments[i]]]).sum();
+    KMedoids { assignments, medoids, cost }
+}
+
+/// Position of the nearest centroid and squared distance to it
+fn nearest(feature: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
+    centroids.iter().enumerate()
+        .map(|(c, centroid)| (c, squared_distance(feature, centroid)))
+        .fold((0, f64::INFINITY), |best, c| if c.1 < best.1 { c } else { best })
+}
+
+fn squared_distance(xs: &[f64], ys: &[f64]) -> f64 {
+    xs.iter().zip(ys).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kmeans_empty() {
+        assert!(kmeans(&[], 3, 1).assignments.is_empty());
+        assert!(kmeans(&[vec![1.0]], 0, 1).centroids.is_empty());
+    }
+
+    #[test]
+    fn test_kmeans_duplicates() {
+        let features = vec![vec![1.0], vec![1.0], vec![1.0]];
+        let result = kmeans(&features, 5, 7);
+        assert_eq!(result.centroids.len(), 3);
+        assert_eq!(result.inertia, 0.0);
+        assert_eq!(result, kmeans(&features, 5, 7));
+    }
+
+    #[test]
+    fn test_kmedoids_medoids() {
+        let series: Vec<TimeSeries> = [1.0, 2.0, 3.0, 10.0, 11.0]
+            .iter()
+            .map(|&v| TimeSeries::new(vec![0], vec![v]))
+            .collect();
+        let result = kmedoids(&series, 2, Distance::Euclidean);
+        assert_eq!(result.assignments, vec![0, 0, 0, 1, 1]);
+        assert_eq!(result.medoids[0], 1);
+        assert_eq!(result.cost, 3.0);
+    }
+}
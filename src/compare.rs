@@ -0,0 +1,82 @@
+//! Period over period comparison
+//!
+//! Each data point is compared with the value at the same time in the previous period,
+//! e.g. the same hour last week. Calendar periods (Month, Quarter, Year) keep the day of month
+//! (see `Frequency::step`), so the prior value is aligned with the calendar and not with a fixed offset.
+//!
+
+use crate::TimeSeries;
+use crate::frequency::Frequency;
+use crate::resample::FillMethod;
+
+
+/// Series compared with the previous period. All series have the index of the current series.
+///   * current - Input series
+///   * prior - Value at the same time in the previous period. NaN if there is no data point at that time
+///   * delta - current - prior
+///   * pct - Percentage change from prior to current
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeriodComparison {
+    pub current: TimeSeries,
+    pub prior: TimeSeries,
+    pub delta: TimeSeries,
+    pub pct: TimeSeries,
+}
+
+
+impl TimeSeries {
+
+    /// Compare each data point with the data point one period earlier
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::frequency::Frequency;
+    ///
+    /// // 2020-01-15, 2020-02-15 and 2020-03-15
+    /// let ts = TimeSeries::new(vec![1_579_046_400_000, 1_581_724_800_000, 1_584_230_400_000], vec![10.0, 12.0, 9.0]);
+    /// let cmp = ts.period_over_period(Frequency::Month);
+    /// assert!(cmp.prior.values[0].is_nan());
+    /// assert_eq!(cmp.prior.values[1..], [10.0, 12.0]);
+    /// assert_eq!(cmp.delta.values[1..], [2.0, -3.0]);
+    /// assert_eq!(cmp.pct.values[1..], [20.0, -25.0]);
+    /// ```
+    pub fn period_over_period(&self, period: Frequency) -> PeriodComparison {
+        let params = || vec![("period", format!("{:?}", period))];
+        let prior_timestamps: Vec<i64> = self.index.iter().map(|&t| period.step_in(t, -1, self.index.resolution)).collect();
+        let mut prior = self.clone();
+        prior.values = self.fill_values(&prior_timestamps, FillMethod::Nan);
+        let mut delta = self.clone();
+        delta.values = self.values.iter().zip(&prior.values).map(|(c, p)| c - p).collect();
+        let mut pct = self.clone();
+        pct.values = delta.values.iter().zip(&prior.values).map(|(d, p)| d / p * 100.0).collect();
+        PeriodComparison {
+            current: self.clone(),
+            prior: prior.logged(self, "period_over_period_prior", params),
+            delta: delta.logged(self, "period_over_period_delta", params),
+            pct: pct.logged(self, "period_over_period_pct", params),
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency::DAY;
+
+    #[test]
+    fn test_week_over_week() {
+        let index: Vec<i64> = (0..15).map(|d| d * DAY).collect();
+        let values: Vec<f64> = (0..15).map(|d| (d % 7) as f64 + (d / 7) as f64 * 10.0).collect();
+        let cmp = TimeSeries::new(index, values).period_over_period(Frequency::Week);
+        assert!(cmp.delta.values[..7].iter().all(|v| v.is_nan()));
+        assert!(cmp.delta.values[7..].iter().all(|&v| v == 10.0));
+        assert!(cmp.pct.values[7].is_infinite());
+        assert_eq!(cmp.current.len(), 15);
+    }
+}
@@ -0,0 +1,205 @@
+//! Compressed in-memory storage
+//!
+//! CompressedTimeSeries keeps data points in a single bit stream.
+//! Timestamps are stored as delta-of-delta and values are XOR encoded (Gorilla paper, Facebook 2015).
+//! Regular timestamps take 1 bit and repeated values take 1 bit, so typical sensor data
+//! needs only a few bits per data point instead of 16 bytes.
+//!
+//! Data points can only be appended and read sequentially.
+//!
+
+use crate::{TimeSeries, DataPoint, ToSeries};
+use crate::encoding::{BitReader, BitWriter, DeltaOfDelta, XorDecoder, XorEncoder};
+
+
+/// Append only compressed series
+#[derive(Clone, Debug, Default)]
+pub struct CompressedTimeSeries {
+    bits: BitWriter,
+    timestamps: DeltaOfDelta,
+    values: XorEncoder,
+    last: Option<DataPoint>,
+    len: usize,
+}
+
+/// Iterator which decompresses data points
+pub struct CompressedIter<'a> {
+    reader: BitReader<'a>,
+    timestamps: DeltaOfDelta,
+    values: XorDecoder,
+    remaining: usize,
+}
+
+
+impl CompressedTimeSeries {
+
+    /// Create empty series
+    pub fn new() -> CompressedTimeSeries {
+        CompressedTimeSeries {
+            bits: BitWriter::new(),
+            timestamps: DeltaOfDelta::new(),
+            values: XorEncoder::new(),
+            last: None,
+            len: 0,
+        }
+    }
+
+    /// Compress all data points of the series.
+    /// Quality codes and provenance are not stored.
+    pub fn from_series(ts: &TimeSeries) -> CompressedTimeSeries {
+        let mut compressed = CompressedTimeSeries::new();
+        ts.iter().for_each(|dp| { compressed.push(dp); });
+        compressed
+    }
+
+    /// Append data point.
+    /// Data point is ignored (and false is returned) if it is not after the last data point.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    /// use timeseries::compressed::CompressedTimeSeries;
+    ///
+    /// let mut ts = CompressedTimeSeries::new();
+    /// for t in 0..1000 {
+    ///     ts.push(DataPoint::new(t * 1000, 21.5));
+    /// }
+    /// assert!(!ts.push(DataPoint::new(0, 1.0)));
+    /// assert_eq!(ts.len(), 1000);
+    /// assert!(ts.size_bytes() < 300);
+    /// ```
+    pub fn push(&mut self, dp: DataPoint) -> bool {
+        if self.last.as_ref().is_some_and(|last| dp.timestamp <= last.timestamp) {
+            return false;
+        }
+        self.timestamps.encode(dp.timestamp, &mut self.bits);
+        self.values.encode(dp.value, &mut self.bits);
+        self.last = Some(dp);
+        self.len += 1;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Size of the compressed data in bytes
+    pub fn size_bytes(&self) -> usize {
+        self.bits.as_bytes().len()
+    }
+
+    /// Last data point. Available without decompression
+    pub fn last(&self) -> Option<DataPoint> {
+        self.last.clone()
+    }
+
+    /// Iterate over data points from the oldest to the newest
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::compressed::CompressedTimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.5, 3.2]);
+    /// let compressed = CompressedTimeSeries::from_series(&ts);
+    /// assert_eq!(compressed.iter().map(|dp| dp.value).sum::<f64>(), 6.7);
+    /// ```
+    pub fn iter(&self) -> CompressedIter<'_> {
+        CompressedIter {
+            reader: BitReader::new(self.bits.as_bytes()),
+            timestamps: DeltaOfDelta::new(),
+            values: XorDecoder::new(),
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a> Iterator for CompressedIter<'a> {
+    type Item = DataPoint;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let timestamp = self.timestamps.decode(&mut self.reader)?;
+        let value = self.values.decode(&mut self.reader)?;
+        Some(DataPoint::new(timestamp, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ToSeries for CompressedTimeSeries {
+    /// Decompress into TimeSeries
+    fn to_series(&self) -> TimeSeries {
+        let (index, values) = self.iter().map(|dp| (dp.timestamp, dp.value)).unzip();
+        TimeSeries::new(index, values)
+    }
+}
+
+impl From<&TimeSeries> for CompressedTimeSeries {
+    fn from(ts: &TimeSeries) -> CompressedTimeSeries {
+        CompressedTimeSeries::from_series(ts)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(n: usize) -> TimeSeries {
+        let index = (0..n as i64).map(|i| 1_600_000_000_000 + i * 1000).collect();
+        let values = (0..n).map(|i| 20.0 + ((i / 10) % 5) as f64 * 0.5).collect();
+        TimeSeries::new(index, values)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ts = sensor(10_000);
+        let compressed = CompressedTimeSeries::from_series(&ts);
+        assert_eq!(compressed.len(), ts.len());
+        assert_eq!(compressed.to_series(), ts);
+        assert_eq!(compressed.last(), ts.last());
+    }
+
+    #[test]
+    fn test_under_2_bytes_per_point() {
+        let ts = sensor(100_000);
+        let compressed = CompressedTimeSeries::from(&ts);
+        assert!(compressed.size_bytes() < 2 * ts.len());
+    }
+
+    #[test]
+    fn test_append_after_read() {
+        let ts = TimeSeries::new(vec![10, 20, 35], vec![1.0, f64::NAN, -4.5]);
+        let mut compressed = CompressedTimeSeries::from_series(&ts);
+        assert_eq!(compressed.iter().count(), 3);
+        assert!(compressed.push(DataPoint::new(36, 7.0)));
+        assert!(!compressed.push(DataPoint::new(36, 8.0)));
+        let values: Vec<f64> = compressed.iter().map(|dp| dp.value).collect();
+        assert_eq!(values.len(), 4);
+        assert!(values[1].is_nan());
+        assert_eq!(values[3], 7.0);
+    }
+
+    #[test]
+    fn test_empty() {
+        let compressed = CompressedTimeSeries::new();
+        assert!(compressed.is_empty());
+        assert_eq!(compressed.iter().next(), None);
+        assert!(compressed.to_series().is_empty());
+    }
+}
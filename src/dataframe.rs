@@ -0,0 +1,133 @@
+//! Conversion to and from polars DataFrame
+//!
+//! Timestamps are stored in Int64 column (raw index values) and values in Float64 column.
+//!
+
+use std::convert::TryFrom;
+
+use polars::prelude::*;
+
+use crate::TimeSeries;
+use crate::frame::TimeSeriesFrame;
+
+
+/// Name of the timestamp column in DataFrames created from series
+pub const TIMESTAMP_COLUMN: &str = "timestamp";
+/// Name of the value column in DataFrames created from series
+pub const VALUE_COLUMN: &str = "value";
+
+
+impl From<&TimeSeries> for DataFrame {
+    /// Create DataFrame with "timestamp" and "value" columns
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use polars::prelude::DataFrame;
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// let df = DataFrame::from(&ts);
+    /// assert_eq!(df.shape(), (2, 2));
+    /// ```
+    fn from(ts: &TimeSeries) -> DataFrame {
+        DataFrame::new(vec![
+            Column::new(TIMESTAMP_COLUMN.into(), &ts.index.values),
+            Column::new(VALUE_COLUMN.into(), &ts.values),
+        ]).expect("Columns have the same length")
+    }
+}
+
+impl TryFrom<&TimeSeriesFrame> for DataFrame {
+    type Error = PolarsError;
+
+    /// Create DataFrame with "timestamp" column and column for each series.
+    /// Returns error if column names are not unique (also with the "timestamp" column).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use polars::prelude::DataFrame;
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let frame = TimeSeriesFrame::new(vec![1, 2]).with_column("a", vec![1.0, 2.0]);
+    /// assert_eq!(DataFrame::try_from(&frame).unwrap().shape(), (2, 2));
+    /// let frame = frame.with_column("a", vec![3.0, 4.0]);
+    /// assert!(DataFrame::try_from(&frame).is_err());
+    /// ```
+    fn try_from(frame: &TimeSeriesFrame) -> PolarsResult<DataFrame> {
+        let mut columns = vec![Column::new(TIMESTAMP_COLUMN.into(), &frame.index.values)];
+        columns.extend(frame.names.iter().zip(&frame.columns)
+            .map(|(name, values)| Column::new(name.into(), values)));
+        DataFrame::new(columns)
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Create series from DataFrame columns.
+    /// Columns are cast to Int64 (timestamp) and Float64 (value). Null values are converted to NaN.
+    /// Returns error if the column doesn't exist, can't be cast, or contains null timestamps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use polars::prelude::*;
+    /// use timeseries::TimeSeries;
+    ///
+    /// let df = DataFrame::new(vec![
+    ///     Column::new("time".into(), &[1i64, 2]),
+    ///     Column::new("temp".into(), &[20.5f64, 21.0]),
+    /// ]).unwrap();
+    /// let ts = TimeSeries::from_dataframe(&df, "time", "temp").unwrap();
+    /// assert_eq!(ts, TimeSeries::new(vec![1, 2], vec![20.5, 21.0]));
+    /// ```
+    pub fn from_dataframe(df: &DataFrame, timestamp_column: &str, value_column: &str) -> PolarsResult<TimeSeries> {
+        let timestamps = df.column(timestamp_column)?.cast(&DataType::Int64)?;
+        let index = timestamps.i64()?.into_iter()
+            .map(|t| t.ok_or_else(|| polars_err!(ComputeError: "null timestamp in column {}", timestamp_column)))
+            .collect::<PolarsResult<Vec<i64>>>()?;
+        let values = df.column(value_column)?.cast(&DataType::Float64)?;
+        let values = values.f64()?.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect();
+        Ok(TimeSeries::new(index, values))
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let ts = TimeSeries::new(vec![10, 20, 30], vec![1.0, f64::NAN, 3.0]);
+        let df = DataFrame::from(&ts);
+        let restored = TimeSeries::from_dataframe(&df, TIMESTAMP_COLUMN, VALUE_COLUMN).unwrap();
+        assert_eq!(restored.index, ts.index);
+        assert!(restored.values[1].is_nan());
+    }
+
+    #[test]
+    fn test_missing_column() {
+        let df = DataFrame::from(&TimeSeries::new(vec![1], vec![1.0]));
+        assert!(TimeSeries::from_dataframe(&df, "time", VALUE_COLUMN).is_err());
+    }
+
+    #[test]
+    fn test_frame_columns() {
+        let frame = TimeSeriesFrame::new(vec![1, 2])
+            .with_column("a", vec![1.0, 2.0])
+            .with_column("b", vec![3.0, 4.0]);
+        let df = DataFrame::try_from(&frame).unwrap();
+        assert_eq!(df.get_column_names(), vec!["timestamp", "a", "b"]);
+        let b = TimeSeries::from_dataframe(&df, TIMESTAMP_COLUMN, "b").unwrap();
+        assert_eq!(b.values, vec![3.0, 4.0]);
+        let frame = TimeSeriesFrame::new(vec![1]).with_column(TIMESTAMP_COLUMN, vec![1.0]);
+        assert!(DataFrame::try_from(&frame).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! Distances between series
+//!
+//! Distances compare values by position and ignore timestamps,
+//! so profiles recorded on different days can be compared with each other.
+//!
+
+use crate::TimeSeries;
+use crate::stats::pearson;
+
+
+/// Distance measure
+///   * Euclidean - Square root of the sum of squared differences. Series need the same length
+///   * Dtw - Dynamic Time Warping with optional Sakoe-Chiba band (see `dtw`)
+///   * Correlation - 1 - Pearson correlation. Value between 0 and 2. Series need the same length
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distance {
+    Euclidean,
+    Dtw(Option<usize>),
+    Correlation,
+}
+
+impl Distance {
+
+    /// Calculate distance between 2 series. None if it is not defined for them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::distance::Distance;
+    ///
+    /// let a = TimeSeries::new(vec![1, 2, 3], vec![0.0, 1.0, 2.0]);
+    /// let b = TimeSeries::new(vec![4, 5, 6], vec![0.0, 0.0, 1.0]);
+    /// assert_eq!(Distance::Euclidean.between(&a, &b), Some(2f64.sqrt()));
+    /// assert_eq!(Distance::Dtw(None).between(&a, &b), Some(1.0));
+    /// ```
+    pub fn between(&self, a: &TimeSeries, b: &TimeSeries) -> Option<f64> {
+        match *self {
+            Distance::Euclidean => euclidean(a, b),
+            Distance::Dtw(band) => dtw(a, b, band),
+            Distance::Correlation => correlation(a, b),
+        }
+    }
+}
+
+
+/// Euclidean distance. None if series have different length.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::distance;
+///
+/// let a = TimeSeries::new(vec![1, 2], vec![0.0, 0.0]);
+/// let b = TimeSeries::new(vec![1, 2], vec![3.0, 4.0]);
+/// assert_eq!(distance::euclidean(&a, &b), Some(5.0));
+/// ```
+pub fn euclidean(a: &TimeSeries, b: &TimeSeries) -> Option<f64> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let sum: f64 = a.values.iter().zip(&b.values).map(|(x, y)| (x - y) * (x - y)).sum();
+    Some(sum.sqrt())
+}
+
+/// Dynamic Time Warping distance: the smallest sum of absolute differences
+/// over all monotonic alignments of the data points.
+/// Band limits how far (in data points) aligned points can be from the diagonal.
+/// It is widened to the length difference, so an alignment always exists.
+/// None if any series is empty.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::distance;
+///
+/// let a = TimeSeries::new(vec![1, 2, 3, 4], vec![0.0, 1.0, 2.0, 1.0]);
+/// let b = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![0.0, 0.0, 1.0, 2.0, 1.0]);
+/// assert_eq!(distance::dtw(&a, &b, None), Some(0.0));
+/// assert_eq!(distance::dtw(&a, &b, Some(1)), Some(0.0));
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(a, b), fields(a_len = a.len(), b_len = b.len())))]
+pub fn dtw(a: &TimeSeries, b: &TimeSeries, band: Option<usize>) -> Option<f64> {
+    let (n, m) = (a.len(), b.len());
+    if n == 0 || m == 0 {
+        return None;
+    }
+    let band = band.unwrap_or(n.max(m)).max(n.abs_diff(m));
+    // Only the previous row of the cost matrix is kept
+    let mut prev = vec![f64::INFINITY; m + 1];
+    let mut row = vec![f64::INFINITY; m + 1];
+    prev[0] = 0.0;
+    for i in 1..=n {
+        row.fill(f64::INFINITY);
+        let from = i.saturating_sub(band).max(1);
+        let to = (i + band).min(m);
+        for j in from..=to {
+            let cost = (a.values[i - 1] - b.values[j - 1]).abs();
+            row[j] = cost + prev[j - 1].min(prev[j]).min(row[j - 1]);
+        }
+        std::mem::swap(&mut prev, &mut row);
+    }
+    Some(prev[m])
+}
+
+/// Correlation distance: 1 - Pearson correlation of values.
+/// None if series have different length, less than 2 data points or constant values.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::distance;
+///
+/// let a = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+/// let b = TimeSeries::new(vec![1, 2, 3], vec![10.0, 20.0, 30.0]);
+/// assert_eq!(distance::correlation(&a, &b), Some(0.0));
+/// ```
+pub fn correlation(a: &TimeSeries, b: &TimeSeries) -> Option<f64> {
+    if a.len() != b.len() {
+        return None;
+    }
+    pearson(&a.values, &b.values).map(|r| 1.0 - r)
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn series(values: Vec<f64>) -> TimeSeries {
+        TimeSeries::new((0..values.len() as i64).collect(), values)
+    }
+
+    #[test]
+    fn test_dtw_band() {
+        let a = series(vec![0.0, 1.0, 0.0, 0.0, 0.0]);
+        let b = series(vec![0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(dtw(&a, &b, None), Some(0.0));
+        assert_eq!(dtw(&a, &b, Some(0)), Some(2.0));
+        assert_eq!(dtw(&a, &b, Some(1)), Some(2.0));
+        assert_eq!(dtw(&a, &b, Some(2)), Some(0.0));
+    }
+
+    #[test]
+    fn test_dtw_empty() {
+        assert_eq!(dtw(&series(vec![]), &series(vec![1.0]), None), None);
+        assert_eq!(dtw(&series(vec![1.0]), &series(vec![1.0, 2.0, 4.0]), Some(0)), Some(4.0));
+    }
+
+    #[test]
+    fn test_different_length() {
+        let a = series(vec![1.0, 2.0]);
+        let b = series(vec![1.0, 2.0, 3.0]);
+        assert_eq!(euclidean(&a, &b), None);
+        assert_eq!(correlation(&a, &b), None);
+        assert_approx_eq!(Distance::Correlation.between(&a, &series(vec![2.0, 1.0])).unwrap(), 2.0);
+    }
+}
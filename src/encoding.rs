@@ -0,0 +1,360 @@
+//! Low level encodings used by the binary format and compressed storage
+//!   * Bit streams
+//!   * Zigzag varints for integers
+//!   * Delta-of-delta encoding of timestamps (Gorilla paper, Facebook 2015)
+//!   * XOR encoding of floats (Gorilla paper, Facebook 2015)
+//!
+
+/// Write bits into bytes, most significant bit first
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct BitWriter {
+    bytes: Vec<u8>,
+    /// Number of bits used in the last byte (0 means the byte is full or there are no bytes)
+    used: u32,
+}
+
+/// Read bits written by BitWriter
+#[derive(Clone, Debug)]
+pub(crate) struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+/// State of the XOR float encoder
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct XorEncoder {
+    prev: Option<u64>,
+    leading: u32,
+    trailing: u32,
+}
+
+/// State of the XOR float decoder
+#[derive(Clone, Debug, Default)]
+pub(crate) struct XorDecoder {
+    prev: Option<u64>,
+    leading: u32,
+    trailing: u32,
+}
+
+/// State of the delta-of-delta timestamp encoder and decoder
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct DeltaOfDelta {
+    prev: Option<i64>,
+    delta: i64,
+}
+
+/// Delta-of-delta buckets: prefix, prefix length and value size in bits.
+/// Zero is stored as a single 0 bit.
+const DOD_BUCKETS: [(u64, u32, u32); 5] = [
+    (0b10, 2, 7),
+    (0b110, 3, 9),
+    (0b1110, 4, 12),
+    (0b11110, 5, 32),
+    (0b11111, 5, 64),
+];
+
+
+impl BitWriter {
+
+    pub(crate) fn new() -> BitWriter {
+        BitWriter::default()
+    }
+
+    /// Write the lowest n bits of the value (n <= 64)
+    pub(crate) fn write_bits(&mut self, value: u64, n: u32) {
+        for i in (0..n).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    pub(crate) fn write_bit(&mut self, bit: bool) {
+        if self.used == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> self.used;
+        }
+        self.used = (self.used + 1) % 8;
+    }
+
+    /// Number of bits written
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        if self.used == 0 { self.bytes.len() * 8 } else { (self.bytes.len() - 1) * 8 + self.used as usize }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[cfg(feature = "io")]
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<'a> BitReader<'a> {
+
+    pub(crate) fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        BitReader { bytes, pos: 0 }
+    }
+
+    pub(crate) fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.pos / 8)?;
+        let bit = byte & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    /// Read n bits (n <= 64)
+    pub(crate) fn read_bits(&mut self, n: u32) -> Option<u64> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Some(value)
+    }
+}
+
+impl XorEncoder {
+
+    pub(crate) fn new() -> XorEncoder {
+        XorEncoder::default()
+    }
+
+    pub(crate) fn encode(&mut self, value: f64, out: &mut BitWriter) {
+        let bits = value.to_bits();
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => {
+                out.write_bits(bits, 64);
+                self.prev = Some(bits);
+                // Force new block size for the second value
+                self.leading = u32::MAX;
+                return;
+            }
+        };
+        self.prev = Some(bits);
+        let xor = bits ^ prev;
+        if xor == 0 {
+            out.write_bit(false);
+            return;
+        }
+        out.write_bit(true);
+        // Leading zeros are stored on 5 bits
+        let leading = xor.leading_zeros().min(31);
+        let trailing = xor.trailing_zeros();
+        if self.leading != u32::MAX && leading >= self.leading && trailing >= self.trailing {
+            out.write_bit(false);
+            out.write_bits(xor >> self.trailing, 64 - self.leading - self.trailing);
+        } else {
+            let meaningful = 64 - leading - trailing;
+            out.write_bit(true);
+            out.write_bits(leading as u64, 5);
+            // 64 meaningful bits are stored as 0
+            out.write_bits((meaningful % 64) as u64, 6);
+            out.write_bits(xor >> trailing, meaningful);
+            self.leading = leading;
+            self.trailing = trailing;
+        }
+    }
+}
+
+impl XorDecoder {
+
+    pub(crate) fn new() -> XorDecoder {
+        XorDecoder::default()
+    }
+
+    /// Next value. None if there is no more data or the block size is invalid (corrupted data)
+    pub(crate) fn decode(&mut self, input: &mut BitReader<'_>) -> Option<f64> {
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => {
+                let bits = input.read_bits(64)?;
+                self.prev = Some(bits);
+                return Some(f64::from_bits(bits));
+            }
+        };
+        let xor = if !input.read_bit()? {
+            0
+        } else {
+            if input.read_bit()? {
+                self.leading = input.read_bits(5)? as u32;
+                let meaningful = match input.read_bits(6)? as u32 { 0 => 64, n => n };
+                self.trailing = 64u32.checked_sub(self.leading)?.checked_sub(meaningful)?;
+            }
+            let meaningful = 64u32.checked_sub(self.leading)?.checked_sub(self.trailing)?;
+            input.read_bits(meaningful)?.checked_shl(self.trailing)?
+        };
+        let bits = prev ^ xor;
+        self.prev = Some(bits);
+        Some(f64::from_bits(bits))
+    }
+}
+
+impl DeltaOfDelta {
+
+    pub(crate) fn new() -> DeltaOfDelta {
+        DeltaOfDelta::default()
+    }
+
+    pub(crate) fn encode(&mut self, timestamp: i64, out: &mut BitWriter) {
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => {
+                out.write_bits(timestamp as u64, 64);
+                self.prev = Some(timestamp);
+                return;
+            }
+        };
+        let delta = timestamp.wrapping_sub(prev);
+        let dod = delta.wrapping_sub(self.delta);
+        self.prev = Some(timestamp);
+        self.delta = delta;
+        if dod == 0 {
+            out.write_bit(false);
+            return;
+        }
+        let &(prefix, prefix_len, size) = DOD_BUCKETS.iter()
+            .find(|&&(_, _, size)| size == 64 || (-(1 << (size - 1))..(1 << (size - 1))).contains(&dod))
+            .unwrap();
+        out.write_bits(prefix, prefix_len);
+        out.write_bits(dod as u64 & (u64::MAX >> (64 - size)), size);
+    }
+
+    pub(crate) fn decode(&mut self, input: &mut BitReader<'_>) -> Option<i64> {
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => {
+                let timestamp = input.read_bits(64)? as i64;
+                self.prev = Some(timestamp);
+                return Some(timestamp);
+            }
+        };
+        let mut ones = 0;
+        while ones < 4 && input.read_bit()? {
+            ones += 1;
+        }
+        let dod = if ones == 0 {
+            0
+        } else {
+            let size = DOD_BUCKETS[ones - 1 + (ones == 4 && input.read_bit()?) as usize].2;
+            // Sign extend
+            ((input.read_bits(size)? << (64 - size)) as i64) >> (64 - size)
+        };
+        self.delta = self.delta.wrapping_add(dod);
+        let timestamp = prev.wrapping_add(self.delta);
+        self.prev = Some(timestamp);
+        Some(timestamp)
+    }
+}
+
+
+/// Append integer as zigzag encoded varint
+#[cfg(feature = "io")]
+pub(crate) fn write_varint(value: i64, out: &mut Vec<u8>) {
+    let mut n = ((value << 1) ^ (value >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Read zigzag encoded varint starting at pos. Moves pos after the value
+#[cfg(feature = "io")]
+pub(crate) fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<i64> {
+    let mut n: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        if shift >= 64 {
+            return None;
+        }
+        n |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Some(((n >> 1) as i64) ^ -((n & 1) as i64))
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits() {
+        let mut writer = BitWriter::new();
+        writer.write_bits(0b101, 3);
+        writer.write_bits(u64::MAX, 64);
+        assert_eq!(writer.len(), 67);
+        let mut reader = BitReader::new(writer.as_bytes());
+        assert_eq!(reader.read_bits(3), Some(0b101));
+        assert_eq!(reader.read_bits(64), Some(u64::MAX));
+        assert_eq!(reader.read_bits(8), None);
+    }
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn test_varint() {
+        let values = [0, 1, -1, 63, -64, 1_000_000, i64::MAX, i64::MIN];
+        let mut bytes = vec![];
+        values.iter().for_each(|&v| write_varint(v, &mut bytes));
+        let mut pos = 0;
+        let decoded: Vec<i64> = (0..values.len()).map(|_| read_varint(&bytes, &mut pos).unwrap()).collect();
+        assert_eq!(decoded, values);
+        assert_eq!(read_varint(&bytes, &mut pos), None);
+    }
+
+    #[test]
+    fn test_xor_round_trip() {
+        let values = [12.0, 12.0, 12.5, 11.0, -3.25, f64::NAN, 0.0, f64::MAX, 1e-300, 24.0];
+        let mut writer = BitWriter::new();
+        let mut encoder = XorEncoder::new();
+        values.iter().for_each(|&v| encoder.encode(v, &mut writer));
+        let mut reader = BitReader::new(writer.as_bytes());
+        let mut decoder = XorDecoder::new();
+        for &v in &values {
+            assert_eq!(decoder.decode(&mut reader).unwrap().to_bits(), v.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_delta_of_delta_round_trip() {
+        let timestamps = [1_600_000_000_000, 1_600_000_001_000, 1_600_000_002_000, 1_600_000_002_999,
+                          1_600_000_003_200, 1_600_000_010_000, 1_600_005_000_000, 0, i64::MAX, i64::MIN];
+        let mut writer = BitWriter::new();
+        let mut encoder = DeltaOfDelta::new();
+        timestamps.iter().for_each(|&t| encoder.encode(t, &mut writer));
+        let mut reader = BitReader::new(writer.as_bytes());
+        let mut decoder = DeltaOfDelta::new();
+        for &t in &timestamps {
+            assert_eq!(decoder.decode(&mut reader), Some(t));
+        }
+    }
+
+    #[test]
+    fn test_regular_timestamps_use_one_bit() {
+        let mut writer = BitWriter::new();
+        let mut encoder = DeltaOfDelta::new();
+        (0..1000).for_each(|i| encoder.encode(i * 1000, &mut writer));
+        // Full first timestamp, 12 bits bucket for the first delta, then 1 bit per timestamp
+        assert_eq!(writer.len(), 64 + 4 + 12 + 998);
+    }
+
+    #[test]
+    fn test_xor_repeated_values_are_small() {
+        let mut writer = BitWriter::new();
+        let mut encoder = XorEncoder::new();
+        (0..1000).for_each(|_| encoder.encode(21.5, &mut writer));
+        assert_eq!(writer.len(), 64 + 999);
+    }
+}
@@ -0,0 +1,95 @@
+//! Error type returned by the IO functions
+//!
+//! Variants tell callers what went wrong, so they can decide whether to retry (Io, Http, Database),
+//! skip the input (Parse, InvalidIndex, InvalidFormat) or abort.
+//!
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::merge::OverlapError;
+
+
+/// Errors returned by the IO functions
+///   * Io - File system or network stream failed
+///   * Parse - Text which can't be parsed at the line (starting from 1) and column (starting from 0)
+///   * InvalidIndex - Timestamps can't be used, e.g. their format is not known
+///   * LengthMismatch - Number of items is different from the expected one
+///   * InvalidFormat - Data is not in the expected format or is corrupted
+///   * Unsupported - Operation requires a feature which is not enabled or not available on this platform
+///   * Overlap - Series can't be joined, because their time ranges overlap
+///   * Http - Request failed (`http` feature)
+///   * Database - Query failed (`sqlite` and `postgres` features)
+///   * File - Error while reading one of many files, with the path of this file
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Parse error at line {line}, column {column}: {cause}")]
+    Parse { line: u64, column: usize, cause: String },
+    #[error("Invalid index: {0}")]
+    InvalidIndex(String),
+    #[error("Length mismatch: expected {expected}, got {actual}")]
+    LengthMismatch { expected: usize, actual: usize },
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+    #[error(transparent)]
+    Overlap(#[from] OverlapError),
+    #[error("HTTP error: {0}")]
+    Http(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Database error: {0}")]
+    Database(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Error in file {}: {source}", path.display())]
+    File { path: PathBuf, source: Box<Error> },
+}
+
+/// Result of the IO functions
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+#[cfg(feature = "io")]
+impl Error {
+
+    pub(crate) fn invalid_format(cause: &str) -> Error {
+        Error::InvalidFormat(cause.to_owned())
+    }
+
+    pub(crate) fn invalid_index(cause: &str) -> Error {
+        Error::InvalidIndex(cause.to_owned())
+    }
+}
+
+#[cfg(feature = "io")]
+impl From<csv::Error> for Error {
+
+    /// IO errors are kept, all other errors are reported as Parse errors at their position
+    fn from(error: csv::Error) -> Error {
+        let line = error.position().map_or(0, |p| p.line());
+        let column = match error.kind() {
+            csv::ErrorKind::Utf8 { err, .. } => err.field(),
+            _ => 0,
+        };
+        let cause = error.to_string();
+        match error.into_kind() {
+            csv::ErrorKind::Io(e) => Error::Io(e),
+            _ => Error::Parse { line, column, cause },
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Error {
+        Error::Database(Box::new(error))
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<postgres::Error> for Error {
+    fn from(error: postgres::Error) -> Error {
+        Error::Database(Box::new(error))
+    }
+}
@@ -0,0 +1,198 @@
+//! Extracting events (episodes) and constant runs from series
+//!
+//! Episode is a period when the series stays above a threshold,
+//! for example a rain event or an alert.
+//! Run is a period when the series keeps the same value, for example a setpoint.
+//!
+
+use crate::TimeSeries;
+
+
+/// Period when the series is above the threshold
+///   * start - Timestamp of the first data point above the threshold
+///   * end - Timestamp of the last data point above the threshold
+///   * peak - Maximum value in the episode
+///   * area - Integral of the value above the threshold in seconds (see `TimeSeries::integral`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Episode {
+    pub start: i64,
+    pub end: i64,
+    pub peak: f64,
+    pub area: f64,
+}
+
+impl Episode {
+
+    /// Duration in milliseconds
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+/// Consecutive data points with the same value
+///   * start - Timestamp of the first data point
+///   * end - Timestamp of the last data point
+///   * value - Value of the first data point
+///   * count - Number of data points
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    pub start: i64,
+    pub end: i64,
+    pub value: f64,
+    pub count: usize,
+}
+
+
+impl TimeSeries {
+
+    /// Find episodes where consecutive data points are above the threshold.
+    /// Episodes shorter then min_duration (in milliseconds) are skipped.
+    /// NaN values end the episode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let rain = TimeSeries::new(vec![0, 1000, 2000, 3000, 4000, 5000], vec![0.0, 2.0, 4.0, 0.0, 1.0, 0.0]);
+    /// let episodes = rain.episodes_above(0.0, 1000);
+    /// assert_eq!(episodes.len(), 1);
+    /// assert_eq!((episodes[0].start, episodes[0].end), (1000, 2000));
+    /// assert_eq!(episodes[0].peak, 4.0);
+    /// assert_eq!(episodes[0].area, 3.0);
+    /// ```
+    pub fn episodes_above(&self, threshold: f64, min_duration: i64) -> Vec<Episode> {
+        let mut episodes = vec![];
+        let mut start = 0;
+        while start < self.len() {
+            let end = start + self.values[start..].iter().take_while(|&&v| v > threshold).count();
+            if end == start {
+                start += 1;
+                continue;
+            }
+            let episode = Episode {
+                start: self.index[start],
+                end: self.index[end-1],
+                peak: self.values[start..end].iter().cloned().fold(f64::NAN, f64::max),
+                area: self.select(&(start..end).collect::<Vec<usize>>()).integral()
+                    - threshold * (self.index[end-1] - self.index[start]) as f64 / 1000.0,
+            };
+            if episode.duration() >= min_duration {
+                episodes.push(episode);
+            }
+            start = end;
+        }
+        episodes
+    }
+
+    /// Split series into runs of equal values. NaN values are equal to each other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let state = TimeSeries::new(vec![1, 2, 3, 4], vec![0.0, 0.0, 1.0, 0.0]);
+    /// let runs = state.runs();
+    /// assert_eq!(runs.len(), 3);
+    /// assert_eq!((runs[0].start, runs[0].end, runs[0].count), (1, 2, 2));
+    /// ```
+    pub fn runs(&self) -> Vec<Run> {
+        self.runs_within(0.0)
+    }
+
+    /// Split series into runs of values which differ from the first value in the run
+    /// by at most epsilon.
+    pub fn runs_within(&self, epsilon: f64) -> Vec<Run> {
+        self.run_ranges(epsilon).into_iter()
+            .map(|(start, end)| Run {
+                start: self.index[start],
+                end: self.index[end-1],
+                value: self.values[start],
+                count: end - start,
+            })
+            .collect()
+    }
+
+    /// Keep only the first data point of each run (the points where the value changes)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let setpoint = TimeSeries::new(vec![1, 2, 3, 4], vec![20.0, 20.0, 21.0, 21.0]);
+    /// assert_eq!(setpoint.drop_repeats(), TimeSeries::new(vec![1, 3], vec![20.0, 21.0]));
+    /// ```
+    pub fn drop_repeats(&self) -> TimeSeries {
+        self.drop_repeats_within(0.0)
+    }
+
+    /// Keep only the first data point of each run of values within epsilon (see `runs_within`)
+    pub fn drop_repeats_within(&self, epsilon: f64) -> TimeSeries {
+        let positions: Vec<usize> = self.run_ranges(epsilon).into_iter().map(|(start, _)| start).collect();
+        self.select(&positions).logged(self, "drop_repeats", || vec![("epsilon", epsilon.to_string())])
+    }
+
+    /// Start and end (exclusive) positions of each run
+    fn run_ranges(&self, epsilon: f64) -> Vec<(usize, usize)> {
+        let same = |a: f64, b: f64| (a.is_nan() && b.is_nan()) || (a - b).abs() <= epsilon;
+        let mut ranges = vec![];
+        let mut start = 0;
+        while start < self.len() {
+            let first = self.values[start];
+            let end = start + 1 + self.values[start+1..].iter().take_while(|&&v| same(first, v)).count();
+            ranges.push((start, end));
+            start = end;
+        }
+        ranges
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_area_above_threshold() {
+        let ts = TimeSeries::new(vec![0, 2000, 4000], vec![3.0, 5.0, 3.0]);
+        let episodes = ts.episodes_above(2.0, 0);
+        assert_eq!(episodes, vec![Episode { start: 0, end: 4000, peak: 5.0, area: 8.0 }]);
+    }
+
+    #[test]
+    fn test_episode_at_the_end_and_nan() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![5.0, f64::NAN, 5.0, 6.0]);
+        let episodes = ts.episodes_above(1.0, 0);
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[0].duration(), 0);
+        assert_eq!((episodes[1].start, episodes[1].end, episodes[1].peak), (3, 4, 6.0));
+    }
+
+    #[test]
+    fn test_runs_within_epsilon() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 1.05, 1.08, 1.2, f64::NAN]);
+        let runs = ts.runs_within(0.1);
+        assert_eq!(runs.iter().map(|r| r.count).collect::<Vec<usize>>(), vec![3, 1, 1]);
+        assert_eq!(ts.drop_repeats_within(0.1).index.values, vec![1, 4, 5]);
+        assert_eq!(ts.runs().len(), 5);
+    }
+
+    #[test]
+    fn test_nan_run() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![f64::NAN, f64::NAN, 1.0]);
+        assert_eq!(ts.runs()[0].count, 2);
+        assert!(TimeSeries::empty().runs().is_empty());
+    }
+
+    #[test]
+    fn test_no_episodes() {
+        assert!(TimeSeries::empty().episodes_above(0.0, 0).is_empty());
+        let ts = TimeSeries::new(vec![1, 2], vec![1.0, 1.0]);
+        assert!(ts.episodes_above(1.0, 0).is_empty());
+    }
+}
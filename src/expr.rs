@@ -0,0 +1,618 @@
+//! Expressions over frame columns
+//!
+//! `Expr` describes derived series with per-point math, moving windows and reductions.
+//! Expressions are compiled into a `Plan`, which:
+//!   * Evaluates each common subexpression only once
+//!   * Folds operations on constants
+//!   * Evaluates all expressions together, in a single pass over the rows
+//!
+//! Reductions (like `sum`) need all rows before their value is known, so each level of
+//! nested reductions adds one more pass. Only results which are needed by a later pass are stored.
+//!
+//! Windows are counted in rows. Rows without full window get NaN, so every output column
+//! has the same length as the frame index.
+//!
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::frame::TimeSeriesFrame;
+use crate::resample::{Aggregation, BucketStats};
+use crate::stats::{covariance, mean};
+
+
+/// Expression which gives the value for each row
+///   * Column - Column of the frame with the given name
+///   * Literal - The same value in each row
+///   * Unary - Function of a single value
+///   * Binary - Function of two values from the same row
+///   * Window - Function of the current and previous rows
+///   * Reduce - Aggregation of all rows. The value is repeated in each row
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Column(String),
+    Literal(f64),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Window(WindowOp, Box<Expr>),
+    Reduce(Aggregation, Box<Expr>),
+}
+
+/// Functions of a single value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    Neg,
+    Abs,
+    Sqrt,
+    Ln,
+    Exp,
+}
+
+/// Functions of two values. Min and Max ignore NaN values
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Min,
+    Max,
+}
+
+/// Functions of the moving window of rows
+///   * Lag(n) - Value n rows before
+///   * Diff - Difference to the previous row
+///   * RollingMean(n) - Mean of the last n rows
+///   * RollingSum(n) - Sum of the last n rows
+///   * RollingStd(n) - Sample standard deviation of the last n rows
+///   * Ewma(span) - Exponentially weighted moving average with smoothing factor `2 / (span + 1)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WindowOp {
+    Lag(usize),
+    Diff,
+    RollingMean(usize),
+    RollingSum(usize),
+    RollingStd(usize),
+    Ewma(usize),
+}
+
+/// Expression refers to a column which is not in the frame
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprError {
+    UnknownColumn(String),
+}
+
+/// Compiled expressions. Can be evaluated on many frames.
+pub struct Plan {
+    nodes: Vec<Node>,
+    pass: Vec<usize>,
+    stored: Vec<bool>,
+    outputs: Vec<(String, usize)>,
+    passes: usize,
+}
+
+/// Plan node. Inputs are given by node ids, which are always smaller than the node id.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    Column(String),
+    Literal(u64),
+    Unary(UnaryOp, usize),
+    Binary(BinaryOp, usize, usize),
+    Window(WindowOp, usize),
+    Reduce(Aggregation, usize),
+}
+
+/// State of the window node
+enum WindowState {
+    Buffer(VecDeque<f64>),
+    Last(Option<f64>),
+}
+
+
+/// Column with the given name
+pub fn col(name: &str) -> Expr {
+    Expr::Column(name.to_owned())
+}
+
+/// Constant value
+pub fn lit(value: f64) -> Expr {
+    Expr::Literal(value)
+}
+
+
+impl Expr {
+
+    pub fn abs(self) -> Expr {
+        Expr::Unary(UnaryOp::Abs, Box::new(self))
+    }
+
+    pub fn sqrt(self) -> Expr {
+        Expr::Unary(UnaryOp::Sqrt, Box::new(self))
+    }
+
+    pub fn ln(self) -> Expr {
+        Expr::Unary(UnaryOp::Ln, Box::new(self))
+    }
+
+    pub fn exp(self) -> Expr {
+        Expr::Unary(UnaryOp::Exp, Box::new(self))
+    }
+
+    /// Smaller of both values in each row
+    pub fn min(self, other: Expr) -> Expr {
+        Expr::Binary(BinaryOp::Min, Box::new(self), Box::new(other))
+    }
+
+    /// Larger of both values in each row
+    pub fn max(self, other: Expr) -> Expr {
+        Expr::Binary(BinaryOp::Max, Box::new(self), Box::new(other))
+    }
+
+    pub fn lag(self, n: usize) -> Expr {
+        Expr::Window(WindowOp::Lag(n), Box::new(self))
+    }
+
+    pub fn diff(self) -> Expr {
+        Expr::Window(WindowOp::Diff, Box::new(self))
+    }
+
+    pub fn rolling_mean(self, window: usize) -> Expr {
+        Expr::Window(WindowOp::RollingMean(window), Box::new(self))
+    }
+
+    pub fn rolling_sum(self, window: usize) -> Expr {
+        Expr::Window(WindowOp::RollingSum(window), Box::new(self))
+    }
+
+    pub fn rolling_std(self, window: usize) -> Expr {
+        Expr::Window(WindowOp::RollingStd(window), Box::new(self))
+    }
+
+    pub fn ewma(self, span: usize) -> Expr {
+        Expr::Window(WindowOp::Ewma(span), Box::new(self))
+    }
+
+    /// Aggregate all rows (see `resample::Aggregation`). WeightedMean is calculated as Mean.
+    pub fn reduce(self, aggregation: Aggregation) -> Expr {
+        Expr::Reduce(aggregation, Box::new(self))
+    }
+
+    pub fn sum(self) -> Expr {
+        self.reduce(Aggregation::Sum)
+    }
+
+    pub fn mean(self) -> Expr {
+        self.reduce(Aggregation::Mean)
+    }
+}
+
+impl Add for Expr {
+    type Output = Expr;
+
+    fn add(self, other: Expr) -> Expr {
+        Expr::Binary(BinaryOp::Add, Box::new(self), Box::new(other))
+    }
+}
+
+impl Sub for Expr {
+    type Output = Expr;
+
+    fn sub(self, other: Expr) -> Expr {
+        Expr::Binary(BinaryOp::Sub, Box::new(self), Box::new(other))
+    }
+}
+
+impl Mul for Expr {
+    type Output = Expr;
+
+    fn mul(self, other: Expr) -> Expr {
+        Expr::Binary(BinaryOp::Mul, Box::new(self), Box::new(other))
+    }
+}
+
+impl Div for Expr {
+    type Output = Expr;
+
+    fn div(self, other: Expr) -> Expr {
+        Expr::Binary(BinaryOp::Div, Box::new(self), Box::new(other))
+    }
+}
+
+impl Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        Expr::Unary(UnaryOp::Neg, Box::new(self))
+    }
+}
+
+
+impl TimeSeriesFrame {
+
+    /// Evaluate named expressions. Each expression gives one column of the new frame.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::frame::TimeSeriesFrame;
+    /// use timeseries::expr::col;
+    ///
+    /// let frame = TimeSeriesFrame::new(vec![1, 2, 3])
+    ///     .with_column("a", vec![1.0, 2.0, 3.0])
+    ///     .with_column("b", vec![3.0, 2.0, 1.0]);
+    /// let total = col("a") + col("b");
+    /// let output = frame.evaluate(&[
+    ///     ("total", total.clone()),
+    ///     ("share", col("a") / total.clone()),
+    ///     ("smooth", total.rolling_mean(2)),
+    /// ]).unwrap();
+    /// assert_eq!(output.columns[0], vec![4.0, 4.0, 4.0]);
+    /// assert_eq!(output.columns[1], vec![0.25, 0.5, 0.75]);
+    /// assert_eq!(output.columns[2][1..], [4.0, 4.0]);
+    /// ```
+    pub fn evaluate(&self, exprs: &[(&str, Expr)]) -> Result<TimeSeriesFrame, ExprError> {
+        Plan::new(exprs).evaluate(self)
+    }
+}
+
+
+impl Plan {
+
+    /// Compile named expressions
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::expr::{Plan, col, lit};
+    ///
+    /// let spread = col("ask") - col("bid");
+    /// let plan = Plan::new(&[
+    ///     ("spread", spread.clone()),
+    ///     ("spread_bp", spread.clone() * lit(10_000.0) / col("bid")),
+    ///     ("anomaly", spread.clone() - spread.mean()),
+    /// ]);
+    /// // ask, bid, spread, 10000, spread * 10000, (spread * 10000) / bid, mean, spread - mean
+    /// assert_eq!(plan.len(), 8);
+    /// assert_eq!(plan.passes(), 2);
+    /// ```
+    pub fn new(exprs: &[(&str, Expr)]) -> Plan {
+        let mut nodes = vec![];
+        let mut ids = HashMap::new();
+        let outputs: Vec<(String, usize)> = exprs.iter()
+            .map(|(name, expr)| (name.to_string(), add_expr(expr, &mut nodes, &mut ids)))
+            .collect();
+
+        let mut pass = vec![0; nodes.len()];
+        for (id, node) in nodes.iter().enumerate() {
+            pass[id] = match *node {
+                Node::Column(_) | Node::Literal(_) => 0,
+                Node::Unary(_, a) | Node::Window(_, a) => pass[a],
+                Node::Binary(_, a, b) => pass[a].max(pass[b]),
+                Node::Reduce(_, a) => pass[a] + 1,
+            };
+        }
+
+        // Row values are kept only when they are used by a later pass or returned.
+        // Columns and literals are cheap to read again, reductions are single values.
+        let mut stored = vec![false; nodes.len()];
+        for (id, node) in nodes.iter().enumerate() {
+            match *node {
+                Node::Unary(_, a) | Node::Window(_, a) => stored[a] |= pass[a] < pass[id],
+                Node::Binary(_, a, b) => {
+                    stored[a] |= pass[a] < pass[id];
+                    stored[b] |= pass[b] < pass[id];
+                },
+                _ => (),
+            }
+        }
+        for &(_, id) in &outputs {
+            stored[id] = true;
+        }
+        for (id, node) in nodes.iter().enumerate() {
+            if matches!(node, Node::Column(_) | Node::Literal(_) | Node::Reduce(..)) {
+                stored[id] = false;
+            }
+        }
+
+        let passes = pass.iter().max().map_or(0, |&p| p + 1);
+        Plan { nodes, pass, stored, outputs, passes }
+    }
+
+    /// Number of distinct operations
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Number of passes over the rows
+    pub fn passes(&self) -> usize {
+        self.passes
+    }
+
+    /// Evaluate expressions on the frame. Output frame has the same index.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(rows = frame.len(), nodes = self.len())))]
+    pub fn evaluate(&self, frame: &TimeSeriesFrame) -> Result<TimeSeriesFrame, ExprError> {
+        let columns = self.nodes.iter()
+            .map(|node| match node {
+                Node::Column(name) => frame.names.iter().position(|n| n == name)
+                    .map(Some)
+                    .ok_or_else(|| ExprError::UnknownColumn(name.clone())),
+                _ => Ok(None),
+            })
+            .collect::<Result<Vec<Option<usize>>, ExprError>>()?;
+
+        let rows = frame.len();
+        let mut row = vec![f64::NAN; self.len()];
+        let mut stored: Vec<Vec<f64>> = self.stored.iter()
+            .map(|&s| if s { Vec::with_capacity(rows) } else { vec![] })
+            .collect();
+        for p in 0..self.passes {
+            let mut windows: Vec<Option<WindowState>> = self.nodes.iter().enumerate()
+                .map(|(id, node)| match node {
+                    Node::Window(op, _) if self.pass[id] == p => Some(WindowState::new(*op)),
+                    _ => None,
+                })
+                .collect();
+            let mut reductions: HashMap<usize, BucketStats> = self.nodes.iter().enumerate()
+                .filter(|&(id, node)| matches!(node, Node::Reduce(..)) && self.pass[id] == p + 1)
+                .map(|(id, _)| (id, BucketStats::empty()))
+                .collect();
+
+            for r in 0..rows {
+                for (id, node) in self.nodes.iter().enumerate() {
+                    let value = match *node {
+                        Node::Column(_) => frame.columns[columns[id].unwrap()][r],
+                        Node::Literal(bits) => f64::from_bits(bits),
+                        Node::Reduce(..) => continue,
+                        _ if self.pass[id] < p => {
+                            if self.stored[id] {
+                                row[id] = stored[id][r];
+                            }
+                            continue;
+                        },
+                        _ if self.pass[id] > p => continue,
+                        Node::Unary(op, a) => op.apply(row[a]),
+                        Node::Binary(op, a, b) => op.apply(row[a], row[b]),
+                        Node::Window(op, a) => windows[id].as_mut().unwrap().push(op, row[a]),
+                    };
+                    row[id] = value;
+                    if self.stored[id] {
+                        stored[id].push(value);
+                    }
+                }
+                for (&id, stats) in reductions.iter_mut() {
+                    if let Node::Reduce(_, a) = self.nodes[id] {
+                        *stats = stats.merge(&BucketStats::single(row[a]));
+                    }
+                }
+            }
+
+            for (id, stats) in reductions {
+                if let Node::Reduce(aggregation, _) = self.nodes[id] {
+                    row[id] = stats.value(aggregation).unwrap_or(f64::NAN);
+                }
+            }
+        }
+
+        let mut output = TimeSeriesFrame::new(frame.index.values.clone());
+        output.index.resolution = frame.index.resolution;
+        for (name, id) in &self.outputs {
+            let values = match self.nodes[*id] {
+                Node::Column(_) => frame.columns[columns[*id].unwrap()].clone(),
+                Node::Literal(bits) => vec![f64::from_bits(bits); rows],
+                Node::Reduce(..) => vec![row[*id]; rows],
+                _ => stored[*id].clone(),
+            };
+            output = output.with_column(name, values);
+        }
+        Ok(output)
+    }
+}
+
+/// Add expression to the plan and return its node id.
+/// Existing node is reused if the same operation was already added.
+fn add_expr(expr: &Expr, nodes: &mut Vec<Node>, ids: &mut HashMap<Node, usize>) -> usize {
+    let node = match expr {
+        _ if constant(expr).is_some() => Node::Literal(constant(expr).unwrap().to_bits()),
+        Expr::Column(name) => Node::Column(name.clone()),
+        Expr::Literal(value) => Node::Literal(value.to_bits()),
+        Expr::Unary(op, a) => Node::Unary(*op, add_expr(a, nodes, ids)),
+        Expr::Binary(op, a, b) => {
+            let a = add_expr(a, nodes, ids);
+            let b = add_expr(b, nodes, ids);
+            if op.is_commutative() { Node::Binary(*op, a.min(b), a.max(b)) } else { Node::Binary(*op, a, b) }
+        },
+        Expr::Window(op, a) => Node::Window(*op, add_expr(a, nodes, ids)),
+        Expr::Reduce(aggregation, a) => Node::Reduce(*aggregation, add_expr(a, nodes, ids)),
+    };
+    *ids.entry(node.clone()).or_insert_with(|| {
+        nodes.push(node);
+        nodes.len() - 1
+    })
+}
+
+/// Value of the expression which doesn't depend on the rows
+fn constant(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Literal(value) => Some(*value),
+        Expr::Unary(op, a) => Some(op.apply(constant(a)?)),
+        Expr::Binary(op, a, b) => Some(op.apply(constant(a)?, constant(b)?)),
+        _ => None,
+    }
+}
+
+
+impl UnaryOp {
+
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            UnaryOp::Neg => -x,
+            UnaryOp::Abs => x.abs(),
+            UnaryOp::Sqrt => x.sqrt(),
+            UnaryOp::Ln => x.ln(),
+            UnaryOp::Exp => x.exp(),
+        }
+    }
+}
+
+impl BinaryOp {
+
+    fn apply(self, x: f64, y: f64) -> f64 {
+        match self {
+            BinaryOp::Add => x + y,
+            BinaryOp::Sub => x - y,
+            BinaryOp::Mul => x * y,
+            BinaryOp::Div => x / y,
+            BinaryOp::Min => x.min(y),
+            BinaryOp::Max => x.max(y),
+        }
+    }
+
+    fn is_commutative(self) -> bool {
+        !matches!(self, BinaryOp::Sub | BinaryOp::Div)
+    }
+}
+
+impl WindowState {
+
+    fn new(op: WindowOp) -> WindowState {
+        match op {
+            WindowOp::Lag(n) | WindowOp::RollingMean(n) | WindowOp::RollingSum(n) | WindowOp::RollingStd(n) =>
+                WindowState::Buffer(VecDeque::with_capacity(n + 1)),
+            WindowOp::Diff | WindowOp::Ewma(_) => WindowState::Last(None),
+        }
+    }
+
+    /// Add the next value and return the window value
+    fn push(&mut self, op: WindowOp, x: f64) -> f64 {
+        match (self, op) {
+            (WindowState::Buffer(buffer), WindowOp::Lag(n)) => {
+                buffer.push_back(x);
+                if buffer.len() > n { buffer.pop_front().unwrap() } else { f64::NAN }
+            },
+            (WindowState::Buffer(buffer), WindowOp::RollingMean(n) | WindowOp::RollingSum(n) | WindowOp::RollingStd(n)) => {
+                if n == 0 {
+                    return f64::NAN;
+                }
+                if buffer.len() == n {
+                    buffer.pop_front();
+                }
+                buffer.push_back(x);
+                if buffer.len() < n {
+                    return f64::NAN;
+                }
+                let xs = buffer.make_contiguous();
+                match op {
+                    WindowOp::RollingMean(_) => mean(xs),
+                    WindowOp::RollingSum(_) => xs.iter().sum(),
+                    _ => covariance(xs, xs).map_or(f64::NAN, |v| v.sqrt()),
+                }
+            },
+            (WindowState::Last(last), WindowOp::Diff) => {
+                let value = last.map_or(f64::NAN, |prev| x - prev);
+                *last = Some(x);
+                value
+            },
+            (WindowState::Last(last), WindowOp::Ewma(span)) => {
+                let alpha = 2.0 / (span as f64 + 1.0);
+                let value = last.map_or(x, |prev| alpha * x + (1.0 - alpha) * prev);
+                *last = Some(value);
+                value
+            },
+            _ => unreachable!("State is created for the operation"),
+        }
+    }
+}
+
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::UnknownColumn(name) => write!(f, "Unknown column: {}", name),
+        }
+    }
+}
+
+impl Error for ExprError {}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeSeries;
+
+    fn frame() -> TimeSeriesFrame {
+        TimeSeriesFrame::new(vec![1, 2, 3, 4, 5])
+            .with_column("a", vec![1.0, 2.0, 4.0, 3.0, 6.0])
+            .with_column("b", vec![2.0, 2.0, 1.0, 5.0, 4.0])
+    }
+
+    #[test]
+    fn test_same_as_series() {
+        let frame = frame();
+        let a = frame.column("a").unwrap();
+        let output = frame.evaluate(&[
+            ("mean", col("a").rolling_mean(3)),
+            ("std", col("a").rolling_std(3)),
+            ("ewma", col("a").ewma(2)),
+            ("diff", col("a").diff()),
+            ("lag", col("a").lag(2)),
+        ]).unwrap();
+        assert_eq!(output.columns[0][2..], a.rolling_mean(3).values[2..]);
+        assert_eq!(output.columns[1][2..], a.rolling_std(3).values[2..]);
+        assert_eq!(output.columns[2], a.ewma(2).values);
+        assert_eq!(output.columns[3][1..], a.diff().values[..]);
+        assert_eq!(output.columns[4][2..], [1.0, 2.0, 4.0]);
+        assert!(output.columns[4][1].is_nan());
+    }
+
+    #[test]
+    fn test_common_subexpressions() {
+        let ratio = (col("a") + col("b")) / col("b");
+        let plan = Plan::new(&[("x", ratio.clone().rolling_sum(2)), ("y", (col("b") + col("a")) / col("b"))]);
+        // a, b, a + b, ratio, rolling_sum
+        assert_eq!(plan.len(), 5);
+        assert_eq!(plan.passes(), 1);
+        let output = plan.evaluate(&frame()).unwrap();
+        assert_eq!(output.columns[1], vec![1.5, 2.0, 5.0, 1.6, 2.5]);
+        assert_eq!(output.columns[0][1..], [1.5 + 2.0, 2.0 + 5.0, 5.0 + 1.6, 1.6 + 2.5]);
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let plan = Plan::new(&[("x", col("a") * (lit(2.0) + lit(3.0)).sqrt())]);
+        assert_eq!(plan.len(), 3);
+        let output = plan.evaluate(&frame()).unwrap();
+        assert_eq!(output.columns[0][0], 5f64.sqrt());
+    }
+
+    #[test]
+    fn test_nested_reductions() {
+        let a = col("a");
+        let centered = a.clone() - a.clone().mean();
+        let zscore = centered.clone() / (centered.clone() * centered).mean().sqrt();
+        let plan = Plan::new(&[("z", zscore), ("total", col("b").sum()), ("a", col("a"))]);
+        assert_eq!(plan.passes(), 3);
+        let output = plan.evaluate(&frame()).unwrap();
+        let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.0, 4.0, 3.0, 6.0]);
+        let std = (ts.values.iter().map(|v| (v - 3.2) * (v - 3.2)).sum::<f64>() / 5.0).sqrt();
+        assert!((output.columns[0][4] - (6.0 - 3.2) / std).abs() < 1e-12);
+        assert_eq!(output.columns[1], vec![14.0; 5]);
+        assert_eq!(output.columns[2], ts.values);
+    }
+
+    #[test]
+    fn test_errors_and_empty() {
+        assert_eq!(frame().evaluate(&[("x", col("c"))]), Err(ExprError::UnknownColumn("c".to_owned())));
+        let output = TimeSeriesFrame::new(vec![]).with_column("a", vec![]).evaluate(&[("x", col("a").sum())]).unwrap();
+        assert!(output.columns[0].is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+//! Values at timestamps outside of the series range
+//!
+
+use crate::TimeSeries;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Policy used when requested timestamp is before the first or after the last data point
+///   * Error - Value is not available
+///   * NearestValue - Value of the first or the last data point
+///   * Zero - 0.0
+///   * NaN - f64::NAN
+///   * Linear - Continue the line going through the first (or last) 2 data points.
+///     If they have the same timestamp, the nearest value is used.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Extrapolation {
+    Error,
+    NearestValue,
+    #[default]
+    Zero,
+    NaN,
+    Linear,
+}
+
+impl TimeSeries {
+
+    /// Return value at the given timestamp.
+    /// Inside the series range it is the value of the last data point not after the timestamp.
+    /// Outside of the range the value is given by extrapolation policy.
+    /// Returns None if the value can't be calculated (Error policy or empty series).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::extrapolation::Extrapolation;
+    ///
+    /// let ts = TimeSeries::new(vec![100, 200], vec![1.0, 2.0]);
+    /// assert_eq!(ts.at_with(150, Extrapolation::Error), Some(1.0));
+    /// assert_eq!(ts.at_with(300, Extrapolation::Error), None);
+    /// assert_eq!(ts.at_with(300, Extrapolation::NearestValue), Some(2.0));
+    /// assert_eq!(ts.at_with(300, Extrapolation::Linear), Some(3.0));
+    /// ```
+    pub fn at_with(&self, timestamp: i64, policy: Extrapolation) -> Option<f64> {
+        let pos = self.index.values.partition_point(|&ts| ts <= timestamp);
+        if pos > 0 && (pos < self.len() || self.index[pos-1] == timestamp) {
+            return Some(self.values[pos-1]);
+        }
+        match policy {
+            Extrapolation::Error => None,
+            Extrapolation::Zero => Some(0.0),
+            Extrapolation::NaN => Some(f64::NAN),
+            Extrapolation::NearestValue => self.nearest_edge(pos),
+            Extrapolation::Linear => {
+                if self.len() < 2 {
+                    return self.nearest_edge(pos);
+                }
+                let (a, b) = if pos == 0 { (0, 1) } else { (self.len()-2, self.len()-1) };
+                if self.index[a] == self.index[b] {
+                    return self.nearest_edge(pos);
+                }
+                let slope = (self.values[b] - self.values[a]) / (self.index[b] - self.index[a]) as f64;
+                Some(self.values[a] + slope * (timestamp - self.index[a]) as f64)
+            }
+        }
+    }
+
+    /// Create new series with values at the given timestamps.
+    /// Values are calculated with the `at_with` function.
+    /// Returns None if any of the values can't be calculated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::extrapolation::Extrapolation;
+    ///
+    /// let ts = TimeSeries::new(vec![100, 200], vec![1.0, 2.0]);
+    /// let sampled = ts.sample_at(&[50, 150, 250], Extrapolation::NearestValue).unwrap();
+    /// assert_eq!(sampled, TimeSeries::new(vec![50, 150, 250], vec![1.0, 1.0, 2.0]));
+    /// assert!(ts.sample_at(&[50, 150], Extrapolation::Error).is_none());
+    /// ```
+    pub fn sample_at(&self, timestamps: &[i64], policy: Extrapolation) -> Option<TimeSeries> {
+        let values = timestamps.iter()
+            .map(|&ts| self.at_with(ts, policy))
+            .collect::<Option<Vec<f64>>>()?;
+        Some(self.derived(timestamps.to_vec(), values)
+            .logged(self, "sample_at", || vec![("timestamps", timestamps.len().to_string()), ("policy", format!("{:?}", policy))]))
+    }
+
+    fn nearest_edge(&self, pos: usize) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else if pos == 0 {
+            Some(self.values[0])
+        } else {
+            Some(self.values[self.len()-1])
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_before_first() {
+        let ts = TimeSeries::new(vec![100, 200], vec![1.0, 3.0]);
+        assert_eq!(ts.at_with(0, Extrapolation::Error), None);
+        assert_eq!(ts.at_with(0, Extrapolation::Zero), Some(0.0));
+        assert_eq!(ts.at_with(0, Extrapolation::NearestValue), Some(1.0));
+        assert_eq!(ts.at_with(0, Extrapolation::Linear), Some(-1.0));
+        assert!(ts.at_with(0, Extrapolation::NaN).unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_at_last_point() {
+        let ts = TimeSeries::new(vec![100, 200], vec![1.0, 3.0]);
+        assert_eq!(ts.at_with(200, Extrapolation::Error), Some(3.0));
+        assert_eq!(ts.at_with(201, Extrapolation::Error), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let ts = TimeSeries::empty();
+        assert_eq!(ts.at_with(0, Extrapolation::Zero), Some(0.0));
+        assert_eq!(ts.at_with(0, Extrapolation::NearestValue), None);
+        assert_eq!(ts.at_with(0, Extrapolation::Linear), None);
+    }
+
+    #[test]
+    fn test_linear_single_point() {
+        let ts = TimeSeries::new(vec![100], vec![5.0]);
+        assert_eq!(ts.at_with(300, Extrapolation::Linear), Some(5.0));
+    }
+
+    #[test]
+    fn test_linear_same_timestamps() {
+        let ts = TimeSeries::new(vec![100, 200, 200], vec![1.0, 2.0, 4.0]);
+        assert_eq!(ts.at_with(300, Extrapolation::Linear), Some(4.0));
+        let ts = TimeSeries::new(vec![100, 100, 200], vec![1.0, 2.0, 4.0]);
+        assert_eq!(ts.at_with(0, Extrapolation::Linear), Some(1.0));
+    }
+
+    #[test]
+    fn test_at_after_last() {
+        let ts = TimeSeries::new(vec![100, 200], vec![1.0, 3.0]);
+        assert_eq!(ts.at(0), 0.0);
+        assert_eq!(ts.at(500), 3.0);
+    }
+}
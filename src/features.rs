@@ -0,0 +1,192 @@
+//! Feature extraction for machine learning
+//!
+//! Set of standard features (similar to tsfresh) calculated from series values.
+//! Every series gets the same feature names, so the maps can be turned into rows of a feature matrix.
+//! Features which are not defined for the series (e.g. std of a single value) are NaN.
+//!
+
+use std::collections::BTreeMap;
+
+use crate::TimeSeries;
+use crate::stats::{mean, quantile};
+
+
+/// Lags used for autocorrelation features
+const LAGS: [usize; 5] = [1, 2, 3, 5, 10];
+/// Number of bins used to calculate entropy
+const ENTROPY_BINS: usize = 10;
+
+
+impl TimeSeries {
+
+    /// Calculate standard features of the series values:
+    ///   * length, sum, mean, median, min, max, quantile_25, quantile_75
+    ///   * std, variance (sample), coefficient_of_variation, skewness, kurtosis (excess)
+    ///   * energy (sum of squares), root_mean_square
+    ///   * abs_sum_of_changes, mean_abs_change, mean_change
+    ///   * zero_crossings, mean_crossings, peaks_count (points larger than both neighbours)
+    ///   * count_above_mean, count_below_mean, longest_strike_above_mean, longest_strike_below_mean
+    ///   * first_location_of_max, first_location_of_min (relative position between 0 and 1)
+    ///   * autocorrelation_lag_1, 2, 3, 5 and 10
+    ///   * entropy (Shannon entropy of values in 10 equal width bins)
+    ///   * trend_slope (change per time unit), trend_r2
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1, 2, 3, 4], vec![1.0, -1.0, 2.0, -2.0, 0.0]);
+    /// let features = ts.features();
+    /// assert_eq!(features["mean"], 0.0);
+    /// assert_eq!(features["energy"], 10.0);
+    /// assert_eq!(features["zero_crossings"], 3.0);
+    /// assert_eq!(features["peaks_count"], 1.0);
+    /// assert!(TimeSeries::empty().features()["std"].is_nan());
+    /// ```
+    pub fn features(&self) -> BTreeMap<&'static str, f64> {
+        let xs = &self.values;
+        let n = xs.len();
+        let m = if n == 0 { f64::NAN } else { mean(xs) };
+        let std = self.std().unwrap_or(f64::NAN);
+        let changes: Vec<f64> = xs.windows(2).map(|w| w[1] - w[0]).collect();
+        let mut sorted = xs.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let trend = self.linear_trend();
+
+        let mut features = BTreeMap::new();
+        features.insert("length", n as f64);
+        features.insert("sum", xs.iter().sum());
+        features.insert("mean", m);
+        features.insert("median", quantile(&sorted, 0.5));
+        features.insert("min", self.min().unwrap_or(f64::NAN));
+        features.insert("max", self.max().unwrap_or(f64::NAN));
+        features.insert("quantile_25", quantile(&sorted, 0.25));
+        features.insert("quantile_75", quantile(&sorted, 0.75));
+        features.insert("std", std);
+        features.insert("variance", std * std);
+        features.insert("coefficient_of_variation", std / m);
+        features.insert("skewness", central_moment(xs, m, 3) / central_moment(xs, m, 2).powf(1.5));
+        features.insert("kurtosis", central_moment(xs, m, 4) / central_moment(xs, m, 2).powi(2) - 3.0);
+        let energy: f64 = xs.iter().map(|x| x * x).sum();
+        features.insert("energy", energy);
+        features.insert("root_mean_square", (energy / n as f64).sqrt());
+        features.insert("abs_sum_of_changes", changes.iter().map(|c| c.abs()).sum());
+        features.insert("mean_abs_change", mean_or_nan(&changes.iter().map(|c| c.abs()).collect::<Vec<f64>>()));
+        features.insert("mean_change", mean_or_nan(&changes));
+        features.insert("zero_crossings", crossings(xs, 0.0) as f64);
+        features.insert("mean_crossings", crossings(xs, m) as f64);
+        features.insert("peaks_count", xs.windows(3).filter(|w| w[1] > w[0] && w[1] > w[2]).count() as f64);
+        features.insert("count_above_mean", xs.iter().filter(|&&x| x > m).count() as f64);
+        features.insert("count_below_mean", xs.iter().filter(|&&x| x < m).count() as f64);
+        features.insert("longest_strike_above_mean", longest_strike(xs, |x| x > m) as f64);
+        features.insert("longest_strike_below_mean", longest_strike(xs, |x| x < m) as f64);
+        features.insert("first_location_of_max", first_location(xs, |x, best| x > best));
+        features.insert("first_location_of_min", first_location(xs, |x, best| x < best));
+        for (&lag, name) in LAGS.iter().zip(&["autocorrelation_lag_1", "autocorrelation_lag_2", "autocorrelation_lag_3",
+                                               "autocorrelation_lag_5", "autocorrelation_lag_10"]) {
+            features.insert(name, autocorrelation(xs, m, lag));
+        }
+        features.insert("entropy", self.entropy());
+        features.insert("trend_slope", trend.map(|t| t.slope).unwrap_or(f64::NAN));
+        features.insert("trend_r2", trend.map(|t| t.r2).unwrap_or(f64::NAN));
+        features
+    }
+
+    /// Shannon entropy (in nats) of the values distribution in equal width bins
+    fn entropy(&self) -> f64 {
+        let hist = self.histogram(ENTROPY_BINS);
+        let total: usize = hist.counts.iter().sum();
+        if total == 0 {
+            return f64::NAN;
+        }
+        hist.counts.iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| c as f64 / total as f64)
+            .map(|p| -p * p.ln())
+            .sum()
+    }
+}
+
+
+fn mean_or_nan(xs: &[f64]) -> f64 {
+    if xs.is_empty() { f64::NAN } else { mean(xs) }
+}
+
+fn central_moment(xs: &[f64], m: f64, k: i32) -> f64 {
+    xs.iter().map(|x| (x - m).powi(k)).sum::<f64>() / xs.len() as f64
+}
+
+/// Number of times consecutive values are on the opposite sides of the level
+fn crossings(xs: &[f64], level: f64) -> usize {
+    let sides: Vec<bool> = xs.iter().filter(|&&x| x != level).map(|&x| x > level).collect();
+    sides.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+fn longest_strike<F: Fn(f64) -> bool>(xs: &[f64], predicate: F) -> usize {
+    xs.iter()
+        .fold((0, 0), |(longest, current), &x| {
+            let current = if predicate(x) { current + 1 } else { 0 };
+            (longest.max(current), current)
+        })
+        .0
+}
+
+/// Relative position of the first value which is better than all previous ones
+fn first_location<F: Fn(f64, f64) -> bool>(xs: &[f64], better: F) -> f64 {
+    if xs.is_empty() {
+        return f64::NAN;
+    }
+    let pos = (1..xs.len()).fold(0, |best, i| if better(xs[i], xs[best]) { i } else { best });
+    pos as f64 / xs.len() as f64
+}
+
+fn autocorrelation(xs: &[f64], m: f64, lag: usize) -> f64 {
+    if xs.len() <= lag {
+        return f64::NAN;
+    }
+    let variance = central_moment(xs, m, 2);
+    let sum: f64 = xs.iter().zip(&xs[lag..]).map(|(x, y)| (x - m) * (y - m)).sum();
+    sum / ((xs.len() - lag) as f64 * variance)
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_same_names() {
+        let empty = TimeSeries::empty().features();
+        let ts = TimeSeries::new((0..20).collect(), (0..20).map(|i| (i as f64).sin()).collect());
+        let features = ts.features();
+        assert_eq!(empty.keys().collect::<Vec<_>>(), features.keys().collect::<Vec<_>>());
+        assert!(features.len() >= 30);
+        assert!(features.values().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn test_distribution() {
+        let ts = TimeSeries::new(vec![0, 1, 2, 3], vec![1.0, 2.0, 3.0, 4.0]);
+        let features = ts.features();
+        assert_eq!(features["median"], 2.5);
+        assert_eq!(features["quantile_25"], 1.75);
+        assert_eq!(features["skewness"], 0.0);
+        assert_eq!(features["first_location_of_max"], 0.75);
+        assert_eq!(features["longest_strike_above_mean"], 2.0);
+        assert_approx_eq!(features["trend_slope"], 1.0);
+        assert_approx_eq!(features["entropy"], 4f64.ln());
+    }
+
+    #[test]
+    fn test_autocorrelation() {
+        let xs = [1.0, -1.0, 1.0, -1.0, 1.0, -1.0];
+        assert_approx_eq!(autocorrelation(&xs, 0.0, 1), -1.0);
+        assert_approx_eq!(autocorrelation(&xs, 0.0, 2), 1.0);
+        assert!(autocorrelation(&xs, 0.0, 6).is_nan());
+    }
+}
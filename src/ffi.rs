@@ -0,0 +1,225 @@
+//! C ABI for embedding the library in other languages.
+//!
+//! Enabled with the `ffi` feature. The C header is generated by cbindgen into `timeseries.h`
+//! in the build script output directory (`OUT_DIR`), so the source tree is not changed by the build.
+//! To get a shared or static library and the header in `include/` run:
+//!
+//! ```sh
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! cbindgen --config cbindgen.toml --output include/timeseries.h
+//! ```
+//!
+//! Series are passed as opaque pointers. Every pointer returned by `ts_new`, `ts_read_csv`
+//! or `ts_resample` has to be released with `ts_free`.
+//! Functions returning statistics return NaN if the value can't be calculated.
+//! Quality codes (see `quality` module) are exported as `TS_QUALITY_*` constants.
+//!
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+use crate::TimeSeries;
+use crate::io::csv;
+use crate::resample::Aggregation;
+
+
+/// Aggregation codes for `ts_resample`
+pub const TS_AGGREGATION_MEAN: u32 = 0;
+pub const TS_AGGREGATION_SUM: u32 = 1;
+pub const TS_AGGREGATION_MIN: u32 = 2;
+pub const TS_AGGREGATION_MAX: u32 = 3;
+pub const TS_AGGREGATION_FIRST: u32 = 4;
+pub const TS_AGGREGATION_LAST: u32 = 5;
+pub const TS_AGGREGATION_COUNT: u32 = 6;
+
+/// Create series from index and values arrays of the given length.
+/// Returns NULL if any of the arrays is NULL.
+///
+/// # Safety
+/// `index` and `values` have to point to at least `len` elements.
+#[no_mangle]
+pub unsafe extern "C" fn ts_new(index: *const i64, values: *const f64, len: usize) -> *mut TimeSeries {
+    if index.is_null() || values.is_null() {
+        return ptr::null_mut();
+    }
+    let index = slice::from_raw_parts(index, len).to_vec();
+    let values = slice::from_raw_parts(values, len).to_vec();
+    Box::into_raw(Box::new(TimeSeries::new(index, values)))
+}
+
+/// Load series from CSV file. Returns NULL if the file can't be read.
+///
+/// # Safety
+/// `file_path` has to be a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ts_read_csv(file_path: *const c_char) -> *mut TimeSeries {
+    if file_path.is_null() {
+        return ptr::null_mut();
+    }
+    let file_path = match CStr::from_ptr(file_path).to_str() {
+        Ok(path) => path,
+        Err(_) => return ptr::null_mut(),
+    };
+    match csv::read_from_file(file_path) {
+        Ok(ts) => Box::into_raw(Box::new(ts)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Resample series into buckets of the given number of milliseconds (see `TimeSeries::resample`).
+/// Aggregation is one of the `TS_AGGREGATION_*` codes.
+/// Returns NULL if the frequency is not positive or the aggregation code is unknown.
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_resample(ts: *const TimeSeries, frequency: i64, aggregation: u32) -> *mut TimeSeries {
+    let aggregation = match aggregation {
+        TS_AGGREGATION_MEAN => Aggregation::Mean,
+        TS_AGGREGATION_SUM => Aggregation::Sum,
+        TS_AGGREGATION_MIN => Aggregation::Min,
+        TS_AGGREGATION_MAX => Aggregation::Max,
+        TS_AGGREGATION_FIRST => Aggregation::First,
+        TS_AGGREGATION_LAST => Aggregation::Last,
+        TS_AGGREGATION_COUNT => Aggregation::Count,
+        _ => return ptr::null_mut(),
+    };
+    if frequency <= 0 {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new((*ts).resample(frequency, aggregation)))
+}
+
+/// Release series created by this library. NULL is ignored.
+///
+/// # Safety
+/// `ts` has to be created by this library and can't be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn ts_free(ts: *mut TimeSeries) {
+    if !ts.is_null() {
+        drop(Box::from_raw(ts));
+    }
+}
+
+/// Number of data points
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_len(ts: *const TimeSeries) -> usize {
+    (*ts).len()
+}
+
+/// Pointer to `ts_len` timestamps. Valid until the series is released.
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_index(ts: *const TimeSeries) -> *const i64 {
+    (*ts).index.values.as_ptr()
+}
+
+/// Pointer to `ts_len` values. Valid until the series is released.
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_values(ts: *const TimeSeries) -> *const f64 {
+    (*ts).values.as_ptr()
+}
+
+/// Mean value
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_mean(ts: *const TimeSeries) -> f64 {
+    (*ts).mean().unwrap_or(f64::NAN)
+}
+
+/// Sample standard deviation
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_std(ts: *const TimeSeries) -> f64 {
+    (*ts).std().unwrap_or(f64::NAN)
+}
+
+/// Minimum value
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_min(ts: *const TimeSeries) -> f64 {
+    (*ts).min().unwrap_or(f64::NAN)
+}
+
+/// Maximum value
+///
+/// # Safety
+/// `ts` has to be a valid series pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ts_max(ts: *const TimeSeries) -> f64 {
+    (*ts).max().unwrap_or(f64::NAN)
+}
+
+/// Pearson correlation between 2 series
+///
+/// # Safety
+/// `ts` and `other` have to be valid series pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ts_corr(ts: *const TimeSeries, other: *const TimeSeries) -> f64 {
+    (*ts).corr(&*other).unwrap_or(f64::NAN)
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_create_and_free() {
+        let index = [1, 2, 3];
+        let values = [1.0, 2.0, 6.0];
+        unsafe {
+            let ts = ts_new(index.as_ptr(), values.as_ptr(), 3);
+            assert_eq!(ts_len(ts), 3);
+            assert_eq!(*ts_values(ts).offset(2), 6.0);
+            assert_eq!(ts_mean(ts), 3.0);
+            assert_eq!(ts_max(ts), 6.0);
+            ts_free(ts);
+        }
+    }
+
+    #[test]
+    fn test_resample() {
+        let index = [0, 5, 10, 15, 25];
+        let values = [1.0, 3.0, 2.0, 4.0, 5.0];
+        unsafe {
+            let ts = ts_new(index.as_ptr(), values.as_ptr(), 5);
+            let resampled = ts_resample(ts, 10, TS_AGGREGATION_SUM);
+            assert_eq!(ts_len(resampled), 3);
+            assert_eq!(*ts_index(resampled).offset(1), 10);
+            assert_eq!(*ts_values(resampled).offset(1), 6.0);
+            assert!(ts_resample(ts, 0, TS_AGGREGATION_SUM).is_null());
+            assert!(ts_resample(ts, 10, 100).is_null());
+            ts_free(resampled);
+            ts_free(ts);
+        }
+    }
+
+    #[test]
+    fn test_read_missing_file() {
+        let path = CString::new("testdata/missing.csv").unwrap();
+        unsafe {
+            assert!(ts_read_csv(path.as_ptr()).is_null());
+        }
+    }
+}
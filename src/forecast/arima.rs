@@ -0,0 +1,293 @@
+//! ARIMA(p, d, q) model
+//!
+//! Series is differenced d times and ARMA(p, q) model is fitted to the result:
+//! w(t) - mean = sum(ar(i) * (w(t-i) - mean)) + e(t) + sum(ma(j) * e(t-j))
+//!
+//! Coefficients are estimated by minimizing conditional sum of squares (CSS) of the residuals,
+//! starting from Hannan-Rissanen regression estimates. Mean is estimated only when d = 0.
+//!
+
+use crate::TimeSeries;
+use crate::forecast::{Forecaster, Horizon, Prediction, minimize};
+use crate::kernels::diff;
+use crate::linalg::{Matrix, least_squares};
+use crate::stats::{inverse_normal_cdf, mean};
+
+
+/// Limit of optimizer steps
+const MAX_ITERATIONS: usize = 2000;
+
+
+/// ARIMA model orders. As a forecaster it fits the model to each history
+/// and predicts with 95% prediction interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Order {
+    pub p: usize,
+    pub d: usize,
+    pub q: usize,
+}
+
+/// Fitted ARIMA model
+///   * p, d, q - Model orders
+///   * ar - Autoregressive coefficients (p values)
+///   * ma - Moving average coefficients (q values)
+///   * mean - Mean of the differenced series (0 if d > 0)
+///   * sigma2 - Variance of the residuals
+#[derive(Clone, Debug)]
+pub struct Arima {
+    pub p: usize,
+    pub d: usize,
+    pub q: usize,
+    pub ar: Vec<f64>,
+    pub ma: Vec<f64>,
+    pub mean: f64,
+    pub sigma2: f64,
+    residuals: TimeSeries,
+    /// Series differenced 0..=d times
+    levels: Vec<Vec<f64>>,
+    horizon: Horizon,
+}
+
+
+impl Arima {
+
+    /// Fit model to the series.
+    /// Returns None if the series has NaN values or it is too short for the model orders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::{Rng, SeedableRng};
+    /// use rand::rngs::StdRng;
+    /// use timeseries::TimeSeries;
+    /// use timeseries::forecast::arima::Arima;
+    ///
+    /// // AR(1) process: x(t) = 0.6 * x(t-1) + e(t)
+    /// let mut rng = StdRng::seed_from_u64(1);
+    /// let mut values = vec![0.0];
+    /// for i in 1..1000 {
+    ///     values.push(0.6 * values[i - 1] + rng.gen::<f64>() - 0.5);
+    /// }
+    /// let ts = TimeSeries::new((0..1000).collect(), values);
+    /// let model = Arima::fit(&ts, 1, 0, 0).unwrap();
+    /// assert!((model.ar[0] - 0.6).abs() < 0.1);
+    /// let prediction = model.predict(3);
+    /// assert_eq!(prediction.mean.index.values, vec![1000, 1001, 1002]);
+    /// assert!(prediction.lower.values[0] < prediction.mean.values[0]);
+    /// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(ts), fields(len = ts.len())))]
+    pub fn fit(ts: &TimeSeries, p: usize, d: usize, q: usize) -> Option<Arima> {
+        if ts.values.iter().any(|v| v.is_nan()) {
+            return None;
+        }
+        let mut levels = vec![ts.values.clone()];
+        for level in 0..d {
+            levels.push(diff(&levels[level]));
+        }
+        let w = &levels[d];
+        let n_params = p + q + if d == 0 { 1 } else { 0 };
+        if w.len() <= p + n_params {
+            return None;
+        }
+        let m = if d == 0 { mean(w) } else { 0.0 };
+        let centered: Vec<f64> = w.iter().map(|v| v - m).collect();
+        let start = initial_params(&centered, p, q);
+        let params = minimize(|params| css(&centered, p, params).iter().map(|e| e * e).sum(), start, MAX_ITERATIONS);
+        let errors = css(&centered, p, &params);
+        let sigma2 = errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64;
+        let mut residuals = TimeSeries::new(ts.index.values[d + p..].to_vec(), errors);
+        residuals.index.resolution = ts.index.resolution;
+        Some(Arima {
+            p, d, q,
+            ar: params[..p].to_vec(),
+            ma: params[p..].to_vec(),
+            mean: m,
+            sigma2,
+            residuals,
+            levels,
+            horizon: Horizon::of(ts),
+        })
+    }
+
+    /// Residuals of the fitted model. The first d + p data points have no residuals.
+    pub fn residuals(&self) -> &TimeSeries {
+        &self.residuals
+    }
+
+    /// Forecast h data points with 95% prediction interval
+    pub fn predict(&self, h: usize) -> Prediction {
+        self.predict_interval(h, 0.95)
+    }
+
+    /// Forecast h data points with prediction interval at the given confidence level (between 0 and 1).
+    /// Interval assumes normally distributed residuals.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::forecast::arima::Arima;
+    ///
+    /// let ts = TimeSeries::new((0..10).collect(), vec![1.0, 2.0, 3.0, 4.5, 5.0, 6.0, 7.5, 8.0, 9.0, 10.0]);
+    /// let model = Arima::fit(&ts, 0, 1, 0).unwrap();
+    /// let narrow = model.predict_interval(2, 0.5);
+    /// let wide = model.predict_interval(2, 0.99);
+    /// assert_eq!(narrow.mean.values, vec![10.0, 10.0]);
+    /// assert!(wide.upper.values[1] > narrow.upper.values[1]);
+    /// assert!(narrow.upper.values[1] > narrow.upper.values[0]);
+    /// ```
+    pub fn predict_interval(&self, h: usize, level: f64) -> Prediction {
+        let w = &self.levels[self.d];
+        let mut history: Vec<f64> = w.iter().map(|v| v - self.mean).collect();
+        let mut errors: Vec<f64> = vec![0.0; self.p];
+        errors.extend(&self.residuals.values);
+        let n = history.len();
+        for k in 0..h {
+            let t = n + k;
+            let ar: f64 = self.ar.iter().enumerate().map(|(i, a)| a * history[t - i - 1]).sum();
+            let ma: f64 = self.ma.iter().enumerate().map(|(j, b)| b * errors[t - j - 1]).sum();
+            history.push(ar + ma);
+            errors.push(0.0);
+        }
+        let mut forecast: Vec<f64> = history[n..].iter().map(|v| v + self.mean).collect();
+        // Undo differencing starting from the most differenced level
+        for level in (0..self.d).rev() {
+            let mut last = self.levels[level].last().cloned().unwrap_or(0.0);
+            forecast = forecast.iter().map(|x| { last += x; last }).collect();
+        }
+
+        let level = level.clamp(0.0, 1.0);
+        let z = inverse_normal_cdf(0.5 + level / 2.0);
+        let mut variance = 0.0;
+        let widths: Vec<f64> = self.psi_weights(h).iter().map(|psi| {
+            variance += self.sigma2 * psi * psi;
+            z * variance.sqrt()
+        }).collect();
+        let lower = forecast.iter().zip(&widths).map(|(f, w)| f - w).collect();
+        let upper = forecast.iter().zip(&widths).map(|(f, w)| f + w).collect();
+        Prediction {
+            mean: self.horizon.series(forecast),
+            lower: self.horizon.series(lower),
+            upper: self.horizon.series(upper),
+            level,
+        }
+    }
+
+    /// Weights of past shocks in the forecast (MA infinity representation of the whole model)
+    fn psi_weights(&self, h: usize) -> Vec<f64> {
+        // AR polynomial multiplied by (1 - B)^d
+        let mut poly: Vec<f64> = std::iter::once(1.0).chain(self.ar.iter().map(|a| -a)).collect();
+        for _ in 0..self.d {
+            let mut next = poly.clone();
+            next.push(0.0);
+            for i in 1..next.len() {
+                next[i] -= poly[i - 1];
+            }
+            poly = next;
+        }
+        let mut psi = vec![1.0];
+        for j in 1..h {
+            let ma = self.ma.get(j - 1).cloned().unwrap_or(0.0);
+            let ar: f64 = (1..poly.len().min(j + 1)).map(|i| -poly[i] * psi[j - i]).sum();
+            psi.push(ma + ar);
+        }
+        psi.truncate(h);
+        psi
+    }
+}
+
+
+impl Forecaster for Order {
+    fn forecast(&self, history: &TimeSeries, h: usize) -> Option<Prediction> {
+        Arima::fit(history, self.p, self.d, self.q).map(|model| model.predict(h))
+    }
+}
+
+
+/// Residuals for the coefficients (p AR followed by MA). Residuals before the first p values are 0.
+fn css(w: &[f64], p: usize, params: &[f64]) -> Vec<f64> {
+    let (ar, ma) = params.split_at(p);
+    let mut errors = vec![0.0; w.len()];
+    for t in p..w.len() {
+        let fitted: f64 = ar.iter().enumerate().map(|(i, a)| a * w[t - i - 1]).sum::<f64>()
+            + ma.iter().enumerate().filter(|(j, _)| t > *j).map(|(j, b)| b * errors[t - j - 1]).sum::<f64>();
+        errors[t] = w[t] - fitted;
+    }
+    errors.split_off(p)
+}
+
+/// Hannan-Rissanen estimates: residuals of long AR model are used as lagged errors in linear regression
+fn initial_params(w: &[f64], p: usize, q: usize) -> Vec<f64> {
+    let zeros = vec![0.0; p + q];
+    if p + q == 0 {
+        return zeros;
+    }
+    let long = if q == 0 { 0 } else { (p + q + 1).max((w.len() as f64).ln() as usize * 2).min(w.len() / 3) };
+    let errors = if q == 0 {
+        vec![0.0; w.len()]
+    } else {
+        match regress(w, long, |t, i| w[t - i - 1], long) {
+            Some(phi) => {
+                let mut errors = vec![0.0; w.len()];
+                for t in long..w.len() {
+                    errors[t] = w[t] - phi.iter().enumerate().map(|(i, a)| a * w[t - i - 1]).sum::<f64>();
+                }
+                errors
+            },
+            None => return zeros,
+        }
+    };
+    let start = long + p.max(q);
+    regress(w, p + q, |t, i| if i < p { w[t - i - 1] } else { errors[t - (i - p) - 1] }, start).unwrap_or(zeros)
+}
+
+/// Least squares regression of w(t) for t >= start on k regressors
+fn regress<F: Fn(usize, usize) -> f64>(w: &[f64], k: usize, regressor: F, start: usize) -> Option<Vec<f64>> {
+    if k == 0 || w.len() <= start + k {
+        return None;
+    }
+    let rows: Matrix = (start..w.len()).map(|t| (0..k).map(|i| regressor(t, i)).collect()).collect();
+    least_squares(&rows, &w[start..])
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::generate;
+
+    #[test]
+    fn test_ma1() {
+        let e = generate::white_noise((0..2000).collect(), 1.0, 1).values;
+        let values: Vec<f64> = (0..e.len()).map(|t| 5.0 + e[t] + if t > 0 { 0.5 * e[t - 1] } else { 0.0 }).collect();
+        let ts = TimeSeries::new((0..values.len() as i64).collect(), values);
+        let model = Arima::fit(&ts, 0, 0, 1).unwrap();
+        assert_approx_eq!(model.ma[0], 0.5, 0.1);
+        assert_approx_eq!(model.mean, 5.0, 0.1);
+        assert_approx_eq!(model.sigma2, 1.0, 0.1);
+        assert_eq!(model.residuals().len(), 2000);
+    }
+
+    #[test]
+    fn test_random_walk_interval() {
+        let ts = generate::random_walk((0..500).collect(), 1.0, 2);
+        let model = Arima::fit(&ts, 0, 1, 0).unwrap();
+        assert_eq!(model.psi_weights(3), vec![1.0, 1.0, 1.0]);
+        let prediction = model.predict(4);
+        assert_eq!(prediction.mean.values, vec![ts.values[499]; 4]);
+        let width = |k: usize| prediction.upper.values[k] - prediction.mean.values[k];
+        assert_approx_eq!(width(3) / width(0), 2.0);
+        assert_eq!(model.residuals().len(), 499);
+    }
+
+    #[test]
+    fn test_too_short() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        assert!(Arima::fit(&ts, 2, 1, 0).is_none());
+        assert!(Arima::fit(&TimeSeries::new(vec![1, 2], vec![1.0, f64::NAN]), 0, 0, 0).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+//! Forecasting models
+//!
+//! Models are fitted to the series values and forecast the next data points.
+//! Timestamps of forecasted data points continue the series with its typical (median) time step.
+//!
+
+pub mod arima;
+
+use crate::TimeSeries;
+use crate::index::Resolution;
+
+
+/// Forecast with prediction interval
+///   * mean - Point forecast
+///   * lower, upper - Bounds of the prediction interval
+///   * level - Confidence level of the interval (between 0 and 1)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prediction {
+    pub mean: TimeSeries,
+    pub lower: TimeSeries,
+    pub upper: TimeSeries,
+    pub level: f64,
+}
+
+/// Model which can be fitted to the history and forecast the next h data points.
+/// Returns None if the model can't be fitted to the history.
+/// Implemented for closures, so any model can be evaluated with the `backtest` module.
+pub trait Forecaster {
+    fn forecast(&self, history: &TimeSeries, h: usize) -> Option<Prediction>;
+}
+
+impl<F> Forecaster for F
+where
+    F: Fn(&TimeSeries, usize) -> Option<Prediction> {
+
+    fn forecast(&self, history: &TimeSeries, h: usize) -> Option<Prediction> {
+        self(history, h)
+    }
+}
+
+
+/// Continuation of the series index for forecasted data points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Horizon {
+    last: i64,
+    step: i64,
+    resolution: Resolution,
+}
+
+impl Horizon {
+
+    pub(crate) fn of(ts: &TimeSeries) -> Horizon {
+        let step = ts.index.sampling_report().map_or(1, |r| r.median_delta).max(1);
+        Horizon { last: ts.index.last().unwrap_or(0), step, resolution: ts.index.resolution }
+    }
+
+    /// Series with the values placed at the next timestamps
+    pub(crate) fn series(&self, values: Vec<f64>) -> TimeSeries {
+        let index = (1..=values.len() as i64).map(|k| self.last + k * self.step).collect();
+        let mut ts = TimeSeries::new(index, values);
+        ts.index.resolution = self.resolution;
+        ts
+    }
+}
+
+/// Minimize function with Nelder-Mead simplex method.
+/// Stops after max_iterations or when values at the simplex vertices are (almost) the same.
+pub(crate) fn minimize<F>(f: F, start: Vec<f64>, max_iterations: usize) -> Vec<f64>
+where
+    F: Fn(&[f64]) -> f64 {
+
+    let n = start.len();
+    if n == 0 {
+        return start;
+    }
+    let eval = |x: &[f64]| { let v = f(x); if v.is_nan() { f64::INFINITY } else { v } };
+    let mut simplex: Vec<(Vec<f64>, f64)> = (0..=n).map(|i| {
+        let mut x = start.clone();
+        if i > 0 {
+            x[i - 1] += if x[i - 1] == 0.0 { 0.1 } else { 0.1 * x[i - 1].abs() };
+        }
+        let v = eval(&x);
+        (x, v)
+    }).collect();
+    for _ in 0..max_iterations {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if (simplex[n].1 - simplex[0].1).abs() <= 1e-12 * (1.0 + simplex[0].1.abs()) {
+            break;
+        }
+        let centroid: Vec<f64> = (0..n).map(|j| simplex[..n].iter().map(|(x, _)| x[j]).sum::<f64>() / n as f64).collect();
+        let towards = |t: f64| -> Vec<f64> {
+            centroid.iter().zip(&simplex[n].0).map(|(c, w)| c + t * (w - c)).collect()
+        };
+        let reflected = towards(-1.0);
+        let fr = eval(&reflected);
+        if fr < simplex[0].1 {
+            let expanded = towards(-2.0);
+            let fe = eval(&expanded);
+            simplex[n] = if fe < fr { (expanded, fe) } else { (reflected, fr) };
+        } else if fr < simplex[n - 1].1 {
+            simplex[n] = (reflected, fr);
+        } else {
+            let contracted = towards(0.5);
+            let fc = eval(&contracted);
+            if fc < simplex[n].1 {
+                simplex[n] = (contracted, fc);
+            } else {
+                let best = simplex[0].0.clone();
+                for vertex in simplex.iter_mut().skip(1) {
+                    let x: Vec<f64> = best.iter().zip(&vertex.0).map(|(b, x)| b + 0.5 * (x - b)).collect();
+                    let v = eval(&x);
+                    *vertex = (x, v);
+                }
+            }
+        }
+    }
+    simplex.into_iter().min_by(|a, b| a.1.total_cmp(&b.1)).map(|(x, _)| x).unwrap_or_default()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_minimize_rosenbrock() {
+        let rosenbrock = |x: &[f64]| (1.0 - x[0]).powi(2) + 100.0 * (x[1] - x[0] * x[0]).powi(2);
+        let x = minimize(rosenbrock, vec![-1.0, 1.0], 2000);
+        assert_approx_eq!(x[0], 1.0, 1e-3);
+        assert_approx_eq!(x[1], 1.0, 1e-3);
+    }
+
+    #[test]
+    fn test_horizon() {
+        let ts = TimeSeries::new(vec![0, 10, 20, 25], vec![1.0; 4]);
+        assert_eq!(Horizon::of(&ts).series(vec![1.0, 2.0]).index.values, vec![35, 45]);
+        assert_eq!(Horizon::of(&TimeSeries::empty()).series(vec![1.0]).index.values, vec![1]);
+    }
+}
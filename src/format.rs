@@ -0,0 +1,161 @@
+//! Customizable text rendering of Time Series
+//!
+
+use core::fmt;
+use chrono::{FixedOffset, TimeZone, Utc};
+
+use crate::{TimeSeries, DataPoint};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Builder which controls how the series is printed.
+/// Created with `TimeSeries::format()`.
+///   * datetime_format - chrono format string used for timestamps
+///   * precision - Number of decimal digits printed for values. All digits if None
+///   * max_rows - If series is longer then only first and last rows are printed
+///   * timezone - Timezone offset used when converting timestamps
+pub struct SeriesFormat<'a> {
+    ts: &'a TimeSeries,
+    datetime_format: String,
+    precision: Option<usize>,
+    max_rows: usize,
+    timezone: FixedOffset,
+}
+
+impl<'a> SeriesFormat<'a> {
+
+    /// Create formatter with default settings:
+    /// UTC timestamps with millisecond part (if not zero), all value digits and 10 rows.
+    pub fn new(ts: &'a TimeSeries) -> SeriesFormat<'a> {
+        SeriesFormat {
+            ts,
+            datetime_format: String::from("%Y-%m-%d %H:%M:%S%.f"),
+            precision: None,
+            max_rows: 10,
+            timezone: FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+
+    /// Set datetime format string (see chrono::format::strftime)
+    pub fn datetime_format(mut self, format: &str) -> Self {
+        self.datetime_format = format.to_owned();
+        self
+    }
+
+    /// Set number of decimal digits
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Set maximum number of printed rows
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Set timezone offset
+    pub fn timezone(mut self, timezone: FixedOffset) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Render series as table with aligned columns
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1500], vec![1.0, 2.5]);
+    /// let table = ts.format().precision(1).to_string_table();
+    /// assert_eq!(table, "timestamp                value\n\
+    ///                    1970-01-01 00:00:00        1.0\n\
+    ///                    1970-01-01 00:00:01.500    2.5\n");
+    /// ```
+    pub fn to_string_table(&self) -> String {
+        let rows: Vec<Option<(String, String)>> = self.rows().into_iter()
+            .map(|r| r.map(|dp| (self.format_timestamp(dp.timestamp), self.format_value(dp.value))))
+            .collect();
+        let ts_width = rows.iter().flatten().map(|r| r.0.len()).fold("timestamp".len(), usize::max);
+        let value_width = rows.iter().flatten().map(|r| r.1.len()).fold("value".len(), usize::max);
+        let mut output = format!("{:<tw$}  {:>vw$}\n", "timestamp", "value", tw = ts_width, vw = value_width);
+        for row in rows {
+            match row {
+                Some((ts, v)) => output.push_str(&format!("{:<tw$}  {:>vw$}\n", ts, v, tw = ts_width, vw = value_width)),
+                None => output.push_str("...\n"),
+            }
+        }
+        output
+    }
+
+    /// Rows to print. None marks the place where rows were skipped.
+    fn rows(&self) -> Vec<Option<DataPoint>> {
+        if self.ts.len() <= self.max_rows {
+            self.ts.iter().map(Some).collect()
+        } else {
+            let half = self.max_rows / 2;
+            self.ts.iter().take(half).map(Some)
+                .chain(core::iter::once(None))
+                .chain(self.ts.iter().skip(self.ts.len() - half).map(Some))
+                .collect()
+        }
+    }
+
+    fn format_timestamp(&self, timestamp: i64) -> String {
+        match Utc.timestamp_millis_opt(timestamp).single() {
+            Some(dt) => dt.with_timezone(&self.timezone).format(&self.datetime_format).to_string(),
+            None => timestamp.to_string(),
+        }
+    }
+
+    fn format_value(&self, value: f64) -> String {
+        match self.precision {
+            Some(p) => format!("{:.*}", p, value),
+            None => value.to_string(),
+        }
+    }
+}
+
+impl<'a> fmt::Display for SeriesFormat<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            match row {
+                Some(dp) => writeln!(f, "({}, {})", self.format_timestamp(dp.timestamp), self.format_value(dp.value))?,
+                None => writeln!(f, "...")?,
+            }
+        }
+        writeln!(f)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_milliseconds() {
+        let ts = TimeSeries::new(vec![1500], vec![2.0]);
+        assert_eq!(ts.to_string(), "(1970-01-01 00:00:01.500, 2)\n\n");
+    }
+
+    #[test]
+    fn test_max_rows() {
+        let ts = TimeSeries::new((0..20).collect(), vec![1.0; 20]);
+        let text = ts.format().max_rows(4).datetime_format("%s").to_string();
+        assert_eq!(text, "(0, 1)\n(0, 1)\n...\n(0, 1)\n(0, 1)\n\n");
+    }
+
+    #[test]
+    fn test_timezone() {
+        let ts = TimeSeries::new(vec![0], vec![1.0]);
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let text = ts.format().timezone(tz).datetime_format("%H:%M %z").to_string();
+        assert_eq!(text, "(02:00 +0200, 1)\n\n");
+    }
+}
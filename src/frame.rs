@@ -0,0 +1,200 @@
+//! Multiple series with a shared index
+//!
+//! Frame stores columns of values for the same timestamps.
+//! Missing values are represented as NaN.
+//!
+
+use crate::TimeSeries;
+use crate::index::DateTimeIndex;
+use crate::stats::{covariance, pearson};
+
+
+/// Columns of values with the same index
+///   * index - Timestamps shared by all columns
+///   * names - Column names
+///   * columns - Column values. Each column has the same length as the index
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSeriesFrame {
+    pub index: DateTimeIndex,
+    pub names: Vec<String>,
+    pub columns: Vec<Vec<f64>>,
+}
+
+
+impl TimeSeriesFrame {
+
+    /// Create frame without columns
+    pub fn new(index: Vec<i64>) -> TimeSeriesFrame {
+        TimeSeriesFrame { index: DateTimeIndex::new(index), names: vec![], columns: vec![] }
+    }
+
+    /// Add column. Missing values are set to NaN and extra values are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let frame = TimeSeriesFrame::new(vec![1, 2, 3])
+    ///     .with_column("a", vec![1.0, 2.0, 3.0])
+    ///     .with_column("b", vec![4.0]);
+    /// assert_eq!(frame.width(), 2);
+    /// assert!(frame.columns[1][2].is_nan());
+    /// ```
+    pub fn with_column(mut self, name: &str, mut values: Vec<f64>) -> TimeSeriesFrame {
+        values.resize(self.len(), f64::NAN);
+        self.names.push(name.to_owned());
+        self.columns.push(values);
+        self
+    }
+
+    /// Create frame from series.
+    /// Index contains timestamps from all series. Series without data point at the given timestamp
+    /// get NaN value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let a = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// let b = TimeSeries::new(vec![2, 3], vec![5.0, 6.0]);
+    /// let frame = TimeSeriesFrame::from_series(&[("a", &a), ("b", &b)]);
+    /// assert_eq!(frame.index.values, vec![1, 2, 3]);
+    /// assert_eq!(frame.column("b").unwrap().values[1..], [5.0, 6.0]);
+    /// ```
+    pub fn from_series(series: &[(&str, &TimeSeries)]) -> TimeSeriesFrame {
+        let mut index: Vec<i64> = series.iter().flat_map(|(_, ts)| ts.index.iter().cloned()).collect();
+        index.sort_unstable();
+        index.dedup();
+        let mut frame = TimeSeriesFrame::new(index);
+        for (name, ts) in series {
+            let values = frame.index.iter()
+                .map(|t| match ts.index.values.binary_search(t) {
+                    Ok(pos) => ts.values[pos],
+                    Err(_) => f64::NAN,
+                })
+                .collect();
+            frame = frame.with_column(name, values);
+        }
+        frame
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Number of columns
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Column as series. None if there is no column with the given name
+    pub fn column(&self, name: &str) -> Option<TimeSeries> {
+        self.names.iter().position(|n| n == name)
+            .map(|pos| TimeSeries { index: self.index.clone(), values: self.columns[pos].clone(), ..TimeSeries::empty() })
+    }
+
+    /// Sample covariance between each pair of columns.
+    /// Each pair uses only rows where both values are not NaN.
+    /// Pairs with less then 2 such rows get NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let frame = TimeSeriesFrame::new(vec![1, 2, 3])
+    ///     .with_column("a", vec![1.0, 2.0, 3.0])
+    ///     .with_column("b", vec![2.0, 4.0, f64::NAN]);
+    /// let cov = frame.cov_matrix();
+    /// assert_eq!(cov[0], vec![1.0, 1.0]);
+    /// assert_eq!(cov[1][1], 2.0);
+    /// ```
+    pub fn cov_matrix(&self) -> Vec<Vec<f64>> {
+        self.pairwise(covariance)
+    }
+
+    /// Pearson correlation coefficient between each pair of columns.
+    /// Each pair uses only rows where both values are not NaN.
+    /// Pairs with less then 2 such rows or with a constant column get NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use assert_approx_eq::assert_approx_eq;
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let frame = TimeSeriesFrame::new(vec![1, 2, 3, 4])
+    ///     .with_column("a", vec![1.0, 2.0, 3.0, 4.0])
+    ///     .with_column("b", vec![8.0, 6.0, f64::NAN, 2.0]);
+    /// let corr = frame.corr_matrix();
+    /// assert_approx_eq!(corr[0][1], -1.0);
+    /// assert_approx_eq!(corr[1][1], 1.0);
+    /// ```
+    pub fn corr_matrix(&self) -> Vec<Vec<f64>> {
+        self.pairwise(pearson)
+    }
+
+    /// Symmetric matrix with the function applied to values of each pair of columns
+    fn pairwise<F: Fn(&[f64], &[f64]) -> Option<f64>>(&self, f: F) -> Vec<Vec<f64>> {
+        let values = |i: usize, j: usize| -> (Vec<f64>, Vec<f64>) {
+            self.columns[i].iter().zip(&self.columns[j])
+                .filter(|(x, y)| !x.is_nan() && !y.is_nan())
+                .unzip()
+        };
+        (0..self.width())
+            .map(|i| (0..self.width())
+                .map(|j| {
+                    let (xs, ys) = values(i, j);
+                    f(&xs, &ys).unwrap_or(f64::NAN)
+                })
+                .collect())
+            .collect()
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_column() {
+        let frame = TimeSeriesFrame::new(vec![1]).with_column("a", vec![1.0]);
+        assert!(frame.column("b").is_none());
+    }
+
+    #[test]
+    fn test_from_series_fills_nan() {
+        let a = TimeSeries::new(vec![1, 3], vec![1.0, 3.0]);
+        let b = TimeSeries::new(vec![2], vec![2.0]);
+        let frame = TimeSeriesFrame::from_series(&[("a", &a), ("b", &b)]);
+        assert_eq!(frame.len(), 3);
+        assert!(frame.columns[0][1].is_nan());
+        assert!(frame.columns[1][0].is_nan() && frame.columns[1][2].is_nan());
+    }
+
+    #[test]
+    fn test_matrix_not_enough_values() {
+        let frame = TimeSeriesFrame::new(vec![1, 2])
+            .with_column("a", vec![1.0, 2.0])
+            .with_column("b", vec![f64::NAN, 5.0])
+            .with_column("c", vec![3.0, 3.0]);
+        let corr = frame.corr_matrix();
+        assert!((corr[0][0] - 1.0).abs() < 1e-12);
+        assert!(corr[0][1].is_nan() && corr[1][0].is_nan());
+        assert!(corr[2][2].is_nan());
+        assert_eq!(frame.cov_matrix()[2][2], 0.0);
+        assert!(TimeSeriesFrame::new(vec![]).cov_matrix().is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Fixed and calendar based frequencies
+//!
+//! Fixed frequencies (milliseconds up to days) are aligned to the epoch.
+//! Calendar frequencies use UTC dates:
+//!   * Week starts on Monday
+//!   * Month, Quarter and Year start on the first day of the month
+//!
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveDateTime, Utc};
+
+use crate::index::Resolution;
+
+
+pub(crate) const SECOND: i64 = 1000;
+pub(crate) const MINUTE: i64 = 60 * SECOND;
+pub(crate) const HOUR: i64 = 60 * MINUTE;
+pub(crate) const DAY: i64 = 24 * HOUR;
+pub(crate) const WEEK: i64 = 7 * DAY;
+/// 1970-01-01 was Thursday. First Monday was 4 days later
+const FIRST_MONDAY: i64 = 4 * DAY;
+
+
+/// Time step between data points
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Millisecond(i64),
+    Second(i64),
+    Minute(i64),
+    Hour(i64),
+    Day(i64),
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl From<i64> for Frequency {
+    /// Number of milliseconds
+    fn from(period: i64) -> Frequency {
+        Frequency::Millisecond(period)
+    }
+}
+
+impl Frequency {
+
+    /// Length of the step in milliseconds for fixed frequencies. None for calendar frequencies
+    pub fn fixed_millis(&self) -> Option<i64> {
+        match *self {
+            Frequency::Millisecond(n) => Some(n),
+            Frequency::Second(n) => Some(n * SECOND),
+            Frequency::Minute(n) => Some(n * MINUTE),
+            Frequency::Hour(n) => Some(n * HOUR),
+            Frequency::Day(n) => Some(n * DAY),
+            Frequency::Week => Some(WEEK),
+            _ => None,
+        }
+    }
+
+    /// Fixed frequencies need a positive step. Invalid frequencies give empty ranges
+    /// and don't group data points (see `floor`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::frequency::Frequency;
+    ///
+    /// assert!(Frequency::Minute(5).is_valid());
+    /// assert!(Frequency::Month.is_valid());
+    /// assert!(!Frequency::Millisecond(0).is_valid());
+    /// assert!(!Frequency::Hour(-1).is_valid());
+    /// ```
+    pub fn is_valid(&self) -> bool {
+        self.fixed_millis().is_none_or(|step| step > 0)
+    }
+
+    /// Number of months in calendar step
+    fn months(&self) -> Option<u32> {
+        match self {
+            Frequency::Month => Some(1),
+            Frequency::Quarter => Some(3),
+            Frequency::Year => Some(12),
+            _ => None,
+        }
+    }
+
+    /// Start of the period which contains given timestamp.
+    /// Timestamp is returned unchanged for invalid frequency (see `is_valid`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::frequency::Frequency;
+    ///
+    /// // 2020-02-15 10:30:00
+    /// let ts = 1_581_762_600_000;
+    /// assert_eq!(Frequency::Hour(1).floor(ts), 1_581_760_800_000);
+    /// // 2020-02-01 00:00:00
+    /// assert_eq!(Frequency::Month.floor(ts), 1_580_515_200_000);
+    /// // 2020-01-01 00:00:00
+    /// assert_eq!(Frequency::Quarter.floor(ts), 1_577_836_800_000);
+    /// ```
+    pub fn floor(&self, timestamp: i64) -> i64 {
+        match self {
+            Frequency::Week => (timestamp - FIRST_MONDAY).div_euclid(WEEK) * WEEK + FIRST_MONDAY,
+            Frequency::Month | Frequency::Quarter | Frequency::Year => {
+                let date = to_datetime(timestamp).date();
+                let month = match self {
+                    Frequency::Month => date.month(),
+                    Frequency::Quarter => (date.month() - 1) / 3 * 3 + 1,
+                    _ => 1,
+                };
+                let start = NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap();
+                start.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis()
+            },
+            _ => match self.fixed_millis() {
+                Some(step) if step > 0 => timestamp.div_euclid(step) * step,
+                _ => timestamp,
+            },
+        }
+    }
+
+    /// Move timestamp by n steps. Calendar steps keep the day of month
+    /// (clamped to the last day of shorter months).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::frequency::Frequency;
+    ///
+    /// // 2020-01-31 00:00:00
+    /// let ts = 1_580_428_800_000;
+    /// // 2020-02-29 00:00:00
+    /// assert_eq!(Frequency::Month.step(ts, 1), 1_582_934_400_000);
+    /// assert_eq!(Frequency::Minute(5).step(ts, 2), ts + 600_000);
+    /// ```
+    pub fn step(&self, timestamp: i64, n: i64) -> i64 {
+        match self.months() {
+            Some(months) => {
+                let dt = to_datetime(timestamp);
+                let total = months as i64 * n;
+                let shifted = if total >= 0 {
+                    dt.checked_add_months(Months::new(total as u32))
+                } else {
+                    dt.checked_sub_months(Months::new((-total) as u32))
+                };
+                shifted.expect("Date out of range").and_utc().timestamp_millis()
+            },
+            None => timestamp + n * self.fixed_millis().unwrap(),
+        }
+    }
+
+    /// Like `floor`, but for timestamp in the given resolution
+    pub(crate) fn floor_in(&self, timestamp: i64, resolution: Resolution) -> i64 {
+        if resolution == Resolution::Millisecond {
+            return self.floor(timestamp);
+        }
+        let start = self.floor(resolution.convert(timestamp, Resolution::Millisecond));
+        Resolution::Millisecond.convert(start, resolution)
+    }
+
+    /// Like `step`, but for timestamp in the given resolution.
+    /// Part of the timestamp finer than millisecond is kept.
+    pub(crate) fn step_in(&self, timestamp: i64, n: i64, resolution: Resolution) -> i64 {
+        if resolution == Resolution::Millisecond {
+            return self.step(timestamp, n);
+        }
+        let millis = resolution.convert(timestamp, Resolution::Millisecond);
+        let rest = timestamp - Resolution::Millisecond.convert(millis, resolution);
+        Resolution::Millisecond.convert(self.step(millis, n), resolution) + rest
+    }
+}
+
+fn to_datetime(timestamp: i64) -> NaiveDateTime {
+    DateTime::<Utc>::from_timestamp_millis(timestamp).expect("Timestamp out of range").naive_utc()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(y: i32, m: u32, d: u32) -> i64 {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis()
+    }
+
+    #[test]
+    fn test_week_starts_on_monday() {
+        // 2020-02-13 was Thursday
+        assert_eq!(Frequency::Week.floor(ts(2020, 2, 13) + 5 * HOUR), ts(2020, 2, 10));
+        assert_eq!(Frequency::Week.floor(ts(2020, 2, 10)), ts(2020, 2, 10));
+    }
+
+    #[test]
+    fn test_negative_timestamps() {
+        assert_eq!(Frequency::Year.floor(ts(1969, 7, 1)), ts(1969, 1, 1));
+        assert_eq!(Frequency::Day(1).floor(-1), -DAY);
+    }
+
+    #[test]
+    fn test_invalid_step() {
+        assert_eq!(Frequency::Millisecond(0).floor(15), 15);
+        assert_eq!(Frequency::Second(-2).floor(-15), -15);
+    }
+
+    #[test]
+    fn test_resolution() {
+        let ns = Resolution::Nanosecond;
+        assert_eq!(Frequency::Second(1).floor_in(2_500_000_123, ns), 2_000_000_000);
+        assert_eq!(Frequency::Second(1).step_in(2_500_000_123, 2, ns), 4_500_000_123);
+        assert_eq!(Frequency::Day(1).floor_in(-1, ns), -DAY * 1_000_000);
+        assert_eq!(Frequency::Month.step_in(ts(2020, 1, 31) * 1_000_000 + 7, 1, ns), ts(2020, 2, 29) * 1_000_000 + 7);
+        assert_eq!(Frequency::Minute(1).floor_in(ts(2020, 1, 1) / 1000 + 75, Resolution::Second), ts(2020, 1, 1) / 1000 + 60);
+        assert_eq!(Frequency::Hour(2).step_in(10, -1, Resolution::Second), 10 - 7200);
+    }
+
+    #[test]
+    fn test_year_step() {
+        assert_eq!(Frequency::Year.step(ts(2020, 2, 29), 1), ts(2021, 2, 28));
+        assert_eq!(Frequency::Quarter.step(ts(2020, 5, 1), -2), ts(2019, 11, 1));
+    }
+}
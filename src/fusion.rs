@@ -0,0 +1,135 @@
+//! Fusion of redundant series into a single consensus series
+//!
+//! Output is defined at every timestamp from any of the input series.
+//! Each sensor contributes its last known value, as long as the timestamp is inside
+//! the sensor's time range.
+//!
+
+use std::cmp::Ordering;
+
+use crate::TimeSeries;
+use crate::extrapolation::Extrapolation;
+
+
+/// How values from sensors are combined
+///   * Median - Median of contributing values
+///   * TrimmedMean(p) - Mean after removing fraction `p` of the lowest and highest values
+///   * KalmanWeighted - Inverse variance weighted mean. The noise variance of each sensor
+///     is estimated from its first differences
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    Median,
+    TrimmedMean(f64),
+    KalmanWeighted,
+}
+
+/// Result of sensor fusion
+///   * series - Consensus series
+///   * contributors - Number of sensors which contributed to each data point
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fused {
+    pub series: TimeSeries,
+    pub contributors: Vec<usize>,
+}
+
+
+/// Combine redundant series into one.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::fusion::{combine, Strategy};
+///
+/// let s1 = TimeSeries::new(vec![0, 10, 20], vec![1.0, 2.0, 3.0]);
+/// let s2 = TimeSeries::new(vec![5, 15], vec![1.2, 2.2]);
+/// let s3 = TimeSeries::new(vec![0, 10, 20], vec![9.0, 9.0, 9.0]);
+/// let fused = combine(&[&s1, &s2, &s3], Strategy::Median);
+/// assert_eq!(fused.series.index.values, vec![0, 5, 10, 15, 20]);
+/// assert_eq!(fused.series.values, vec![5.0, 1.2, 2.0, 2.2, 6.0]);
+/// assert_eq!(fused.contributors, vec![2, 3, 3, 3, 2]);
+/// ```
+pub fn combine(series: &[&TimeSeries], strategy: Strategy) -> Fused {
+    let mut index: Vec<i64> = series.iter().flat_map(|ts| ts.index.iter().cloned()).collect();
+    index.sort_unstable();
+    index.dedup();
+    let weights: Vec<f64> = match strategy {
+        Strategy::KalmanWeighted => series.iter().map(|ts| noise_weight(ts)).collect(),
+        _ => vec![1.0; series.len()],
+    };
+    let mut values = Vec::with_capacity(index.len());
+    let mut contributors = Vec::with_capacity(index.len());
+    for &timestamp in &index {
+        let samples: Vec<(f64, f64)> = series.iter().zip(&weights)
+            .filter_map(|(ts, &w)| ts.at_with(timestamp, Extrapolation::Error).map(|v| (v, w)))
+            .collect();
+        contributors.push(samples.len());
+        values.push(reduce(samples, strategy));
+    }
+    Fused { series: TimeSeries::new(index, values), contributors }
+}
+
+fn reduce(mut samples: Vec<(f64, f64)>, strategy: Strategy) -> f64 {
+    if samples.is_empty() {
+        return f64::NAN;
+    }
+    samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    let n = samples.len();
+    match strategy {
+        Strategy::Median => {
+            if n % 2 == 1 { samples[n/2].0 } else { (samples[n/2-1].0 + samples[n/2].0) / 2.0 }
+        },
+        Strategy::TrimmedMean(fraction) => {
+            let k = (n as f64 * fraction.clamp(0.0, 0.5)).floor() as usize;
+            let kept = if 2 * k >= n { &samples[n/2..n/2+1] } else { &samples[k..n-k] };
+            kept.iter().map(|s| s.0).sum::<f64>() / kept.len() as f64
+        },
+        Strategy::KalmanWeighted => {
+            let total: f64 = samples.iter().map(|s| s.1).sum();
+            samples.iter().map(|s| s.0 * s.1).sum::<f64>() / total
+        },
+    }
+}
+
+/// Inverse of the sensor noise variance.
+/// Noise variance is estimated as half of the variance of first differences.
+fn noise_weight(ts: &TimeSeries) -> f64 {
+    let variance = ts.diff().std().map(|s| s * s / 2.0).unwrap_or(0.0);
+    1.0 / variance.max(f64::EPSILON)
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_empty_input() {
+        let fused = combine(&[], Strategy::Median);
+        assert!(fused.series.is_empty());
+        assert!(fused.contributors.is_empty());
+    }
+
+    #[test]
+    fn test_trimmed_mean() {
+        let sensors: Vec<TimeSeries> = vec![1.0, 2.0, 3.0, 4.0, 100.0].into_iter()
+            .map(|v| TimeSeries::new(vec![0], vec![v]))
+            .collect();
+        let refs: Vec<&TimeSeries> = sensors.iter().collect();
+        assert_eq!(combine(&refs, Strategy::TrimmedMean(0.2)).series.values, vec![3.0]);
+        assert_eq!(combine(&refs, Strategy::TrimmedMean(0.0)).series.values, vec![22.0]);
+    }
+
+    #[test]
+    fn test_kalman_prefers_less_noisy_sensor() {
+        let index: Vec<i64> = (0..20).collect();
+        let clean = TimeSeries::new(index.clone(), (0..20).map(|i| 10.0 + 0.01 * (i % 2) as f64).collect());
+        let noisy = TimeSeries::new(index, (0..20).map(|i| 20.0 + 5.0 * (i % 2) as f64).collect());
+        let fused = combine(&[&clean, &noisy], Strategy::KalmanWeighted);
+        assert_approx_eq!(fused.series.values[0], 10.0, 1e-3);
+    }
+}
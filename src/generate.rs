@@ -0,0 +1,163 @@
+//! Synthetic series for testing and simulation
+//!
+//! Generators take the index of the new series and the seed of the random number generator,
+//! so the same arguments always give the same series. Noise is normally distributed.
+//! Anomalies (steps and spikes) can be injected into any series.
+//!
+
+use std::f64::consts::PI;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::TimeSeries;
+
+
+/// Independent values with mean 0 and the given standard deviation
+///
+/// # Example
+///
+/// ```
+/// use timeseries::generate;
+///
+/// let ts = generate::white_noise((0..1000).collect(), 2.0, 42);
+/// assert_eq!(ts.len(), 1000);
+/// assert!((ts.std().unwrap() - 2.0).abs() < 0.2);
+/// assert_eq!(ts, generate::white_noise((0..1000).collect(), 2.0, 42));
+/// ```
+pub fn white_noise(index: Vec<i64>, std: f64, seed: u64) -> TimeSeries {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let values = (0..index.len()).map(|_| std * standard_normal(&mut rng)).collect();
+    TimeSeries::new(index, values)
+}
+
+/// Cumulative sum of white noise starting from the first step
+///
+/// # Example
+///
+/// ```
+/// use timeseries::generate;
+///
+/// let walk = generate::random_walk(vec![1, 2, 3], 1.0, 7);
+/// let noise = generate::white_noise(vec![1, 2, 3], 1.0, 7);
+/// assert_eq!(walk.values[1], noise.values[0] + noise.values[1]);
+/// ```
+pub fn random_walk(index: Vec<i64>, std: f64, seed: u64) -> TimeSeries {
+    ar1(index, 1.0, std, seed)
+}
+
+/// Autoregressive process x(t) = phi * x(t-1) + e(t) where e is white noise. Starts from x = 0.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::generate;
+///
+/// let ts = generate::ar1((0..10).collect(), 0.0, 1.0, 3);
+/// assert_eq!(ts, generate::white_noise((0..10).collect(), 1.0, 3));
+/// ```
+pub fn ar1(index: Vec<i64>, phi: f64, std: f64, seed: u64) -> TimeSeries {
+    let mut ts = white_noise(index, std, seed);
+    for i in 1..ts.len() {
+        ts.values[i] += phi * ts.values[i - 1];
+    }
+    ts
+}
+
+/// Sine wave with the period in the index units and white noise added.
+/// Wave starts at timestamp 0 (value at timestamp 0 is 0 without noise).
+///
+/// # Example
+///
+/// ```
+/// use timeseries::generate;
+/// use assert_approx_eq::assert_approx_eq;
+///
+/// let ts = generate::sinusoid(vec![0, 25, 50, 75], 2.0, 100, 0.0, 1);
+/// assert_approx_eq!(ts.values[1], 2.0);
+/// assert_approx_eq!(ts.values[3], -2.0);
+/// ```
+pub fn sinusoid(index: Vec<i64>, amplitude: f64, period: i64, noise_std: f64, seed: u64) -> TimeSeries {
+    let mut ts = white_noise(index, noise_std, seed);
+    for (t, v) in ts.index.iter().zip(ts.values.iter_mut()) {
+        *v += amplitude * (2.0 * PI * *t as f64 / period as f64).sin();
+    }
+    ts
+}
+
+/// Add the size to all values from the timestamp onwards (level shift)
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::generate;
+///
+/// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 1.0, 1.0]);
+/// assert_eq!(generate::add_step(&ts, 2, 5.0).values, vec![1.0, 6.0, 6.0]);
+/// ```
+pub fn add_step(ts: &TimeSeries, timestamp: i64, size: f64) -> TimeSeries {
+    let mut output = ts.clone();
+    for (t, v) in output.index.iter().zip(output.values.iter_mut()) {
+        if *t >= timestamp {
+            *v += size;
+        }
+    }
+    output.logged(ts, "add_step", || vec![("timestamp", timestamp.to_string()), ("size", size.to_string())])
+}
+
+/// Add the size to values at n randomly selected data points.
+/// Each spike goes up or down with the same probability.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::generate;
+///
+/// let ts = TimeSeries::new((0..10).collect(), vec![0.0; 10]);
+/// let spiky = generate::add_spikes(&ts, 3, 10.0, 42);
+/// assert_eq!(spiky.values.iter().filter(|v| v.abs() == 10.0).count(), 3);
+/// ```
+pub fn add_spikes(ts: &TimeSeries, n: usize, size: f64, seed: u64) -> TimeSeries {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut output = ts.clone();
+    for pos in rand::seq::index::sample(&mut rng, ts.len(), n.min(ts.len())).into_vec() {
+        output.values[pos] += if rng.gen::<bool>() { size } else { -size };
+    }
+    output.logged(ts, "add_spikes", || vec![("n", n.to_string()), ("size", size.to_string()), ("seed", seed.to_string())])
+}
+
+/// Normally distributed value with Box-Muller transform
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    // 1 - u is in (0, 1], so the logarithm is finite
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::stats::mean;
+
+    #[test]
+    fn test_standard_normal() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let xs: Vec<f64> = (0..10_000).map(|_| standard_normal(&mut rng)).collect();
+        assert_approx_eq!(mean(&xs), 0.0, 0.05);
+        let within = xs.iter().filter(|x| x.abs() < 1.0).count() as f64 / xs.len() as f64;
+        assert_approx_eq!(within, 0.683, 0.02);
+    }
+
+    #[test]
+    fn test_empty_index() {
+        assert!(white_noise(vec![], 1.0, 1).is_empty());
+        assert!(ar1(vec![], 0.5, 1.0, 1).is_empty());
+        assert!(add_spikes(&TimeSeries::empty(), 3, 1.0, 1).is_empty());
+    }
+}
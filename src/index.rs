@@ -1,13 +1,166 @@
-use std::ops::Index;
-use std::cmp;
-use std::iter::FromIterator;
-use std::collections::{HashSet, HashMap};
+use core::ops::{Index, Range};
+use core::cmp;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use alloc::collections::{BTreeMap, BTreeSet};
+use chrono::DateTime;
+
+use crate::TimeSeries;
+use crate::frequency::Frequency;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Which data point to keep when timestamps are duplicated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Keep {
+    First,
+    Last,
+}
+
+/// Summary of time differences between consecutive timestamps
+///   * min_delta, median_delta, max_delta - Time differences in index resolution
+///   * jitter - Standard deviation of time differences
+///   * gap_count - Number of time differences larger than 2 x median
+///   * duplicate_count - Number of timestamps which already occurred earlier in the index
+#[derive(Clone, Debug, PartialEq)]
+pub struct SamplingReport {
+    pub min_delta: i64,
+    pub median_delta: i64,
+    pub max_delta: i64,
+    pub jitter: f64,
+    pub gap_count: usize,
+    pub duplicate_count: usize,
+}
+
+/// Unit of timestamps since the epoch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Resolution {
+    Second,
+    #[default]
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+impl Resolution {
+
+    /// Number of units in one second
+    pub fn per_second(&self) -> i64 {
+        match self {
+            Resolution::Second => 1,
+            Resolution::Millisecond => 1_000,
+            Resolution::Microsecond => 1_000_000,
+            Resolution::Nanosecond => 1_000_000_000,
+        }
+    }
+
+    /// Guess the unit of epoch timestamp from its magnitude: seconds (< 10^11),
+    /// milliseconds (< 10^14), microseconds (< 10^17) or nanoseconds.
+    /// The guess is correct for dates between 1973 and 5138, so don't use it for small timestamps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::Resolution;
+    ///
+    /// assert_eq!(Resolution::infer(1_600_000_000), Resolution::Second);
+    /// assert_eq!(Resolution::infer(1_600_000_000_000), Resolution::Millisecond);
+    /// assert_eq!(Resolution::infer(1_600_000_000_000_000_000), Resolution::Nanosecond);
+    /// ```
+    pub fn infer(timestamp: i64) -> Resolution {
+        let magnitude = timestamp.unsigned_abs();
+        if magnitude < 100_000_000_000 {
+            Resolution::Second
+        } else if magnitude < 100_000_000_000_000 {
+            Resolution::Millisecond
+        } else if magnitude < 100_000_000_000_000_000 {
+            Resolution::Microsecond
+        } else {
+            Resolution::Nanosecond
+        }
+    }
+
+    /// Convert timestamp to other resolution.
+    /// Conversion to coarser resolution rounds down (also for timestamps before the epoch).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::Resolution;
+    ///
+    /// assert_eq!(Resolution::Millisecond.convert(1_500, Resolution::Nanosecond), 1_500_000_000);
+    /// assert_eq!(Resolution::Nanosecond.convert(1_999_999, Resolution::Millisecond), 1);
+    /// assert_eq!(Resolution::Millisecond.convert(-1, Resolution::Second), -1);
+    /// ```
+    pub fn convert(&self, timestamp: i64, to: Resolution) -> i64 {
+        let (from, to) = (self.per_second(), to.per_second());
+        if from <= to {
+            timestamp.saturating_mul(to / from)
+        } else {
+            timestamp.div_euclid(from / to)
+        }
+    }
+
+    /// Code used in binary files
+    #[cfg(feature = "io")]
+    pub(crate) fn code(&self) -> u8 {
+        match self {
+            Resolution::Millisecond => 0,
+            Resolution::Second => 1,
+            Resolution::Microsecond => 2,
+            Resolution::Nanosecond => 3,
+        }
+    }
+
+    #[cfg(feature = "io")]
+    pub(crate) fn from_code(code: u8) -> Option<Resolution> {
+        match code {
+            0 => Some(Resolution::Millisecond),
+            1 => Some(Resolution::Second),
+            2 => Some(Resolution::Microsecond),
+            3 => Some(Resolution::Nanosecond),
+            _ => None,
+        }
+    }
+
+    /// Unit name used in CSV files
+    #[cfg(feature = "io")]
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Resolution::Second => "s",
+            Resolution::Millisecond => "ms",
+            Resolution::Microsecond => "us",
+            Resolution::Nanosecond => "ns",
+        }
+    }
+
+    #[cfg(feature = "io")]
+    pub(crate) fn from_name(name: &str) -> Option<Resolution> {
+        match name {
+            "s" => Some(Resolution::Second),
+            "ms" => Some(Resolution::Millisecond),
+            "us" => Some(Resolution::Microsecond),
+            "ns" => Some(Resolution::Nanosecond),
+            _ => None,
+        }
+    }
+}
+
+/// Number of timestamps printed at the start and at the end of the long index
+const PRINTED_EDGE: usize = 3;
 
 
 /// DateTimeIndex is represented as an array of timestamps (i64)
-#[derive(Clone, Debug)]
+///   * values - Timestamps
+///   * resolution - Unit of the timestamps. Milliseconds by default.
+///     Series operations keep the resolution and apply `Frequency` steps in this unit.
+///     Other durations (windows, tolerances, gaps) are given in the same unit as the timestamps.
+#[derive(Clone)]
 pub struct DateTimeIndex {
     pub values: Vec<i64>,
+    pub resolution: Resolution,
 }
 
 impl DateTimeIndex {
@@ -25,7 +178,76 @@ impl DateTimeIndex {
     /// assert_eq!(index.len(), 4);
     /// ```
     pub fn new(values: Vec<i64>) -> DateTimeIndex {
-        DateTimeIndex { values }
+        DateTimeIndex { values, resolution: Resolution::Millisecond }
+    }
+
+    /// Mark timestamps as given in the resolution. Values are not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::{DateTimeIndex, Resolution};
+    ///
+    /// let index = DateTimeIndex::new(vec![1_000_000_001]).with_resolution(Resolution::Nanosecond);
+    /// assert_eq!(index.resolution, Resolution::Nanosecond);
+    /// assert_eq!(index.values, vec![1_000_000_001]);
+    /// ```
+    pub fn with_resolution(mut self, resolution: Resolution) -> DateTimeIndex {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Convert timestamps to the given resolution
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::{DateTimeIndex, Resolution};
+    ///
+    /// let index = DateTimeIndex::new(vec![1_000_000_001, 2_500_000_000]).with_resolution(Resolution::Nanosecond);
+    /// let millis = index.to_resolution(Resolution::Millisecond);
+    /// assert_eq!(millis.values, vec![1_000, 2_500]);
+    /// assert_eq!(millis.resolution, Resolution::Millisecond);
+    /// ```
+    pub fn to_resolution(&self, resolution: Resolution) -> DateTimeIndex {
+        let values = self.values.iter().map(|&ts| self.resolution.convert(ts, resolution)).collect();
+        DateTimeIndex { values, resolution }
+    }
+
+    /// Create index with timestamps from start to end (inclusive) with the given frequency.
+    /// Each timestamp is calculated as n steps from the start, so monthly index
+    /// starting on the 31st stays at the end of the month.
+    /// Index is empty for invalid frequency (see `Frequency::is_valid`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    /// use timeseries::frequency::Frequency;
+    ///
+    /// let index = DateTimeIndex::date_range(0, 10, Frequency::Millisecond(5));
+    /// assert_eq!(index.values, vec![0, 5, 10]);
+    ///
+    /// // 2020-01-31 to 2020-04-30
+    /// let index = DateTimeIndex::date_range(1_580_428_800_000, 1_588_204_800_000, Frequency::Month);
+    /// assert_eq!(index.len(), 4);
+    /// ```
+    pub fn date_range(start: i64, end: i64, frequency: Frequency) -> DateTimeIndex {
+        DateTimeIndex::date_range_in(start, end, frequency, Resolution::Millisecond)
+    }
+
+    /// Like `date_range`, but for start and end in the given resolution.
+    /// Steps shorter than the resolution unit don't create duplicated timestamps.
+    pub(crate) fn date_range_in(start: i64, end: i64, frequency: Frequency, resolution: Resolution) -> DateTimeIndex {
+        if !frequency.is_valid() {
+            return DateTimeIndex::new(vec![]).with_resolution(resolution);
+        }
+        let mut values: Vec<i64> = (0..)
+            .map(|n| frequency.step_in(start, n, resolution))
+            .take_while(|&ts| ts <= end)
+            .collect();
+        values.dedup();
+        DateTimeIndex::new(values).with_resolution(resolution)
     }
 
     /// Infer index sample rate
@@ -39,7 +261,7 @@ impl DateTimeIndex {
     /// let index = DateTimeIndex::new(vec![0, 10, 15, 20, 25, 27]);
     /// assert_eq!(index.infer_sample_rate(), 5);
     pub fn infer_sample_rate(&self) -> i64 {
-        let mut occurrences: HashMap<i64, i64> = HashMap::new();
+        let mut occurrences: BTreeMap<i64, i64> = BTreeMap::new();
         let mut max: (i64, i64) = (0, 0);
 
         self.values.iter().zip(self.values.iter().skip(1))
@@ -58,6 +280,66 @@ impl DateTimeIndex {
         max.0
     }
     
+    /// Time differences between consecutive timestamps.
+    /// Each difference is stored at the timestamp which ends it, so the series is 1 point shorter than the index.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// let index = DateTimeIndex::new(vec![0, 10, 15, 35]);
+    /// let deltas = index.deltas();
+    /// assert_eq!(deltas.index.values, vec![10, 15, 35]);
+    /// assert_eq!(deltas.values, vec![10.0, 5.0, 20.0]);
+    /// ```
+    pub fn deltas(&self) -> TimeSeries {
+        let index = self.values.iter().skip(1).cloned().collect();
+        // Differences which don't fit into i64 are computed in floating point
+        let values = self.values.windows(2)
+            .map(|w| w[1].checked_sub(w[0]).map_or(w[1] as f64 - w[0] as f64, |d| d as f64))
+            .collect();
+        let mut ts = TimeSeries::new(index, values);
+        ts.index.resolution = self.resolution;
+        ts
+    }
+
+    /// Summarize sampling of the index: spread of time differences, gaps and duplicates.
+    /// None if the index has less than 2 timestamps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// let index = DateTimeIndex::new(vec![0, 10, 20, 20, 30, 70, 80]);
+    /// let report = index.sampling_report().unwrap();
+    /// assert_eq!(report.min_delta, 0);
+    /// assert_eq!(report.median_delta, 10);
+    /// assert_eq!(report.max_delta, 40);
+    /// assert_eq!(report.gap_count, 1);
+    /// assert_eq!(report.duplicate_count, 1);
+    /// ```
+    pub fn sampling_report(&self) -> Option<SamplingReport> {
+        if self.len() < 2 {
+            return None;
+        }
+        let mut deltas: Vec<i64> = self.values.windows(2).map(|w| w[1].saturating_sub(w[0])).collect();
+        deltas.sort_unstable();
+        let median_delta = deltas[deltas.len() / 2];
+        let mean = deltas.iter().map(|&d| d as f64).sum::<f64>() / deltas.len() as f64;
+        let variance = deltas.iter().map(|&d| (d as f64 - mean).powi(2)).sum::<f64>() / deltas.len() as f64;
+        let unique: BTreeSet<&i64> = self.values.iter().collect();
+        Some(SamplingReport {
+            min_delta: deltas[0],
+            median_delta,
+            max_delta: deltas[deltas.len() - 1],
+            jitter: variance.sqrt(),
+            gap_count: deltas.iter().filter(|&&d| d > median_delta.saturating_mul(2)).count(),
+            duplicate_count: self.len() - unique.len(),
+        })
+    }
+
     /// Check if index is monotonic increasing
     /// 
     /// # Example
@@ -90,12 +372,163 @@ impl DateTimeIndex {
     /// assert_eq!(ys.is_unique(), false);
     /// ```
     pub fn is_unique(&self) -> bool {
-        let set: HashSet<&i64> = HashSet::from_iter(self.values.iter());
+        let set: BTreeSet<&i64> = BTreeSet::from_iter(self.values.iter());
         set.len() == self.values.len()
     }
 
+    /// Create index with timestamps in increasing order
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// let index = DateTimeIndex::new(vec![3, 1, 2]);
+    /// assert_eq!(index.sorted().values, vec![1, 2, 3]);
+    /// ```
+    pub fn sorted(&self) -> DateTimeIndex {
+        let mut values = self.values.clone();
+        values.sort_unstable();
+        DateTimeIndex { values, resolution: self.resolution }
+    }
+
+    /// Positions which would sort the index. Equal timestamps keep their order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// let index = DateTimeIndex::new(vec![3, 1, 2, 1]);
+    /// assert_eq!(index.argsort(), vec![1, 3, 2, 0]);
+    /// ```
+    pub fn argsort(&self) -> Vec<usize> {
+        let mut positions: Vec<usize> = (0..self.len()).collect();
+        positions.sort_by_key(|&i| self.values[i]);
+        positions
+    }
+
+    /// Create index without duplicated timestamps. The order of timestamps is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::{DateTimeIndex, Keep};
+    ///
+    /// let index = DateTimeIndex::new(vec![1, 2, 1, 3]);
+    /// assert_eq!(index.deduplicate(Keep::First).values, vec![1, 2, 3]);
+    /// assert_eq!(index.deduplicate(Keep::Last).values, vec![2, 1, 3]);
+    /// ```
+    pub fn deduplicate(&self, keep: Keep) -> DateTimeIndex {
+        let values = self.unique_positions(keep).iter().map(|&i| self.values[i]).collect();
+        DateTimeIndex { values, resolution: self.resolution }
+    }
+
+    /// Positions of the first (or last) occurrence of each timestamp in index order
+    pub(crate) fn unique_positions(&self, keep: Keep) -> Vec<usize> {
+        let mut seen = BTreeSet::new();
+        match keep {
+            Keep::First => (0..self.len()).filter(|&i| seen.insert(self.values[i])).collect(),
+            Keep::Last => {
+                let mut positions: Vec<usize> = (0..self.len()).rev().filter(|&i| seen.insert(self.values[i])).collect();
+                positions.reverse();
+                positions
+            }
+        }
+    }
+
+    /// Find timestamps in range [start, end). Index has to be sorted.
+    /// Returns positions of the timestamps and the slice with them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// let index = DateTimeIndex::new(vec![10, 20, 30, 40]);
+    /// let (positions, timestamps) = index.slice_range(15, 40);
+    /// assert_eq!(positions, 1..3);
+    /// assert_eq!(timestamps, &[20, 30]);
+    /// ```
+    pub fn slice_range(&self, start: i64, end: i64) -> (Range<usize>, &[i64]) {
+        let from = self.values.partition_point(|&ts| ts < start);
+        let to = self.values.partition_point(|&ts| ts < end).max(from);
+        (from..to, &self.values[from..to])
+    }
+
+    /// Binary search for the timestamp. Index has to be sorted.
+    /// Returns Ok with the position of the first equal timestamp,
+    /// or Err with the position where the timestamp could be inserted keeping the order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// let index = DateTimeIndex::new(vec![10, 20, 20, 30]);
+    /// assert_eq!(index.position_of(20), Ok(1));
+    /// assert_eq!(index.position_of(25), Err(3));
+    /// assert_eq!(index.position_of(5), Err(0));
+    /// ```
+    pub fn position_of(&self, timestamp: i64) -> Result<usize, usize> {
+        let pos = self.values.partition_point(|&ts| ts < timestamp);
+        if self.values.get(pos) == Some(&timestamp) { Ok(pos) } else { Err(pos) }
+    }
+
+    /// Unit of the timestamps guessed from their magnitude (see `Resolution::infer`).
+    /// None if the index is empty or timestamps look like different units.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::{DateTimeIndex, Resolution};
+    ///
+    /// let index = DateTimeIndex::new(vec![1_600_000_000_000, 1_600_000_060_000]);
+    /// assert_eq!(index.infer_resolution(), Some(Resolution::Millisecond));
+    /// let mixed = DateTimeIndex::new(vec![1_600_000_000, 1_600_000_060_000]);
+    /// assert_eq!(mixed.infer_resolution(), None);
+    /// ```
+    pub fn infer_resolution(&self) -> Option<Resolution> {
+        let first = Resolution::infer(*self.values.first()?);
+        self.values.iter().all(|&t| Resolution::infer(t) == first).then_some(first)
+    }
+
+    /// The first timestamp
+    pub fn first(&self) -> Option<i64> {
+        self.values.first().cloned()
+    }
+
+    /// The last timestamp
+    pub fn last(&self) -> Option<i64> {
+        self.values.last().cloned()
+    }
+
+    /// The smallest timestamp. The same as `first()` for sorted index.
+    pub fn min(&self) -> Option<i64> {
+        self.values.iter().min().cloned()
+    }
+
+    /// The largest timestamp. The same as `last()` for sorted index.
+    pub fn max(&self) -> Option<i64> {
+        self.values.iter().max().cloned()
+    }
+
+    /// Time between the smallest and the largest timestamp
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// assert_eq!(DateTimeIndex::new(vec![20, 10, 50]).span(), Some(40));
+    /// assert_eq!(DateTimeIndex::new(vec![]).span(), None);
+    /// ```
+    pub fn span(&self) -> Option<i64> {
+        Some(self.max()? - self.min()?)
+    }
+
     /// Create iterator
-    pub fn iter(&self) -> std::slice::Iter<i64> {
+    pub fn iter(&self) -> core::slice::Iter<'_, i64> {
         self.values.iter()
     }
     
@@ -103,6 +536,11 @@ impl DateTimeIndex {
     pub fn len(&self) -> usize {
         self.values.len()
     }
+
+    /// Check if index is empty
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
 }
 
 
@@ -114,10 +552,94 @@ impl Index<usize> for DateTimeIndex {
     }
 }
 
+impl Resolution {
+
+    /// Timestamp as UTC datetime. Timestamps out of the datetime range are printed as numbers
+    pub(crate) fn format_timestamp(self, timestamp: i64) -> String {
+        let dt = match self {
+            Resolution::Nanosecond => Some(DateTime::from_timestamp_nanos(timestamp)),
+            _ => DateTime::from_timestamp_micros(self.convert(timestamp, Resolution::Microsecond)),
+        };
+        match dt {
+            Some(dt) => dt.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+            None => timestamp.to_string(),
+        }
+    }
+
+    /// Time step with the largest unit which divides it
+    pub(crate) fn format_duration(self, duration: i64) -> String {
+        let units = [(86_400_000_000_000, "d"), (3_600_000_000_000, "h"), (60_000_000_000, "min"),
+                     (1_000_000_000, "s"), (1_000_000, "ms"), (1_000, "us"), (1, "ns")];
+        let nanos = self.convert(duration, Resolution::Nanosecond);
+        match units.iter().find(|(size, _)| nanos % size == 0) {
+            Some((size, name)) if nanos != 0 => format!("{}{}", nanos / size, name),
+            _ => duration.to_string(),
+        }
+    }
+}
+
+impl DateTimeIndex {
+
+    /// Formatted timestamps. Only the first and the last few are printed for long index
+    fn format_values(&self) -> String {
+        let format = |values: &[i64]| values.iter().map(|&t| self.resolution.format_timestamp(t)).collect::<Vec<String>>().join(", ");
+        if self.len() <= 2 * PRINTED_EDGE {
+            format(&self.values)
+        } else {
+            format!("{}, ..., {}", format(&self.values[..PRINTED_EDGE]), format(&self.values[self.len() - PRINTED_EDGE..]))
+        }
+    }
+}
+
+impl fmt::Display for DateTimeIndex {
+    /// Summary with formatted timestamps, like:
+    /// `DateTimeIndex([2020-01-01 00:00:00, ..., 2020-01-01 09:00:00], len=10, min=..., max=..., freq=1h)`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::index::DateTimeIndex;
+    ///
+    /// let index = DateTimeIndex::new(vec![0, 60_000, 120_000]);
+    /// assert_eq!(index.to_string(), "DateTimeIndex([1970-01-01 00:00:00, 1970-01-01 00:01:00, 1970-01-01 00:02:00], \
+    ///                                len=3, min=1970-01-01 00:00:00, max=1970-01-01 00:02:00, freq=1min)");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DateTimeIndex([{}], len={}", self.format_values(), self.len())?;
+        if let (Some(min), Some(max)) = (self.min(), self.max()) {
+            write!(f, ", min={}, max={}", self.resolution.format_timestamp(min), self.resolution.format_timestamp(max))?;
+        }
+        if self.len() > 1 {
+            write!(f, ", freq={}", self.resolution.format_duration(self.infer_sample_rate()))?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Debug for DateTimeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DateTimeIndex")
+            .field("len", &self.len())
+            .field("resolution", &self.resolution)
+            .field("values", &format_args!("[{}]", self.format_values()))
+            .field("raw", &self.values)
+            .finish()
+    }
+}
+
 impl cmp::PartialEq for DateTimeIndex {
 
     fn eq(&self, other: &Self) -> bool {
-        self.values == other.values
+        self.values == other.values && self.resolution == other.resolution
+    }
+}
+
+impl Eq for DateTimeIndex {}
+
+impl Hash for DateTimeIndex {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.values.hash(state);
+        self.resolution.hash(state);
     }
 }
 
@@ -136,6 +658,12 @@ mod tests {
         assert_eq!(index.len(), 5);
     }
 
+    #[test]
+    fn test_position_of_empty() {
+        assert_eq!(DateTimeIndex::new(vec![]).position_of(1), Err(0));
+        assert_eq!(DateTimeIndex::new(vec![1, 1, 1]).position_of(1), Ok(0));
+    }
+
     #[test]
     fn test_monotonic_empty() {
         let index = DateTimeIndex::new(vec![]);
@@ -148,4 +676,79 @@ mod tests {
         assert!(index.is_monotonic());
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_date_range_month_end() {
+        // 2020-01-31, 2020-02-29, 2020-03-31
+        let index = DateTimeIndex::date_range(1_580_428_800_000, 1_585_612_800_000, Frequency::Month);
+        assert_eq!(index.values, vec![1_580_428_800_000, 1_582_934_400_000, 1_585_612_800_000]);
+    }
+
+    #[test]
+    fn test_date_range_invalid_step() {
+        assert!(DateTimeIndex::date_range(0, 10, Frequency::Millisecond(0)).is_empty());
+        assert!(DateTimeIndex::date_range(0, 10, Frequency::Second(-1)).is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(DateTimeIndex::new(vec![]).to_string(), "DateTimeIndex([], len=0)");
+        let index = DateTimeIndex::new((0..10).map(|i| 1_577_836_800_000 + i * 3_600_000).collect());
+        assert_eq!(index.to_string(), "DateTimeIndex([2020-01-01 00:00:00, 2020-01-01 01:00:00, 2020-01-01 02:00:00, ..., \
+                                       2020-01-01 07:00:00, 2020-01-01 08:00:00, 2020-01-01 09:00:00], \
+                                       len=10, min=2020-01-01 00:00:00, max=2020-01-01 09:00:00, freq=1h)");
+        let index = DateTimeIndex::new(vec![1, 1001]).to_resolution(Resolution::Nanosecond);
+        assert!(index.to_string().ends_with("max=1970-01-01 00:00:01.001, freq=1s)"));
+        assert!(format!("{:?}", index).contains("resolution: Nanosecond"));
+    }
+
+    #[test]
+    fn test_slice_range_outside() {
+        let index = DateTimeIndex::new(vec![10, 20, 30]);
+        assert_eq!(index.slice_range(40, 50).0, 3..3);
+        assert_eq!(index.slice_range(0, 5).1, &[] as &[i64]);
+        assert_eq!(index.slice_range(30, 20).0, 2..2);
+        assert_eq!(index.slice_range(0, 100).1, &[10, 20, 30]);
+    }
+
+    #[test]
+    fn test_first_last_empty() {
+        let index = DateTimeIndex::new(vec![]);
+        assert_eq!(index.first(), None);
+        assert_eq!(index.max(), None);
+    }
+
+    #[test]
+    fn test_sampling_report() {
+        assert_eq!(DateTimeIndex::new(vec![1]).sampling_report(), None);
+        assert!(DateTimeIndex::new(vec![1]).deltas().is_empty());
+        let report = DateTimeIndex::new(vec![0, 1000, 2000, 3000]).sampling_report().unwrap();
+        assert_eq!(report.jitter, 0.0);
+        assert_eq!(report.gap_count, 0);
+        assert_eq!(report.duplicate_count, 0);
+        let report = DateTimeIndex::new(vec![0, 10, 5]).sampling_report().unwrap();
+        assert_eq!(report.min_delta, -5);
+        assert_eq!(report.jitter, 7.5);
+    }
+
+    #[test]
+    fn test_sampling_report_extreme_timestamps() {
+        let index = DateTimeIndex::new(vec![i64::MIN, i64::MAX]);
+        assert_eq!(index.deltas().values, vec![2f64.powi(64)]);
+        let report = index.sampling_report().unwrap();
+        assert_eq!(report.max_delta, i64::MAX);
+        let report = DateTimeIndex::new(vec![0, 4_000_000_000_000_000_000, i64::MAX]).sampling_report().unwrap();
+        assert_eq!(report.median_delta, 5_223_372_036_854_775_807);
+        assert_eq!(report.gap_count, 0);
+    }
+
+    #[test]
+    fn test_resolution_round_trip() {
+        let index = DateTimeIndex::new(vec![-1_500, 0, 1_682_935_200_123]);
+        let nanos = index.to_resolution(Resolution::Nanosecond);
+        assert_eq!(nanos.values[2], 1_682_935_200_123_000_000);
+        assert_eq!(nanos.to_resolution(Resolution::Millisecond), index);
+        assert_eq!(index.to_resolution(Resolution::Second).values, vec![-2, 0, 1_682_935_200]);
+        assert_ne!(nanos, index.clone().with_resolution(Resolution::Nanosecond));
+    }
+
+}
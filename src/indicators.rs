@@ -0,0 +1,245 @@
+//! Technical indicators used in financial analysis
+//!
+//! Indicators return series with the same index as the input.
+//! Data points without enough history get NaN value.
+//!
+
+use crate::TimeSeries;
+use crate::ohlc::Ohlc;
+use crate::rolling::ewma;
+use crate::stats::mean;
+
+
+/// Moving Average Convergence Divergence
+///   * macd - Difference between fast and slow EMA
+///   * signal - EMA of the macd line
+///   * histogram - macd - signal
+#[derive(Clone, Debug, PartialEq)]
+pub struct Macd {
+    pub macd: TimeSeries,
+    pub signal: TimeSeries,
+    pub histogram: TimeSeries,
+}
+
+/// Bollinger Bands
+///   * middle - Moving average
+///   * upper - Moving average + k standard deviations
+///   * lower - Moving average - k standard deviations
+#[derive(Clone, Debug, PartialEq)]
+pub struct BollingerBands {
+    pub middle: TimeSeries,
+    pub upper: TimeSeries,
+    pub lower: TimeSeries,
+}
+
+/// Stochastic oscillator
+///   * k - Position of the close price in the high-low range (0-100)
+///   * d - Moving average of k
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stochastic {
+    pub k: TimeSeries,
+    pub d: TimeSeries,
+}
+
+
+impl TimeSeries {
+
+    /// Relative Strength Index (0-100) with Wilder's smoothing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 1.0, 2.0]);
+    /// let rsi = ts.rsi(2);
+    /// assert!(rsi.values[1].is_nan());
+    /// assert_eq!(rsi.values[2], 50.0);
+    /// assert_eq!(rsi.values[3], 75.0);
+    /// ```
+    pub fn rsi(&self, period: usize) -> TimeSeries {
+        let changes: Vec<f64> = self.values.windows(2).map(|w| w[1] - w[0]).collect();
+        let gains: Vec<f64> = changes.iter().map(|c| c.max(0.0)).collect();
+        let losses: Vec<f64> = changes.iter().map(|c| (-c).max(0.0)).collect();
+        let avg_gain = wilder(&gains, period);
+        let avg_loss = wilder(&losses, period);
+        let mut output = self.clone();
+        // The first data point has no change
+        output.values = vec![f64::NAN; self.len().min(1)];
+        output.values.extend(avg_gain.iter().zip(&avg_loss).map(|(&g, &l)| {
+            if l == 0.0 && g == 0.0 { 50.0 }
+            else if l == 0.0 { 100.0 }
+            else { 100.0 - 100.0 / (1.0 + g / l) }
+        }));
+        output.logged(self, "rsi", || vec![("period", period.to_string())])
+    }
+
+    /// Moving Average Convergence Divergence. Common parameters are 12, 26, 9.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 1.0, 1.0]);
+    /// let macd = ts.macd(12, 26, 9);
+    /// assert_eq!(macd.histogram.values, vec![0.0, 0.0, 0.0]);
+    /// ```
+    pub fn macd(&self, fast: usize, slow: usize, signal: usize) -> Macd {
+        let fast_ema = ewma(&self.values, 2.0 / (fast as f64 + 1.0));
+        let slow_ema = ewma(&self.values, 2.0 / (slow as f64 + 1.0));
+        let line: Vec<f64> = fast_ema.iter().zip(&slow_ema).map(|(f, s)| f - s).collect();
+        let signal_line = ewma(&line, 2.0 / (signal as f64 + 1.0));
+        let histogram = line.iter().zip(&signal_line).map(|(m, s)| m - s).collect();
+        let params = || vec![("fast", fast.to_string()), ("slow", slow.to_string()), ("signal", signal.to_string())];
+        let with_values = |values: Vec<f64>| {
+            let mut output = self.clone();
+            output.values = values;
+            output.logged(self, "macd", params)
+        };
+        Macd {
+            macd: with_values(line),
+            signal: with_values(signal_line),
+            histogram: with_values(histogram),
+        }
+    }
+
+    /// Bollinger Bands. Bands are k sample standard deviations from the moving average.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+    /// let bands = ts.bollinger_bands(3, 2.0);
+    /// assert_eq!(bands.middle.values[2], 2.0);
+    /// assert_eq!(bands.upper.values[2], 4.0);
+    /// assert_eq!(bands.lower.values[2], 0.0);
+    /// ```
+    pub fn bollinger_bands(&self, window: usize, k: f64) -> BollingerBands {
+        let middle = self.rolling_mean(window);
+        let std = self.rolling_std(window);
+        let mut upper = middle.clone();
+        upper.values = middle.values.iter().zip(&std.values).map(|(m, s)| m + k * s).collect();
+        let mut lower = middle.clone();
+        lower.values = middle.values.iter().zip(&std.values).map(|(m, s)| m - k * s).collect();
+        BollingerBands { middle, upper, lower }
+    }
+}
+
+
+/// Average True Range with Wilder's smoothing.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::indicators::atr;
+///
+/// let ticks = TimeSeries::new(vec![0, 5, 10, 15], vec![10.0, 12.0, 11.0, 15.0]);
+/// let candles = ticks.ohlc(10);
+/// let atr = atr(&candles, 2);
+/// assert!(atr.values[0].is_nan());
+/// assert_eq!(atr.values[1], 3.0);
+/// ```
+pub fn atr(candles: &Ohlc, period: usize) -> TimeSeries {
+    let high = &candles.high.values;
+    let low = &candles.low.values;
+    let close = &candles.close.values;
+    let true_range: Vec<f64> = (0..high.len())
+        .map(|i| {
+            let range = high[i] - low[i];
+            if i == 0 {
+                range
+            } else {
+                range.max((high[i] - close[i-1]).abs()).max((low[i] - close[i-1]).abs())
+            }
+        })
+        .collect();
+    let mut output = candles.close.clone();
+    output.values = wilder(&true_range, period);
+    output.logged(&candles.close, "atr", || vec![("period", period.to_string())])
+}
+
+/// Stochastic oscillator. %K is calculated over k_period candles and %D is its moving average.
+/// %K is NaN if the high-low range is 0.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::indicators::stochastic;
+///
+/// let ticks = TimeSeries::new(vec![0, 5, 10, 15, 20, 25], vec![1.0, 3.0, 2.0, 4.0, 3.0, 3.5]);
+/// let stoch = stochastic(&ticks.ohlc(10), 2, 2);
+/// assert_eq!(stoch.k.values[1], 100.0);
+/// assert_eq!(stoch.k.values[2], 75.0);
+/// assert_eq!(stoch.d.values[2], 87.5);
+/// ```
+pub fn stochastic(candles: &Ohlc, k_period: usize, d_period: usize) -> Stochastic {
+    let high = candles.high.rolling_max(k_period);
+    let low = candles.low.rolling_min(k_period);
+    let mut k = candles.close.clone();
+    k.values = (0..k.len())
+        .map(|i| {
+            let range = high.values[i] - low.values[i];
+            if range == 0.0 { f64::NAN } else { 100.0 * (k.values[i] - low.values[i]) / range }
+        })
+        .collect();
+    let k = k.logged(&candles.close, "stochastic", || vec![("k_period", k_period.to_string())]);
+    let d = k.rolling_mean(d_period);
+    Stochastic { k, d }
+}
+
+
+/// Wilder's moving average. The first value is the simple mean of the first period values.
+fn wilder(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || period > values.len() {
+        return vec![f64::NAN; values.len()];
+    }
+    let mut output = vec![f64::NAN; period - 1];
+    let mut avg = mean(&values[..period]);
+    output.push(avg);
+    for &v in &values[period..] {
+        avg = (avg * (period - 1) as f64 + v) / period as f64;
+        output.push(avg);
+    }
+    output
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_rsi_rising() {
+        let ts = TimeSeries::new((0..10).collect(), (0..10).map(|i| i as f64).collect());
+        let rsi = ts.rsi(3);
+        assert_eq!(rsi.len(), 10);
+        assert!(rsi.values[..3].iter().all(|v| v.is_nan()));
+        assert!(rsi.values[3..].iter().all(|&v| v == 100.0));
+        assert!(TimeSeries::empty().rsi(3).is_empty());
+    }
+
+    #[test]
+    fn test_macd_trend() {
+        let ts = TimeSeries::new((0..50).collect(), (0..50).map(|i| i as f64).collect());
+        let macd = ts.macd(12, 26, 9);
+        assert_eq!(macd.macd.len(), 50);
+        assert!(macd.macd.values[49] > 0.0);
+        assert_approx_eq!(macd.histogram.values[0], 0.0);
+    }
+
+    #[test]
+    fn test_wilder() {
+        let avg = wilder(&[2.0, 4.0, 6.0], 2);
+        assert!(avg[0].is_nan());
+        assert_eq!(avg[1..], [3.0, 4.5]);
+    }
+}
@@ -0,0 +1,557 @@
+//! Compact binary format for fast persistence of large series
+//!
+//! File layout (all numbers are little endian):
+//!   * Header (16 bytes) - magic `TSB1`, encoding (u8), flags (u8), timestamp resolution (u8),
+//!     1 reserved byte, number of data points (u64)
+//!   * Plain encoding - all timestamps (i64) followed by all values (f64)
+//!   * Delta and Gorilla encodings - chunks of up to 65536 data points. Each chunk starts with
+//!     the number of points, timestamps byte length and values byte length (3 x u32).
+//!     Timestamps are stored as zigzag varint deltas. Values are stored as f64 (Delta)
+//!     or XOR compressed (Gorilla)
+//!   * Quality - Only if the flags have bit 1 set. One byte (quality code) for each data point
+//!   * Metadata - Only if the flags have bit 0 set. Length (u32) and UTF-8 text with `key: value` lines
+//!
+//! Provenance is not stored.
+//!
+//! Files with Plain encoding can be memory mapped with `open_mmap` (requires `mmap` feature).
+//! Data is then paged from disk only when it is accessed.
+//!
+
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use crate::TimeSeries;
+use crate::error::{Error, Result};
+use crate::index::Resolution;
+use crate::metadata::Metadata;
+#[cfg(feature = "mmap")]
+use crate::{DataPoint, ToSeries};
+#[cfg(feature = "mmap")]
+use crate::slice::TimeSeriesSlice;
+use crate::encoding::{BitReader, BitWriter, XorDecoder, XorEncoder, read_varint, write_varint};
+
+
+const MAGIC: &[u8; 4] = b"TSB1";
+pub(crate) const HEADER_SIZE: usize = 16;
+const CHUNK_SIZE: usize = 65536;
+const FLAG_METADATA: u8 = 1;
+const FLAG_QUALITY: u8 = 2;
+
+
+/// Read-only series backed by memory mapped file
+#[cfg(feature = "mmap")]
+pub struct MmapTimeSeries {
+    mmap: memmap2::Mmap,
+    len: usize,
+    resolution: Resolution,
+    has_quality: bool,
+    metadata: Metadata,
+}
+
+
+/// How data points are encoded
+///   * Plain - Fixed size numbers. Fastest, and can be memory mapped
+///   * Delta - Varint delta encoded timestamps and plain values
+///   * Gorilla - Varint delta encoded timestamps and XOR compressed values.
+///     Smallest for slowly changing values
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Plain,
+    Delta,
+    Gorilla,
+}
+
+impl Encoding {
+
+    fn code(self) -> u8 {
+        match self {
+            Encoding::Plain => 0,
+            Encoding::Delta => 1,
+            Encoding::Gorilla => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Encoding> {
+        match code {
+            0 => Some(Encoding::Plain),
+            1 => Some(Encoding::Delta),
+            2 => Some(Encoding::Gorilla),
+            _ => None,
+        }
+    }
+}
+
+
+/// Save series in binary file
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(ts), fields(len = ts.len()), err))]
+pub fn write(file_path: &str, ts: &TimeSeries, encoding: Encoding) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(file_path)?);
+    write_to(&mut writer, ts, encoding)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Load series from binary file
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+pub fn read(file_path: &str) -> Result<TimeSeries> {
+    read_from(BufReader::new(File::open(file_path)?))
+}
+
+/// Write series in binary format
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::io::binary::{self, Encoding};
+///
+/// let ts = TimeSeries::new(vec![1000, 2000, 3000], vec![1.5, 1.5, 2.0]);
+/// let mut bytes = vec![];
+/// binary::write_to(&mut bytes, &ts, Encoding::Gorilla).unwrap();
+/// assert_eq!(binary::read_from(&bytes[..]).unwrap(), ts);
+/// ```
+pub fn write_to<W: Write>(writer: &mut W, ts: &TimeSeries, encoding: Encoding) -> Result<()> {
+    let mut flags = if ts.metadata.is_empty() { 0 } else { FLAG_METADATA };
+    if ts.quality.is_some() {
+        flags |= FLAG_QUALITY;
+    }
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[encoding.code(), flags, ts.index.resolution.code(), 0])?;
+    writer.write_all(&(ts.len() as u64).to_le_bytes())?;
+    match encoding {
+        Encoding::Plain => {
+            for t in ts.index.iter() {
+                writer.write_all(&t.to_le_bytes())?;
+            }
+            for v in &ts.values {
+                writer.write_all(&v.to_le_bytes())?;
+            }
+        },
+        Encoding::Delta | Encoding::Gorilla => {
+            let chunks = ts.index.values.chunks(CHUNK_SIZE).zip(ts.values.chunks(CHUNK_SIZE));
+            for (index, values) in chunks {
+                let timestamps = encode_timestamps(index);
+                let values = if encoding == Encoding::Delta { encode_plain(values) } else { encode_xor(values) };
+                writer.write_all(&(index.len() as u32).to_le_bytes())?;
+                writer.write_all(&(timestamps.len() as u32).to_le_bytes())?;
+                writer.write_all(&(values.len() as u32).to_le_bytes())?;
+                writer.write_all(&timestamps)?;
+                writer.write_all(&values)?;
+            }
+        },
+    }
+    if let Some(quality) = &ts.quality {
+        writer.write_all(quality)?;
+    }
+    if flags & FLAG_METADATA != 0 {
+        let text = ts.metadata.to_lines().join("\n");
+        writer.write_all(&(text.len() as u32).to_le_bytes())?;
+        writer.write_all(text.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Read series in binary format
+pub fn read_from<R: Read>(mut reader: R) -> Result<TimeSeries> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    decode(&bytes)
+}
+
+/// Encoding, timestamp resolution and number of data points from the header
+pub(crate) fn read_header(bytes: &[u8]) -> Result<(Encoding, Resolution, usize)> {
+    if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+        return Err(Error::invalid_format("Not a binary series file"));
+    }
+    let encoding = Encoding::from_code(bytes[4]).ok_or_else(|| Error::invalid_format("Unknown encoding"))?;
+    let resolution = Resolution::from_code(bytes[6]).ok_or_else(|| Error::invalid_index("Unknown timestamp resolution"))?;
+    let count = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+    Ok((encoding, resolution, count))
+}
+
+/// Decode the whole file content
+pub(crate) fn decode(bytes: &[u8]) -> Result<TimeSeries> {
+    let (encoding, resolution, count) = read_header(bytes)?;
+    let body = &bytes[HEADER_SIZE..];
+    let (mut ts, mut end) = match encoding {
+        Encoding::Plain => {
+            if count.checked_mul(16).is_none_or(|size| body.len() < size) {
+                return Err(unexpected_end());
+            }
+            let index = body[..count * 8].chunks_exact(8)
+                .map(|b| i64::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            let values = decode_plain(&body[count * 8..count * 16]);
+            (TimeSeries::new(index, values), count * 16)
+        },
+        Encoding::Delta | Encoding::Gorilla => {
+            // Count comes from the file, so it is not trusted for allocation
+            let mut index = Vec::with_capacity(count.min(body.len()));
+            let mut values = Vec::with_capacity(count.min(body.len()));
+            let mut pos = 0;
+            while index.len() < count {
+                let header = body.get(pos..pos + 12).ok_or_else(unexpected_end)?;
+                let n = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+                let ts_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+                let values_len = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+                pos += 12;
+                let ts_bytes = body.get(pos..pos + ts_len).ok_or_else(unexpected_end)?;
+                pos += ts_len;
+                let value_bytes = body.get(pos..pos + values_len).ok_or_else(unexpected_end)?;
+                pos += values_len;
+                index.extend(decode_timestamps(ts_bytes, n).ok_or_else(|| Error::invalid_index("Invalid timestamps"))?);
+                if encoding == Encoding::Delta {
+                    values.extend(decode_plain(value_bytes));
+                } else {
+                    values.extend(decode_xor(value_bytes, n).ok_or_else(|| Error::invalid_format("Invalid values"))?);
+                }
+                if n == 0 {
+                    return Err(Error::invalid_format("Empty chunk"));
+                }
+                if index.len() != values.len() {
+                    return Err(Error::LengthMismatch { expected: index.len(), actual: values.len() });
+                }
+            }
+            (TimeSeries::new(index, values), pos)
+        },
+    };
+    ts.index.resolution = resolution;
+    if bytes[5] & FLAG_QUALITY != 0 {
+        let quality = body.get(end..end + ts.len()).ok_or_else(unexpected_end)?;
+        ts.quality = Some(quality.to_vec());
+        end += ts.len();
+    }
+    if bytes[5] & FLAG_METADATA != 0 {
+        ts.metadata = decode_metadata(&body[end..]).ok_or_else(|| Error::invalid_format("Invalid metadata"))?;
+    }
+    Ok(ts)
+}
+
+fn unexpected_end() -> Error {
+    Error::invalid_format("Unexpected end of data")
+}
+
+fn decode_metadata(bytes: &[u8]) -> Option<Metadata> {
+    let len = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?) as usize;
+    let text = std::str::from_utf8(bytes.get(4..4 + len)?).ok()?;
+    let mut metadata = Metadata::default();
+    text.lines().for_each(|line| metadata.parse_line(line));
+    Some(metadata)
+}
+
+
+/// Open binary file with Plain encoding as memory mapped series.
+/// The file should not be modified while it is mapped.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::io::binary::{self, Encoding};
+///
+/// let file_path = std::env::temp_dir().join("timeseries_doc.tsb");
+/// let file_path = file_path.to_str().unwrap();
+/// let ts = TimeSeries::new(vec![1000, 2000, 3000], vec![1.5, 1.5, 2.0]);
+/// binary::write(file_path, &ts, Encoding::Plain).unwrap();
+/// let mapped = binary::open_mmap(file_path).unwrap();
+/// assert_eq!(mapped.values(), &[1.5, 1.5, 2.0]);
+/// ```
+#[cfg(feature = "mmap")]
+pub fn open_mmap(file_path: &str) -> Result<MmapTimeSeries> {
+    if cfg!(target_endian = "big") {
+        return Err(Error::Unsupported("Memory mapped files are only supported on little endian platforms".to_owned()));
+    }
+    let file = File::open(file_path)?;
+    // Safety: the file is mapped read-only. Modifying it while mapped is documented as unsupported.
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let (encoding, resolution, len) = read_header(&mmap)?;
+    if encoding != Encoding::Plain {
+        return Err(Error::Unsupported("Only Plain encoding can be memory mapped".to_owned()));
+    }
+    if len.checked_mul(16).and_then(|size| size.checked_add(HEADER_SIZE)).is_none_or(|size| mmap.len() < size) {
+        return Err(unexpected_end());
+    }
+    let has_quality = mmap[5] & FLAG_QUALITY != 0;
+    let mut end = HEADER_SIZE + len * 16;
+    if has_quality {
+        end += len;
+        if mmap.len() < end {
+            return Err(unexpected_end());
+        }
+    }
+    let metadata = if mmap[5] & FLAG_METADATA != 0 {
+        decode_metadata(&mmap[end..]).ok_or_else(|| Error::invalid_format("Invalid metadata"))?
+    } else {
+        Metadata::default()
+    };
+    Ok(MmapTimeSeries { mmap, len, resolution, has_quality, metadata })
+}
+
+#[cfg(feature = "mmap")]
+impl MmapTimeSeries {
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Unit of the timestamps
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Timestamps
+    pub fn index(&self) -> &[i64] {
+        let bytes = &self.mmap[HEADER_SIZE..HEADER_SIZE + self.len * 8];
+        // Safety: mapping is page aligned and the header size is a multiple of 8,
+        // so there is no prefix. Every bit pattern is a valid i64.
+        let (prefix, index, _) = unsafe { bytes.align_to::<i64>() };
+        assert!(prefix.is_empty());
+        index
+    }
+
+    /// Values
+    pub fn values(&self) -> &[f64] {
+        let start = HEADER_SIZE + self.len * 8;
+        let bytes = &self.mmap[start..start + self.len * 8];
+        // Safety: see index
+        let (prefix, values, _) = unsafe { bytes.align_to::<f64>() };
+        assert!(prefix.is_empty());
+        values
+    }
+
+    /// Quality codes. None if the file has no quality channel
+    pub fn quality(&self) -> Option<&[u8]> {
+        let start = HEADER_SIZE + self.len * 16;
+        if self.has_quality { Some(&self.mmap[start..start + self.len]) } else { None }
+    }
+
+    /// Return nth element of the series
+    pub fn nth(&self, pos: usize) -> Option<DataPoint> {
+        if pos < self.len {
+            let quality = self.quality().map(|q| q[pos]);
+            Some(DataPoint { timestamp: self.index()[pos], value: self.values()[pos], quality })
+        } else {
+            None
+        }
+    }
+
+    /// Copy data points in the time range [start, end) into TimeSeries
+    pub fn range(&self, start: i64, end: i64) -> TimeSeries {
+        let index = self.index();
+        let from = index.partition_point(|&t| t < start);
+        let to = from.max(index.partition_point(|&t| t < end));
+        let mut ts = TimeSeries::new(index[from..to].to_vec(), self.values()[from..to].to_vec());
+        ts.index.resolution = self.resolution;
+        ts.quality = self.quality().map(|q| q[from..to].to_vec());
+        ts
+    }
+
+    /// Borrowed view of all data points. Data is not copied
+    pub fn as_slice(&self) -> TimeSeriesSlice<'_> {
+        let slice = TimeSeries::from_slices(self.index(), self.values())
+            .expect("Index and values have the same length")
+            .with_resolution(self.resolution)
+            .with_metadata(&self.metadata);
+        match self.quality() {
+            Some(quality) => slice.with_quality(quality),
+            None => slice,
+        }
+    }
+
+    /// Iterate over data points
+    pub fn iter(&self) -> impl Iterator<Item = DataPoint> + '_ {
+        (0..self.len).filter_map(move |pos| self.nth(pos))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ToSeries for MmapTimeSeries {
+    /// Copy all data points into memory
+    fn to_series(&self) -> TimeSeries {
+        let mut ts = TimeSeries::new(self.index().to_vec(), self.values().to_vec());
+        ts.index.resolution = self.resolution;
+        ts.quality = self.quality().map(|q| q.to_vec());
+        ts.metadata = self.metadata.clone();
+        ts
+    }
+}
+
+fn encode_timestamps(index: &[i64]) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut prev = 0i64;
+    for &t in index {
+        write_varint(t.wrapping_sub(prev), &mut bytes);
+        prev = t;
+    }
+    bytes
+}
+
+fn decode_timestamps(bytes: &[u8], n: usize) -> Option<Vec<i64>> {
+    let mut pos = 0;
+    let mut prev = 0i64;
+    (0..n).map(|_| {
+        prev = prev.wrapping_add(read_varint(bytes, &mut pos)?);
+        Some(prev)
+    }).collect()
+}
+
+fn encode_plain(values: &[f64]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_plain(bytes: &[u8]) -> Vec<f64> {
+    bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect()
+}
+
+fn encode_xor(values: &[f64]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    let mut encoder = XorEncoder::new();
+    values.iter().for_each(|&v| encoder.encode(v, &mut writer));
+    writer.into_bytes()
+}
+
+fn decode_xor(bytes: &[u8], n: usize) -> Option<Vec<f64>> {
+    let mut reader = BitReader::new(bytes);
+    let mut decoder = XorDecoder::new();
+    (0..n).map(|_| decoder.decode(&mut reader)).collect()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(n: usize) -> TimeSeries {
+        let index = (0..n as i64).map(|i| 1_600_000_000_000 + i * 1000).collect();
+        let values = (0..n).map(|i| 20.0 + ((i / 10) % 5) as f64 * 0.5).collect();
+        TimeSeries::new(index, values)
+    }
+
+    #[test]
+    fn test_round_trip_all_encodings() {
+        let ts = sensor(CHUNK_SIZE + 10);
+        for &encoding in &[Encoding::Plain, Encoding::Delta, Encoding::Gorilla] {
+            let mut bytes = vec![];
+            write_to(&mut bytes, &ts, encoding).unwrap();
+            assert_eq!(read_from(&bytes[..]).unwrap(), ts);
+        }
+    }
+
+    #[test]
+    fn test_compressed_is_smaller() {
+        let ts = sensor(10_000);
+        let mut plain = vec![];
+        write_to(&mut plain, &ts, Encoding::Plain).unwrap();
+        let mut gorilla = vec![];
+        write_to(&mut gorilla, &ts, Encoding::Gorilla).unwrap();
+        assert!(gorilla.len() * 5 < plain.len());
+    }
+
+    #[test]
+    fn test_empty_and_invalid() {
+        let mut bytes = vec![];
+        write_to(&mut bytes, &TimeSeries::empty(), Encoding::Gorilla).unwrap();
+        assert!(read_from(&bytes[..]).unwrap().is_empty());
+        assert!(read_from(&b"time,value\n"[..]).is_err());
+        let mut truncated = vec![];
+        write_to(&mut truncated, &sensor(100), Encoding::Delta).unwrap();
+        truncated.truncate(50);
+        assert!(matches!(read_from(&truncated[..]), Err(Error::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_corrupted_data() {
+        let ts = sensor(200).with_quality(vec![1; 200]).with_name("sensor");
+        for &encoding in &[Encoding::Plain, Encoding::Delta, Encoding::Gorilla] {
+            let mut bytes = vec![];
+            write_to(&mut bytes, &ts, encoding).unwrap();
+            for bit in 0..bytes.len() * 8 {
+                let mut corrupted = bytes.clone();
+                corrupted[bit / 8] ^= 1 << (bit % 8);
+                // Any result is fine as long as the reader doesn't panic
+                let _ = read_from(&corrupted[..]);
+            }
+            for len in 0..bytes.len() {
+                let _ = read_from(&bytes[..len]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let ts = sensor(10).with_name("temperature").with_unit("C").with_tag("room", "1");
+        for &encoding in &[Encoding::Plain, Encoding::Gorilla] {
+            let mut bytes = vec![];
+            write_to(&mut bytes, &ts, encoding).unwrap();
+            assert_eq!(read_from(&bytes[..]).unwrap().metadata, ts.metadata);
+            bytes.truncate(bytes.len() - 2);
+            assert!(read_from(&bytes[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_quality() {
+        let ts = sensor(10).with_quality(vec![0, 1, 2, 3]).with_name("temperature");
+        for &encoding in &[Encoding::Plain, Encoding::Delta, Encoding::Gorilla] {
+            let mut bytes = vec![];
+            write_to(&mut bytes, &ts, encoding).unwrap();
+            let loaded = read_from(&bytes[..]).unwrap();
+            assert_eq!(loaded.quality, ts.quality);
+            assert_eq!(loaded.metadata, ts.metadata);
+        }
+    }
+
+    #[test]
+    fn test_resolution() {
+        let ts = sensor(10).to_resolution(Resolution::Nanosecond);
+        for &encoding in &[Encoding::Plain, Encoding::Delta, Encoding::Gorilla] {
+            let mut bytes = vec![];
+            write_to(&mut bytes, &ts, encoding).unwrap();
+            assert_eq!(read_from(&bytes[..]).unwrap(), ts);
+        }
+    }
+
+    #[test]
+    fn test_file() {
+        let file_path = std::env::temp_dir().join("timeseries_binary.tsb");
+        let file_path = file_path.to_str().unwrap();
+        let ts = sensor(100);
+        write(file_path, &ts, Encoding::Delta).unwrap();
+        assert_eq!(read(file_path).unwrap(), ts);
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_mmap() {
+        let file_path = std::env::temp_dir().join("timeseries_mmap.tsb");
+        let file_path = file_path.to_str().unwrap();
+        let ts = sensor(1000).with_name("sensor").with_quality(vec![1; 1000]);
+        write(file_path, &ts, Encoding::Plain).unwrap();
+        let mapped = open_mmap(file_path).unwrap();
+        assert_eq!(mapped.metadata().name.as_deref(), Some("sensor"));
+        assert_eq!(mapped.len(), 1000);
+        assert_eq!(mapped.to_series(), ts);
+        assert_eq!(mapped.nth(999), ts.last());
+        assert_eq!(mapped.nth(0).unwrap().quality, Some(1));
+        assert_eq!(mapped.iter().count(), 1000);
+        assert_eq!(mapped.range(1_600_000_001_000, 1_600_000_003_000).len(), 2);
+        assert_eq!(mapped.as_slice().range(1_600_000_001_000..1_600_000_003_000).len(), 2);
+        assert_eq!(mapped.as_slice().to_series(), ts);
+        // Changing the mapped file is undefined behavior, so the map is dropped first
+        drop(mapped);
+        write(file_path, &ts, Encoding::Gorilla).unwrap();
+        assert!(open_mmap(file_path).is_err());
+    }
+}
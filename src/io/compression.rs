@@ -0,0 +1,175 @@
+//! Transparent compression based on the file extension
+//!   * `.gz` - gzip (requires `gzip` feature)
+//!   * `.zst` - zstd (requires `zstd` feature)
+//!
+//! Other files are read and written without compression.
+//!
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+
+enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Compression used for the file. Error if the required feature is not enabled
+fn compression(file_path: &str) -> Result<Compression> {
+    let compression = match Path::new(file_path).extension().and_then(|e| e.to_str()) {
+        Some("gz") => Compression::Gzip,
+        Some("zst") => Compression::Zstd,
+        _ => Compression::None,
+    };
+    match compression {
+        Compression::Gzip if !cfg!(feature = "gzip") => Err(unsupported(file_path)),
+        Compression::Zstd if !cfg!(feature = "zstd") => Err(unsupported(file_path)),
+        _ => Ok(compression),
+    }
+}
+
+/// Open file for reading with decompression
+pub(crate) fn open(file_path: &str) -> Result<Box<dyn Read>> {
+    decompress(file_path, File::open(file_path)?)
+}
+
+/// Decompress data read from the file with the given path
+pub(crate) fn decompress<'a, R: Read + 'a>(file_path: &str, reader: R) -> Result<Box<dyn Read + 'a>> {
+    let compression = compression(file_path)?;
+    let file = BufReader::new(reader);
+    match compression {
+        Compression::None => Ok(Box::new(file)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::read::MultiGzDecoder::new(file))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(zstd::stream::read::Decoder::with_buffer(file)?)),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("Checked by compression()"),
+    }
+}
+
+/// File writer with optional compression.
+/// `finish` has to be called after the last write to complete the compressed stream.
+pub(crate) enum Writer {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+}
+
+impl Writer {
+
+    /// Write the end of the compressed stream and flush the data to the file
+    pub(crate) fn finish(self) -> Result<()> {
+        #[allow(clippy::infallible_destructuring_match)]
+        let mut file = match self {
+            Writer::Plain(file) => file,
+            #[cfg(feature = "gzip")]
+            Writer::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            Writer::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Writer::Plain(file) => file.write(buf),
+            #[cfg(feature = "gzip")]
+            Writer::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Writer::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Writer::Plain(file) => file.flush(),
+            #[cfg(feature = "gzip")]
+            Writer::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Writer::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Create file for writing with compression
+pub(crate) fn create(file_path: &str) -> Result<Writer> {
+    let compression = compression(file_path)?;
+    writer(File::create(file_path)?, compression)
+}
+
+/// Open file for appending with compression. Compressed data is added as a new stream,
+/// which is supported by the readers.
+pub(crate) fn append(file_path: &str) -> Result<Writer> {
+    let compression = compression(file_path)?;
+    writer(OpenOptions::new().create(true).append(true).open(file_path)?, compression)
+}
+
+fn writer(file: File, compression: Compression) -> Result<Writer> {
+    let file = BufWriter::new(file);
+    match compression {
+        Compression::None => Ok(Writer::Plain(file)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Writer::Gzip(flate2::write::GzEncoder::new(file, flate2::Compression::default()))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Writer::Zstd(zstd::stream::write::Encoder::new(file, 0)?)),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("Checked by compression()"),
+    }
+}
+
+fn unsupported(file_path: &str) -> Error {
+    Error::Unsupported(format!("Compression of {} is not supported. Enable gzip or zstd feature", file_path))
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(file_name: &str) -> Vec<u8> {
+        let file_path = std::env::temp_dir().join(file_name);
+        let file_path = file_path.to_str().unwrap();
+        let mut writer = create(file_path).unwrap();
+        writer.write_all(b"time,value\n").unwrap();
+        writer.finish().unwrap();
+        let mut content = vec![];
+        open(file_path).unwrap().read_to_end(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(round_trip("timeseries_plain.csv"), b"time,value\n");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+        assert_eq!(round_trip("timeseries_compressed.csv.gz"), b"time,value\n");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        assert_eq!(round_trip("timeseries_compressed.csv.zst"), b"time,value\n");
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn test_gzip_disabled() {
+        assert!(matches!(create("timeseries_disabled.csv.gz"), Err(Error::Unsupported(_))));
+    }
+}
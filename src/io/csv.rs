@@ -1,57 +1,743 @@
-use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
 use csv;
 use chrono::prelude::*;
-use serde::Serialize;
-use dtinfer;
 
 use crate::TimeSeries;
+use crate::builder::OnUnitMismatch;
+use crate::error::{Error, Result};
+use crate::frame::TimeSeriesFrame;
+use crate::index::Resolution;
+use crate::metadata::Metadata;
+use crate::io::compression;
+use crate::io::timestamp::TimestampFormat;
+use crate::progress::{Progress, ProgressReader, Reporter};
+#[cfg(feature = "parallel")]
+use std::path::PathBuf;
+#[cfg(feature = "parallel")]
+use crate::index::DateTimeIndex;
+#[cfg(feature = "parallel")]
+use crate::merge::OverlapPolicy;
+#[cfg(feature = "parallel")]
+use crate::series_set::{labels, SeriesSet};
 
 
-#[derive(Serialize)]
-struct Row {
-    timestamp: String,
-    value: f64,
+/// Row which could not be parsed
+///   * line - Line number in the file (starting from 1)
+///   * content - Raw row content
+///   * cause - Error message
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowError {
+    pub line: u64,
+    pub content: String,
+    pub cause: String,
 }
 
+/// Metadata line with the unit of epoch timestamps
+const EPOCH_KEY: &str = "epoch:";
 
-/// Load series from the given CSV file
-pub fn read_from_file(file_path: &str) -> Result<TimeSeries, Box<dyn Error>> {
-    let mut rdr = csv::Reader::from_path(file_path)?;
+/// Metadata, epoch unit, number of lines and the reader positioned after them
+type MetadataLines<R> = (Metadata, Option<Resolution>, u64, BufReader<R>);
+
+/// Timestamp, value and optional quality code
+type ParsedRow = (i64, f64, Option<u8>);
+
+/// How timestamps are written
+///   * Formatted - Datetime in the chrono format, like `%Y-%m-%d %H:%M:%S`
+///   * Epoch - Raw index value, number of units since the epoch in the index resolution
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimestampStyle {
+    Formatted(String),
+    Epoch,
+}
+
+/// Options for loading series or frame from CSV file
+///   * resolution - Index resolution. Timestamps are converted to it
+///     (default: epoch unit saved in the file or milliseconds)
+///   * on_unit_mismatch - What to do with epoch timestamps which look like different unit than
+///     the first timestamp (default: `OnUnitMismatch::Error`). Not used if the file has the epoch unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReadOptions {
+    resolution: Option<Resolution>,
+    on_unit_mismatch: OnUnitMismatch,
+}
+
+/// Options for saving series as CSV file
+///   * timestamp - How timestamps are written (default: epoch)
+///   * headers - Names of the timestamp and value columns (default: `timestamp` and `value`)
+///   * delimiter - Field delimiter (default: `,`)
+///   * precision - Number of decimal places of the values (default: shortest exact representation)
+///   * append - Add rows to the existing file. Metadata and header are written only to a new file
+#[derive(Clone, Debug, PartialEq)]
+pub struct WriteOptions {
+    timestamp: TimestampStyle,
+    headers: (String, String),
+    delimiter: u8,
+    precision: Option<usize>,
+    append: bool,
+}
+
+
+/// Load series from the given CSV file.
+/// The first column contains timestamps. Datetimes with timezone offset, naive datetimes (as UTC)
+/// and epoch numbers (seconds, milliseconds, microseconds or nanoseconds) are supported.
+/// If the third column is named `quality`, then it is loaded as quality codes.
+/// Lines starting with `#` before the header are loaded as metadata (see `write_to_file`).
+/// Files with `.gz` and `.zst` extension are decompressed (requires `gzip` or `zstd` feature).
+/// Index has the resolution saved in the `# epoch:` line (see `write_to_file_with_options`).
+/// Otherwise timestamps are converted to milliseconds (see `read_from_file_with_resolution`).
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+pub fn read_from_file(file_path: &str) -> Result<TimeSeries> {
+    read_records(compression::open(file_path)?, None, &ReadOptions::new())
+}
+
+/// Load series from the given CSV file with timestamps converted to the given resolution.
+/// Use it for high frequency data, which would lose precision in milliseconds.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+pub fn read_from_file_with_resolution(file_path: &str, resolution: Resolution) -> Result<TimeSeries> {
+    read_records(compression::open(file_path)?, None, &ReadOptions::new().resolution(resolution))
+}
+
+/// Load series from the given CSV file with the given options
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+pub fn read_from_file_with_options(file_path: &str, options: &ReadOptions) -> Result<TimeSeries> {
+    read_records(compression::open(file_path)?, None, options)
+}
+
+/// Load series from the given CSV file (see `read_from_file`) and report the number of bytes read.
+/// Total is the file size (compressed size for compressed files).
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(progress), err))]
+pub fn read_from_file_with_progress(file_path: &str, progress: Progress) -> Result<TimeSeries> {
+    let file = File::open(file_path)?;
+    let reporter = Reporter::new(progress, Some(file.metadata()?.len()));
+    read_records(compression::decompress(file_path, ProgressReader::new(file, reporter))?, None, &ReadOptions::new())
+}
+
+/// Load series from the given CSV file, skipping rows which can't be parsed.
+/// Returns the series with valid rows and the list of errors.
+/// Error is returned only if the file can't be opened or has no header.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+pub fn read_from_file_with_errors(file_path: &str) -> Result<(TimeSeries, Vec<RowError>)> {
+    let mut errors = vec![];
+    let ts = read_records(compression::open(file_path)?, Some(&mut errors), &ReadOptions::new())?;
+    Ok((ts, errors))
+}
+
+/// Load series from CSV data provided by the reader
+///
+/// # Example
+///
+/// ```
+/// use timeseries::io::csv;
+///
+/// let data = "time,value\n2020-01-01 00:00:00,1.5\n2020-01-01 01:00:00,2.5\n";
+/// let ts = csv::read_from_reader(data.as_bytes()).unwrap();
+/// assert_eq!(ts.values, vec![1.5, 2.5]);
+///
+/// let data = "time,value\n2020-01-01T02:00:00+02:00,1.5\n";
+/// let ts = csv::read_from_reader(data.as_bytes()).unwrap();
+/// assert_eq!(ts.index.values, vec![1_577_836_800_000]);
+/// ```
+pub fn read_from_reader<R: Read>(reader: R) -> Result<TimeSeries> {
+    read_records(reader, None, &ReadOptions::new())
+}
+
+/// Load series from CSV data provided by the reader with timestamps in the given resolution
+///
+/// # Example
+///
+/// ```
+/// use timeseries::io::csv;
+/// use timeseries::index::Resolution;
+///
+/// let data = "time,value\n1682935200000000001,1.5\n1682935200000000002,2.5\n";
+/// let ts = csv::read_from_reader_with_resolution(data.as_bytes(), Resolution::Nanosecond).unwrap();
+/// assert_eq!(ts.index.values, vec![1_682_935_200_000_000_001, 1_682_935_200_000_000_002]);
+/// assert_eq!(ts.index.resolution, Resolution::Nanosecond);
+/// ```
+pub fn read_from_reader_with_resolution<R: Read>(reader: R, resolution: Resolution) -> Result<TimeSeries> {
+    read_records(reader, None, &ReadOptions::new().resolution(resolution))
+}
+
+/// Load series from CSV data provided by the reader with the given options
+///
+/// # Example
+///
+/// ```
+/// use timeseries::builder::OnUnitMismatch;
+/// use timeseries::io::csv::{self, ReadOptions};
+///
+/// // The second timestamp is in seconds
+/// let data = "time,value\n1600000000000,1.5\n1600000060,2.5\n";
+/// assert!(csv::read_from_reader(data.as_bytes()).is_err());
+/// let options = ReadOptions::new().on_unit_mismatch(OnUnitMismatch::Convert);
+/// let ts = csv::read_from_reader_with_options(data.as_bytes(), &options).unwrap();
+/// assert_eq!(ts.index.values, vec![1_600_000_000_000, 1_600_000_060_000]);
+/// ```
+pub fn read_from_reader_with_options<R: Read>(reader: R, options: &ReadOptions) -> Result<TimeSeries> {
+    read_records(reader, None, options)
+}
+
+/// Load series from CSV data provided by the reader, skipping rows which can't be parsed.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::io::csv;
+///
+/// let data = "time,value\n2020-01-01 00:00:00,1.5\n2020-01-01 01:00:00,x\n";
+/// let (ts, errors) = csv::read_from_reader_with_errors(data.as_bytes()).unwrap();
+/// assert_eq!(ts.values, vec![1.5]);
+/// assert_eq!(errors[0].line, 3);
+/// assert_eq!(errors[0].content, "2020-01-01 01:00:00,x");
+/// ```
+pub fn read_from_reader_with_errors<R: Read>(reader: R) -> Result<(TimeSeries, Vec<RowError>)> {
+    let mut errors = vec![];
+    let ts = read_records(reader, Some(&mut errors), &ReadOptions::new())?;
+    Ok((ts, errors))
+}
+
+/// Read metadata and all records. If errors are collected, then invalid rows are skipped.
+/// Otherwise the first invalid row stops reading.
+/// Without the resolution the index gets the epoch unit of the file or milliseconds.
+fn read_records<R: Read>(reader: R, mut errors: Option<&mut Vec<RowError>>, options: &ReadOptions)
+    -> Result<TimeSeries> {
+
+    let (metadata, epoch_unit, metadata_lines, reader) = read_metadata(reader)?;
+    let resolution = options.resolution.or(epoch_unit).unwrap_or_default();
+    let mut rdr = csv::Reader::from_reader(reader);
     let mut index: Vec<i64> = Vec::new();
     let mut data: Vec<f64> = Vec::new();
-    let mut infered_format: Option<String> = None;
+    let mut quality: Vec<u8> = Vec::new();
+    let has_quality = rdr.headers()?.get(2).map(|h| h.trim() == "quality").unwrap_or(false);
+    let mut infered_format: Option<TimestampFormat> = epoch_unit.map(TimestampFormat::Epoch);
     for result in rdr.records() {
-        let record = result?;
+        let record = match (result, errors.as_mut()) {
+            (Ok(record), _) => record,
+            (Err(e), Some(errors)) => {
+                let line = e.position().map(|p| p.line() + metadata_lines).unwrap_or(0);
+                skip_row(errors, RowError { line, content: String::new(), cause: e.to_string() });
+                continue;
+            },
+            (Err(e), None) => return Err(with_line_offset(e.into(), metadata_lines)),
+        };
         if infered_format.is_none() {
-            infered_format = dtinfer::infer_best(&record[0]);
+            infered_format = TimestampFormat::infer(&record[0]);
+        }
+        // Unit of each epoch timestamp is checked, unless it is given in the file
+        let parsed = match (&infered_format, epoch_unit) {
+            (Some(TimestampFormat::Epoch(unit)), None) => check_unit(&record[0], *unit, options.on_unit_mismatch)
+                .and_then(|detected| parse_record(&record, &Some(TimestampFormat::Epoch(detected)), resolution, has_quality)),
+            _ => parse_record(&record, &infered_format, resolution, has_quality),
+        };
+        match (parsed, errors.as_mut()) {
+            (Ok(Some((idx, v, q))), _) => {
+                index.push(idx);
+                data.push(v);
+                if let Some(q) = q {
+                    quality.push(q);
+                }
+            },
+            (Ok(None), None) => (),
+            (Ok(None), Some(errors)) => skip_row(errors, row_error(&record, metadata_lines, "Unknown timestamp format".into())),
+            (Err((_, cause)), Some(errors)) => skip_row(errors, row_error(&record, metadata_lines, cause)),
+            (Err((column, cause)), None) => return Err(parse_error(&record, metadata_lines, column, cause)),
         }
-        if let Some(datetime_format) = &infered_format {
-            println!("[{}]", &record[0]);
-            println!("{}", &datetime_format);
-            println!("{:?}", NaiveDateTime::parse_from_str(&record[0], &datetime_format));
-            let idx = NaiveDateTime::parse_from_str(&record[0], &datetime_format)?.timestamp_millis();
-            let v: f64 = record[1].parse::<f64>()?;
-            index.push(idx);
-            data.push(v);
+    }
+
+    let mut ts = TimeSeries::new(index, data);
+    ts.index.resolution = resolution;
+    ts.metadata = metadata;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(rows = ts.len(), "Loaded CSV records");
+    Ok(if has_quality { ts.with_quality(quality) } else { ts })
+}
+
+/// Read metadata from lines starting with `#`.
+/// Epoch unit is known if the file was written by `write_to_file_with_options`.
+fn read_metadata<R: Read>(reader: R) -> Result<MetadataLines<R>> {
+    let mut reader = BufReader::new(reader);
+    let mut metadata = Metadata::default();
+    let mut epoch_unit = None;
+    let mut lines = 0;
+    while reader.fill_buf()?.first() == Some(&b'#') {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line[1..].trim();
+        match line.strip_prefix(EPOCH_KEY) {
+            Some(unit) => epoch_unit = Resolution::from_name(unit.trim()),
+            None => metadata.parse_line(line),
         }
+        lines += 1;
+    }
+    Ok((metadata, epoch_unit, lines, reader))
+}
+
+/// Parse timestamp, value and optional quality. None if the timestamp format is not known.
+/// Error has the column which can't be parsed and the cause.
+fn parse_record(record: &csv::StringRecord, format: &Option<TimestampFormat>, resolution: Resolution, has_quality: bool)
+    -> std::result::Result<Option<ParsedRow>, (usize, String)> {
+
+    match format {
+        Some(timestamp_format) => {
+            let idx = timestamp_format.parse(&record[0], resolution).map_err(|e| (0, e))?;
+            let v: f64 = record.get(1)
+                .ok_or_else(|| "Missing value column".to_owned())
+                .and_then(|v| v.parse::<f64>().map_err(|e| e.to_string()))
+                .map_err(|e| (1, e))?;
+            let q = if has_quality {
+                let q = record.get(2)
+                    .ok_or_else(|| "Missing quality column".to_owned())
+                    .and_then(|q| q.parse::<u8>().map_err(|e| e.to_string()))
+                    .map_err(|e| (2, e))?;
+                Some(q)
+            } else {
+                None
+            };
+            Ok(Some((idx, v, q)))
+        },
+        None => Ok(None),
+    }
+}
+
+/// Unit of the epoch timestamp. Error if it looks like other unit than expected and the policy is Error
+fn check_unit(text: &str, expected: Resolution, policy: OnUnitMismatch) -> std::result::Result<Resolution, (usize, String)> {
+    match TimestampFormat::infer(text) {
+        Some(TimestampFormat::Epoch(detected)) if detected != expected => match policy {
+            OnUnitMismatch::Error => Err((0, format!("Timestamp looks like {:?} units, but the first one is in {:?}", detected, expected))),
+            OnUnitMismatch::Convert => Ok(detected),
+        },
+        _ => Ok(expected),
+    }
+}
+
+/// Record invalid row which is skipped
+fn skip_row(errors: &mut Vec<RowError>, error: RowError) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(line = error.line, cause = %error.cause, "Skipping invalid CSV row");
+    errors.push(error);
+}
+
+/// Parse error at the record line (including metadata lines)
+fn parse_error(record: &csv::StringRecord, line_offset: u64, column: usize, cause: String) -> Error {
+    let line = record.position().map(|p| p.line() + line_offset).unwrap_or(0);
+    Error::Parse { line, column, cause }
+}
+
+/// CSV reader doesn't see metadata lines, so they are added to the line of the parse error
+fn with_line_offset(error: Error, line_offset: u64) -> Error {
+    match error {
+        Error::Parse { line, column, cause } if line > 0 => Error::Parse { line: line + line_offset, column, cause },
+        e => e,
     }
+}
 
-    Ok(TimeSeries::new(index, data))
+fn row_error(record: &csv::StringRecord, line_offset: u64, cause: String) -> RowError {
+    let line = record.position().map(|p| p.line() + line_offset).unwrap_or(0);
+    let content = record.iter().collect::<Vec<&str>>().join(",");
+    RowError { line, content, cause }
 }
 
-fn timestamp_format(ts: i64, format: &str) -> String {
-    let dt = Utc.timestamp(ts/1000, 0);
+fn timestamp_format(ts: i64, resolution: Resolution, format: &str) -> String {
+    let dt = match resolution {
+        Resolution::Nanosecond => Utc.timestamp_nanos(ts),
+        _ => {
+            let micros = resolution.convert(ts, Resolution::Microsecond);
+            DateTime::from_timestamp_micros(micros).unwrap_or_default()
+        },
+    };
     dt.format(format).to_string()
 }
 
-/// Save series as CSV file
-pub fn write_to_file(file_path: &str, ts: &TimeSeries, datetime_format: &str)  -> Result<(), Box<dyn Error>>{
-    let mut wtr = csv::Writer::from_path(file_path)?;
-    ts.iter()
-        .map(|dp| Row { timestamp: timestamp_format(dp.timestamp, datetime_format), value: dp.value })
-        .for_each(|row| wtr.serialize(&row).unwrap());
-    wtr.flush()?;
-    Ok(())
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions { resolution: None, on_unit_mismatch: OnUnitMismatch::Error }
+    }
+}
+
+impl ReadOptions {
+
+    pub fn new() -> ReadOptions {
+        ReadOptions::default()
+    }
+
+    pub fn resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = Some(resolution);
+        self
+    }
+
+    pub fn on_unit_mismatch(mut self, policy: OnUnitMismatch) -> Self {
+        self.on_unit_mismatch = policy;
+        self
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            timestamp: TimestampStyle::Epoch,
+            headers: ("timestamp".to_owned(), "value".to_owned()),
+            delimiter: b',',
+            precision: None,
+            append: false,
+        }
+    }
+}
+
+impl WriteOptions {
+
+    pub fn new() -> WriteOptions {
+        WriteOptions::default()
+    }
+
+    /// Write timestamps as datetimes in the given format
+    pub fn datetime_format(mut self, format: &str) -> Self {
+        self.timestamp = TimestampStyle::Formatted(format.to_owned());
+        self
+    }
+
+    /// Write timestamps as epoch numbers
+    pub fn epoch(mut self) -> Self {
+        self.timestamp = TimestampStyle::Epoch;
+        self
+    }
+
+    pub fn headers(mut self, timestamp: &str, value: &str) -> Self {
+        self.headers = (timestamp.to_owned(), value.to_owned());
+        self
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+}
+
+/// Save series as CSV file.
+/// Timestamps are formatted in the index resolution, so `%.f` keeps sub millisecond precision.
+/// Quality codes are saved in the third column if the series has them.
+/// Metadata is saved before the header in lines like `# name: flow`.
+/// Files with `.gz` and `.zst` extension are compressed (requires `gzip` or `zstd` feature).
+/// Timestamps are truncated to the precision of the format. Use `write_to_file_with_options`
+/// with default options to save epoch timestamps, which are loaded without any loss.
+pub fn write_to_file(file_path: &str, ts: &TimeSeries, datetime_format: &str)  -> Result<()>{
+    write_to_file_with_options(file_path, ts, &WriteOptions::new().datetime_format(datetime_format))
+}
+
+/// Save series as CSV file with the given options (see `write_to_file`).
+/// With epoch timestamps the unit is saved in the `# epoch: ms` line, so the readers load the same series.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::io::csv::{self, WriteOptions};
+///
+/// let file_path = std::env::temp_dir().join("timeseries_doc_options.csv");
+/// let file_path = file_path.to_str().unwrap();
+/// let options = WriteOptions::new().epoch().headers("time", "flow").precision(2);
+/// csv::write_to_file_with_options(file_path, &TimeSeries::new(vec![1_000_000_000_000], vec![1.0]), &options).unwrap();
+/// csv::write_to_file_with_options(file_path, &TimeSeries::new(vec![1_000_000_001_000], vec![2.5]), &options.append(true)).unwrap();
+/// let content = std::fs::read_to_string(file_path).unwrap();
+/// assert_eq!(content, "# epoch: ms\ntime,flow\n1000000000000,1.00\n1000000001000,2.50\n");
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(ts, options), fields(len = ts.len()), err))]
+pub fn write_to_file_with_options(file_path: &str, ts: &TimeSeries, options: &WriteOptions) -> Result<()> {
+    let append = options.append && Path::new(file_path).metadata().is_ok_and(|m| m.len() > 0);
+    let mut writer = if append { compression::append(file_path)? } else { compression::create(file_path)? };
+    if !append {
+        for line in ts.metadata.to_lines() {
+            writeln!(writer, "# {}", line)?;
+        }
+        if options.timestamp == TimestampStyle::Epoch {
+            writeln!(writer, "# {} {}", EPOCH_KEY, ts.index.resolution.name())?;
+        }
+    }
+    let mut wtr = csv::WriterBuilder::new().delimiter(options.delimiter).from_writer(writer);
+    if !append {
+        let mut header = vec![options.headers.0.as_str(), options.headers.1.as_str()];
+        if ts.quality.is_some() {
+            header.push("quality");
+        }
+        wtr.write_record(&header)?;
+    }
+    for dp in ts.iter() {
+        let timestamp = match &options.timestamp {
+            TimestampStyle::Formatted(format) => timestamp_format(dp.timestamp, ts.index.resolution, format),
+            TimestampStyle::Epoch => dp.timestamp.to_string(),
+        };
+        let value = match options.precision {
+            Some(precision) => format!("{:.*}", precision, dp.value),
+            None => format!("{:?}", dp.value),
+        };
+        let mut record = vec![timestamp, value];
+        if let Some(quality) = dp.quality {
+            record.push(quality.to_string());
+        }
+        wtr.write_record(&record)?;
+    }
+    wtr.into_inner().map_err(|e| e.into_error())?.finish()
+}
+
+/// Load frame from CSV file in wide format.
+/// The first column contains timestamps and each other column is loaded as a series.
+/// Column names are taken from the header. Empty cells are loaded as NaN.
+/// Index has the resolution saved in the `# epoch:` line, other metadata lines are skipped
+/// (see `read_from_file`).
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+pub fn read_frame_from_file(file_path: &str) -> Result<TimeSeriesFrame> {
+    read_frame_from_reader(compression::open(file_path)?)
+}
+
+/// Load frame from CSV file in wide format with the given options
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+pub fn read_frame_from_file_with_options(file_path: &str, options: &ReadOptions) -> Result<TimeSeriesFrame> {
+    read_frame_from_reader_with_options(compression::open(file_path)?, options)
+}
+
+/// Load frame from CSV data in wide format provided by the reader
+///
+/// # Example
+///
+/// ```
+/// use timeseries::io::csv;
+///
+/// let data = "time,temp,humidity\n2020-01-01 00:00:00,20.5,40\n2020-01-01 01:00:00,21.0,\n";
+/// let frame = csv::read_frame_from_reader(data.as_bytes()).unwrap();
+/// assert_eq!(frame.names, vec!["temp", "humidity"]);
+/// assert_eq!(frame.column("temp").unwrap().values, vec![20.5, 21.0]);
+/// assert!(frame.columns[1][1].is_nan());
+/// ```
+pub fn read_frame_from_reader<R: Read>(reader: R) -> Result<TimeSeriesFrame> {
+    read_frame_from_reader_with_options(reader, &ReadOptions::new())
+}
+
+/// Load frame from CSV data in wide format provided by the reader with the given options
+///
+/// # Example
+///
+/// ```
+/// use timeseries::index::Resolution;
+/// use timeseries::io::csv::{self, ReadOptions};
+///
+/// let data = "time,a\n2020-01-01T00:00:00.000000001Z,1.5\n";
+/// let options = ReadOptions::new().resolution(Resolution::Nanosecond);
+/// let frame = csv::read_frame_from_reader_with_options(data.as_bytes(), &options).unwrap();
+/// assert_eq!(frame.index.values, vec![1_577_836_800_000_000_001]);
+/// ```
+pub fn read_frame_from_reader_with_options<R: Read>(reader: R, options: &ReadOptions) -> Result<TimeSeriesFrame> {
+    let (_, epoch_unit, metadata_lines, reader) = read_metadata(reader)?;
+    let resolution = options.resolution.or(epoch_unit).unwrap_or_default();
+    let mut rdr = csv::Reader::from_reader(reader);
+    let names: Vec<String> = rdr.headers()?.iter().skip(1).map(|h| h.trim().to_owned()).collect();
+    let mut index: Vec<i64> = Vec::new();
+    let mut columns: Vec<Vec<f64>> = vec![Vec::new(); names.len()];
+    let mut infered_format: Option<TimestampFormat> = epoch_unit.map(TimestampFormat::Epoch);
+    for result in rdr.records() {
+        let record = result.map_err(|e| with_line_offset(e.into(), metadata_lines))?;
+        if infered_format.is_none() {
+            infered_format = TimestampFormat::infer(&record[0]);
+        }
+        let timestamp_format = match (&infered_format, epoch_unit) {
+            (Some(TimestampFormat::Epoch(unit)), None) => check_unit(&record[0], *unit, options.on_unit_mismatch)
+                .map(TimestampFormat::Epoch)
+                .map_err(|(column, cause)| parse_error(&record, metadata_lines, column, cause))?,
+            (Some(format), _) => format.clone(),
+            (None, _) => return Err(Error::invalid_index("Unknown timestamp format")),
+        };
+        let timestamp = timestamp_format.parse(&record[0], resolution)
+            .map_err(|cause| parse_error(&record, metadata_lines, 0, cause))?;
+        index.push(timestamp);
+        for (i, column) in columns.iter_mut().enumerate() {
+            let value = match record.get(i + 1).map(|v| v.trim()) {
+                None | Some("") => f64::NAN,
+                Some(v) => v.parse::<f64>().map_err(|e| parse_error(&record, metadata_lines, i + 1, e.to_string()))?,
+            };
+            column.push(value);
+        }
+    }
+    let mut frame = names.iter().zip(columns)
+        .fold(TimeSeriesFrame::new(index), |frame, (name, values)| frame.with_column(name, values));
+    frame.index.resolution = resolution;
+    Ok(frame)
+}
+
+/// Save frame as CSV file in wide format.
+/// NaN values are saved as empty cells.
+/// Timestamps are truncated to the precision of the format. Use `write_frame_to_file_with_options`
+/// with default options to save epoch timestamps, which are loaded without any loss.
+pub fn write_frame_to_file(file_path: &str, frame: &TimeSeriesFrame, datetime_format: &str) -> Result<()> {
+    write_frame_to_file_with_options(file_path, frame, &WriteOptions::new().datetime_format(datetime_format))
+}
+
+/// Save frame as CSV file in wide format with the given options (see `write_to_file_with_options`).
+/// Column names are used as headers, so only the timestamp header is taken from the options.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::frame::TimeSeriesFrame;
+/// use timeseries::index::Resolution;
+/// use timeseries::io::csv::{self, WriteOptions};
+///
+/// let file_path = std::env::temp_dir().join("timeseries_doc_frame_options.csv");
+/// let file_path = file_path.to_str().unwrap();
+/// let mut frame = TimeSeriesFrame::new(vec![1_000_000_000_000_000_001]).with_column("a", vec![1.5]);
+/// frame.index.resolution = Resolution::Nanosecond;
+/// csv::write_frame_to_file_with_options(file_path, &frame, &WriteOptions::new()).unwrap();
+/// let content = std::fs::read_to_string(file_path).unwrap();
+/// assert_eq!(content, "# epoch: ns\ntimestamp,a\n1000000000000000001,1.5\n");
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(frame, options), fields(len = frame.len()), err))]
+pub fn write_frame_to_file_with_options(file_path: &str, frame: &TimeSeriesFrame, options: &WriteOptions) -> Result<()> {
+    let append = options.append && Path::new(file_path).metadata().is_ok_and(|m| m.len() > 0);
+    let mut writer = if append { compression::append(file_path)? } else { compression::create(file_path)? };
+    if !append && options.timestamp == TimestampStyle::Epoch {
+        writeln!(writer, "# {} {}", EPOCH_KEY, frame.index.resolution.name())?;
+    }
+    let mut wtr = csv::WriterBuilder::new().delimiter(options.delimiter).from_writer(writer);
+    if !append {
+        let mut header = vec![options.headers.0.as_str()];
+        header.extend(frame.names.iter().map(|n| n.as_str()));
+        wtr.write_record(&header)?;
+    }
+    for (row, &ts) in frame.index.iter().enumerate() {
+        let timestamp = match &options.timestamp {
+            TimestampStyle::Formatted(format) => timestamp_format(ts, frame.index.resolution, format),
+            TimestampStyle::Epoch => ts.to_string(),
+        };
+        let mut record = vec![timestamp];
+        record.extend(frame.columns.iter().map(|column| match (column[row], options.precision) {
+            (v, _) if v.is_nan() => String::new(),
+            (v, Some(precision)) => format!("{:.*}", precision, v),
+            (v, None) => v.to_string(),
+        }));
+        wtr.write_record(&record)?;
+    }
+    wtr.into_inner().map_err(|e| e.into_error())?.finish()
+}
+
+
+/// Options for loading many files with `read_directory` (requires `parallel` feature)
+///   * resolution - Resolution of the loaded timestamps (default: milliseconds)
+///   * overlap - How series from files with overlapping time ranges are joined (default: error)
+#[cfg(feature = "parallel")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReadDirectoryOptions {
+    resolution: Resolution,
+    overlap: OverlapPolicy,
+}
+
+#[cfg(feature = "parallel")]
+impl Default for ReadDirectoryOptions {
+    fn default() -> Self {
+        ReadDirectoryOptions { resolution: Resolution::Millisecond, overlap: OverlapPolicy::Error }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl ReadDirectoryOptions {
+
+    pub fn new() -> ReadDirectoryOptions {
+        ReadDirectoryOptions::default()
+    }
+
+    pub fn resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    pub fn overlap(mut self, overlap: OverlapPolicy) -> Self {
+        self.overlap = overlap;
+        self
+    }
+}
+
+/// Load all files matching the glob pattern in parallel and join them into a single series.
+/// Series are ordered by their first timestamp, so files don't have to be named in time order.
+///
+/// # Example
+///
+/// ```no_run
+/// use timeseries::io::csv::{self, ReadDirectoryOptions};
+/// use timeseries::merge::OverlapPolicy;
+///
+/// let options = ReadDirectoryOptions::new().overlap(OverlapPolicy::KeepOther);
+/// let ts = csv::read_directory("exports/2020-*.csv", &options).unwrap();
+/// ```
+#[cfg(feature = "parallel")]
+pub fn read_directory(pattern: &str, options: &ReadDirectoryOptions) -> Result<TimeSeries> {
+    let mut series: Vec<TimeSeries> = read_files(pattern, options)?.into_iter()
+        .map(|(_, ts)| ts)
+        .filter(|ts| !ts.is_empty())
+        .collect();
+    series.sort_by_key(|ts| ts.index[0]);
+    // Only series with overlapping time ranges are joined with the overlap policy
+    let mut parts: Vec<TimeSeries> = Vec::with_capacity(series.len());
+    for ts in series {
+        match parts.last_mut() {
+            Some(last) if last.index.last().is_some_and(|t| ts.index[0] <= t) => *last = last.concat(&ts, options.overlap)?,
+            _ => parts.push(ts),
+        }
+    }
+    let len = parts.iter().map(|ts| ts.len()).sum();
+    let mut index = Vec::with_capacity(len);
+    let mut values = Vec::with_capacity(len);
+    let mut quality = if parts.iter().any(|ts| ts.quality.is_some()) { Some(Vec::with_capacity(len)) } else { None };
+    for ts in &parts {
+        index.extend_from_slice(&ts.index.values);
+        values.extend_from_slice(&ts.values);
+        if let Some(quality) = &mut quality {
+            quality.extend((0..ts.len()).map(|i| ts.quality_at(i)));
+        }
+    }
+    let index = DateTimeIndex::new(index).with_resolution(options.resolution);
+    Ok(TimeSeries { index, values, quality, ..TimeSeries::empty() })
+}
+
+/// Load all files matching the glob pattern in parallel into the set.
+/// Each series has label `file` with the file name without extension.
+#[cfg(feature = "parallel")]
+pub fn read_directory_to_set(pattern: &str, options: &ReadDirectoryOptions) -> Result<SeriesSet> {
+    let mut set = SeriesSet::new();
+    for (name, ts) in read_files(pattern, options)? {
+        set.insert(labels(&[("file", &name)]), ts);
+    }
+    Ok(set)
+}
+
+/// Load files matching the pattern in parallel. Returns file stems with series.
+#[cfg(feature = "parallel")]
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(options), err))]
+fn read_files(pattern: &str, options: &ReadDirectoryOptions) -> Result<Vec<(String, TimeSeries)>> {
+    use rayon::prelude::*;
+
+    let paths = glob::glob(pattern)
+        .map_err(|e| Error::InvalidFormat(e.to_string()))?
+        .collect::<std::result::Result<Vec<PathBuf>, _>>()
+        .map_err(|e| Error::File { path: e.path().to_owned(), source: Box::new(Error::Io(e.into())) })?;
+    let series = paths.par_iter()
+        .map(|path| {
+            let file_path = path.to_str()
+                .ok_or_else(|| Error::InvalidFormat(format!("Invalid file name: {}", path.display())))?;
+            let ts = read_from_file_with_resolution(file_path, options.resolution)
+                .map_err(|e| Error::File { path: path.clone(), source: Box::new(e) })?;
+            let name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+            Ok((name, ts))
+        })
+        .collect::<Result<Vec<(String, TimeSeries)>>>()?;
+    Ok(series)
 }
 
 
@@ -67,4 +753,228 @@ mod tests {
         let ts = read_from_file("testdata/rain.csv").unwrap();
         assert_eq!(ts.len(), 96670);
     }
+
+    #[test]
+    fn test_read_with_progress() {
+        let calls = std::cell::RefCell::new(vec![]);
+        let ts = read_from_file_with_progress("testdata/rain.csv", &|done, total| calls.borrow_mut().push((done, total))).unwrap();
+        assert_eq!(ts.len(), 96670);
+        let size = std::fs::metadata("testdata/rain.csv").unwrap().len();
+        assert_eq!(calls.borrow().last(), Some(&(size, Some(size))));
+        assert!(calls.borrow().len() <= 102);
+    }
+
+    #[test]
+    fn test_quality_round_trip() {
+        let file_path = std::env::temp_dir().join("timeseries_quality.csv");
+        let file_path = file_path.to_str().unwrap();
+        let ts = TimeSeries::new(vec![0, 3_600_000], vec![1.0, 2.0]).with_quality(vec![0, 2]);
+        write_to_file(file_path, &ts, "%Y-%m-%d %H:%M:%S").unwrap();
+        let loaded = read_from_file(file_path).unwrap();
+        assert_eq!(loaded, ts);
+    }
+
+    #[test]
+    fn test_resolution_round_trip() {
+        let file_path = std::env::temp_dir().join("timeseries_resolution.csv");
+        let file_path = file_path.to_str().unwrap();
+        let mut ts = TimeSeries::new(vec![1_682_935_200_000_000_001, 1_682_935_200_000_000_750], vec![1.0, 2.0]);
+        ts.index.resolution = Resolution::Nanosecond;
+        write_to_file(file_path, &ts, "%Y-%m-%dT%H:%M:%S%.9fZ").unwrap();
+        let loaded = read_from_file_with_resolution(file_path, Resolution::Nanosecond).unwrap();
+        assert_eq!(loaded, ts);
+        let loaded = read_from_file(file_path).unwrap();
+        assert_eq!(loaded.index.values, vec![1_682_935_200_000, 1_682_935_200_000]);
+    }
+
+    #[test]
+    fn test_epoch_round_trip() {
+        let file_path = std::env::temp_dir().join("timeseries_epoch.csv");
+        let file_path = file_path.to_str().unwrap();
+        let ts = TimeSeries::new(vec![1_600_000_000_123, 1_600_000_000_456], vec![0.1, 2.0]).with_name("flow");
+        let options = WriteOptions::new().epoch().delimiter(b';');
+        write_to_file_with_options(file_path, &ts, &options).unwrap();
+        let content = std::fs::read_to_string(file_path).unwrap();
+        assert_eq!(content, "# name: flow\n# epoch: ms\ntimestamp;value\n1600000000123;0.1\n1600000000456;2.0\n");
+    }
+
+    #[test]
+    fn test_default_options_round_trip() {
+        let file_path = std::env::temp_dir().join("timeseries_round_trip.csv");
+        let file_path = file_path.to_str().unwrap();
+        let series = vec![
+            TimeSeries::new(vec![-1500, 0, 1, 2], vec![0.1, -1e-300, f64::INFINITY, 1.0 / 3.0]),
+            TimeSeries::new(vec![1_600_000_000_001], vec![2.5]).with_quality(vec![3]).with_name("x").with_unit("mm"),
+        ];
+        for ts in &series {
+            write_to_file_with_options(file_path, ts, &WriteOptions::new()).unwrap();
+            let loaded = read_from_file(file_path).unwrap();
+            assert_eq!(&loaded, ts);
+            assert_eq!(loaded.metadata, ts.metadata);
+        }
+        let mut ts = TimeSeries::new(vec![5_000_001, 1_600_000_000_000_000_007], vec![1.0, 2.0]);
+        ts.index.resolution = Resolution::Nanosecond;
+        write_to_file_with_options(file_path, &ts, &WriteOptions::new()).unwrap();
+        assert_eq!(read_from_file(file_path).unwrap(), ts);
+        assert_eq!(read_from_file_with_resolution(file_path, Resolution::Millisecond).unwrap(), ts.to_resolution(Resolution::Millisecond));
+    }
+
+    #[test]
+    fn test_mixed_epoch_units() {
+        let data = "time,value\n1600000000,1.0\n1600000001000,2.0\n1600000002000000,3.0\n";
+        match read_from_reader(data.as_bytes()) {
+            Err(Error::Parse { line, column, .. }) => assert_eq!((line, column), (3, 0)),
+            other => panic!("Expected parse error, got {:?}", other),
+        }
+        let (ts, errors) = read_from_reader_with_errors(data.as_bytes()).unwrap();
+        assert_eq!(ts.index.values, vec![1_600_000_000_000]);
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<u64>>(), vec![3, 4]);
+        let options = ReadOptions::new().resolution(Resolution::Microsecond).on_unit_mismatch(OnUnitMismatch::Convert);
+        let ts = read_from_reader_with_options(data.as_bytes(), &options).unwrap();
+        assert_eq!(ts.index.values, vec![1_600_000_000_000_000, 1_600_000_001_000_000, 1_600_000_002_000_000]);
+        // Unit saved in the file is not checked
+        let data = "# epoch: ms\ntime,value\n5,1.0\n1600000001000,2.0\n";
+        assert_eq!(read_from_reader(data.as_bytes()).unwrap().index.values, vec![5, 1_600_000_001_000]);
+    }
+
+    #[test]
+    fn test_read_missing_column() {
+        let data = "time\n2020-01-01 00:00:00\n";
+        assert!(read_from_reader(data.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let data = "# source: test\ntime,value\n2020-01-01 00:00:00,1.0\n2020-01-01 01:00:00,abc\n";
+        match read_from_reader(data.as_bytes()) {
+            Err(Error::Parse { line, column, .. }) => assert_eq!((line, column), (4, 1)),
+            other => panic!("Expected parse error, got {:?}", other),
+        }
+        assert!(matches!(read_from_file("testdata/missing.csv"), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_collect_row_errors() {
+        let data = "time,value\n2020-01-01 00:00:00,1.0\nbad,2.0\n2020-01-01 02:00:00\n2020-01-01 03:00:00,4.0\n";
+        assert!(read_from_reader(data.as_bytes()).is_err());
+        let (ts, errors) = read_from_reader_with_errors(data.as_bytes()).unwrap();
+        assert_eq!(ts.values, vec![1.0, 4.0]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line, 3);
+        assert_eq!(errors[0].content, "bad,2.0");
+        assert_eq!(errors[1].line, 4);
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let file_path = std::env::temp_dir().join("timeseries_metadata.csv");
+        let file_path = file_path.to_str().unwrap();
+        let ts = TimeSeries::new(vec![0], vec![1.0]).with_name("rain").with_unit("mm").with_tag("station", "12");
+        write_to_file(file_path, &ts, "%Y-%m-%d %H:%M:%S").unwrap();
+        let loaded = read_from_file(file_path).unwrap();
+        assert_eq!(loaded.metadata, ts.metadata);
+        let data = "# name: x\ntime,value\n2020-01-01 00:00:00,1.0\nbad,2.0\n";
+        let (ts, errors) = read_from_reader_with_errors(data.as_bytes()).unwrap();
+        assert_eq!(ts.metadata.name.as_deref(), Some("x"));
+        assert_eq!(errors[0].line, 4);
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let file_path = std::env::temp_dir().join("timeseries_frame.csv");
+        let file_path = file_path.to_str().unwrap();
+        let frame = TimeSeriesFrame::new(vec![0, 1000])
+            .with_column("a", vec![1.0, 2.0])
+            .with_column("b", vec![f64::NAN, 4.5]);
+        write_frame_to_file(file_path, &frame, "%Y-%m-%d %H:%M:%S").unwrap();
+        let loaded = read_frame_from_file(file_path).unwrap();
+        assert_eq!(loaded.index, frame.index);
+        assert_eq!(loaded.names, frame.names);
+        assert_eq!(loaded.columns[0], frame.columns[0]);
+        assert!(loaded.columns[1][0].is_nan());
+        assert_eq!(loaded.columns[1][1], 4.5);
+    }
+
+    #[test]
+    fn test_frame_round_trip_nanoseconds() {
+        let file_path = std::env::temp_dir().join("timeseries_frame_ns.csv");
+        let file_path = file_path.to_str().unwrap();
+        let mut frame = TimeSeriesFrame::new(vec![1_600_000_000_000_000_001, 1_600_000_000_000_000_002])
+            .with_column("a", vec![1.0, f64::NAN]);
+        frame.index.resolution = Resolution::Nanosecond;
+        write_frame_to_file_with_options(file_path, &frame, &WriteOptions::new()).unwrap();
+        let loaded = read_frame_from_file(file_path).unwrap();
+        assert_eq!(loaded.index, frame.index);
+        assert_eq!(loaded.index.resolution, Resolution::Nanosecond);
+        assert_eq!(loaded.columns[0][0], 1.0);
+        assert!(loaded.columns[0][1].is_nan());
+
+        write_frame_to_file(file_path, &frame, "%Y-%m-%dT%H:%M:%S%.9fZ").unwrap();
+        let options = ReadOptions::new().resolution(Resolution::Nanosecond);
+        assert_eq!(read_frame_from_file_with_options(file_path, &options).unwrap().index, frame.index);
+    }
+
+    #[test]
+    fn test_read_epoch_seconds() {
+        let data = "time,value\n1577836800,1.0\n1577836860,2.0\n";
+        let ts = read_from_reader(data.as_bytes()).unwrap();
+        assert_eq!(ts.index.values, vec![1_577_836_800_000, 1_577_836_860_000]);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_read_directory() {
+        let dir = std::env::temp_dir().join("timeseries_directory");
+        std::fs::create_dir_all(&dir).unwrap();
+        let day1 = TimeSeries::new(vec![0, 1000], vec![1.0, 2.0]);
+        let day2 = TimeSeries::new(vec![1000, 2000], vec![20.0, 3.0]);
+        write_to_file(dir.join("b.csv").to_str().unwrap(), &day1, "%Y-%m-%d %H:%M:%S").unwrap();
+        write_to_file(dir.join("a.csv").to_str().unwrap(), &day2, "%Y-%m-%d %H:%M:%S").unwrap();
+        let pattern = dir.join("*.csv");
+        let pattern = pattern.to_str().unwrap();
+
+        assert!(read_directory(pattern, &ReadDirectoryOptions::new()).is_err());
+        let options = ReadDirectoryOptions::new().overlap(OverlapPolicy::KeepSelf);
+        let ts = read_directory(pattern, &options).unwrap();
+        assert_eq!(ts, TimeSeries::new(vec![0, 1000, 2000], vec![1.0, 2.0, 3.0]));
+        let set = read_directory_to_set(pattern, &options).unwrap();
+        assert_eq!(set.get(&labels(&[("file", "a")])), Some(&day2));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_read_directory_merge() {
+        let dir = std::env::temp_dir().join("timeseries_directory_merge");
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = [("1", vec![0, 1000], vec![1.0, 2.0]), ("2", vec![1000, 2000], vec![3.0, 4.0]),
+                     ("3", vec![5000, 6000], vec![5.0, 6.0])];
+        for (name, index, values) in files.iter() {
+            let ts = TimeSeries::new(index.clone(), values.clone());
+            write_to_file(dir.join(format!("{}.csv", name)).to_str().unwrap(), &ts, "%Y-%m-%d %H:%M:%S").unwrap();
+        }
+        let pattern = dir.join("*.csv");
+        let options = ReadDirectoryOptions::new()
+            .resolution(Resolution::Nanosecond)
+            .overlap(OverlapPolicy::Merge(crate::resample::Aggregation::Sum));
+        let ts = read_directory(pattern.to_str().unwrap(), &options).unwrap();
+        assert_eq!(ts.index.resolution, Resolution::Nanosecond);
+        assert_eq!(ts.index.values, vec![0, 1_000_000_000, 2_000_000_000, 5_000_000_000, 6_000_000_000]);
+        assert_eq!(ts.values, vec![1.0, 5.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_read_directory_error_has_path() {
+        let dir = std::env::temp_dir().join("timeseries_directory_error");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("bad.csv"), "time,value\n2020-01-01 00:00:00,1.0\n2020-01-01 01:00:00,abc\n").unwrap();
+        let pattern = dir.join("*.csv");
+        match read_directory(pattern.to_str().unwrap(), &ReadDirectoryOptions::new()) {
+            Err(Error::File { path, source }) => {
+                assert_eq!(path, dir.join("bad.csv"));
+                assert!(matches!(*source, Error::Parse { line: 3, column: 1, .. }));
+            },
+            other => panic!("Expected file error, got {:?}", other),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+//! Load series served by HTTP APIs (requires `http` feature)
+//!
+//! Response body is parsed the same way as the local files:
+//!   * CSV - See `io::csv::read_from_file`
+//!   * JSON - Array of data points like `[{"timestamp": 1000, "value": 1.5}]`.
+//!     Quality code is optional.
+//!
+
+use crate::{TimeSeries, DataPoint};
+use crate::error::{Error, Result};
+use crate::index::Resolution;
+use crate::io::csv::{self, RowError};
+
+
+/// Load series from CSV served at the given URL
+pub fn read_csv_from_url(url: &str) -> Result<TimeSeries> {
+    csv::read_from_reader(get(url)?.into_reader())
+}
+
+/// Load series from CSV served at the given URL with timestamps in the given resolution
+pub fn read_csv_from_url_with_resolution(url: &str, resolution: Resolution) -> Result<TimeSeries> {
+    csv::read_from_reader_with_resolution(get(url)?.into_reader(), resolution)
+}
+
+/// Load series from CSV served at the given URL, skipping rows which can't be parsed
+pub fn read_csv_from_url_with_errors(url: &str) -> Result<(TimeSeries, Vec<RowError>)> {
+    csv::read_from_reader_with_errors(get(url)?.into_reader())
+}
+
+/// Load series from JSON array of data points served at the given URL
+pub fn read_json_from_url(url: &str) -> Result<TimeSeries> {
+    let datapoints: Vec<DataPoint> = serde_json::from_reader(get(url)?.into_reader()).map_err(|e| {
+        if e.is_io() {
+            Error::Io(e.into())
+        } else {
+            Error::Parse { line: e.line() as u64, column: e.column(), cause: e.to_string() }
+        }
+    })?;
+    Ok(TimeSeries::from_datapoints(datapoints))
+}
+
+/// Send GET request. Error status codes are returned as errors.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+fn get(url: &str) -> Result<ureq::Response> {
+    ureq::get(url).call().map_err(|e| Error::Http(Box::new(e)))
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve the same body for the given number of requests. Returns server URL
+    fn serve(body: &'static str, requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf);
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_csv() {
+        let url = serve("time,value\n2020-01-01 00:00:00,1.5\n2020-01-01 01:00:00,x\n", 2);
+        assert!(read_csv_from_url(&url).is_err());
+        let (ts, errors) = read_csv_from_url_with_errors(&url).unwrap();
+        assert_eq!(ts.values, vec![1.5]);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_json() {
+        let url = serve(r#"[{"timestamp": 1, "value": 1.5}, {"timestamp": 2, "value": 2.5, "quality": 3}]"#, 1);
+        let ts = read_json_from_url(&url).unwrap();
+        assert_eq!(ts.index.values, vec![1, 2]);
+        assert_eq!(ts.values, vec![1.5, 2.5]);
+        assert_eq!(ts.quality, Some(vec![0, 3]));
+    }
+}
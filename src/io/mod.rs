@@ -1 +1,9 @@
-pub mod csv;
\ No newline at end of file
+pub mod binary;
+mod compression;
+pub mod csv;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub mod sql;
+mod timestamp;
+pub mod wal;
@@ -0,0 +1,222 @@
+//! Load and save series in relational databases
+//!   * SQLite - `rusqlite::Connection` (requires `sqlite` feature)
+//!   * Postgres - `postgres::Client` (requires `postgres` feature)
+//!
+//! Timestamp column can have epoch numbers or datetimes (text or native type).
+//! Epoch numbers are in the resolution given to the reader (milliseconds by default),
+//! and datetimes are parsed the same way as in CSV files (see `io::timestamp`).
+//! Series is saved as table with `timestamp` (epoch in index resolution) and `value` columns.
+//!
+
+use crate::TimeSeries;
+use crate::error::{Error, Result};
+use crate::index::Resolution;
+use crate::io::timestamp::TimestampFormat;
+
+
+/// Database connection which can be used to load and save series
+pub trait Connection {
+
+    /// Run the query and return text of the timestamp column and the value column for each row
+    fn query_rows(&mut self, sql: &str, ts_col: &str, value_col: &str) -> Result<Vec<(String, f64)>>;
+
+    /// Create table (if it doesn't exist) and insert all data points in a single transaction
+    fn insert_rows(&mut self, table: &str, index: &[i64], values: &[f64]) -> Result<()>;
+}
+
+
+/// Load series from the query result. Rows should be ordered by the timestamp.
+/// Epoch numbers in the timestamp column are milliseconds.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "sqlite")] {
+/// use timeseries::io::sql;
+///
+/// let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+/// conn.execute_batch("CREATE TABLE m (t TEXT, v REAL);
+///                     INSERT INTO m VALUES ('2020-01-01 00:00:00', 1.5), ('2020-01-01 01:00:00', 2.5);").unwrap();
+/// let ts = sql::read_query(&mut conn, "SELECT t, v FROM m ORDER BY t", "t", "v").unwrap();
+/// assert_eq!(ts.values, vec![1.5, 2.5]);
+/// # }
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(conn), err))]
+pub fn read_query<C: Connection>(conn: &mut C, sql: &str, ts_col: &str, value_col: &str) -> Result<TimeSeries> {
+    read_query_with_resolution(conn, sql, ts_col, value_col, Resolution::Millisecond)
+}
+
+/// Load series from the query result (see `read_query`) with index in the given resolution.
+/// Epoch numbers in the timestamp column are in this resolution.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "sqlite")] {
+/// use timeseries::io::sql;
+/// use timeseries::index::Resolution;
+///
+/// let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+/// conn.execute_batch("CREATE TABLE m (t INTEGER, v REAL);
+///                     INSERT INTO m VALUES (1500000000, 1.5), (2500000000, 2.5);").unwrap();
+/// let ts = sql::read_query_with_resolution(&mut conn, "SELECT t, v FROM m ORDER BY t", "t", "v", Resolution::Nanosecond).unwrap();
+/// assert_eq!(ts.index.values, vec![1_500_000_000, 2_500_000_000]);
+/// assert_eq!(ts.index.resolution, Resolution::Nanosecond);
+/// # }
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(conn), err))]
+pub fn read_query_with_resolution<C: Connection>(conn: &mut C, sql: &str, ts_col: &str, value_col: &str, resolution: Resolution)
+    -> Result<TimeSeries> {
+
+    let rows = conn.query_rows(sql, ts_col, value_col)?;
+    let format = match rows.first() {
+        Some((sample, _)) => match TimestampFormat::infer(sample) {
+            Some(TimestampFormat::Epoch(_)) => TimestampFormat::Epoch(resolution),
+            Some(format) => format,
+            None => return Err(Error::invalid_index("Unknown timestamp format")),
+        },
+        None => return Ok(TimeSeries::empty().to_resolution(resolution)),
+    };
+    let index = rows.iter()
+        .map(|(t, _)| format.parse(t, resolution).map_err(Error::InvalidIndex))
+        .collect::<Result<Vec<i64>>>()?;
+    let values = rows.iter().map(|&(_, v)| v).collect();
+    let mut ts = TimeSeries::new(index, values);
+    ts.index.resolution = resolution;
+    Ok(ts)
+}
+
+/// Save series into the table. Table is created if it doesn't exist.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(conn, ts), fields(len = ts.len()), err))]
+pub fn write_table<C: Connection>(conn: &mut C, table: &str, ts: &TimeSeries) -> Result<()> {
+    conn.insert_rows(&quote(table), &ts.index.values, &ts.values)
+}
+
+/// Quoted SQL identifier
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+
+#[cfg(feature = "sqlite")]
+impl Connection for rusqlite::Connection {
+
+    fn query_rows(&mut self, sql: &str, ts_col: &str, value_col: &str) -> Result<Vec<(String, f64)>> {
+        use rusqlite::types::ValueRef;
+
+        let mut stmt = self.prepare(sql)?;
+        let ts_pos = stmt.column_index(ts_col)?;
+        let value_pos = stmt.column_index(value_col)?;
+        let mut rows = stmt.query([])?;
+        let mut output = vec![];
+        while let Some(row) = rows.next()? {
+            let timestamp = match row.get_ref(ts_pos)? {
+                ValueRef::Integer(t) => t.to_string(),
+                ValueRef::Real(t) => t.to_string(),
+                ValueRef::Text(t) => String::from_utf8(t.to_vec()).map_err(|e| Error::InvalidIndex(e.to_string()))?,
+                _ => return Err(Error::invalid_index("Invalid timestamp column type")),
+            };
+            let value = match row.get_ref(value_pos)? {
+                ValueRef::Null => f64::NAN,
+                ValueRef::Integer(v) => v as f64,
+                ValueRef::Real(v) => v,
+                _ => return Err(Error::invalid_format("Invalid value column type")),
+            };
+            output.push((timestamp, value));
+        }
+        Ok(output)
+    }
+
+    fn insert_rows(&mut self, table: &str, index: &[i64], values: &[f64]) -> Result<()> {
+        let tx = self.transaction()?;
+        tx.execute(&format!("CREATE TABLE IF NOT EXISTS {} (timestamp INTEGER, value REAL)", table), [])?;
+        {
+            let mut stmt = tx.prepare(&format!("INSERT INTO {} (timestamp, value) VALUES (?1, ?2)", table))?;
+            for (t, v) in index.iter().zip(values) {
+                stmt.execute(rusqlite::params![t, v])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+
+#[cfg(feature = "postgres")]
+impl Connection for postgres::Client {
+
+    fn query_rows(&mut self, sql: &str, ts_col: &str, value_col: &str) -> Result<Vec<(String, f64)>> {
+        use postgres::types::Type;
+        use chrono::{DateTime, NaiveDateTime, Utc};
+
+        let mut output = vec![];
+        for row in self.query(sql, &[])? {
+            let column = row.columns().iter().find(|c| c.name() == ts_col).ok_or_else(|| Error::invalid_index("Missing timestamp column"))?;
+            let timestamp = match *column.type_() {
+                Type::INT8 => row.try_get::<_, i64>(ts_col)?.to_string(),
+                Type::INT4 => row.try_get::<_, i32>(ts_col)?.to_string(),
+                Type::TIMESTAMPTZ => row.try_get::<_, DateTime<Utc>>(ts_col)?.to_rfc3339(),
+                Type::TIMESTAMP => row.try_get::<_, NaiveDateTime>(ts_col)?.and_utc().to_rfc3339(),
+                _ => row.try_get::<_, String>(ts_col)?,
+            };
+            let value = row.try_get::<_, Option<f64>>(value_col)?.unwrap_or(f64::NAN);
+            output.push((timestamp, value));
+        }
+        Ok(output)
+    }
+
+    fn insert_rows(&mut self, table: &str, index: &[i64], values: &[f64]) -> Result<()> {
+        let mut tx = self.transaction()?;
+        tx.execute(format!("CREATE TABLE IF NOT EXISTS {} (timestamp BIGINT, value DOUBLE PRECISION)", table).as_str(), &[])?;
+        let stmt = tx.prepare(&format!("INSERT INTO {} (timestamp, value) VALUES ($1, $2)", table))?;
+        for (t, v) in index.iter().zip(values) {
+            let params: [&(dyn postgres::types::ToSql + Sync); 2] = [t, v];
+            tx.execute(&stmt, &params)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_round_trip() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        let ts = TimeSeries::new(vec![1_600_000_000_000, 1_600_000_001_000], vec![1.5, f64::NAN]);
+        write_table(&mut conn, "my \"table\"", &ts).unwrap();
+        let loaded = read_query(&mut conn, "SELECT * FROM \"my \"\"table\"\"\" ORDER BY timestamp", "timestamp", "value").unwrap();
+        assert_eq!(loaded.index, ts.index);
+        assert_eq!(loaded.values[0], 1.5);
+        assert!(loaded.values[1].is_nan());
+    }
+
+    #[test]
+    fn test_resolution_round_trip() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        let ts = TimeSeries::new(vec![1, 2, 1_000], vec![1.0, 2.0, 3.0]);
+        write_table(&mut conn, "small", &ts).unwrap();
+        assert_eq!(read_query(&mut conn, "SELECT * FROM small ORDER BY timestamp", "timestamp", "value").unwrap(), ts);
+
+        let mut ts = TimeSeries::new(vec![7, 1_600_000_000_000_000_007], vec![1.0, 2.0]);
+        ts.index.resolution = Resolution::Nanosecond;
+        write_table(&mut conn, "nanos", &ts).unwrap();
+        let sql = "SELECT * FROM nanos ORDER BY timestamp";
+        let loaded = read_query_with_resolution(&mut conn, sql, "timestamp", "value", Resolution::Nanosecond).unwrap();
+        assert_eq!(loaded, ts);
+    }
+
+    #[test]
+    fn test_missing_column() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        write_table(&mut conn, "m", &TimeSeries::new(vec![1], vec![1.0])).unwrap();
+        assert!(read_query(&mut conn, "SELECT * FROM m", "time", "value").is_err());
+        assert!(read_query(&mut conn, "SELECT * FROM m WHERE value > 5", "timestamp", "value").unwrap().is_empty());
+    }
+}
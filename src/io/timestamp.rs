@@ -0,0 +1,139 @@
+//! Parsing timestamps from text
+//!
+//! The format is inferred from the first value and used for the whole column.
+//! Supported formats:
+//!   * Epoch numbers. The unit is detected from the magnitude: seconds (< 10^11), milliseconds (< 10^14),
+//!     microseconds (< 10^17) or nanoseconds
+//!   * RFC 3339 and ISO 8601 datetimes with timezone offset, e.g. `2023-05-01T12:00:00+02:00`
+//!   * Naive datetimes recognized by dtinfer. They are treated as UTC
+//!
+//! All timestamps are converted to UTC in the requested resolution (milliseconds by default).
+//!
+
+use chrono::{DateTime, NaiveDateTime, TimeZone};
+
+use crate::index::Resolution;
+
+
+/// Formats with timezone offset which are not covered by RFC 3339 parser
+const OFFSET_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f%z",
+    "%Y-%m-%dT%H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+];
+
+
+/// Timestamp format detected from the sample
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum TimestampFormat {
+    /// Number of units since the epoch
+    Epoch(Resolution),
+    Rfc3339,
+    WithOffset(&'static str),
+    Naive(String),
+}
+
+
+impl TimestampFormat {
+
+    /// Detect format from the sample value
+    pub(crate) fn infer(sample: &str) -> Option<TimestampFormat> {
+        let sample = sample.trim();
+        if let Ok(number) = sample.parse::<f64>() {
+            return Some(TimestampFormat::Epoch(Resolution::infer(number as i64)));
+        }
+        if DateTime::parse_from_rfc3339(sample).is_ok() {
+            return Some(TimestampFormat::Rfc3339);
+        }
+        if let Some(format) = OFFSET_FORMATS.iter().find(|f| DateTime::parse_from_str(sample, f).is_ok()) {
+            return Some(TimestampFormat::WithOffset(format));
+        }
+        dtinfer::infer_best(sample).map(TimestampFormat::Naive)
+    }
+
+    /// Parse text into UTC timestamp in the given resolution. Error has the cause
+    pub(crate) fn parse(&self, text: &str, resolution: Resolution) -> Result<i64, String> {
+        let text = text.trim();
+        match self {
+            TimestampFormat::Epoch(unit) => match text.parse::<i64>() {
+                Ok(n) => Ok(unit.convert(n, resolution)),
+                Err(_) => {
+                    let x = text.parse::<f64>().map_err(|e| e.to_string())?;
+                    let (from, to) = (unit.per_second(), resolution.per_second());
+                    let scaled = if from <= to { x * (to / from) as f64 } else { x / (from / to) as f64 };
+                    Ok(scaled.floor() as i64)
+                }
+            },
+            TimestampFormat::Rfc3339 => from_datetime(DateTime::parse_from_rfc3339(text).map_err(|e| e.to_string())?, resolution),
+            TimestampFormat::WithOffset(format) => {
+                from_datetime(DateTime::parse_from_str(text, format).map_err(|e| e.to_string())?, resolution)
+            },
+            TimestampFormat::Naive(format) => {
+                from_datetime(NaiveDateTime::parse_from_str(text, format).map_err(|e| e.to_string())?.and_utc(), resolution)
+            },
+        }
+    }
+}
+
+fn from_datetime<Tz: TimeZone>(dt: DateTime<Tz>, resolution: Resolution) -> Result<i64, String> {
+    let timestamp = match resolution {
+        Resolution::Second => dt.timestamp(),
+        Resolution::Millisecond => dt.timestamp_millis(),
+        Resolution::Microsecond => dt.timestamp_micros(),
+        Resolution::Nanosecond => dt.timestamp_nanos_opt().ok_or("Timestamp out of range")?,
+    };
+    Ok(timestamp)
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> i64 {
+        TimestampFormat::infer(text).unwrap().parse(text, Resolution::Millisecond).unwrap()
+    }
+
+    #[test]
+    fn test_offsets() {
+        // 2023-05-01 10:00:00 UTC
+        let expected = 1_682_935_200_000;
+        assert_eq!(parse("2023-05-01T12:00:00+02:00"), expected);
+        assert_eq!(parse("2023-05-01T10:00:00Z"), expected);
+        assert_eq!(parse("2023-05-01 07:00:00-0300"), expected);
+        assert_eq!(parse("2023-05-01 12:00:00.000+02:00"), expected);
+    }
+
+    #[test]
+    fn test_epoch_units() {
+        assert_eq!(parse("1682935200"), 1_682_935_200_000);
+        assert_eq!(parse("1682935200.5"), 1_682_935_200_500);
+        assert_eq!(parse("1682935200000"), 1_682_935_200_000);
+        assert_eq!(parse("1682935200000000"), 1_682_935_200_000);
+        assert_eq!(parse("1682935200000000000"), 1_682_935_200_000);
+    }
+
+    #[test]
+    fn test_naive() {
+        assert_eq!(parse("2023-05-01 10:00:00"), 1_682_935_200_000);
+        assert!(TimestampFormat::infer("invalid").is_none());
+    }
+
+    #[test]
+    fn test_format_kept_for_column() {
+        let format = TimestampFormat::infer("2023-05-01T12:00:00+02:00").unwrap();
+        assert!(format.parse("2023-05-01 12:00:00", Resolution::Millisecond).is_err());
+    }
+
+    #[test]
+    fn test_resolution() {
+        let parse_ns = |text| TimestampFormat::infer(text).unwrap().parse(text, Resolution::Nanosecond).unwrap();
+        assert_eq!(parse_ns("1682935200000000123"), 1_682_935_200_000_000_123);
+        assert_eq!(parse_ns("1682935200.5"), 1_682_935_200_500_000_000);
+        assert_eq!(parse_ns("2023-05-01T10:00:00.123456789Z"), 1_682_935_200_123_456_789);
+    }
+}
@@ -0,0 +1,393 @@
+//! Append-only log for incremental persistence of live series
+//!
+//! Each data point pushed to a live series can be appended to the log and recovered after restart.
+//! The log grows with every point, so from time to time it should be compacted into the snapshot.
+//!
+//! Log file layout (all numbers are little endian):
+//!   * Header (4 bytes) - magic `TSW1`
+//!   * Records - payload length (u32), payload and FNV-1a checksum of the payload (u32).
+//!     Payload has timestamp (i64), value (f64), number of labels (u16) and for each label
+//!     name and value as length (u16) prefixed UTF-8 text.
+//!
+//! Incomplete or corrupted record at the end of the log (e.g. after crash during write) is dropped.
+//! Corrupted records in the middle of the log are skipped and reading continues
+//! from the next valid record.
+//!
+//! Snapshot file layout:
+//!   * Header (8 bytes) - magic `TSS1`, number of series (u32)
+//!   * Series - number of labels (u16), labels encoded as in the log record,
+//!     length (u64) and series in the binary format (see `io::binary`).
+//!
+//! Quality codes are not logged.
+//!
+
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::{TimeSeries, DataPoint};
+use crate::error::{Error, Result};
+use crate::io::binary::{self, Encoding};
+use crate::series_set::{Labels, SeriesSet};
+
+
+const LOG_MAGIC: &[u8; 4] = b"TSW1";
+const SNAPSHOT_MAGIC: &[u8; 4] = b"TSS1";
+
+
+/// Log of data points with the snapshot of older data
+pub struct WriteAheadLog {
+    log_path: String,
+    snapshot_path: String,
+    writer: BufWriter<File>,
+}
+
+
+impl WriteAheadLog {
+
+    /// Open log for appending. Files are created if they don't exist.
+    /// Broken record at the end of the log is removed.
+    pub fn open(log_path: &str, snapshot_path: &str) -> Result<WriteAheadLog> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(log_path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        if bytes.is_empty() {
+            file.write_all(LOG_MAGIC)?;
+        } else {
+            let valid = decode_log(&bytes, |_, _| ())?;
+            file.set_len(valid as u64)?;
+        }
+        let writer = BufWriter::new(OpenOptions::new().append(true).open(log_path)?);
+        Ok(WriteAheadLog { log_path: log_path.to_owned(), snapshot_path: snapshot_path.to_owned(), writer })
+    }
+
+    /// Append data point of the unlabeled series
+    pub fn append(&mut self, dp: &DataPoint) -> Result<()> {
+        self.append_labeled(&Labels::new(), dp)
+    }
+
+    /// Append data point of the series with the given labels
+    pub fn append_labeled(&mut self, labels: &Labels, dp: &DataPoint) -> Result<()> {
+        let mut payload = vec![];
+        payload.extend_from_slice(&dp.timestamp.to_le_bytes());
+        payload.extend_from_slice(&dp.value.to_le_bytes());
+        write_labels(labels, &mut payload)?;
+        self.writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&payload)?;
+        self.writer.write_all(&checksum(&payload).to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Write buffered records and wait until they are stored on disk
+    pub fn sync(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        Ok(())
+    }
+
+    /// Load the snapshot and replay the log
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    pub fn recover_set(&mut self) -> Result<SeriesSet> {
+        self.writer.flush()?;
+        let mut set = if Path::new(&self.snapshot_path).exists() {
+            read_snapshot(&self.snapshot_path)?
+        } else {
+            SeriesSet::new()
+        };
+        decode_log(&fs::read(&self.log_path)?, |labels, dp| set.push(labels, dp))?;
+        Ok(set)
+    }
+
+    /// Recover the unlabeled series
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    /// use timeseries::io::binary::Encoding;
+    /// use timeseries::io::wal::WriteAheadLog;
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let log_path = dir.join("timeseries_doc.wal");
+    /// let snapshot_path = dir.join("timeseries_doc.tss");
+    /// let _ = std::fs::remove_file(&log_path);
+    /// let _ = std::fs::remove_file(&snapshot_path);
+    /// let (log_path, snapshot_path) = (log_path.to_str().unwrap(), snapshot_path.to_str().unwrap());
+    ///
+    /// let mut wal = WriteAheadLog::open(log_path, snapshot_path).unwrap();
+    /// wal.append(&DataPoint::new(1, 1.0)).unwrap();
+    /// wal.compact(Encoding::Gorilla).unwrap();
+    /// wal.append(&DataPoint::new(2, 2.0)).unwrap();
+    /// wal.sync().unwrap();
+    ///
+    /// let mut wal = WriteAheadLog::open(log_path, snapshot_path).unwrap();
+    /// assert_eq!(wal.recover().unwrap().values, vec![1.0, 2.0]);
+    /// ```
+    pub fn recover(&mut self) -> Result<TimeSeries> {
+        let set = self.recover_set()?;
+        Ok(set.get(&Labels::new()).cloned().unwrap_or_else(TimeSeries::empty))
+    }
+
+    /// Save all data into the snapshot and clear the log.
+    /// The new snapshot replaces the old one only after it is completely written.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    pub fn compact(&mut self, encoding: Encoding) -> Result<()> {
+        let set = self.recover_set()?;
+        let tmp_path = format!("{}.tmp", self.snapshot_path);
+        write_snapshot(&tmp_path, &set, encoding)?;
+        fs::rename(&tmp_path, &self.snapshot_path)?;
+        sync_parent_dir(&self.snapshot_path)?;
+        let mut file = File::create(&self.log_path)?;
+        file.write_all(LOG_MAGIC)?;
+        file.sync_data()?;
+        self.writer = BufWriter::new(OpenOptions::new().append(true).open(&self.log_path)?);
+        Ok(())
+    }
+}
+
+
+/// Save series set in the snapshot file
+pub fn write_snapshot(file_path: &str, set: &SeriesSet, encoding: Encoding) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(file_path)?);
+    writer.write_all(SNAPSHOT_MAGIC)?;
+    writer.write_all(&(set.len() as u32).to_le_bytes())?;
+    for (labels, ts) in set.iter() {
+        let mut header = vec![];
+        write_labels(labels, &mut header)?;
+        writer.write_all(&header)?;
+        let mut bytes = vec![];
+        binary::write_to(&mut bytes, ts, encoding)?;
+        writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        writer.write_all(&bytes)?;
+    }
+    writer.flush()?;
+    writer.get_ref().sync_data()?;
+    Ok(())
+}
+
+/// Load series set from the snapshot file
+pub fn read_snapshot(file_path: &str) -> Result<SeriesSet> {
+    let bytes = fs::read(file_path)?;
+    if bytes.len() < 8 || &bytes[..4] != SNAPSHOT_MAGIC {
+        return Err(Error::invalid_format("Not a snapshot file"));
+    }
+    let count = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    let mut set = SeriesSet::new();
+    let mut pos = 8;
+    for _ in 0..count {
+        let labels = read_labels(&bytes, &mut pos).ok_or_else(|| Error::invalid_format("Invalid labels"))?;
+        let len = bytes.get(pos..pos + 8).ok_or_else(|| Error::invalid_format("Unexpected end of data"))?;
+        let len = u64::from_le_bytes(len.try_into().unwrap()) as usize;
+        pos += 8;
+        let series = bytes.get(pos..pos.saturating_add(len)).ok_or_else(|| Error::invalid_format("Unexpected end of data"))?;
+        pos += len;
+        set.insert(labels, binary::decode(series)?);
+    }
+    Ok(set)
+}
+
+
+/// Make the rename in the directory durable
+fn sync_parent_dir(file_path: &str) -> Result<()> {
+    let dir = Path::new(file_path).parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    // Directories can't be opened as files on Windows
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Call f for each valid record. Corrupted record is skipped by searching for the next valid one.
+/// Returns the end of the last valid record, so the broken tail can be removed.
+fn decode_log<F: FnMut(Labels, DataPoint)>(bytes: &[u8], mut f: F) -> Result<usize> {
+    if bytes.len() < LOG_MAGIC.len() || &bytes[..4] != LOG_MAGIC {
+        return Err(Error::invalid_format("Not a log file"));
+    }
+    let mut pos = LOG_MAGIC.len();
+    let mut end = pos;
+    while pos < bytes.len() {
+        if let Some((labels, dp, len)) = decode_record(&bytes[pos..]) {
+            f(labels, dp);
+            pos += len;
+            end = pos;
+        } else {
+            pos += 1;
+        }
+    }
+    Ok(end)
+}
+
+/// Labels, data point and size of the record
+fn decode_record(bytes: &[u8]) -> Option<(Labels, DataPoint, usize)> {
+    let len = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?) as usize;
+    let payload = bytes.get(4..4 + len)?;
+    let sum = u32::from_le_bytes(bytes.get(4 + len..8 + len)?.try_into().ok()?);
+    if sum != checksum(payload) {
+        return None;
+    }
+    let timestamp = i64::from_le_bytes(payload.get(0..8)?.try_into().ok()?);
+    let value = f64::from_le_bytes(payload.get(8..16)?.try_into().ok()?);
+    let mut pos = 16;
+    let labels = read_labels(payload, &mut pos)?;
+    if pos != payload.len() {
+        return None;
+    }
+    Some((labels, DataPoint::new(timestamp, value), len + 8))
+}
+
+fn write_labels(labels: &Labels, bytes: &mut Vec<u8>) -> Result<()> {
+    let count: u16 = labels.len().try_into().map_err(|_| Error::invalid_format("Too many labels"))?;
+    bytes.extend_from_slice(&count.to_le_bytes());
+    for (name, value) in labels {
+        write_text(name, bytes)?;
+        write_text(value, bytes)?;
+    }
+    Ok(())
+}
+
+fn read_labels(bytes: &[u8], pos: &mut usize) -> Option<Labels> {
+    let count = u16::from_le_bytes(bytes.get(*pos..*pos + 2)?.try_into().ok()?);
+    *pos += 2;
+    let mut labels = Labels::new();
+    for _ in 0..count {
+        let name = read_text(bytes, pos)?;
+        let value = read_text(bytes, pos)?;
+        labels.insert(name, value);
+    }
+    Some(labels)
+}
+
+fn write_text(text: &str, bytes: &mut Vec<u8>) -> Result<()> {
+    let len: u16 = text.len().try_into().map_err(|_| Error::invalid_format("Label too long"))?;
+    bytes.extend_from_slice(&len.to_le_bytes());
+    bytes.extend_from_slice(text.as_bytes());
+    Ok(())
+}
+
+fn read_text(bytes: &[u8], pos: &mut usize) -> Option<String> {
+    let len = u16::from_le_bytes(bytes.get(*pos..*pos + 2)?.try_into().ok()?) as usize;
+    let text = std::str::from_utf8(bytes.get(*pos + 2..*pos + 2 + len)?).ok()?;
+    *pos += 2 + len;
+    Some(text.to_owned())
+}
+
+/// 32 bit FNV-1a hash
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, &b| (hash ^ b as u32).wrapping_mul(0x01000193))
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::series_set::labels;
+
+    fn paths(name: &str) -> (String, String) {
+        let dir = std::env::temp_dir();
+        let log_path = dir.join(format!("{}.wal", name));
+        let snapshot_path = dir.join(format!("{}.tss", name));
+        let _ = fs::remove_file(&log_path);
+        let _ = fs::remove_file(&snapshot_path);
+        (log_path.to_str().unwrap().to_owned(), snapshot_path.to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn test_recover_labeled() {
+        let (log_path, snapshot_path) = paths("timeseries_wal_labeled");
+        let a = labels(&[("host", "a")]);
+        let b = labels(&[("host", "b"), ("metric", "cpu")]);
+        {
+            let mut wal = WriteAheadLog::open(&log_path, &snapshot_path).unwrap();
+            wal.append_labeled(&a, &DataPoint::new(1, 1.0)).unwrap();
+            wal.append_labeled(&b, &DataPoint::new(1, 5.0)).unwrap();
+            wal.compact(Encoding::Delta).unwrap();
+            wal.append_labeled(&a, &DataPoint::new(2, 2.0)).unwrap();
+        }
+        let set = WriteAheadLog::open(&log_path, &snapshot_path).unwrap().recover_set().unwrap();
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get(&a).unwrap().values, vec![1.0, 2.0]);
+        assert_eq!(set.get(&b).unwrap().values, vec![5.0]);
+        assert!(set.get(&b).unwrap().metadata.is_empty());
+    }
+
+    #[test]
+    fn test_torn_record_is_dropped() {
+        let (log_path, snapshot_path) = paths("timeseries_wal_torn");
+        {
+            let mut wal = WriteAheadLog::open(&log_path, &snapshot_path).unwrap();
+            wal.append(&DataPoint::new(1, 1.0)).unwrap();
+            wal.append(&DataPoint::new(2, 2.0)).unwrap();
+        }
+        let len = fs::metadata(&log_path).unwrap().len();
+        OpenOptions::new().write(true).open(&log_path).unwrap().set_len(len - 3).unwrap();
+        let mut wal = WriteAheadLog::open(&log_path, &snapshot_path).unwrap();
+        wal.append(&DataPoint::new(3, 3.0)).unwrap();
+        assert_eq!(wal.recover().unwrap().index.values, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_corrupted_record_is_skipped() {
+        let (log_path, snapshot_path) = paths("timeseries_wal_skip");
+        {
+            let mut wal = WriteAheadLog::open(&log_path, &snapshot_path).unwrap();
+            for i in 1..=3 {
+                wal.append_labeled(&labels(&[("host", "a")]), &DataPoint::new(i, i as f64)).unwrap();
+            }
+        }
+        let mut bytes = fs::read(&log_path).unwrap();
+        let record_len = (bytes.len() - LOG_MAGIC.len()) / 3;
+        bytes[LOG_MAGIC.len() + record_len + 10] ^= 0xff;
+        fs::write(&log_path, &bytes).unwrap();
+        let mut wal = WriteAheadLog::open(&log_path, &snapshot_path).unwrap();
+        assert_eq!(fs::metadata(&log_path).unwrap().len(), bytes.len() as u64);
+        wal.append_labeled(&labels(&[("host", "a")]), &DataPoint::new(4, 4.0)).unwrap();
+        let set = wal.recover_set().unwrap();
+        assert_eq!(set.get(&labels(&[("host", "a")])).unwrap().index.values, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_snapshot_keeps_tags() {
+        let (_, snapshot_path) = paths("timeseries_wal_tags");
+        let a = labels(&[("host", "a")]);
+        let mut ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+        ts.metadata.tags = labels(&[("host", "b"), ("region", "eu")]);
+        let mut set = SeriesSet::new();
+        set.insert(a.clone(), ts.clone());
+        write_snapshot(&snapshot_path, &set, Encoding::Delta).unwrap();
+        let set = read_snapshot(&snapshot_path).unwrap();
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(&a).unwrap().metadata, ts.metadata);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let (log_path, snapshot_path) = paths("timeseries_wal_invalid");
+        fs::write(&log_path, b"time,value\n").unwrap();
+        assert!(WriteAheadLog::open(&log_path, &snapshot_path).is_err());
+        fs::write(&snapshot_path, b"TSS1\x01\x00\x00\x00").unwrap();
+        assert!(read_snapshot(&snapshot_path).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_snapshot() {
+        let (_, snapshot_path) = paths("timeseries_wal_corrupted");
+        let mut set = SeriesSet::new();
+        for i in 0..50 {
+            set.push(labels(&[("host", "a")]), DataPoint::new(i * 1000, (i % 7) as f64 * 0.5));
+        }
+        write_snapshot(&snapshot_path, &set, Encoding::Gorilla).unwrap();
+        let bytes = fs::read(&snapshot_path).unwrap();
+        for bit in 0..bytes.len() * 8 {
+            let mut corrupted = bytes.clone();
+            corrupted[bit / 8] ^= 1 << (bit % 8);
+            fs::write(&snapshot_path, &corrupted).unwrap();
+            // Any result is fine as long as the reader doesn't panic
+            let _ = read_snapshot(&snapshot_path);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Kalman filter and smoother for univariate state space models
+//!
+//! Models:
+//!   * LocalLevel - value is a random walk observed with noise
+//!   * LocalTrend - value changes with a slope, which is a random walk (white noise acceleration)
+//!
+//! Time step dt between data points is measured in typical (median) sampling intervals,
+//! so regular series have dt = 1 and noise parameters don't depend on the timestamp resolution.
+//! Process noise grows with dt. NaN values are treated as missing, so their estimates are imputed.
+//!
+
+use crate::TimeSeries;
+use crate::linalg::{self, Matrix};
+
+
+/// Initial slope variance relative to the variance of values
+const DIFFUSE_SCALE: f64 = 1e4;
+
+
+/// State space model
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateModel {
+    LocalLevel,
+    LocalTrend,
+}
+
+/// Kalman filter
+///   * model - State space model
+///   * process_noise - Variance of the state change in one sampling interval
+///   * measurement_noise - Variance of the observation noise
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KalmanFilter {
+    pub model: StateModel,
+    pub process_noise: f64,
+    pub measurement_noise: f64,
+}
+
+/// State estimates after the forward pass
+struct Pass {
+    predicted: Vec<(Vec<f64>, Matrix)>,
+    filtered: Vec<(Vec<f64>, Matrix)>,
+    transitions: Vec<Matrix>,
+}
+
+
+impl KalmanFilter {
+
+    pub fn new(model: StateModel, process_noise: f64, measurement_noise: f64) -> KalmanFilter {
+        KalmanFilter { model, process_noise, measurement_noise }
+    }
+
+    /// Estimate level at each data point using data points up to it (forward pass only).
+    /// Returns NaN values if the series has no values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::kalman::{KalmanFilter, StateModel};
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1, 2, 3], vec![1.0, 1.2, f64::NAN, 0.8]);
+    /// let filtered = KalmanFilter::new(StateModel::LocalLevel, 0.01, 1.0).filter(&ts);
+    /// assert!(filtered.values.iter().all(|v| (0.8..1.2).contains(v)));
+    /// ```
+    pub fn filter(&self, ts: &TimeSeries) -> TimeSeries {
+        let mut output = ts.clone();
+        output.values = match self.forward(ts) {
+            Some(pass) => pass.filtered.iter().map(|(x, _)| x[0]).collect(),
+            None => vec![f64::NAN; ts.len()],
+        };
+        output.logged(ts, "kalman_filter", || self.params())
+    }
+
+    /// Estimate level at each data point using all data points (Rauch-Tung-Striebel smoother).
+    /// Returns NaN values if the series has no values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::kalman::{KalmanFilter, StateModel};
+    /// use assert_approx_eq::assert_approx_eq;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 10, 20, 40], vec![0.0, 1.0, 2.0, 4.0]);
+    /// let smoothed = KalmanFilter::new(StateModel::LocalTrend, 0.001, 0.001).smooth(&ts);
+    /// assert_approx_eq!(smoothed.values[3], 4.0, 0.01);
+    ///
+    /// // Missing value is imputed
+    /// let ts = TimeSeries::new(vec![0, 10, 20, 30, 40], vec![0.0, 1.0, f64::NAN, 3.0, 4.0]);
+    /// let smoothed = KalmanFilter::new(StateModel::LocalTrend, 0.001, 0.001).smooth(&ts);
+    /// assert_approx_eq!(smoothed.values[2], 2.0, 0.01);
+    /// ```
+    pub fn smooth(&self, ts: &TimeSeries) -> TimeSeries {
+        let mut output = ts.clone();
+        output.values = match self.forward(ts) {
+            Some(pass) => {
+                let n = ts.len();
+                let mut states: Vec<Vec<f64>> = pass.filtered.iter().map(|(x, _)| x.clone()).collect();
+                for k in (0..n.saturating_sub(1)).rev() {
+                    let (x_pred, p_pred) = &pass.predicted[k + 1];
+                    let gain = match linalg::invert(p_pred.clone()) {
+                        Some(inverse) => linalg::mul(&linalg::mul(&pass.filtered[k].1, &linalg::transpose(&pass.transitions[k + 1])), &inverse),
+                        // No uncertainty in the prediction, so the next state doesn't change this one
+                        None => continue,
+                    };
+                    let innovation: Vec<f64> = states[k + 1].iter().zip(x_pred).map(|(s, p)| s - p).collect();
+                    let correction = linalg::mul_vec(&gain, &innovation);
+                    states[k] = states[k].iter().zip(correction).map(|(s, c)| s + c).collect();
+                }
+                states.iter().map(|x| x[0]).collect()
+            },
+            None => vec![f64::NAN; ts.len()],
+        };
+        output.logged(ts, "kalman_smooth", || self.params())
+    }
+
+    /// Forward pass. None if there are no values to start from
+    fn forward(&self, ts: &TimeSeries) -> Option<Pass> {
+        let first = ts.values.iter().cloned().find(|v| !v.is_nan())?;
+        let step = ts.index.sampling_report().map_or(1, |r| r.median_delta).max(1) as f64;
+        let r = self.measurement_noise;
+        // Slope is unknown at the start, so its variance is large relative to the values spread
+        let diffuse = DIFFUSE_SCALE * (ts.std().filter(|v| v.is_finite()).map_or(0.0, |v| v * v) + r);
+        let (mut x, mut p) = match self.model {
+            StateModel::LocalLevel => (vec![first], vec![vec![r]]),
+            StateModel::LocalTrend => (vec![first, 0.0], vec![vec![r, 0.0], vec![0.0, diffuse]]),
+        };
+        let mut pass = Pass { predicted: vec![], filtered: vec![], transitions: vec![] };
+        for (pos, &value) in ts.values.iter().enumerate() {
+            let dt = if pos == 0 { 0.0 } else { (ts.index[pos] - ts.index[pos - 1]) as f64 / step };
+            let (f, q) = self.transition(dt);
+            x = linalg::mul_vec(&f, &x);
+            p = linalg::add(&linalg::mul(&linalg::mul(&f, &p), &linalg::transpose(&f)), &q);
+            pass.predicted.push((x.clone(), p.clone()));
+            pass.transitions.push(f);
+            if !value.is_nan() {
+                // Only the level is observed
+                let s = p[0][0] + r;
+                let gain: Vec<f64> = p.iter().map(|row| row[0] / s).collect();
+                let residual = value - x[0];
+                x = x.iter().zip(&gain).map(|(x, k)| x + k * residual).collect();
+                let first_row = p[0].clone();
+                p = p.iter().zip(&gain)
+                    .map(|(row, k)| row.iter().zip(&first_row).map(|(a, b)| a - k * b).collect())
+                    .collect();
+            }
+            pass.filtered.push((x.clone(), p.clone()));
+        }
+        Some(pass)
+    }
+
+    /// State transition and process noise covariance for the time step
+    fn transition(&self, dt: f64) -> (Matrix, Matrix) {
+        let q = self.process_noise;
+        match self.model {
+            StateModel::LocalLevel => (vec![vec![1.0]], vec![vec![q * dt]]),
+            StateModel::LocalTrend => (
+                vec![vec![1.0, dt], vec![0.0, 1.0]],
+                vec![vec![q * dt.powi(3) / 3.0, q * dt * dt / 2.0], vec![q * dt * dt / 2.0, q * dt]],
+            ),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("model", format!("{:?}", self.model)),
+            ("process_noise", self.process_noise.to_string()),
+            ("measurement_noise", self.measurement_noise.to_string()),
+        ]
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_no_values() {
+        let ts = TimeSeries::new(vec![1, 2], vec![f64::NAN, f64::NAN]);
+        let kf = KalmanFilter::new(StateModel::LocalLevel, 1.0, 1.0);
+        assert!(kf.filter(&ts).values.iter().all(|v| v.is_nan()));
+        assert!(kf.smooth(&TimeSeries::empty()).is_empty());
+    }
+
+    #[test]
+    fn test_constant_level() {
+        let ts = TimeSeries::new((0..50).collect(), (0..50).map(|i| if i % 2 == 0 { 9.0 } else { 11.0 }).collect());
+        let kf = KalmanFilter::new(StateModel::LocalLevel, 1e-6, 1.0);
+        let smoothed = kf.smooth(&ts);
+        assert_approx_eq!(smoothed.values[25], 10.0, 0.1);
+        assert_approx_eq!(kf.filter(&ts).values[49], 10.0, 0.2);
+    }
+
+    #[test]
+    fn test_irregular_gap() {
+        // Large gap lets the level move more, so the filter follows the jump faster
+        let regular = TimeSeries::new(vec![0, 1, 2, 3], vec![0.0, 0.0, 0.0, 10.0]);
+        let gap = TimeSeries::new(vec![0, 1, 2, 100], vec![0.0, 0.0, 0.0, 10.0]);
+        let kf = KalmanFilter::new(StateModel::LocalLevel, 0.1, 1.0);
+        assert!(kf.filter(&gap).values[3] > kf.filter(&regular).values[3]);
+    }
+}
@@ -0,0 +1,80 @@
+//! Operations on plain slices of timestamps and values
+//!
+//! Series functions use these kernels instead of iterating over DataPoints,
+//! so no intermediate data points or series are created.
+//!
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Source of a merged data point
+///   * Left(i) - Only the left slice has this timestamp (at position i)
+///   * Right(j) - Only the right slice has this timestamp (at position j)
+///   * Both(i, j) - Both slices have this timestamp
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Pick {
+    Left(usize),
+    Right(usize),
+    Both(usize, usize),
+}
+
+
+/// Differences between consecutive values
+pub(crate) fn diff(values: &[f64]) -> Vec<f64> {
+    values.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// Merge two sorted indexes. The result stops at the first timestamp which is not
+/// greater than the previous one (same rule as `TimeSeries::from_datapoints`).
+pub(crate) fn merge_picks(left: &[i64], right: &[i64]) -> Vec<Pick> {
+    let mut picks = Vec::with_capacity(left.len() + right.len());
+    let mut last = i64::MIN;
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() || j < right.len() {
+        let (pick, timestamp) = if j == right.len() || (i < left.len() && left[i] < right[j]) {
+            i += 1;
+            (Pick::Left(i-1), left[i-1])
+        } else if i == left.len() || right[j] < left[i] {
+            j += 1;
+            (Pick::Right(j-1), right[j-1])
+        } else {
+            i += 1;
+            j += 1;
+            (Pick::Both(i-1, j-1), left[i-1])
+        };
+        if !picks.is_empty() && timestamp <= last {
+            break;
+        }
+        last = timestamp;
+        picks.push(pick);
+    }
+    picks
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_picks() {
+        let picks = merge_picks(&[1, 3, 5], &[2, 3]);
+        assert_eq!(picks, vec![Pick::Left(0), Pick::Right(0), Pick::Both(1, 1), Pick::Left(2)]);
+        assert_eq!(merge_picks(&[], &[]), vec![]);
+    }
+
+    #[test]
+    fn test_merge_picks_unsorted() {
+        assert_eq!(merge_picks(&[1, 5, 2], &[]), vec![Pick::Left(0), Pick::Left(1)]);
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff(&[1.0, 4.0, 2.0]), vec![3.0, -2.0]);
+        assert!(diff(&[1.0]).is_empty());
+    }
+}
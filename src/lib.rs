@@ -2,35 +2,188 @@
 //!
 //! Process Time Series in memory
 //!
+//! ## no_std
+//!
+//! Without the default `std` feature the crate is `no_std` and needs `alloc` and the `libm` feature
+//! (for floating point functions). Only the core types are available then: `TimeSeries`,
+//! `DateTimeIndex`, statistics, moving windows and resampling.
+//!
+//! ## Tracing
+//!
+//! With the `tracing` feature file operations and heavy algorithms (resampling, change point
+//! detection, DTW, model fitting, ...) are wrapped in `tracing` spans, and rows skipped by
+//! the CSV reader are reported as warnings. The library never prints to stdout.
+//!
 
-use std::iter::FromIterator;
-use std::fmt;
-use std::cmp;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("Either `std` or `libm` feature is required");
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use core::iter::FromIterator;
+use core::fmt;
+use core::cmp;
+use core::hash::{Hash, Hasher};
 use serde::{Deserialize, Serialize};
-use chrono::NaiveDateTime;
+#[cfg(feature = "std")]
+use rand::SeedableRng;
+#[cfg(feature = "std")]
+use rand::rngs::StdRng;
 
-use crate::index::DateTimeIndex;
+use crate::index::{DateTimeIndex, Keep, Resolution};
+use crate::builder::TimeSeriesBuilder;
+use crate::format::SeriesFormat;
+use crate::provenance::Provenance;
+use crate::metadata::Metadata;
+use crate::kernels::Pick;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 
+#[cfg(feature = "ndarray")]
+pub mod array;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod arithmetic;
+#[cfg(feature = "std")]
+pub mod backtest;
+#[cfg(feature = "std")]
+pub mod bounded;
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod calculus;
+#[cfg(feature = "std")]
+pub mod calendar;
+#[cfg(feature = "std")]
+pub mod categorical;
+#[cfg(feature = "std")]
+pub mod changepoint;
+#[cfg(feature = "std")]
+pub mod cluster;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod compressed;
+#[cfg(feature = "std")]
+pub mod distance;
+#[cfg(feature = "std")]
+mod encoding;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod events;
+#[cfg(feature = "std")]
+pub mod expr;
+pub mod extrapolation;
+#[cfg(feature = "std")]
+pub mod features;
+#[cfg(feature = "std")]
+pub mod forecast;
+pub mod format;
+#[cfg(feature = "std")]
+pub mod frame;
+pub mod frequency;
+#[cfg(feature = "std")]
+pub mod fusion;
+#[cfg(feature = "std")]
+pub mod generate;
 pub mod index;
+#[cfg(feature = "std")]
+pub mod indicators;
+mod kernels;
+#[cfg(feature = "io")]
 pub mod io;
+#[cfg(feature = "std")]
+pub mod kalman;
+#[cfg(feature = "std")]
+mod linalg;
+#[cfg(feature = "std")]
+pub mod lookup;
+#[cfg(feature = "std")]
+pub mod mask;
+#[cfg(feature = "std")]
+pub mod merge;
+pub mod metadata;
+#[cfg(feature = "std")]
+pub mod metrics;
+pub mod nan;
+#[cfg(feature = "std")]
+pub mod ohlc;
+#[cfg(feature = "std")]
+pub mod online;
+#[cfg(feature = "linalg")]
+pub mod pca;
+#[cfg(feature = "std")]
+pub mod piecewise;
+#[cfg(feature = "std")]
+pub mod pipeline;
+#[cfg(not(feature = "std"))]
+mod prelude;
+#[cfg(feature = "std")]
+pub mod profile;
+pub mod progress;
+pub mod provenance;
+pub mod quality;
+#[cfg(feature = "std")]
+pub mod reduce;
+pub mod resample;
+pub mod rolling;
+#[cfg(feature = "std")]
+pub mod rollup;
+pub mod segment_tree;
+pub mod slice;
+#[cfg(feature = "std")]
+pub mod series_set;
+#[cfg(feature = "std")]
+pub mod smoothing;
+#[cfg(feature = "fft")]
+pub mod spectral;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod supervised;
+#[cfg(feature = "std")]
+pub mod symbolic;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod trend;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub use crate::error::Error;
 
 
 /// Time Series with normalized data
-///   * index - Index based on timestamp in millisecond resolution
+///   * index - Index based on timestamp in millisecond resolution (see `index::Resolution`)
 ///   * values - Data points
+///   * quality - Optional quality code for each data point (see `quality` module)
+///   * provenance - Optional log of applied operations (see `provenance` module)
+///   * metadata - Name, unit and tags (see `metadata` module)
 #[derive(Clone, Debug)]
 pub struct TimeSeries {
     pub index: DateTimeIndex,
-    pub values: Vec<f64>
+    pub values: Vec<f64>,
+    pub quality: Option<Vec<u8>>,
+    pub provenance: Option<Provenance>,
+    pub metadata: Metadata,
 }
 
 /// Single data point
 ///   * timestamp - Data point timestamp
 ///   * value - Data point value
+///   * quality - Optional quality code (see `quality` module). None is the same as GOOD
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct DataPoint {
     pub timestamp: i64,
-    pub value: f64
+    pub value: f64,
+    #[serde(default)]
+    pub quality: Option<u8>,
 }
 
 
@@ -66,13 +219,14 @@ impl TimeSeries {
         if index.len() != values.len() {
             let mut vs = values;
             vs.resize(index.len(), 0.0);
-            TimeSeries { index: DateTimeIndex::new(index), values: vs }
+            TimeSeries { index: DateTimeIndex::new(index), values: vs, quality: None, provenance: None, metadata: Metadata::default() }
         } else {
-            TimeSeries { index: DateTimeIndex::new(index), values }
+            TimeSeries { index: DateTimeIndex::new(index), values, quality: None, provenance: None, metadata: Metadata::default() }
         }
     }
 
-    /// Create a new Time Series from from rows of tuples of timestamp and value
+    /// Create a new Time Series from from rows of tuples of timestamp and value.
+    /// If any data point has quality code, then the series gets quality channel.
     ///
     /// # Example
     ///
@@ -95,10 +249,31 @@ impl TimeSeries {
         }
         let index = datapoints.iter().take(size).map(|r| r.timestamp).collect();
         let values = datapoints.iter().take(size).map(|r| r.value).collect();
-        TimeSeries { index: DateTimeIndex::new(index), values }
+        let quality = if datapoints.iter().take(size).any(|r| r.quality.is_some()) {
+            Some(datapoints.iter().take(size).map(|r| r.quality.unwrap_or(quality::GOOD)).collect())
+        } else {
+            None
+        };
+        TimeSeries { index: DateTimeIndex::new(index), values, quality, provenance: None, metadata: Metadata::default() }
+    }
+
+    /// Create builder which allows to choose how invalid input data is handled
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::builder::OnUnsorted;
+    ///
+    /// let ts = TimeSeries::builder().on_unsorted(OnUnsorted::Sort).build(vec![2, 1], vec![2.0, 1.0]).unwrap();
+    /// assert_eq!(ts.index.values, vec![1, 2]);
+    /// ```
+    pub fn builder() -> TimeSeriesBuilder {
+        TimeSeriesBuilder::new()
     }
 
-    /// Calculates the difference between series values
+    /// Calculates the difference between series values.
+    /// Each difference gets the worst (highest) quality code of both data points.
     ///
     /// # Example
     ///
@@ -115,11 +290,12 @@ impl TimeSeries {
             TimeSeries::empty()
         } else {
             let index = self.index.values[1..].to_owned();
-            let mut new_values = vec![0.0; self.len()-1];
-            for i in 1..self.len() {
-                new_values[i-1] = self.values[i] - self.values[i-1];
-            }
-            TimeSeries::new(index, new_values)
+            let values = kernels::diff(&self.values);
+            let quality = self.quality.as_ref()
+                .map(|q| q.windows(2).map(|w| cmp::max(w[0], w[1])).collect());
+            let index = DateTimeIndex::new(index).with_resolution(self.index.resolution);
+            TimeSeries { index, values, quality, provenance: None, metadata: self.metadata.clone() }
+                .logged(self, "diff", Vec::new)
         }
     }
 
@@ -139,6 +315,19 @@ impl TimeSeries {
         self.index.len()
     }
 
+    /// Returns true if the series has no elements.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// assert!(TimeSeries::empty().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
     /// Return nth element of the series.
     ///
     /// # Example
@@ -154,13 +343,17 @@ impl TimeSeries {
     /// ```
     pub fn nth(&self, pos: usize) -> Option<DataPoint> {
         if pos < self.len() {
-            Some(DataPoint::new(self.index[pos], self.values[pos]))
+            let quality = self.quality.as_ref().map(|q| q[pos]);
+            Some(DataPoint { timestamp: self.index[pos], value: self.values[pos], quality })
         } else {
             None
         }
     }
 
-    /// Return element by its timestamp index. Or 0 if not found
+    /// Return element by its timestamp index.
+    /// Before the first data point 0 is returned, after the last one the last value.
+    /// Use `at_with` to select different extrapolation policy,
+    /// or `asof` and `at_nearest` to get None when there is no data point.
     ///
     /// # Example
     ///
@@ -173,16 +366,259 @@ impl TimeSeries {
     /// assert_eq!(ts.at(10), 0.0);
     /// assert_eq!(ts.at(110), 1.0);
     /// assert_eq!(ts.at(165), 2.5);
+    /// assert_eq!(ts.at(220), 3.2);
     /// assert_eq!(ts.at(500), 3.2);
     /// ```
     pub fn at(&self, timestamp: i64) -> f64 {
-        let pos = match self.index.iter().position(|&ts| timestamp < ts) {
-            Some(idx) => idx,
-            _ => self.len(),
-        };
+        let pos = self.index.values.partition_point(|&ts| ts <= timestamp);
         if pos > 0 { self.values[pos-1] } else { 0.0 }
     }
 
+    /// Position of the data point with the given timestamp, like `slice::binary_search`.
+    /// Err contains the position where a data point with this timestamp could be inserted.
+    /// See `DateTimeIndex::position_of`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![100, 160, 220], vec![1.0, 2.5, 3.2]);
+    /// assert_eq!(ts.position_of(160), Ok(1));
+    /// assert_eq!(ts.position_of(200), Err(2));
+    /// assert_eq!(ts.position_of(300), Err(3));
+    /// ```
+    pub fn position_of(&self, timestamp: i64) -> Result<usize, usize> {
+        self.index.position_of(timestamp)
+    }
+
+    /// Return the first n elements of the series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let index = vec![1, 2, 3, 4, 5];
+    /// let data = vec![1.0, 2.5, 3.2, 4.0, 3.0];
+    /// let ts = TimeSeries::new(index, data);
+    /// assert_eq!(ts.head(2), TimeSeries::new(vec![1, 2], vec![1.0, 2.5]));
+    /// assert_eq!(ts.head(10).len(), 5);
+    /// ```
+    pub fn head(&self, n: usize) -> TimeSeries {
+        let positions: Vec<usize> = (0..cmp::min(n, self.len())).collect();
+        self.select(&positions).logged(self, "head", || vec![("n", n.to_string())])
+    }
+
+    /// Return the last n elements of the series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let index = vec![1, 2, 3, 4, 5];
+    /// let data = vec![1.0, 2.5, 3.2, 4.0, 3.0];
+    /// let ts = TimeSeries::new(index, data);
+    /// assert_eq!(ts.tail(2), TimeSeries::new(vec![4, 5], vec![4.0, 3.0]));
+    /// assert_eq!(ts.tail(10).len(), 5);
+    /// ```
+    pub fn tail(&self, n: usize) -> TimeSeries {
+        let start = self.len() - cmp::min(n, self.len());
+        let positions: Vec<usize> = (start..self.len()).collect();
+        self.select(&positions).logged(self, "tail", || vec![("n", n.to_string())])
+    }
+
+    /// Drop data points older then the timestamp.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.5, 3.2, 4.0, 3.0]);
+    /// assert_eq!(ts.truncate_before(4), TimeSeries::new(vec![4, 5], vec![4.0, 3.0]));
+    /// assert!(ts.truncate_before(10).is_empty());
+    /// ```
+    pub fn truncate_before(&self, timestamp: i64) -> TimeSeries {
+        let positions: Vec<usize> = (0..self.len()).filter(|&i| self.index[i] >= timestamp).collect();
+        self.select(&positions).logged(self, "truncate_before", || vec![("timestamp", timestamp.to_string())])
+    }
+
+    /// Split into data points before the timestamp and data points from the timestamp on.
+    /// Like `truncate_before`, the index doesn't have to be sorted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.5, 3.2, 4.0, 3.0]);
+    /// let (before, after) = ts.split_at_time(3);
+    /// assert_eq!(before, TimeSeries::new(vec![1, 2], vec![1.0, 2.5]));
+    /// assert_eq!(after, TimeSeries::new(vec![3, 4, 5], vec![3.2, 4.0, 3.0]));
+    /// ```
+    pub fn split_at_time(&self, timestamp: i64) -> (TimeSeries, TimeSeries) {
+        let (before, after): (Vec<usize>, Vec<usize>) = (0..self.len()).partition(|&i| self.index[i] < timestamp);
+        let params = || vec![("timestamp", timestamp.to_string())];
+        (self.select(&before).logged(self, "split_at_time", params),
+         self.select(&after).logged(self, "split_at_time", params))
+    }
+
+    /// Split into contiguous segments wherever the time between consecutive data points
+    /// is larger then max_gap (in index resolution).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 10, 11, 20], vec![1.0, 2.0, 3.0, 10.0, 11.0, 20.0]);
+    /// let segments = ts.split_on_gaps(5);
+    /// assert_eq!(segments.len(), 3);
+    /// assert_eq!(segments[1], TimeSeries::new(vec![10, 11], vec![10.0, 11.0]));
+    /// ```
+    pub fn split_on_gaps(&self, max_gap: i64) -> Vec<TimeSeries> {
+        let mut bounds = vec![0];
+        // Difference which doesn't fit into i64 is always a gap
+        bounds.extend((1..self.len()).filter(|&i| self.index[i].checked_sub(self.index[i-1]).is_none_or(|gap| gap > max_gap)));
+        bounds.push(self.len());
+        let params = || vec![("max_gap", max_gap.to_string())];
+        bounds.windows(2)
+            .filter(|w| w[0] < w[1])
+            .map(|w| self.select(&(w[0]..w[1]).collect::<Vec<usize>>()).logged(self, "split_on_gaps", params))
+            .collect()
+    }
+
+    /// Return the first element of the series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{TimeSeries, DataPoint};
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.5, 3.2]);
+    /// assert_eq!(ts.first(), Some(DataPoint::new(1, 1.0)));
+    /// assert_eq!(TimeSeries::empty().first(), None);
+    /// ```
+    pub fn first(&self) -> Option<DataPoint> {
+        self.nth(0)
+    }
+
+    /// Return the last element of the series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{TimeSeries, DataPoint};
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.5, 3.2]);
+    /// assert_eq!(ts.last(), Some(DataPoint::new(3, 3.2)));
+    /// assert_eq!(TimeSeries::empty().last(), None);
+    /// ```
+    pub fn last(&self) -> Option<DataPoint> {
+        if self.is_empty() { None } else { self.nth(self.len()-1) }
+    }
+
+    /// Return n randomly selected elements of the series.
+    /// Selected points keep their time order. The same seed always gives the same sample.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let index = vec![1, 2, 3, 4, 5];
+    /// let data = vec![1.0, 2.5, 3.2, 4.0, 3.0];
+    /// let ts = TimeSeries::new(index, data);
+    /// let sample = ts.sample(3, 42);
+    /// assert_eq!(sample.len(), 3);
+    /// assert_eq!(sample, ts.sample(3, 42));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn sample(&self, n: usize, seed: u64) -> TimeSeries {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut positions = rand::seq::index::sample(&mut rng, self.len(), cmp::min(n, self.len())).into_vec();
+        positions.sort_unstable();
+        self.select(&positions).logged(self, "sample", || vec![("n", n.to_string()), ("seed", seed.to_string())])
+    }
+
+    /// Create new series from data points at the given positions
+    pub(crate) fn select(&self, positions: &[usize]) -> TimeSeries {
+        let index = positions.iter().map(|&i| self.index[i]).collect();
+        let values = positions.iter().map(|&i| self.values[i]).collect();
+        let quality = self.quality.as_ref().map(|q| positions.iter().map(|&i| q[i]).collect());
+        let index = DateTimeIndex::new(index).with_resolution(self.index.resolution);
+        TimeSeries { index, values, quality, provenance: None, metadata: self.metadata.clone() }
+    }
+
+    /// New series with the index in the resolution of this series and with its metadata.
+    /// Used by operations which build the output index from timestamps of this series.
+    pub(crate) fn derived(&self, index: Vec<i64>, values: Vec<f64>) -> TimeSeries {
+        let mut ts = TimeSeries::new(index, values);
+        ts.index.resolution = self.index.resolution;
+        ts.metadata = self.metadata.clone();
+        ts
+    }
+
+    /// Convert index to the given timestamp resolution
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::index::Resolution;
+    ///
+    /// let ts = TimeSeries::new(vec![1_000, 2_000], vec![1.0, 2.0]);
+    /// let nanos = ts.to_resolution(Resolution::Nanosecond);
+    /// assert_eq!(nanos.index.values, vec![1_000_000_000, 2_000_000_000]);
+    /// assert_eq!(nanos.to_resolution(Resolution::Millisecond), ts);
+    /// ```
+    pub fn to_resolution(&self, resolution: Resolution) -> TimeSeries {
+        let mut ts = self.clone().logged(self, "to_resolution", || vec![("resolution", format!("{:?}", resolution))]);
+        ts.index = self.index.to_resolution(resolution);
+        ts
+    }
+
+    /// This series if it already has the given resolution, otherwise converted copy
+    pub(crate) fn in_resolution(&self, resolution: Resolution) -> Cow<'_, TimeSeries> {
+        if self.index.resolution == resolution {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.to_resolution(resolution))
+        }
+    }
+
+    /// Sort data points by timestamp. Data points with the same timestamp keep their order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![3, 1, 2], vec![3.0, 1.0, 2.0]);
+    /// assert_eq!(ts.sort_by_time(), TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]));
+    /// ```
+    pub fn sort_by_time(&self) -> TimeSeries {
+        self.select(&self.index.argsort()).logged(self, "sort_by_time", Vec::new)
+    }
+
+    /// Remove data points with duplicated timestamps
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::index::Keep;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 1, 2], vec![1.0, 5.0, 2.0]);
+    /// assert_eq!(ts.drop_duplicates(Keep::Last).values, vec![5.0, 2.0]);
+    /// ```
+    pub fn drop_duplicates(&self, keep: Keep) -> TimeSeries {
+        self.select(&self.index.unique_positions(keep))
+            .logged(self, "drop_duplicates", || vec![("keep", format!("{:?}", keep))])
+    }
+
     /// Create iterator
     /// 
     /// # Example
@@ -195,16 +631,32 @@ impl TimeSeries {
     /// let ts = TimeSeries::new(index, values);
     /// assert_eq!(ts.iter().count(), 5);
     /// ```
-    pub fn iter(&self) -> TimeSeriesIter {
+    pub fn iter(&self) -> TimeSeriesIter<'_> {
         TimeSeriesIter {
             ts: self,
             index: 0,
         }
     }
 
+    /// Create formatter which allows to customize how the series is printed
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 60_000], vec![1.0, 2.5]);
+    /// let text = ts.format().datetime_format("%H:%M").precision(2).to_string();
+    /// assert_eq!(text, "(00:00, 1.00)\n(00:01, 2.50)\n\n");
+    /// ```
+    pub fn format(&self) -> SeriesFormat<'_> {
+        SeriesFormat::new(self)
+    }
+
     /// Merge 2 series. The resulting series will contain data points from both series
     /// If series contains data point with the same timestamp, then the value 
-    /// from first series is taken
+    /// from first series is taken.
+    /// Use `merge_with` or `merge_by` to combine such values (see `merge` module).
     /// 
     /// # Example
     /// 
@@ -216,7 +668,7 @@ impl TimeSeries {
     /// let data2 = vec![DataPoint::new(40, 41.0), DataPoint::new(45, 42.5), DataPoint::new(50, 53.2), 
     ///                  DataPoint::new(55, 54.0), DataPoint::new(60, 63.0)];
     /// let expected = vec![DataPoint::new(10, 1.0), DataPoint::new(20, 2.5), DataPoint::new(30, 3.2), 
-    ///                     DataPoint::new(40, 4.0), DataPoint::new(45, 42.5), DataPoint::new(50, 3.2), 
+    ///                     DataPoint::new(40, 4.0), DataPoint::new(45, 42.5), DataPoint::new(50, 3.0), 
     ///                     DataPoint::new(55, 54.0), DataPoint::new(60, 63.0)];
     /// let ts1 = TimeSeries::from_datapoints(data1);
     /// let ts2 = TimeSeries::from_datapoints(data2);
@@ -225,35 +677,22 @@ impl TimeSeries {
     /// assert_eq!(ts_merged, ts_expected);
     /// ```
     pub fn merge(&self, other: &TimeSeries) -> TimeSeries {
-        let mut output: Vec<DataPoint> = vec![];
-        let mut pos1 = 0;
-        let mut pos2 = 0;
-
-        while pos1 < self.len() || pos2 < other.len() {
-            if pos1 == self.len() {
-                output.push(other.nth(pos2).unwrap());
-                pos2 += 1;
-            } else if pos2 == other.len() {
-                output.push(self.nth(pos1).unwrap());
-                pos1 += 1;
-            } else {
-                let dp1 = self.nth(pos1).unwrap();
-                let dp2 = other.nth(pos2).unwrap();
-                if dp1.timestamp == dp2.timestamp {
-                    output.push(self.nth(pos1).unwrap());
-                    pos1 += 1;
-                    pos2 += 1;
-                } else if dp1.timestamp < dp2.timestamp {
-                    output.push(self.nth(pos1).unwrap());
-                    pos1 += 1;
-                } else {
-                    output.push(other.nth(pos2).unwrap());
-                    pos2 += 1;
-                }
-            }
-        } 
-
-        TimeSeries::from_datapoints(output)
+        let other = &*other.in_resolution(self.index.resolution);
+        let picks = kernels::merge_picks(&self.index.values, &other.index.values);
+        let source = |pick: &Pick| match *pick {
+            Pick::Left(i) | Pick::Both(i, _) => (self, i),
+            Pick::Right(j) => (other, j),
+        };
+        let index = picks.iter().map(&source).map(|(ts, i)| ts.index[i]).collect();
+        let values = picks.iter().map(&source).map(|(ts, i)| ts.values[i]).collect();
+        let quality = if self.quality.is_some() || other.quality.is_some() {
+            Some(picks.iter().map(&source).map(|(ts, i)| ts.quality_at(i)).collect())
+        } else {
+            None
+        };
+        let index = DateTimeIndex::new(index).with_resolution(self.index.resolution);
+        TimeSeries { index, values, quality, provenance: None, metadata: self.metadata.clone() }
+            .logged(self, "merge", || vec![("other_len", other.len().to_string())])
     }
 }
 
@@ -269,7 +708,7 @@ impl<'a> Iterator for TimeSeriesIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         if self.index < self.ts.len() {
             self.index += 1;
-            Some(DataPoint::new(self.ts.index[self.index-1], self.ts.values[self.index-1]))
+            self.ts.nth(self.index-1)
         } else {
             None
         }
@@ -287,25 +726,14 @@ impl FromIterator<DataPoint> for TimeSeries {
 
 impl fmt::Display for TimeSeries {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fn write_record(f: &mut fmt::Formatter<'_>, r: DataPoint) {
-            let naive_datetime = NaiveDateTime::from_timestamp(r.timestamp/1000, 0);
-            let _ = write!(f, "({}, {})\n", naive_datetime, r.value);
-        };
-        if self.len() < 10 {
-            self.iter().for_each(|dp| write_record(f, dp));
-        } else {
-            self.iter().take(5).for_each(|dp| write_record(f, dp));
-            let _ = write!(f, "...\n");
-            self.iter().skip(self.len()-5).for_each(|dp| write_record(f, dp));
-        }
-        write!(f, "\n")
+        write!(f, "{}", self.format())
     }
 }
 
 impl cmp::PartialEq for TimeSeries {
 
     fn eq(&self, other: &Self) -> bool {
-        self.index == other.index && self.values == self.values
+        self.index == other.index && self.values == other.values && self.quality == other.quality
     }
 }
 
@@ -335,14 +763,107 @@ impl ToSeries for DateTimeIndex {
 impl DataPoint {
 
     pub fn new(timestamp: i64, value: f64) -> DataPoint {
-        DataPoint { timestamp, value }
+        DataPoint { timestamp, value, quality: None }
+    }
+
+    /// Set quality code
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{TimeSeries, DataPoint};
+    /// use timeseries::quality;
+    ///
+    /// let ts = TimeSeries::from_datapoints(vec![
+    ///     DataPoint::new(1, 1.0),
+    ///     DataPoint::new(2, 0.0).with_quality(quality::MISSING)]);
+    /// assert_eq!(ts.quality, Some(vec![quality::GOOD, quality::MISSING]));
+    /// assert_eq!(ts.nth(1).unwrap().quality, Some(quality::MISSING));
+    /// ```
+    pub fn with_quality(mut self, quality: u8) -> DataPoint {
+        self.quality = Some(quality);
+        self
+    }
+}
+
+impl DataPoint {
+
+    /// Compare with the other data point, allowing the values to differ by epsilon.
+    /// Timestamps and quality codes have to be the same. NaN values are equal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    ///
+    /// assert!(DataPoint::new(1, 0.1 + 0.2).approx_eq(&DataPoint::new(1, 0.3), 1e-9));
+    /// assert!(!DataPoint::new(1, 0.3).approx_eq(&DataPoint::new(2, 0.3), 1e-9));
+    /// ```
+    pub fn approx_eq(&self, other: &DataPoint, epsilon: f64) -> bool {
+        let values_eq = (self.value - other.value).abs() <= epsilon || (self.value.is_nan() && other.value.is_nan());
+        self.timestamp == other.timestamp && values_eq && self.quality_code() == other.quality_code()
+    }
+
+    /// Quality code. Data points without quality code are GOOD
+    fn quality_code(&self) -> u8 {
+        self.quality.unwrap_or(quality::GOOD)
+    }
+
+    /// Value used for comparison and hashing, where -0.0 is 0.0 and all NaN values are the same
+    fn value_key(&self) -> f64 {
+        if self.value == 0.0 {
+            0.0
+        } else if self.value.is_nan() {
+            f64::NAN
+        } else {
+            self.value
+        }
     }
 }
 
 impl cmp::PartialEq for DataPoint {
 
+    /// Data points without quality code are equal to GOOD data points.
+    /// Values are compared like with `==`, except that NaN values are equal to each other.
     fn eq(&self, other: &Self) -> bool {
-        self.timestamp == other.timestamp && self.value == self.value
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl Eq for DataPoint {}
+
+impl cmp::PartialOrd for DataPoint {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl cmp::Ord for DataPoint {
+
+    /// Order by timestamp. Data points with the same timestamp are ordered by value and quality code.
+    /// Values are ordered with `f64::total_cmp`, but 0.0 and -0.0 are equal and NaN is after all numbers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    ///
+    /// let mut dps = vec![DataPoint::new(3, 1.0), DataPoint::new(1, 5.0), DataPoint::new(2, 0.0)];
+    /// dps.sort();
+    /// assert_eq!(dps.iter().map(|dp| dp.timestamp).collect::<Vec<i64>>(), vec![1, 2, 3]);
+    /// ```
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.timestamp.cmp(&other.timestamp)
+            .then_with(|| self.value_key().total_cmp(&other.value_key()))
+            .then_with(|| self.quality_code().cmp(&other.quality_code()))
+    }
+}
+
+impl Hash for DataPoint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.timestamp.hash(state);
+        self.value_key().to_bits().hash(state);
+        self.quality_code().hash(state);
     }
 }
 
@@ -409,7 +930,7 @@ mod tests {
         let data2 = vec![DataPoint::new(40, 41.0), DataPoint::new(45, 42.5), DataPoint::new(50, 53.2), 
                          DataPoint::new(55, 54.0), DataPoint::new(60, 63.0)];
         let expected = vec![DataPoint::new(10, 1.0), DataPoint::new(20, 2.5), DataPoint::new(30, 3.2), 
-                            DataPoint::new(40, 4.0), DataPoint::new(45, 42.5), DataPoint::new(50, 3.2), 
+                            DataPoint::new(40, 4.0), DataPoint::new(45, 42.5), DataPoint::new(50, 3.0), 
                             DataPoint::new(55, 54.0), DataPoint::new(60, 63.0)];
         let ts1 = TimeSeries::from_datapoints(data1);
         let ts2 = TimeSeries::from_datapoints(data2);
@@ -418,4 +939,133 @@ mod tests {
         assert_eq!(ts_merged, ts_expected);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_diff_keeps_resolution() {
+        let ts = TimeSeries::new(vec![1, 2, 4], vec![1.0, 3.0, 4.0]).to_resolution(Resolution::Nanosecond);
+        let diff = ts.diff();
+        assert_eq!(diff.index.resolution, Resolution::Nanosecond);
+        assert_eq!(diff.index.values, vec![2_000_000, 4_000_000]);
+        assert_eq!(ts.derivative().values, vec![2000.0, 500.0]);
+        assert_eq!(ts.sort_by_time(), ts);
+        assert_eq!(ts.gt(2.0).to_series().index, ts.index);
+    }
+
+    #[test]
+    fn test_derived_series_keep_resolution_and_metadata() {
+        use crate::extrapolation::Extrapolation;
+        use crate::resample::{Aggregation, FillMethod};
+
+        let ts = TimeSeries::new(vec![1, 2, 4], vec![1.0, 3.0, 4.0])
+            .to_resolution(Resolution::Nanosecond)
+            .with_name("flow");
+        let outputs = [
+            ts.diff(), ts.head(2), ts.merge(&ts), ts.merge_by(&ts, |a, _| a), ts.merge_with(&ts, Aggregation::Max),
+            ts.resample(2_000_000, Aggregation::Mean), ts.upsample(1_000_000, FillMethod::Ffill),
+            ts.sample_at(&[2_000_000], Extrapolation::NearestValue).unwrap(),
+        ];
+        for output in outputs.iter() {
+            assert_eq!(output.index.resolution, Resolution::Nanosecond);
+            assert_eq!(output.metadata.name.as_deref(), Some("flow"));
+            assert!(output.provenance.is_none());
+        }
+        let aligned = ts.align_to(&TimeSeries::new(vec![2], vec![0.0]), FillMethod::Ffill);
+        assert_eq!(aligned.index.resolution, Resolution::Millisecond);
+        assert_eq!(aligned.values, vec![3.0]);
+        assert_eq!(aligned.metadata.name.as_deref(), Some("flow"));
+    }
+
+    #[test]
+    fn test_head_tail_empty() {
+        let ts = TimeSeries::empty();
+        assert!(ts.head(3).is_empty());
+        assert!(ts.tail(3).is_empty());
+    }
+
+    #[test]
+    fn test_sample() {
+        let values: Vec<f64> = (0..100).map(|i| i as f64).collect();
+        let index = (0..100).collect();
+        let ts = TimeSeries::new(index, values);
+        let sample = ts.sample(10, 1);
+        assert_eq!(sample.len(), 10);
+        assert!(sample.index.is_monotonic());
+        assert!(sample.index.is_unique());
+        assert_eq!(ts.sample(200, 1).len(), 100);
+    }
+
+    #[test]
+    fn test_sort_then_drop_duplicates() {
+        let ts = TimeSeries::new(vec![2, 1, 2, 0], vec![2.0, 1.0, 3.0, 0.0])
+            .with_quality(vec![0, 1, 2, 3]);
+        let clean = ts.sort_by_time().drop_duplicates(Keep::Last);
+        assert_eq!(clean.index.values, vec![0, 1, 2]);
+        assert_eq!(clean.values, vec![0.0, 1.0, 3.0]);
+        assert_eq!(clean.quality, Some(vec![3, 1, 2]));
+    }
+
+    #[test]
+    fn test_datapoint_quality_round_trip() {
+        let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]).with_quality(vec![0, 3]);
+        let copy: TimeSeries = ts.iter().collect();
+        assert_eq!(copy, ts);
+        assert_eq!(DataPoint::new(1, 1.0), DataPoint::new(1, 1.0).with_quality(quality::GOOD));
+        assert_ne!(DataPoint::new(1, 1.0), DataPoint::new(1, 1.0).with_quality(quality::SUSPECT));
+    }
+
+    #[test]
+    fn test_split() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]).with_quality(vec![0, 1, 2]);
+        let (before, after) = ts.split_at_time(0);
+        assert!(before.is_empty());
+        assert_eq!(after.quality, ts.quality);
+        assert_eq!(ts.split_at_time(10).0, ts);
+        assert_eq!(ts.split_on_gaps(1), vec![ts.clone()]);
+        assert_eq!(ts.split_on_gaps(0).len(), 3);
+        assert!(TimeSeries::empty().split_on_gaps(1).is_empty());
+    }
+
+    #[test]
+    fn test_split_extreme_and_unsorted() {
+        let ts = TimeSeries::new(vec![i64::MIN, 0, i64::MAX], vec![1.0, 2.0, 3.0]);
+        assert_eq!(ts.split_on_gaps(10).len(), 3);
+        assert_eq!(ts.split_on_gaps(i64::MAX).len(), 2);
+        let ts = TimeSeries::new(vec![3, 1, 4, 2], vec![3.0, 1.0, 4.0, 2.0]);
+        let (before, after) = ts.split_at_time(3);
+        assert_eq!(before.index.values, vec![1, 2]);
+        assert_eq!(after, ts.truncate_before(3));
+    }
+
+    #[test]
+    fn test_datapoint_ordering() {
+        use std::collections::HashSet;
+
+        assert!(DataPoint::new(1, 5.0) < DataPoint::new(2, 0.0));
+        assert!(DataPoint::new(1, 0.0) < DataPoint::new(1, 5.0));
+        assert_eq!(DataPoint::new(1, f64::NAN), DataPoint::new(1, f64::NAN));
+        let set: HashSet<DataPoint> = vec![DataPoint::new(1, 1.0), DataPoint::new(1, 1.0).with_quality(quality::GOOD)]
+            .into_iter().collect();
+        assert_eq!(set.len(), 1);
+        assert!(DataPoint::new(1, 1.0).approx_eq(&DataPoint::new(1, 1.05), 0.1));
+        assert!(!DataPoint::new(1, 1.0).approx_eq(&DataPoint::new(1, 1.05).with_quality(quality::SUSPECT), 0.1));
+        assert!(DataPoint::new(1, f64::NAN).approx_eq(&DataPoint::new(1, f64::NAN), 0.1));
+    }
+
+    #[test]
+    fn test_eq_compares_values() {
+        assert_ne!(DataPoint::new(1, 1.0), DataPoint::new(1, 2.0));
+        assert_ne!(TimeSeries::new(vec![1, 2], vec![1.0, 2.0]), TimeSeries::new(vec![1, 2], vec![1.0, 3.0]));
+    }
+
+    #[test]
+    fn test_datapoint_signed_zero_and_nan() {
+        use std::collections::HashSet;
+
+        assert_eq!(DataPoint::new(1, 0.0), DataPoint::new(1, -0.0));
+        assert_eq!(DataPoint::new(1, f64::NAN), DataPoint::new(1, -f64::NAN));
+        assert!(DataPoint::new(1, f64::INFINITY) < DataPoint::new(1, f64::NAN));
+        let set: HashSet<DataPoint> = vec![DataPoint::new(1, 0.0), DataPoint::new(1, -0.0), DataPoint::new(1, f64::NAN),
+                                           DataPoint::new(1, -f64::NAN)].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+}
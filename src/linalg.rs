@@ -0,0 +1,147 @@
+//! Small dense matrices used by model fitting
+//!
+//! Matrices are stored as rows. They are expected to be small (a few rows),
+//! so simple O(n^3) algorithms are used.
+//!
+
+pub(crate) type Matrix = Vec<Vec<f64>>;
+
+
+pub(crate) fn identity(n: usize) -> Matrix {
+    (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect()
+}
+
+pub(crate) fn transpose(a: &Matrix) -> Matrix {
+    let cols = a.first().map_or(0, |row| row.len());
+    (0..cols).map(|j| a.iter().map(|row| row[j]).collect()).collect()
+}
+
+pub(crate) fn mul(a: &Matrix, b: &Matrix) -> Matrix {
+    a.iter()
+        .map(|row| (0..b[0].len()).map(|j| row.iter().zip(b).map(|(x, b_row)| x * b_row[j]).sum()).collect())
+        .collect()
+}
+
+pub(crate) fn mul_vec(a: &Matrix, x: &[f64]) -> Vec<f64> {
+    a.iter().map(|row| row.iter().zip(x).map(|(a, b)| a * b).sum()).collect()
+}
+
+pub(crate) fn add(a: &Matrix, b: &Matrix) -> Matrix {
+    a.iter().zip(b).map(|(x, y)| x.iter().zip(y).map(|(x, y)| x + y).collect()).collect()
+}
+
+/// Invert matrix with Gauss-Jordan elimination (partial pivoting).
+/// None if the matrix is singular.
+pub(crate) fn invert(mut matrix: Matrix) -> Option<Matrix> {
+    let n = matrix.len();
+    let mut inverse = identity(n);
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let p = matrix[col][col];
+        if p == 0.0 {
+            return None;
+        }
+        for j in 0..n {
+            matrix[col][j] /= p;
+            inverse[col][j] /= p;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = matrix[row][col];
+                for j in 0..n {
+                    matrix[row][j] -= factor * matrix[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+
+/// Least squares solution of A x = b (normal equations). None if columns of A are linearly dependent.
+pub(crate) fn least_squares(a: &Matrix, b: &[f64]) -> Option<Vec<f64>> {
+    let at = transpose(a);
+    let inverse = invert(mul(&at, a))?;
+    Some(mul_vec(&inverse, &mul_vec(&at, b)))
+}
+
+/// Eigenvalues and eigenvectors of a symmetric matrix (cyclic Jacobi rotations).
+/// Eigenvectors are returned as rows, in the same order as the eigenvalues.
+#[cfg(feature = "linalg")]
+pub(crate) fn symmetric_eigen(mut a: Matrix) -> (Vec<f64>, Matrix) {
+    const MAX_SWEEPS: usize = 100;
+    let n = a.len();
+    let mut vectors = identity(n);
+    for _ in 0..MAX_SWEEPS {
+        let off_diagonal: f64 = (0..n).map(|i| (i+1..n).map(|j| a[i][j] * a[i][j]).sum::<f64>()).sum();
+        if off_diagonal < f64::EPSILON * f64::EPSILON {
+            break;
+        }
+        for p in 0..n {
+            for q in p+1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                let rotate = |x: f64, y: f64| (c * x - s * y, s * x + c * y);
+                for row in a.iter_mut().chain(vectors.iter_mut()) {
+                    let (x, y) = rotate(row[p], row[q]);
+                    row[p] = x;
+                    row[q] = y;
+                }
+                let (head, tail) = a.split_at_mut(q);
+                for (x, y) in head[p].iter_mut().zip(tail[0].iter_mut()) {
+                    let rotated = rotate(*x, *y);
+                    *x = rotated.0;
+                    *y = rotated.1;
+                }
+            }
+        }
+    }
+    let values = (0..n).map(|i| a[i][i]).collect();
+    (values, transpose(&vectors))
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invert() {
+        let a = vec![vec![0.0, 2.0], vec![1.0, 1.0]];
+        let inverse = invert(a.clone()).unwrap();
+        assert_eq!(mul(&a, &inverse), identity(2));
+        assert_eq!(invert(vec![vec![1.0, 2.0], vec![2.0, 4.0]]), None);
+    }
+
+    #[test]
+    fn test_least_squares() {
+        let a = vec![vec![1.0, 0.0], vec![1.0, 1.0], vec![1.0, 2.0]];
+        let x = least_squares(&a, &[1.0, 3.0, 5.0]).unwrap();
+        assert!((x[0] - 1.0).abs() < 1e-12 && (x[1] - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    #[cfg(feature = "linalg")]
+    fn test_symmetric_eigen() {
+        let a = vec![vec![2.0, 1.0, 0.0], vec![1.0, 2.0, 0.0], vec![0.0, 0.0, 5.0]];
+        let (values, vectors) = symmetric_eigen(a.clone());
+        for (value, vector) in values.iter().zip(&vectors) {
+            let av = mul_vec(&a, vector);
+            assert!(av.iter().zip(vector).all(|(x, v)| (x - value * v).abs() < 1e-9));
+        }
+        let mut sorted = values.clone();
+        sorted.sort_by(f64::total_cmp);
+        assert!((sorted[0] - 1.0).abs() < 1e-9 && (sorted[1] - 3.0).abs() < 1e-9 && (sorted[2] - 5.0).abs() < 1e-9);
+    }
+}
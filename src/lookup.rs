@@ -0,0 +1,124 @@
+//! Finding data points close to the given timestamp
+//!
+//! Unlike `TimeSeries::at`, these functions return None when there is no matching data point,
+//! so a missing value can't be confused with a real zero.
+//!
+
+use crate::{TimeSeries, DataPoint};
+
+
+impl TimeSeries {
+
+    /// The last data point at or before the given timestamp
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{TimeSeries, DataPoint};
+    ///
+    /// let ts = TimeSeries::new(vec![10, 20], vec![0.0, 2.0]);
+    /// assert_eq!(ts.asof(15), Some(DataPoint::new(10, 0.0)));
+    /// assert_eq!(ts.asof(20), Some(DataPoint::new(20, 2.0)));
+    /// assert_eq!(ts.asof(5), None);
+    /// ```
+    pub fn asof(&self, timestamp: i64) -> Option<DataPoint> {
+        let pos = self.index.values.partition_point(|&t| t <= timestamp);
+        pos.checked_sub(1).and_then(|pos| self.nth(pos))
+    }
+
+    /// The data point closest to the given timestamp, if it is not further then tolerance (in milliseconds).
+    /// If two data points are equally close, the earlier one is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{TimeSeries, DataPoint};
+    ///
+    /// let ts = TimeSeries::new(vec![10, 20], vec![1.0, 2.0]);
+    /// assert_eq!(ts.at_nearest(18, 5), Some(DataPoint::new(20, 2.0)));
+    /// assert_eq!(ts.at_nearest(15, 5), Some(DataPoint::new(10, 1.0)));
+    /// assert_eq!(ts.at_nearest(30, 5), None);
+    /// ```
+    pub fn at_nearest(&self, timestamp: i64, tolerance: i64) -> Option<DataPoint> {
+        let pos = self.index.values.partition_point(|&t| t < timestamp);
+        let before = pos.checked_sub(1).map(|p| (p, timestamp - self.index[p]));
+        let after = if pos < self.len() { Some((pos, self.index[pos] - timestamp)) } else { None };
+        let nearest = match (before, after) {
+            (Some(b), Some(a)) => if a.1 < b.1 { a } else { b },
+            (Some(b), None) => b,
+            (None, Some(a)) => a,
+            (None, None) => return None,
+        };
+        if nearest.1 <= tolerance { self.nth(nearest.0) } else { None }
+    }
+
+    /// For each data point find the last data point of the other series at or before it
+    /// (for example the last quote before each trade).
+    /// The result has the index of this series and values from the other series.
+    /// Value is NaN if there is no such data point within tolerance (in milliseconds).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let trades = TimeSeries::new(vec![100, 250, 400], vec![10.0, 11.0, 12.0]);
+    /// let quotes = TimeSeries::new(vec![90, 200, 240], vec![9.9, 10.5, 10.9]);
+    /// let joined = trades.asof_join(&quotes, 50);
+    /// assert_eq!(joined.index, trades.index);
+    /// assert_eq!(joined.values[..2], [9.9, 10.9]);
+    /// assert!(joined.values[2].is_nan());
+    /// ```
+    pub fn asof_join(&self, other: &TimeSeries, tolerance: i64) -> TimeSeries {
+        let other = &*other.in_resolution(self.index.resolution);
+        let mut pos = 0;
+        let values = self.index.iter()
+            .map(|&t| {
+                while pos < other.len() && other.index[pos] <= t {
+                    pos += 1;
+                }
+                match pos.checked_sub(1) {
+                    Some(p) if t - other.index[p] <= tolerance => other.values[p],
+                    _ => f64::NAN,
+                }
+            })
+            .collect();
+        self.derived(self.index.values.clone(), values)
+            .logged(self, "asof_join", || vec![("other_len", other.len().to_string()), ("tolerance", tolerance.to_string())])
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_value_is_found() {
+        let ts = TimeSeries::new(vec![10], vec![0.0]);
+        assert_eq!(ts.asof(100), Some(DataPoint::new(10, 0.0)));
+        assert_eq!(ts.at_nearest(0, 10), Some(DataPoint::new(10, 0.0)));
+        assert_eq!(ts.at_nearest(0, 9), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let ts = TimeSeries::empty();
+        assert_eq!(ts.asof(0), None);
+        assert_eq!(ts.at_nearest(0, 100), None);
+        assert!(ts.asof_join(&ts, 10).is_empty());
+    }
+
+    #[test]
+    fn test_asof_join_exact_match() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![0.0, 0.0, 0.0]);
+        let other = TimeSeries::new(vec![2], vec![5.0]);
+        let joined = ts.asof_join(&other, 0);
+        assert!(joined.values[0].is_nan());
+        assert_eq!(joined.values[1], 5.0);
+        assert!(joined.values[2].is_nan());
+    }
+}
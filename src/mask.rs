@@ -0,0 +1,204 @@
+//! Boolean series created by comparing values
+//!
+//! Masks can be combined with logical operations and used to filter other series.
+//!
+
+use crate::TimeSeries;
+use crate::index::DateTimeIndex;
+use crate::metadata::Metadata;
+
+
+/// Series of boolean values
+///   * index - Timestamps of the data points
+///   * values - Result of the comparison at each data point
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoolSeries {
+    pub index: DateTimeIndex,
+    pub values: Vec<bool>,
+}
+
+
+impl BoolSeries {
+
+    /// Create mask. Index and values should have the same length.
+    pub fn new(index: Vec<i64>, values: Vec<bool>) -> BoolSeries {
+        let size = index.len().min(values.len());
+        BoolSeries { index: DateTimeIndex::new(index[..size].to_vec()), values: values[..size].to_vec() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Number of true values
+    pub fn count(&self) -> usize {
+        self.values.iter().filter(|&&v| v).count()
+    }
+
+    /// Value at the given timestamp. False if there is no data point at this timestamp.
+    pub fn at(&self, timestamp: i64) -> bool {
+        self.index.values.binary_search(&timestamp).is_ok_and(|pos| self.values[pos])
+    }
+
+    /// Logical and. Value is true if both masks are true at the given timestamp.
+    /// The result has the index of this mask.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 5.0, 2.0, 6.0]);
+    /// let mask = ts.gt(1.5).and(&ts.lt(5.5));
+    /// assert_eq!(mask.values, vec![false, true, true, false]);
+    /// ```
+    pub fn and(&self, other: &BoolSeries) -> BoolSeries {
+        self.zip_with(other, |a, b| a && b)
+    }
+
+    /// Logical or. The result has the index of this mask.
+    pub fn or(&self, other: &BoolSeries) -> BoolSeries {
+        self.zip_with(other, |a, b| a || b)
+    }
+
+    /// Logical negation
+    pub fn invert(&self) -> BoolSeries {
+        BoolSeries { index: self.index.clone(), values: self.values.iter().map(|v| !v).collect() }
+    }
+
+    /// Convert to numeric series with 1.0 for true and 0.0 for false
+    pub fn to_series(&self) -> TimeSeries {
+        let values = self.values.iter().map(|&v| if v { 1.0 } else { 0.0 }).collect();
+        TimeSeries { index: self.index.clone(), values, quality: None, provenance: None, metadata: Metadata::default() }
+    }
+
+    fn zip_with<F: Fn(bool, bool) -> bool>(&self, other: &BoolSeries, f: F) -> BoolSeries {
+        let values = if self.index == other.index {
+            self.values.iter().zip(&other.values).map(|(&a, &b)| f(a, b)).collect()
+        } else {
+            self.index.iter().zip(&self.values).map(|(&ts, &a)| f(a, other.at(ts))).collect()
+        };
+        BoolSeries { index: self.index.clone(), values }
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Mask with true where value is greater then threshold
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 5.0, 3.0]);
+    /// assert_eq!(ts.gt(3.0).values, vec![false, true, false]);
+    /// ```
+    pub fn gt(&self, threshold: f64) -> BoolSeries {
+        self.mask(|v| v > threshold)
+    }
+
+    /// Mask with true where value is greater or equal to threshold
+    pub fn ge(&self, threshold: f64) -> BoolSeries {
+        self.mask(|v| v >= threshold)
+    }
+
+    /// Mask with true where value is less then threshold
+    pub fn lt(&self, threshold: f64) -> BoolSeries {
+        self.mask(|v| v < threshold)
+    }
+
+    /// Mask with true where value is less or equal to threshold
+    pub fn le(&self, threshold: f64) -> BoolSeries {
+        self.mask(|v| v <= threshold)
+    }
+
+    /// Mask with true where value is in the range [low, high]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 5.0, 3.0]);
+    /// assert_eq!(ts.between_values(1.0, 3.0).count(), 2);
+    /// ```
+    pub fn between_values(&self, low: f64, high: f64) -> BoolSeries {
+        self.mask(|v| low <= v && v <= high)
+    }
+
+    /// Mask with the result of predicate for each value. NaN values can be tested with `f64::is_nan`.
+    pub fn mask<F: Fn(f64) -> bool>(&self, predicate: F) -> BoolSeries {
+        BoolSeries { index: self.index.clone(), values: self.values.iter().map(|&v| predicate(v)).collect() }
+    }
+
+    /// Keep only data points where the mask is true at the same timestamp.
+    /// Mask can be created from a different series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let rain = TimeSeries::new(vec![1, 2, 3], vec![0.0, 2.5, 0.5]);
+    /// let flow = TimeSeries::new(vec![1, 2, 3], vec![10.0, 12.0, 15.0]);
+    /// let wet = flow.filter_mask(&rain.gt(1.0));
+    /// assert_eq!(wet, TimeSeries::new(vec![2], vec![12.0]));
+    /// ```
+    pub fn filter_mask(&self, mask: &BoolSeries) -> TimeSeries {
+        let positions: Vec<usize> = if self.index == mask.index {
+            (0..self.len()).filter(|&i| mask.values[i]).collect()
+        } else {
+            (0..self.len()).filter(|&i| mask.at(self.index[i])).collect()
+        };
+        self.select(&positions).logged(self, "filter_mask", || vec![("selected", positions.len().to_string())])
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparisons_with_nan() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, f64::NAN, 3.0]);
+        assert_eq!(ts.ge(1.0).values, vec![true, false, true]);
+        assert_eq!(ts.le(1.0).values, vec![true, false, false]);
+        assert_eq!(ts.lt(1.0).invert().values, vec![true, true, true]);
+        assert_eq!(ts.mask(f64::is_nan).count(), 1);
+    }
+
+    #[test]
+    fn test_different_index() {
+        let mask = BoolSeries::new(vec![1, 3, 5], vec![true, true, false]);
+        let other = BoolSeries::new(vec![1, 2, 3], vec![false, true, true]);
+        assert_eq!(mask.or(&other).values, vec![true, true, false]);
+        assert_eq!(mask.and(&other).values, vec![false, true, false]);
+        let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(ts.filter_mask(&mask).values, vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_filter_keeps_quality() {
+        let mut ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+        ts.quality = Some(vec![7, 9]);
+        let filtered = ts.filter_mask(&ts.gt(1.5));
+        assert_eq!(filtered.quality, Some(vec![9]));
+        assert_eq!(filtered.len(), 1);
+    }
+
+    #[test]
+    fn test_to_series() {
+        let mask = BoolSeries::new(vec![1, 2], vec![true, false]);
+        assert_eq!(mask.to_series(), TimeSeries::new(vec![1, 2], vec![1.0, 0.0]));
+    }
+}
@@ -0,0 +1,309 @@
+//! Merging series with conflict resolution
+//!
+//! `TimeSeries::merge` keeps the value of the first series when both series have a data point
+//! at the same timestamp. Functions in this module combine such values with `Aggregation`
+//! (First, Last, Mean, Min, Max, ...) or with a custom function.
+//!
+//! Quality of combined data points is the worst (highest) quality code of the sources,
+//! except for First and Last which keep the quality of the selected data point.
+//!
+//! `TimeSeries::concat` is a faster alternative for series which follow each other in time
+//! (e.g. backfill and live data). Overlapping ranges are handled with `OverlapPolicy`.
+//!
+
+use std::borrow::Cow;
+use std::cmp::{self, Reverse};
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::fmt;
+
+use crate::TimeSeries;
+use crate::index::DateTimeIndex;
+use crate::kernels::{self, Pick};
+use crate::metadata::Metadata;
+use crate::resample::{Aggregation, BucketStats};
+
+
+/// How `concat` handles the other series starting before the end of this series
+///   * Error - Return `OverlapError`
+///   * KeepSelf - Skip data points of the other series up to the end of this series
+///   * KeepOther - Drop data points of this series from the start of the other series
+///   * Merge(aggregation) - Merge both series like `merge_with`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OverlapPolicy {
+    Error,
+    KeepSelf,
+    KeepOther,
+    Merge(Aggregation),
+}
+
+/// Series can't be concatenated, because the other series starts at `first`,
+/// which is not after the `last` timestamp of this series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverlapError {
+    pub first: i64,
+    pub last: i64,
+}
+
+
+impl TimeSeries {
+
+    /// Append other series which follows this one in time.
+    /// If the series don't overlap, data points are copied without merging.
+    /// The other series is converted to the resolution of this series.
+    /// Metadata of this series is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::merge::OverlapPolicy;
+    ///
+    /// let backfill = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+    /// let live = TimeSeries::new(vec![3, 4], vec![30.0, 4.0]);
+    /// assert!(backfill.concat(&live, OverlapPolicy::Error).is_err());
+    /// let ts = backfill.concat(&live, OverlapPolicy::KeepSelf).unwrap();
+    /// assert_eq!(ts, TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 3.0, 4.0]));
+    /// let ts = backfill.concat(&live, OverlapPolicy::KeepOther).unwrap();
+    /// assert_eq!(ts, TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 30.0, 4.0]));
+    /// ```
+    pub fn concat(&self, other: &TimeSeries, policy: OverlapPolicy) -> Result<TimeSeries, OverlapError> {
+        let other = other.in_resolution(self.index.resolution);
+        let (head, tail) = match (self.index.last(), other.index.first()) {
+            (Some(last), Some(first)) if first <= last => match policy {
+                OverlapPolicy::Error => return Err(OverlapError { first, last }),
+                OverlapPolicy::KeepSelf => (self.len(), other.index.values.partition_point(|&t| t <= last)),
+                OverlapPolicy::KeepOther => (self.index.values.partition_point(|&t| t < first), 0),
+                OverlapPolicy::Merge(aggregation) => return Ok(self.merge_with(&other, aggregation)),
+            },
+            _ => (self.len(), 0),
+        };
+        let index = [&self.index.values[..head], &other.index.values[tail..]].concat();
+        let values = [&self.values[..head], &other.values[tail..]].concat();
+        let quality = if self.quality.is_some() || other.quality.is_some() {
+            Some((0..head).map(|i| self.quality_at(i)).chain((tail..other.len()).map(|j| other.quality_at(j))).collect())
+        } else {
+            None
+        };
+        let index = DateTimeIndex::new(index).with_resolution(self.index.resolution);
+        Ok(TimeSeries { index, values, quality, provenance: None, metadata: self.metadata.clone() }
+            .logged(self, "concat", || vec![("other_len", other.len().to_string()), ("policy", format!("{:?}", policy))]))
+    }
+
+    /// Merge with other series. Values at the same timestamp are combined with the aggregation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let old = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+    /// let backfill = TimeSeries::new(vec![2, 3, 4], vec![20.0, 30.0, 40.0]);
+    /// let merged = old.merge_with(&backfill, Aggregation::Last);
+    /// assert_eq!(merged, TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 20.0, 30.0, 40.0]));
+    /// let merged = old.merge_with(&backfill, Aggregation::Max);
+    /// assert_eq!(merged.values, vec![1.0, 20.0, 30.0, 40.0]);
+    /// ```
+    pub fn merge_with(&self, other: &TimeSeries, aggregation: Aggregation) -> TimeSeries {
+        let mut output = merge_many(&[self, other], aggregation);
+        output.metadata = self.metadata.clone();
+        output.logged(self, "merge_with", || vec![("other_len", other.len().to_string()), ("aggregation", format!("{:?}", aggregation))])
+    }
+
+    /// Merge with other series. Values at the same timestamp are combined with the function
+    /// (called with the value from this series first).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let a = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// let b = TimeSeries::new(vec![2, 3], vec![5.0, 6.0]);
+    /// let merged = a.merge_by(&b, |x, y| x * y);
+    /// assert_eq!(merged.values, vec![1.0, 10.0, 6.0]);
+    /// ```
+    pub fn merge_by<F: Fn(f64, f64) -> f64>(&self, other: &TimeSeries, f: F) -> TimeSeries {
+        let other = &*other.in_resolution(self.index.resolution);
+        let picks = kernels::merge_picks(&self.index.values, &other.index.values);
+        let index = picks.iter()
+            .map(|pick| match *pick {
+                Pick::Left(i) | Pick::Both(i, _) => self.index[i],
+                Pick::Right(j) => other.index[j],
+            })
+            .collect();
+        let values = picks.iter()
+            .map(|pick| match *pick {
+                Pick::Left(i) => self.values[i],
+                Pick::Right(j) => other.values[j],
+                Pick::Both(i, j) => f(self.values[i], other.values[j]),
+            })
+            .collect();
+        let quality = if self.quality.is_some() || other.quality.is_some() {
+            Some(picks.iter()
+                .map(|pick| match *pick {
+                    Pick::Left(i) => self.quality_at(i),
+                    Pick::Right(j) => other.quality_at(j),
+                    Pick::Both(i, j) => cmp::max(self.quality_at(i), other.quality_at(j)),
+                })
+                .collect())
+        } else {
+            None
+        };
+        let index = DateTimeIndex::new(index).with_resolution(self.index.resolution);
+        TimeSeries { index, values, quality, provenance: None, metadata: self.metadata.clone() }
+            .logged(self, "merge_by", || vec![("other_len", other.len().to_string())])
+    }
+}
+
+
+/// Merge many series in a single pass (k-way merge).
+/// Values at the same timestamp are combined with the aggregation, in the order of the series.
+/// All series are converted to the resolution of the first series.
+/// Metadata and provenance are not copied.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::merge::merge_many;
+/// use timeseries::resample::Aggregation;
+///
+/// let a = TimeSeries::new(vec![1, 3], vec![1.0, 3.0]);
+/// let b = TimeSeries::new(vec![2, 3], vec![2.0, 5.0]);
+/// let c = TimeSeries::new(vec![3, 4], vec![7.0, 4.0]);
+/// let merged = merge_many(&[&a, &b, &c], Aggregation::Mean);
+/// assert_eq!(merged, TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 5.0, 4.0]));
+/// let merged = merge_many(&[&a, &b, &c], Aggregation::First);
+/// assert_eq!(merged.values, vec![1.0, 2.0, 3.0, 4.0]);
+/// ```
+pub fn merge_many(series: &[&TimeSeries], aggregation: Aggregation) -> TimeSeries {
+    let resolution = series.first().map(|ts| ts.index.resolution).unwrap_or_default();
+    let series: Vec<Cow<TimeSeries>> = series.iter().map(|ts| ts.in_resolution(resolution)).collect();
+    // Heap entries are (timestamp, series, position), so equal timestamps come in series order
+    let mut heap: BinaryHeap<Reverse<(i64, usize, usize)>> = series.iter().enumerate()
+        .filter(|(_, ts)| !ts.is_empty())
+        .map(|(s, ts)| Reverse((ts.index[0], s, 0)))
+        .collect();
+    let has_quality = series.iter().any(|ts| ts.quality.is_some());
+    let mut index = vec![];
+    let mut values = vec![];
+    let mut quality = vec![];
+    while let Some(&Reverse((timestamp, _, _))) = heap.peek() {
+        let mut stats = BucketStats::empty();
+        let mut qualities = vec![];
+        while let Some(&Reverse((t, s, pos))) = heap.peek() {
+            if t != timestamp {
+                break;
+            }
+            heap.pop();
+            stats = stats.merge(&BucketStats::single(series[s].values[pos]));
+            qualities.push(series[s].quality_at(pos));
+            if pos + 1 < series[s].len() {
+                heap.push(Reverse((series[s].index[pos+1], s, pos+1)));
+            }
+        }
+        index.push(timestamp);
+        values.push(stats.value(aggregation).unwrap_or(f64::NAN));
+        quality.push(match aggregation {
+            Aggregation::First => qualities[0],
+            Aggregation::Last => qualities[qualities.len()-1],
+            _ => qualities.iter().cloned().max().unwrap_or_default(),
+        });
+    }
+    let quality = if has_quality { Some(quality) } else { None };
+    let index = DateTimeIndex::new(index).with_resolution(resolution);
+    TimeSeries { index, values, quality, provenance: None, metadata: Metadata::default() }
+}
+
+impl fmt::Display for OverlapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Series starting at {} overlaps series ending at {}", self.first, self.last)
+    }
+}
+
+impl Error for OverlapError {}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::Resolution;
+
+    #[test]
+    fn test_first_same_as_merge() {
+        let a = TimeSeries::new(vec![1, 2, 5], vec![1.0, 2.0, 5.0]).with_quality(vec![0, 1, 0]);
+        let b = TimeSeries::new(vec![2, 3], vec![20.0, 3.0]).with_quality(vec![2, 2]);
+        assert_eq!(a.merge_with(&b, Aggregation::First), a.merge(&b));
+        assert_eq!(a.merge_with(&b, Aggregation::First).quality, a.merge(&b).quality);
+    }
+
+    #[test]
+    fn test_quality_of_combined_points() {
+        let a = TimeSeries::new(vec![1], vec![1.0]).with_quality(vec![1]);
+        let b = TimeSeries::new(vec![1], vec![3.0]);
+        assert_eq!(a.merge_with(&b, Aggregation::Last).quality, Some(vec![0]));
+        assert_eq!(a.merge_with(&b, Aggregation::Mean).quality, Some(vec![1]));
+        assert_eq!(a.merge_by(&b, |x, y| x + y).quality, Some(vec![1]));
+    }
+
+    #[test]
+    fn test_merge_many_empty() {
+        assert!(merge_many(&[], Aggregation::Sum).is_empty());
+        let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+        assert_eq!(merge_many(&[&TimeSeries::empty(), &ts], Aggregation::Sum), ts);
+        assert_eq!(merge_many(&[&ts, &ts, &ts], Aggregation::Count).values, vec![3.0, 3.0]);
+    }
+
+    #[test]
+    fn test_concat() {
+        let a = TimeSeries::new(vec![1, 2, 5], vec![1.0, 2.0, 5.0]).with_quality(vec![0, 1, 0]);
+        let b = TimeSeries::new(vec![6, 7], vec![6.0, 7.0]);
+        let ts = a.concat(&b, OverlapPolicy::Error).unwrap();
+        assert_eq!(ts.index.values, vec![1, 2, 5, 6, 7]);
+        assert_eq!(ts.quality, Some(vec![0, 1, 0, 0, 0]));
+        assert_eq!(TimeSeries::empty().concat(&b, OverlapPolicy::Error).unwrap(), b);
+        assert_eq!(a.concat(&TimeSeries::empty(), OverlapPolicy::Error).unwrap(), a);
+    }
+
+    #[test]
+    fn test_concat_overlap() {
+        let a = TimeSeries::new(vec![1, 2, 5], vec![1.0, 2.0, 5.0]);
+        let b = TimeSeries::new(vec![2, 4, 6], vec![20.0, 40.0, 60.0]);
+        assert_eq!(a.concat(&b, OverlapPolicy::Error), Err(OverlapError { first: 2, last: 5 }));
+        assert_eq!(a.concat(&b, OverlapPolicy::KeepSelf).unwrap().values, vec![1.0, 2.0, 5.0, 60.0]);
+        assert_eq!(a.concat(&b, OverlapPolicy::KeepOther).unwrap().values, vec![1.0, 20.0, 40.0, 60.0]);
+        assert_eq!(a.concat(&b, OverlapPolicy::Merge(Aggregation::Sum)).unwrap().values, vec![1.0, 22.0, 40.0, 5.0, 60.0]);
+    }
+
+    #[test]
+    fn test_concat_resolution() {
+        let a = TimeSeries::new(vec![1_000], vec![1.0]);
+        let b = TimeSeries { index: DateTimeIndex::new(vec![2]).with_resolution(Resolution::Second), ..TimeSeries::new(vec![2], vec![2.0]) };
+        assert_eq!(a.concat(&b, OverlapPolicy::Error).unwrap().index.values, vec![1_000, 2_000]);
+    }
+
+    #[test]
+    fn test_merge_keeps_resolution() {
+        let ns = |index: Vec<i64>, values: Vec<f64>| TimeSeries {
+            index: DateTimeIndex::new(index).with_resolution(Resolution::Nanosecond),
+            ..TimeSeries::new(vec![0; values.len()], values)
+        };
+        let a = ns(vec![1, 3], vec![1.0, 3.0]);
+        let b = ns(vec![2, 3], vec![2.0, 5.0]);
+        let expected = ns(vec![1, 2, 3], vec![1.0, 2.0, 8.0]);
+        assert_eq!(merge_many(&[&a, &b], Aggregation::Sum), expected);
+        assert_eq!(a.merge_with(&b, Aggregation::Sum), expected);
+        assert_eq!(a.merge_by(&b, |x, y| x + y), expected);
+        assert_eq!(a.merge(&b).index, expected.index);
+        assert_eq!(a.concat(&b, OverlapPolicy::Merge(Aggregation::Sum)).unwrap(), expected);
+        // Millisecond series is converted to nanoseconds
+        let c = TimeSeries::new(vec![0], vec![4.0]);
+        assert_eq!(a.merge(&c).index.values, vec![0, 1, 3]);
+        assert_eq!(merge_many(&[&c, &a], Aggregation::Sum).index.values, vec![0]);
+    }
+}
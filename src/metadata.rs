@@ -0,0 +1,127 @@
+//! Descriptive information attached to the series
+//!
+//! Metadata is copied to the output of operations on the series
+//! and it is saved by CSV and binary io functions.
+//!
+
+use alloc::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+use crate::TimeSeries;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Series description
+///   * name - Series name
+///   * unit - Unit of the values
+///   * tags - Any other key-value pairs
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub unit: Option<String>,
+    pub tags: BTreeMap<String, String>,
+}
+
+
+impl Metadata {
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.unit.is_none() && self.tags.is_empty()
+    }
+
+    /// Metadata as `key: value` lines. Tags have `tag.` prefix.
+    #[cfg(feature = "io")]
+    pub(crate) fn to_lines(&self) -> Vec<String> {
+        let clean = |text: &str| text.replace(['\n', '\r'], " ");
+        let mut lines = vec![];
+        if let Some(name) = &self.name {
+            lines.push(format!("name: {}", clean(name)));
+        }
+        if let Some(unit) = &self.unit {
+            lines.push(format!("unit: {}", clean(unit)));
+        }
+        for (key, value) in &self.tags {
+            lines.push(format!("tag.{}: {}", clean(key), clean(value)));
+        }
+        lines
+    }
+
+    /// Parse line created by `to_lines`. Unknown lines are ignored.
+    #[cfg(feature = "io")]
+    pub(crate) fn parse_line(&mut self, line: &str) {
+        if let Some((key, value)) = line.split_once(": ") {
+            let value = value.trim().to_owned();
+            match key.trim() {
+                "name" => self.name = Some(value),
+                "unit" => self.unit = Some(value),
+                key => if let Some(tag) = key.strip_prefix("tag.") {
+                    self.tags.insert(tag.to_owned(), value);
+                },
+            }
+        }
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Set series name
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2], vec![1.0, 3.0])
+    ///     .with_name("cpu")
+    ///     .with_unit("%")
+    ///     .with_tag("host", "a");
+    /// let output = ts.diff();
+    /// assert_eq!(output.metadata.name.as_deref(), Some("cpu"));
+    /// assert_eq!(output.metadata.tags["host"], "a");
+    /// ```
+    pub fn with_name(mut self, name: &str) -> TimeSeries {
+        self.metadata.name = Some(name.to_owned());
+        self
+    }
+
+    /// Set unit of the values
+    pub fn with_unit(mut self, unit: &str) -> TimeSeries {
+        self.metadata.unit = Some(unit.to_owned());
+        self
+    }
+
+    /// Add tag
+    pub fn with_tag(mut self, key: &str, value: &str) -> TimeSeries {
+        self.metadata.tags.insert(key.to_owned(), value.to_owned());
+        self
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn test_lines_round_trip() {
+        let ts = TimeSeries::empty().with_name("flow\nrate").with_unit("m3/s").with_tag("site", "A: 1");
+        let mut metadata = Metadata::default();
+        ts.metadata.to_lines().iter().for_each(|line| metadata.parse_line(line));
+        assert_eq!(metadata.name.as_deref(), Some("flow rate"));
+        assert_eq!(metadata.unit.as_deref(), Some("m3/s"));
+        assert_eq!(metadata.tags["site"], "A: 1");
+    }
+
+    #[test]
+    fn test_metadata_is_not_compared() {
+        let ts = TimeSeries::new(vec![1], vec![1.0]);
+        assert_eq!(ts.clone().with_name("a"), ts);
+        assert!(ts.metadata.is_empty());
+    }
+}
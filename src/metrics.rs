@@ -0,0 +1,211 @@
+//! Evaluation metrics between actual and predicted values
+//!
+//! Series are aligned with `AlignPolicy` (see `reduce` module) and the metric is calculated
+//! from pairs of values at the same timestamp. Pairs with NaN value are skipped, so with
+//! `AlignPolicy::Union` only timestamps present in both series are used.
+//! Metrics return None if there are no pairs or the metric is not defined for the values.
+//!
+
+use crate::TimeSeries;
+use crate::reduce::AlignPolicy;
+use crate::resample::FillMethod;
+
+
+/// Mean absolute error
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::metrics;
+/// use timeseries::reduce::AlignPolicy;
+///
+/// let actual = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+/// let predicted = TimeSeries::new(vec![2, 3, 4], vec![4.0, 2.0, 9.0]);
+/// assert_eq!(metrics::mae(&actual, &predicted, AlignPolicy::Intersection), Some(1.5));
+/// assert_eq!(metrics::mae(&actual, &TimeSeries::empty(), AlignPolicy::Union), None);
+/// ```
+pub fn mae(actual: &TimeSeries, predicted: &TimeSeries, policy: AlignPolicy) -> Option<f64> {
+    mean_absolute_error(&pairs(actual, predicted, policy))
+}
+
+/// Mean squared error
+pub fn mse(actual: &TimeSeries, predicted: &TimeSeries, policy: AlignPolicy) -> Option<f64> {
+    mean_squared_error(&pairs(actual, predicted, policy))
+}
+
+/// Root mean squared error
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::metrics;
+/// use timeseries::reduce::AlignPolicy;
+///
+/// let actual = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+/// let predicted = TimeSeries::new(vec![1, 2], vec![4.0, 6.0]);
+/// assert_eq!(metrics::mse(&actual, &predicted, AlignPolicy::Union), Some(12.5));
+/// assert_eq!(metrics::rmse(&actual, &predicted, AlignPolicy::Union), Some(12.5f64.sqrt()));
+/// ```
+pub fn rmse(actual: &TimeSeries, predicted: &TimeSeries, policy: AlignPolicy) -> Option<f64> {
+    mse(actual, predicted, policy).map(|v| v.sqrt())
+}
+
+/// Mean absolute percentage error (in percent). Pairs with zero actual value are skipped.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::metrics;
+/// use timeseries::reduce::AlignPolicy;
+///
+/// let actual = TimeSeries::new(vec![1, 2, 3], vec![0.0, 2.0, 4.0]);
+/// let predicted = TimeSeries::new(vec![1, 2, 3], vec![1.0, 3.0, 3.0]);
+/// assert_eq!(metrics::mape(&actual, &predicted, AlignPolicy::Union), Some(37.5));
+/// ```
+pub fn mape(actual: &TimeSeries, predicted: &TimeSeries, policy: AlignPolicy) -> Option<f64> {
+    mean_absolute_percentage_error(&pairs(actual, predicted, policy))
+}
+
+/// Symmetric mean absolute percentage error (between 0 and 200).
+/// Pairs where both values are zero have no error.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::metrics;
+/// use timeseries::reduce::AlignPolicy;
+///
+/// let actual = TimeSeries::new(vec![1, 2], vec![0.0, 1.0]);
+/// let predicted = TimeSeries::new(vec![1, 2], vec![0.0, 3.0]);
+/// assert_eq!(metrics::smape(&actual, &predicted, AlignPolicy::Union), Some(50.0));
+/// ```
+pub fn smape(actual: &TimeSeries, predicted: &TimeSeries, policy: AlignPolicy) -> Option<f64> {
+    symmetric_mean_absolute_percentage_error(&pairs(actual, predicted, policy))
+}
+
+/// Mean absolute scaled error. Error is scaled by the mean absolute error of the naive
+/// (previous value) forecast on the training series.
+/// Returns None if the training series has less than 2 values or all its values are the same.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::metrics;
+/// use timeseries::reduce::AlignPolicy;
+///
+/// let training = TimeSeries::new(vec![1, 2, 3], vec![1.0, 3.0, 5.0]);
+/// let actual = TimeSeries::new(vec![4, 5], vec![7.0, 9.0]);
+/// let predicted = TimeSeries::new(vec![4, 5], vec![6.0, 8.0]);
+/// assert_eq!(metrics::mase(&actual, &predicted, &training, AlignPolicy::Union), Some(0.5));
+/// ```
+pub fn mase(actual: &TimeSeries, predicted: &TimeSeries, training: &TimeSeries, policy: AlignPolicy) -> Option<f64> {
+    let scale = mean_absolute_error(&training.values.windows(2).map(|w| (w[1], w[0])).collect::<Vec<_>>())?;
+    if scale > 0.0 {
+        mae(actual, predicted, policy).map(|v| v / scale)
+    } else {
+        None
+    }
+}
+
+/// Coefficient of determination R². Returns None if all actual values are the same.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::metrics;
+/// use timeseries::reduce::AlignPolicy;
+/// use timeseries::resample::FillMethod;
+///
+/// let actual = TimeSeries::new(vec![0, 10, 20], vec![1.0, 2.0, 3.0]);
+/// let predicted = TimeSeries::new(vec![0, 20], vec![1.0, 3.0]);
+/// assert_eq!(metrics::r2(&actual, &predicted, AlignPolicy::Fill(FillMethod::Interpolate)), Some(1.0));
+/// ```
+pub fn r2(actual: &TimeSeries, predicted: &TimeSeries, policy: AlignPolicy) -> Option<f64> {
+    r_squared(&pairs(actual, predicted, policy))
+}
+
+
+/// Pairs of actual and predicted values at the aligned timestamps
+fn pairs(actual: &TimeSeries, predicted: &TimeSeries, policy: AlignPolicy) -> Vec<(f64, f64)> {
+    let mut index: Vec<i64> = actual.index.iter().chain(predicted.index.iter()).cloned().collect();
+    index.sort_unstable();
+    index.dedup();
+    let method = match policy {
+        AlignPolicy::Fill(method) => method,
+        _ => FillMethod::Nan,
+    };
+    actual.fill_values(&index, method).into_iter()
+        .zip(predicted.fill_values(&index, method))
+        .filter(|(a, p)| !a.is_nan() && !p.is_nan())
+        .collect()
+}
+
+fn mean(xs: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = xs.fold((0.0, 0), |(sum, count), x| (sum + x, count + 1));
+    if count > 0 { Some(sum / count as f64) } else { None }
+}
+
+pub(crate) fn mean_absolute_error(pairs: &[(f64, f64)]) -> Option<f64> {
+    mean(pairs.iter().map(|(a, p)| (a - p).abs()))
+}
+
+pub(crate) fn mean_squared_error(pairs: &[(f64, f64)]) -> Option<f64> {
+    mean(pairs.iter().map(|(a, p)| (a - p) * (a - p)))
+}
+
+pub(crate) fn mean_absolute_percentage_error(pairs: &[(f64, f64)]) -> Option<f64> {
+    mean(pairs.iter().filter(|(a, _)| *a != 0.0).map(|(a, p)| 100.0 * ((a - p) / a).abs()))
+}
+
+pub(crate) fn symmetric_mean_absolute_percentage_error(pairs: &[(f64, f64)]) -> Option<f64> {
+    mean(pairs.iter().map(|(a, p)| {
+        let denominator = a.abs() + p.abs();
+        if denominator > 0.0 { 200.0 * (a - p).abs() / denominator } else { 0.0 }
+    }))
+}
+
+pub(crate) fn r_squared(pairs: &[(f64, f64)]) -> Option<f64> {
+    let m = mean(pairs.iter().map(|(a, _)| *a))?;
+    let total: f64 = pairs.iter().map(|(a, _)| (a - m) * (a - m)).sum();
+    let residual: f64 = pairs.iter().map(|(a, p)| (a - p) * (a - p)).sum();
+    if total > 0.0 { Some(1.0 - residual / total) } else { None }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        let actual = TimeSeries::new(vec![1, 2, 3], vec![1.0, f64::NAN, 3.0]);
+        let predicted = TimeSeries::new(vec![2, 3, 5], vec![2.0, 4.0, 6.0]);
+        assert_eq!(pairs(&actual, &predicted, AlignPolicy::Union), vec![(3.0, 4.0)]);
+        assert_eq!(pairs(&actual, &predicted, AlignPolicy::Fill(FillMethod::Ffill)), vec![(3.0, 4.0), (3.0, 6.0)]);
+    }
+
+    #[test]
+    fn test_undefined() {
+        assert_eq!(mean_absolute_percentage_error(&[(0.0, 1.0)]), None);
+        assert_eq!(symmetric_mean_absolute_percentage_error(&[(0.0, 0.0)]), Some(0.0));
+        assert_eq!(r_squared(&[(1.0, 1.0), (1.0, 2.0)]), None);
+        let constant = TimeSeries::new(vec![1, 2], vec![1.0, 1.0]);
+        assert_eq!(mase(&constant, &constant, &constant, AlignPolicy::Union), None);
+    }
+
+    #[test]
+    fn test_r_squared() {
+        // Predicting the mean gives 0
+        assert_eq!(r_squared(&[(1.0, 2.0), (3.0, 2.0)]), Some(0.0));
+        assert_eq!(r_squared(&[(1.0, 3.0), (3.0, 1.0)]), Some(-3.0));
+    }
+}
@@ -0,0 +1,122 @@
+//! Handling of NaN (missing) values
+//!
+//! Operations which accept `NanPolicy` handle NaN values in the same way:
+//!   * Propagate - Any NaN makes the result NaN
+//!   * Skip - NaN values are ignored (default)
+//!   * Error - Any NaN is an error
+//!
+//! The same policy is used by `TimeSeriesBuilder` for NaN values in the input.
+//!
+//! Aggregations without policy parameter (e.g. `mean`, `std`, `resample`, `rolling_mean`)
+//! use the default policy, so NaN values are skipped.
+//!
+
+use core::error::Error;
+use core::fmt;
+
+use crate::resample::{Aggregation, BucketStats};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// How operations handle NaN values
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum NanPolicy {
+    Propagate,
+    #[default]
+    Skip,
+    Error,
+}
+
+/// NaN value found with `NanPolicy::Error`
+///   * position - Position of the first NaN value
+#[derive(Clone, Debug, PartialEq)]
+pub struct NanError {
+    pub position: usize,
+}
+
+
+impl NanPolicy {
+
+    /// Return error if the policy is Error and there is a NaN value
+    pub(crate) fn check(self, values: &[f64]) -> Result<(), NanError> {
+        match values.iter().position(|v| v.is_nan()) {
+            Some(position) if self == NanPolicy::Error => Err(NanError { position }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Values used by the policy. Skip and Error drop NaN values.
+    pub(crate) fn filter(self, values: &[f64]) -> Vec<f64> {
+        if self == NanPolicy::Propagate {
+            values.to_vec()
+        } else {
+            values.iter().cloned().filter(|v| !v.is_nan()).collect()
+        }
+    }
+
+    /// Aggregate values. None if there are no values (after skipping NaN).
+    /// Error policy should be checked first with `check`, here it works as Skip.
+    pub(crate) fn aggregate(self, values: &[f64], aggregation: Aggregation) -> Option<f64> {
+        if self == NanPolicy::Propagate && values.iter().any(|v| v.is_nan()) {
+            return Some(f64::NAN);
+        }
+        values.iter()
+            .filter(|v| !v.is_nan())
+            .fold(BucketStats::empty(), |acc, &v| acc.merge(&BucketStats::single(v)))
+            .value(aggregation)
+    }
+}
+
+impl fmt::Display for NanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NaN value at position {}", self.position)
+    }
+}
+
+impl Error for NanError {}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        let values = [1.0, f64::NAN, 3.0];
+        assert_eq!(NanPolicy::Skip.aggregate(&values, Aggregation::Mean), Some(2.0));
+        assert_eq!(NanPolicy::Skip.aggregate(&values, Aggregation::Count), Some(2.0));
+        assert!(NanPolicy::Propagate.aggregate(&values, Aggregation::Max).unwrap().is_nan());
+        assert_eq!(NanPolicy::Error.check(&values), Err(NanError { position: 1 }));
+        assert_eq!(NanPolicy::Skip.check(&values), Ok(()));
+        assert_eq!(NanPolicy::Skip.aggregate(&[f64::NAN], Aggregation::Sum), None);
+    }
+
+    #[test]
+    fn test_default_policy_skips_nan() {
+        use crate::TimeSeries;
+
+        let ts = TimeSeries::new(vec![0, 5, 10, 15], vec![1.0, f64::NAN, 3.0, 5.0]);
+        assert_eq!(ts.mean(), Some(3.0));
+        assert_eq!(ts.std(), Some(2.0));
+        assert_eq!(ts.min(), Some(1.0));
+        assert_eq!(ts.max(), Some(5.0));
+        assert_eq!(ts.resample(10, Aggregation::Mean).values, vec![1.0, 4.0]);
+        assert_eq!(ts.resample(10, Aggregation::Sum).values, vec![1.0, 8.0]);
+        assert_eq!(ts.rolling_mean(2).values[1..], [1.0, 3.0, 4.0]);
+        assert_eq!(ts.rolling_min(2).values[1..], [1.0, 3.0, 3.0]);
+        assert_eq!(ts.rolling_max(2).values[1..], [1.0, 3.0, 5.0]);
+        assert!(ts.rolling_std(2).values[2].is_nan());
+        assert_eq!(ts.rolling_std(3).values[3], 2f64.sqrt());
+        assert_eq!(ts.rolling_time(10).mean().values, vec![1.0, 1.0, 3.0, 4.0]);
+        let output = ts.pipeline().resample(10, Aggregation::Mean).rolling_mean(2).collect();
+        assert_eq!(output.values[1..], ts.resample(10, Aggregation::Mean).rolling_mean(2).values[1..]);
+        let all_nan = TimeSeries::new(vec![0, 5], vec![f64::NAN, f64::NAN]);
+        assert_eq!(all_nan.mean(), None);
+        assert_eq!(all_nan.max(), None);
+        assert!(all_nan.resample(10, Aggregation::Mean).values[0].is_nan());
+    }
+}
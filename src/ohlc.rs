@@ -0,0 +1,109 @@
+//! Open, high, low, close (candle) aggregation
+//!
+//! Typical use is building candles from tick data.
+//!
+
+use crate::TimeSeries;
+use crate::frequency::Frequency;
+use crate::resample::Aggregation;
+
+
+/// Candles for each time bucket. All series share the same index
+///   * open - First value in the bucket
+///   * high - Maximum value
+///   * low - Minimum value
+///   * close - Last value
+///   * volume - Sum of volume in the bucket (only with `ohlc_with_volume`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ohlc {
+    pub open: TimeSeries,
+    pub high: TimeSeries,
+    pub low: TimeSeries,
+    pub close: TimeSeries,
+    pub volume: Option<TimeSeries>,
+}
+
+
+impl TimeSeries {
+
+    /// Aggregate series into candles.
+    /// Buckets without data points are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ticks = TimeSeries::new(vec![0, 3, 6, 12, 15], vec![10.0, 12.0, 9.0, 11.0, 13.0]);
+    /// let candles = ticks.ohlc(10);
+    /// assert_eq!(candles.open.values, vec![10.0, 11.0]);
+    /// assert_eq!(candles.high.values, vec![12.0, 13.0]);
+    /// assert_eq!(candles.low.values, vec![9.0, 11.0]);
+    /// assert_eq!(candles.close.values, vec![9.0, 13.0]);
+    /// ```
+    pub fn ohlc<F: Into<Frequency>>(&self, frequency: F) -> Ohlc {
+        let frequency = frequency.into();
+        Ohlc {
+            open: self.resample(frequency, Aggregation::First),
+            high: self.resample(frequency, Aggregation::Max),
+            low: self.resample(frequency, Aggregation::Min),
+            close: self.resample(frequency, Aggregation::Last),
+            volume: None,
+        }
+    }
+
+    /// Aggregate series into candles with volume.
+    /// Volume is the sum of volume series values in the bucket.
+    /// Volume outside of the price buckets is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ticks = TimeSeries::new(vec![0, 3, 12], vec![10.0, 12.0, 11.0]);
+    /// let sizes = TimeSeries::new(vec![0, 3, 12], vec![100.0, 50.0, 20.0]);
+    /// let candles = ticks.ohlc_with_volume(10, &sizes);
+    /// assert_eq!(candles.volume.unwrap().values, vec![150.0, 20.0]);
+    /// ```
+    pub fn ohlc_with_volume<F: Into<Frequency>>(&self, frequency: F, volume: &TimeSeries) -> Ohlc {
+        let frequency = frequency.into();
+        let mut candles = self.ohlc(frequency);
+        let sums = volume.in_resolution(self.index.resolution).resample(frequency, Aggregation::Sum);
+        let values = candles.open.index.iter()
+            .map(|ts| match sums.index.values.binary_search(ts) {
+                Ok(pos) => sums.values[pos],
+                Err(_) => 0.0,
+            })
+            .collect();
+        let mut volume = TimeSeries::new(candles.open.index.values.clone(), values);
+        volume.index.resolution = self.index.resolution;
+        candles.volume = Some(volume);
+        candles
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        let candles = TimeSeries::empty().ohlc(10);
+        assert!(candles.open.is_empty());
+        assert!(candles.volume.is_none());
+    }
+
+    #[test]
+    fn test_volume_missing_in_bucket() {
+        let ticks = TimeSeries::new(vec![0, 10, 20], vec![1.0, 2.0, 3.0]);
+        let sizes = TimeSeries::new(vec![0, 25, 35], vec![5.0, 7.0, 9.0]);
+        let candles = ticks.ohlc_with_volume(10, &sizes);
+        assert_eq!(candles.volume.unwrap().values, vec![5.0, 0.0, 7.0]);
+        assert_eq!(candles.close.index.values, vec![0, 10, 20]);
+    }
+}
@@ -0,0 +1,353 @@
+//! Streaming statistics
+//!
+//! Accumulators are updated with one data point at a time and use constant memory,
+//! so they can be used in live pipelines where the full series is never stored.
+//! NaN values are ignored by all accumulators.
+//!
+
+use std::cmp::Ordering;
+
+use crate::DataPoint;
+
+
+/// Mean and variance with Welford's algorithm
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Moments {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+/// Minimum and maximum with their timestamps
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Extremes {
+    min: Option<DataPoint>,
+    max: Option<DataPoint>,
+}
+
+/// Quantile estimation with the P² algorithm (Jain and Chlamtac).
+/// Uses 5 markers instead of storing the values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct P2Quantile {
+    p: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+/// Exponentially weighted moving average with smoothing factor alpha
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ewma {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+/// Accumulator for the most common statistics
+#[derive(Clone, Debug, PartialEq)]
+pub struct OnlineStats {
+    moments: Moments,
+    extremes: Extremes,
+    median: P2Quantile,
+    last: Option<DataPoint>,
+}
+
+/// Snapshot of the OnlineStats
+///   * count - Number of data points with non NaN value
+///   * mean - Arithmetic mean
+///   * std - Sample standard deviation
+///   * min - Minimum value
+///   * max - Maximum value
+///   * median - Estimated median
+///   * last - The most recent data point
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub std: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub median: Option<f64>,
+    pub last: Option<DataPoint>,
+}
+
+
+impl Moments {
+
+    pub fn new() -> Moments {
+        Moments::default()
+    }
+
+    pub fn push(&mut self, dp: &DataPoint) {
+        if dp.value.is_nan() {
+            return;
+        }
+        self.count += 1;
+        let delta = dp.value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (dp.value - self.mean);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Arithmetic mean. None if there are no values
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 { None } else { Some(self.mean) }
+    }
+
+    /// Sample variance. None if there are less then 2 values
+    pub fn variance(&self) -> Option<f64> {
+        if self.count < 2 { None } else { Some(self.m2 / (self.count - 1) as f64) }
+    }
+}
+
+impl Extremes {
+
+    pub fn new() -> Extremes {
+        Extremes::default()
+    }
+
+    pub fn push(&mut self, dp: &DataPoint) {
+        if dp.value.is_nan() {
+            return;
+        }
+        if self.min.as_ref().is_none_or(|m| dp.value < m.value) {
+            self.min = Some(dp.clone());
+        }
+        if self.max.as_ref().is_none_or(|m| dp.value > m.value) {
+            self.max = Some(dp.clone());
+        }
+    }
+
+    /// Data point with the smallest value. The first one if there are ties
+    pub fn min(&self) -> Option<&DataPoint> {
+        self.min.as_ref()
+    }
+
+    /// Data point with the largest value. The first one if there are ties
+    pub fn max(&self) -> Option<&DataPoint> {
+        self.max.as_ref()
+    }
+}
+
+impl P2Quantile {
+
+    /// Create estimator of quantile p (between 0 and 1)
+    pub fn new(p: f64) -> P2Quantile {
+        P2Quantile {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    pub fn push(&mut self, dp: &DataPoint) {
+        let x = dp.value;
+        if x.is_nan() {
+            return;
+        }
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            }
+            return;
+        }
+        self.count += 1;
+
+        let k = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            (1..5).find(|&i| x < self.heights[i]).unwrap() - 1
+        };
+        for i in k+1..5 {
+            self.positions[i] += 1.0;
+        }
+        for i in 0..5 {
+            self.desired[i] += self.increments[i];
+        }
+
+        for i in 1..4 {
+            let d = self.desired[i] - self.positions[i];
+            if (d >= 1.0 && self.positions[i+1] - self.positions[i] > 1.0)
+                || (d <= -1.0 && self.positions[i-1] - self.positions[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] = if self.heights[i-1] < parabolic && parabolic < self.heights[i+1] {
+                    parabolic
+                } else {
+                    self.linear(i, d)
+                };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    /// Estimated quantile. Exact value if there are less then 5 values
+    pub fn quantile(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else if self.count < 5 {
+            let mut xs = self.heights[..self.count].to_vec();
+            xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let pos = self.p * (xs.len() - 1) as f64;
+            let lo = pos.floor() as usize;
+            let hi = pos.ceil() as usize;
+            Some(xs[lo] + (xs[hi] - xs[lo]) * (pos - lo as f64))
+        } else {
+            Some(self.heights[2])
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + d / (n[i+1] - n[i-1]) * (
+            (n[i] - n[i-1] + d) * (q[i+1] - q[i]) / (n[i+1] - n[i])
+            + (n[i+1] - n[i] - d) * (q[i] - q[i-1]) / (n[i] - n[i-1]))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        self.heights[i] + d * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
+    }
+}
+
+impl Ewma {
+
+    /// Create average with smoothing factor alpha (between 0 and 1).
+    /// The first value initializes the average.
+    pub fn new(alpha: f64) -> Ewma {
+        Ewma { alpha, value: None }
+    }
+
+    pub fn push(&mut self, dp: &DataPoint) {
+        if dp.value.is_nan() {
+            return;
+        }
+        self.value = Some(match self.value {
+            Some(prev) => self.alpha * dp.value + (1.0 - self.alpha) * prev,
+            None => dp.value,
+        });
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+impl Default for OnlineStats {
+    fn default() -> OnlineStats {
+        OnlineStats::new()
+    }
+}
+
+impl OnlineStats {
+
+    pub fn new() -> OnlineStats {
+        OnlineStats {
+            moments: Moments::new(),
+            extremes: Extremes::new(),
+            median: P2Quantile::new(0.5),
+            last: None,
+        }
+    }
+
+    /// Update statistics with the next data point
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    /// use timeseries::online::OnlineStats;
+    ///
+    /// let mut stats = OnlineStats::new();
+    /// for (i, v) in [1.0, 2.0, 3.0, f64::NAN].iter().enumerate() {
+    ///     stats.push(&DataPoint::new(i as i64, *v));
+    /// }
+    /// let summary = stats.snapshot();
+    /// assert_eq!(summary.count, 3);
+    /// assert_eq!(summary.mean, Some(2.0));
+    /// assert_eq!(summary.std, Some(1.0));
+    /// assert_eq!(summary.median, Some(2.0));
+    /// assert_eq!(summary.last.unwrap().timestamp, 3);
+    /// ```
+    pub fn push(&mut self, dp: &DataPoint) {
+        self.moments.push(dp);
+        self.extremes.push(dp);
+        self.median.push(dp);
+        self.last = Some(dp.clone());
+    }
+
+    pub fn snapshot(&self) -> Summary {
+        Summary {
+            count: self.moments.count(),
+            mean: self.moments.mean(),
+            std: self.moments.variance().map(|v| v.sqrt()),
+            min: self.extremes.min().map(|dp| dp.value),
+            max: self.extremes.max().map(|dp| dp.value),
+            median: self.median.quantile(),
+            last: self.last.clone(),
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::TimeSeries;
+
+    #[test]
+    fn test_moments_match_batch() {
+        let ts = TimeSeries::new((0..100).collect(), (0..100).map(|i| ((i * 37) % 101) as f64).collect());
+        let mut moments = Moments::new();
+        ts.iter().for_each(|dp| moments.push(&dp));
+        assert_approx_eq!(moments.mean().unwrap(), ts.mean().unwrap());
+        assert_approx_eq!(moments.variance().unwrap().sqrt(), ts.std().unwrap());
+    }
+
+    #[test]
+    fn test_extremes_keep_timestamp() {
+        let mut extremes = Extremes::new();
+        for (i, v) in [3.0, 1.0, 5.0, 1.0].iter().enumerate() {
+            extremes.push(&DataPoint::new(i as i64, *v));
+        }
+        assert_eq!(extremes.min(), Some(&DataPoint::new(1, 1.0)));
+        assert_eq!(extremes.max(), Some(&DataPoint::new(2, 5.0)));
+    }
+
+    #[test]
+    fn test_p2_quantile() {
+        let mut q = P2Quantile::new(0.9);
+        // Values 0..1000 in shuffled order
+        for i in 0..1000 {
+            q.push(&DataPoint::new(i, ((i * 7919) % 1000) as f64));
+        }
+        assert_approx_eq!(q.quantile().unwrap(), 900.0, 10.0);
+        assert_eq!(P2Quantile::new(0.5).quantile(), None);
+    }
+
+    #[test]
+    fn test_ewma() {
+        let mut ewma = Ewma::new(0.5);
+        assert_eq!(ewma.value(), None);
+        ewma.push(&DataPoint::new(0, 2.0));
+        ewma.push(&DataPoint::new(1, 4.0));
+        assert_eq!(ewma.value(), Some(3.0));
+    }
+}
@@ -0,0 +1,135 @@
+//! Principal component analysis of frame columns
+//!
+//! Enabled with the `linalg` feature.
+//! Components are fitted on rows where all columns have values.
+//! Rows with any NaN value get NaN component values.
+//!
+
+use core::cmp::Ordering;
+
+use crate::frame::TimeSeriesFrame;
+use crate::linalg::{self, Matrix};
+
+
+/// Result of the principal component analysis
+///   * components - Frame with column values projected on each component (named pc1, pc2, ...)
+///   * loadings - Weight of each frame column in each component (one row per component).
+///     The largest weight of each component is positive.
+///   * explained_variance - Variance of each component
+///   * explained_variance_ratio - Part of the total variance explained by each component
+///     (0 if all columns are constant)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pca {
+    pub components: TimeSeriesFrame,
+    pub loadings: Vec<Vec<f64>>,
+    pub explained_variance: Vec<f64>,
+    pub explained_variance_ratio: Vec<f64>,
+}
+
+
+impl TimeSeriesFrame {
+
+    /// Project columns on the n components with the largest variance.
+    /// None if there are less then 2 rows without NaN values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use assert_approx_eq::assert_approx_eq;
+    /// use timeseries::frame::TimeSeriesFrame;
+    ///
+    /// let frame = TimeSeriesFrame::new(vec![1, 2, 3, 4])
+    ///     .with_column("a", vec![1.0, 2.0, 3.0, 4.0])
+    ///     .with_column("b", vec![2.0, 4.0, 6.0, 8.0]);
+    /// let pca = frame.pca(1).unwrap();
+    /// assert_eq!(pca.components.names, vec!["pc1"]);
+    /// assert_approx_eq!(pca.explained_variance_ratio[0], 1.0);
+    /// assert_approx_eq!(pca.loadings[0][1], 2.0 / 5f64.sqrt());
+    /// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(len = self.len(), width = self.width())))]
+    pub fn pca(&self, n_components: usize) -> Option<Pca> {
+        let rows: Vec<usize> = (0..self.len())
+            .filter(|&i| self.columns.iter().all(|c| !c[i].is_nan()))
+            .collect();
+        if rows.len() < 2 || self.width() == 0 {
+            return None;
+        }
+        let means: Vec<f64> = self.columns.iter()
+            .map(|c| rows.iter().map(|&i| c[i]).sum::<f64>() / rows.len() as f64)
+            .collect();
+        let centered: Matrix = rows.iter()
+            .map(|&i| self.columns.iter().zip(&means).map(|(c, m)| c[i] - m).collect())
+            .collect();
+        let scale = 1.0 / (rows.len() - 1) as f64;
+        let cov: Matrix = linalg::mul(&linalg::transpose(&centered), &centered).iter()
+            .map(|row| row.iter().map(|v| v * scale).collect())
+            .collect();
+        let total_variance: f64 = (0..self.width()).map(|i| cov[i][i]).sum();
+
+        let (values, vectors) = linalg::symmetric_eigen(cov);
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|&a, &b| values[b].partial_cmp(&values[a]).unwrap_or(Ordering::Equal));
+        order.truncate(n_components);
+        let loadings: Vec<Vec<f64>> = order.iter().map(|&k| with_positive_max(&vectors[k])).collect();
+        let explained_variance: Vec<f64> = order.iter().map(|&k| values[k].max(0.0)).collect();
+        let explained_variance_ratio = explained_variance.iter()
+            .map(|v| if total_variance > 0.0 { v / total_variance } else { 0.0 })
+            .collect();
+
+        let mut components = TimeSeriesFrame { index: self.index.clone(), names: vec![], columns: vec![] };
+        for (k, weights) in loadings.iter().enumerate() {
+            let values = (0..self.len())
+                .map(|i| self.columns.iter().zip(&means).zip(weights).map(|((c, m), w)| (c[i] - m) * w).sum())
+                .collect();
+            components = components.with_column(&format!("pc{}", k + 1), values);
+        }
+        Some(Pca { components, loadings, explained_variance, explained_variance_ratio })
+    }
+}
+
+
+/// Eigenvector with the sign flipped so its largest (absolute) weight is positive
+fn with_positive_max(vector: &[f64]) -> Vec<f64> {
+    let max = vector.iter().cloned().fold(0.0, |acc: f64, v| if v.abs() > acc.abs() { v } else { acc });
+    let sign = if max < 0.0 { -1.0 } else { 1.0 };
+    vector.iter().map(|v| v * sign).collect()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nan_rows() {
+        let frame = TimeSeriesFrame::new(vec![1, 2, 3, 4])
+            .with_column("a", vec![1.0, f64::NAN, 3.0, 5.0])
+            .with_column("b", vec![0.0, 1.0, 0.0, 0.0]);
+        let pca = frame.pca(5).unwrap();
+        assert_eq!(pca.loadings.len(), 2);
+        assert_eq!(pca.loadings[0], vec![1.0, 0.0]);
+        assert_eq!(pca.components.columns[0][0], -2.0);
+        assert!(pca.components.columns[0][1].is_nan());
+        assert_eq!(pca.explained_variance, vec![4.0, 0.0]);
+    }
+
+    #[test]
+    fn test_constant_columns() {
+        let frame = TimeSeriesFrame::new(vec![1, 2, 3])
+            .with_column("a", vec![1.0, 1.0, 1.0])
+            .with_column("b", vec![2.0, 2.0, 2.0]);
+        let pca = frame.pca(2).unwrap();
+        assert_eq!(pca.explained_variance, vec![0.0, 0.0]);
+        assert_eq!(pca.explained_variance_ratio, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_not_enough_rows() {
+        let frame = TimeSeriesFrame::new(vec![1, 2]).with_column("a", vec![1.0, f64::NAN]);
+        assert!(frame.pca(1).is_none());
+        assert!(TimeSeriesFrame::new(vec![1, 2]).pca(1).is_none());
+    }
+}
@@ -0,0 +1,170 @@
+//! Piecewise linear approximation
+//!
+//! Series is approximated with connected line segments using the sliding window algorithm.
+//! Each segment starts at the data point where the previous one ends and is extended
+//! as long as all data points in it are within the max error from the line.
+//! Range of slopes which keep all points of the segment within the error (swing door)
+//! is updated with each point, so the whole series is split in linear time.
+//! Slow-moving signals can be stored as a few segments instead of all data points.
+//!
+
+use crate::TimeSeries;
+
+
+/// Line between 2 data points
+///   * start, end - Timestamps of the first and the last data point in the segment
+///   * start_value, end_value - Values at the start and the end
+///   * slope - Change of value per millisecond
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: i64,
+    pub end: i64,
+    pub start_value: f64,
+    pub end_value: f64,
+    pub slope: f64,
+}
+
+impl Segment {
+
+    fn new(ts: &TimeSeries, start: usize, end: usize) -> Segment {
+        let (t0, t1) = (ts.index[start], ts.index[end]);
+        let (v0, v1) = (ts.values[start], ts.values[end]);
+        let slope = if t1 > t0 { (v1 - v0) / (t1 - t0) as f64 } else { 0.0 };
+        Segment { start: t0, end: t1, start_value: v0, end_value: v1, slope }
+    }
+
+    /// Value of the line at the given timestamp
+    pub fn predict(&self, timestamp: i64) -> f64 {
+        self.start_value + self.slope * (timestamp - self.start) as f64
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Split series into line segments. All data points are within max_error from their segment.
+    /// Timestamps where the slope changes are the starts of the segments (except the first one).
+    /// Series with a single data point has one segment with zero length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1, 2, 3, 4, 5], vec![0.0, 1.0, 2.1, 3.0, 2.0, 1.0]);
+    /// let segments = ts.segment(0.2);
+    /// assert_eq!(segments.len(), 2);
+    /// assert_eq!(segments[1].start, 3);
+    /// assert_eq!(segments[1].slope, -1.0);
+    /// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(len = self.len())))]
+    pub fn segment(&self, max_error: f64) -> Vec<Segment> {
+        if self.len() < 2 {
+            return (0..self.len()).map(|i| Segment::new(self, i, i)).collect();
+        }
+        let mut segments = vec![];
+        let mut start = 0;
+        while start < self.len() - 1 {
+            let mut door = SwingDoor::new(self.index[start], self.values[start], max_error);
+            let mut end = start + 1;
+            while end + 1 < self.len() {
+                door.add(self.index[end], self.values[end]);
+                if !door.fits(self.index[end + 1], self.values[end + 1]) {
+                    break;
+                }
+                end += 1;
+            }
+            segments.push(Segment::new(self, start, end));
+            start = end;
+        }
+        segments
+    }
+}
+
+
+/// Range of slopes of lines from the start point, which are within max error from all added points
+struct SwingDoor {
+    start: i64,
+    start_value: f64,
+    max_error: f64,
+    low: f64,
+    high: f64,
+}
+
+impl SwingDoor {
+
+    fn new(start: i64, start_value: f64, max_error: f64) -> SwingDoor {
+        SwingDoor { start, start_value, max_error, low: f64::NEG_INFINITY, high: f64::INFINITY }
+    }
+
+    /// Narrow the range of slopes, so the line is close to the data point
+    fn add(&mut self, timestamp: i64, value: f64) {
+        let dt = (timestamp - self.start) as f64;
+        let dv = value - self.start_value;
+        if dt > 0.0 {
+            self.low = self.low.max((dv - self.max_error) / dt);
+            self.high = self.high.min((dv + self.max_error) / dt);
+        } else if dv.abs() > self.max_error {
+            self.low = f64::INFINITY;
+        }
+        if value.is_nan() {
+            self.low = f64::INFINITY;
+        }
+    }
+
+    /// Check if the line ending at the data point has slope within the range
+    fn fits(&self, timestamp: i64, value: f64) -> bool {
+        let slope = if timestamp > self.start { (value - self.start_value) / (timestamp - self.start) as f64 } else { 0.0 };
+        self.low <= slope && slope <= self.high
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_bound() {
+        let index: Vec<i64> = (0..200).map(|i| i * 10).collect();
+        let values: Vec<f64> = (0..200).map(|i| (i as f64 / 20.0).sin()).collect();
+        let ts = TimeSeries::new(index, values);
+        let segments = ts.segment(0.05);
+        assert!(segments.len() > 1 && segments.len() < 50);
+        assert_eq!(segments[0].start, 0);
+        assert_eq!(segments[segments.len() - 1].end, 1990);
+        for dp in ts.iter() {
+            let segment = segments.iter().find(|s| s.start <= dp.timestamp && dp.timestamp <= s.end).unwrap();
+            assert!((segment.predict(dp.timestamp) - dp.value).abs() <= 0.05);
+        }
+    }
+
+    #[test]
+    fn test_long_segment() {
+        let index: Vec<i64> = (0..100_000).collect();
+        let values: Vec<f64> = (0..100_000).map(|i| i as f64 * 0.5 + (i % 3) as f64 * 0.01).collect();
+        let ts = TimeSeries::new(index, values);
+        let segments = ts.segment(0.1);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].end, 99_999);
+    }
+
+    #[test]
+    fn test_duplicate_timestamps() {
+        let ts = TimeSeries::new(vec![0, 0, 1, 2, 2], vec![0.0, 0.05, 1.0, 2.0, 5.0]);
+        let segments = ts.segment(0.1);
+        assert_eq!(segments.len(), 2);
+        assert_eq!((segments[0].start, segments[0].end), (0, 2));
+        assert_eq!((segments[1].start, segments[1].end), (2, 2));
+    }
+
+    #[test]
+    fn test_short_series() {
+        assert!(TimeSeries::empty().segment(1.0).is_empty());
+        let segments = TimeSeries::new(vec![5], vec![2.0]).segment(1.0);
+        assert_eq!(segments, vec![Segment { start: 5, end: 5, start_value: 2.0, end_value: 2.0, slope: 0.0 }]);
+    }
+}
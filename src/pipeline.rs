@@ -0,0 +1,327 @@
+//! Lazy chains of operations
+//!
+//! `Pipeline` records operations and runs them all in a single pass over the data points
+//! when `collect` is called. Each stage passes data points to the next one as soon as they are ready,
+//! so intermediate series are never allocated. The result is the same as calling
+//! the eager methods one after another.
+//!
+//! Quality codes are not kept. Provenance log gets one operation per stage.
+//!
+
+use std::collections::VecDeque;
+
+use crate::TimeSeries;
+use crate::frequency::Frequency;
+use crate::index::Resolution;
+use crate::resample::{Aggregation, BucketStats, FillMethod};
+use crate::stats::{nan_mean, nan_std};
+
+
+/// Lazy chain of operations on the series. Created with `TimeSeries::pipeline()`.
+pub struct Pipeline<'a> {
+    source: &'a TimeSeries,
+    stages: Vec<Stage<'a>>,
+}
+
+/// Recorded operation
+enum Stage<'a> {
+    FillGaps(Frequency, FillMethod),
+    Resample(Frequency, Aggregation),
+    Map(Box<dyn Fn(f64) -> f64 + 'a>),
+    RollingMean(usize),
+    RollingStd(usize),
+    Ewma(usize),
+}
+
+/// Running stage with its state
+enum Operator<'s> {
+    FillGaps { frequency: Frequency, resolution: Resolution, method: FillMethod, start: Option<i64>, step: i64, prev: Option<(i64, f64)> },
+    Resample { frequency: Frequency, resolution: Resolution, aggregation: Aggregation, bucket: Option<i64>, stats: BucketStats },
+    Map(&'s dyn Fn(f64) -> f64),
+    Rolling { window: usize, buffer: VecDeque<f64>, f: fn(&[f64]) -> f64 },
+    Ewma { alpha: f64, value: Option<f64> },
+}
+
+
+impl TimeSeries {
+
+    /// Start lazy chain of operations on this series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::{Aggregation, FillMethod};
+    ///
+    /// let ts = TimeSeries::new(vec![0, 20, 30, 40], vec![1.0, 3.0, 5.0, 7.0]);
+    /// let output = ts.pipeline()
+    ///     .fill_gaps(10, FillMethod::Interpolate)
+    ///     .resample(20, Aggregation::Mean)
+    ///     .rolling_mean(2)
+    ///     .collect();
+    /// let expected = ts.upsample(10, FillMethod::Interpolate)
+    ///     .resample(20, Aggregation::Mean)
+    ///     .rolling_mean(2);
+    /// assert_eq!(output.index, expected.index);
+    /// assert_eq!(output.values[1..], expected.values[1..]);
+    /// ```
+    pub fn pipeline(&self) -> Pipeline<'_> {
+        Pipeline { source: self, stages: vec![] }
+    }
+}
+
+
+impl<'a> Pipeline<'a> {
+
+    /// Fill missing timestamps on the regular grid (see `TimeSeries::upsample`)
+    pub fn fill_gaps<F: Into<Frequency>>(mut self, frequency: F, method: FillMethod) -> Self {
+        self.stages.push(Stage::FillGaps(frequency.into(), method));
+        self
+    }
+
+    /// Aggregate data points in each period (see `TimeSeries::resample`)
+    pub fn resample<F: Into<Frequency>>(mut self, frequency: F, aggregation: Aggregation) -> Self {
+        self.stages.push(Stage::Resample(frequency.into(), aggregation));
+        self
+    }
+
+    /// Apply function to each value
+    pub fn map<F: Fn(f64) -> f64 + 'a>(mut self, f: F) -> Self {
+        self.stages.push(Stage::Map(Box::new(f)));
+        self
+    }
+
+    /// Moving average (see `TimeSeries::rolling_mean`)
+    pub fn rolling_mean(mut self, window: usize) -> Self {
+        self.stages.push(Stage::RollingMean(window));
+        self
+    }
+
+    /// Moving sample standard deviation (see `TimeSeries::rolling_std`)
+    pub fn rolling_std(mut self, window: usize) -> Self {
+        self.stages.push(Stage::RollingStd(window));
+        self
+    }
+
+    /// Exponentially weighted moving average (see `TimeSeries::ewma`)
+    pub fn ewma(mut self, span: usize) -> Self {
+        self.stages.push(Stage::Ewma(span));
+        self
+    }
+
+    /// Run all operations in a single pass and return the result.
+    /// Metadata and index resolution are taken from the source series.
+    pub fn collect(self) -> TimeSeries {
+        let resolution = self.source.index.resolution;
+        let mut operators: Vec<Operator> = self.stages.iter().map(|stage| Operator::new(stage, resolution)).collect();
+        let mut index = Vec::with_capacity(self.source.len());
+        let mut values = Vec::with_capacity(self.source.len());
+        {
+            let mut output = |t, v| {
+                index.push(t);
+                values.push(v);
+            };
+            for (&t, &v) in self.source.index.values.iter().zip(&self.source.values) {
+                push(&mut operators, t, v, &mut output);
+            }
+            for i in 0..operators.len() {
+                let (head, tail) = operators.split_at_mut(i + 1);
+                head[i].finish(&mut |t, v| push(tail, t, v, &mut output));
+            }
+        }
+
+        let mut output = TimeSeries::new(index, values);
+        output.index.resolution = self.source.index.resolution;
+        output.metadata = self.source.metadata.clone();
+        output.provenance = self.source.provenance.clone();
+        for stage in &self.stages {
+            output.log_inplace(stage.name(), || stage.params());
+        }
+        output
+    }
+}
+
+
+impl Stage<'_> {
+
+    /// Name of the equivalent eager operation
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::FillGaps(..) => "upsample",
+            Stage::Resample(..) => "resample",
+            Stage::Map(_) => "map",
+            Stage::RollingMean(_) => "rolling_mean",
+            Stage::RollingStd(_) => "rolling_std",
+            Stage::Ewma(_) => "ewma",
+        }
+    }
+
+    /// Parameters of the equivalent eager operation
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Stage::FillGaps(frequency, method) =>
+                vec![("frequency", format!("{:?}", frequency)), ("method", format!("{:?}", method))],
+            Stage::Resample(frequency, aggregation) =>
+                vec![("frequency", format!("{:?}", frequency)), ("aggregation", format!("{:?}", aggregation))],
+            Stage::Map(_) => vec![],
+            Stage::RollingMean(window) | Stage::RollingStd(window) => vec![("window", window.to_string())],
+            Stage::Ewma(span) => vec![("span", span.to_string())],
+        }
+    }
+}
+
+
+impl<'s> Operator<'s> {
+
+    fn new(stage: &'s Stage, resolution: Resolution) -> Operator<'s> {
+        match stage {
+            &Stage::FillGaps(frequency, method) =>
+                Operator::FillGaps { frequency, resolution, method, start: None, step: 0, prev: None },
+            &Stage::Resample(frequency, aggregation) =>
+                Operator::Resample { frequency, resolution, aggregation, bucket: None, stats: BucketStats::empty() },
+            Stage::Map(f) => Operator::Map(f.as_ref()),
+            &Stage::RollingMean(window) => Operator::rolling(window, nan_mean),
+            &Stage::RollingStd(window) => Operator::rolling(window, nan_std),
+            &Stage::Ewma(span) => Operator::Ewma { alpha: 2.0 / (span as f64 + 1.0), value: None },
+        }
+    }
+
+    fn rolling(window: usize, f: fn(&[f64]) -> f64) -> Operator<'s> {
+        Operator::Rolling { window, buffer: VecDeque::with_capacity(window), f }
+    }
+
+    /// Process data point and emit data points which are ready
+    fn push(&mut self, t: i64, v: f64, emit: &mut dyn FnMut(i64, f64)) {
+        match self {
+            Operator::FillGaps { frequency, .. } | Operator::Resample { frequency, .. } if !frequency.is_valid() => (),
+            Operator::FillGaps { frequency, resolution, method, start, step, prev } => {
+                let start = *start.get_or_insert(t);
+                loop {
+                    let next = frequency.step_in(start, *step, *resolution);
+                    if next >= t {
+                        break;
+                    }
+                    // Steps shorter than the resolution unit give the same timestamp again
+                    if *step > 0 && frequency.step_in(start, *step - 1, *resolution) == next {
+                        *step += 1;
+                        continue;
+                    }
+                    let value = match *prev {
+                        Some((pt, pv)) if pt == next => pv,
+                        Some((_, pv)) if *method == FillMethod::Ffill => pv,
+                        Some((pt, pv)) if *method == FillMethod::Interpolate => pv + (v - pv) * (next - pt) as f64 / (t - pt) as f64,
+                        _ => f64::NAN,
+                    };
+                    emit(next, value);
+                    *step += 1;
+                }
+                *prev = Some((t, v));
+            },
+            Operator::Resample { frequency, resolution, aggregation, bucket, stats } => {
+                let current = frequency.floor_in(t, *resolution);
+                if let Some(b) = *bucket {
+                    if b != current {
+                        emit(b, stats.value(*aggregation).unwrap_or(f64::NAN));
+                        *stats = BucketStats::empty();
+                    }
+                }
+                *bucket = Some(current);
+                // NaN values are skipped as in TimeSeries::resample
+                if !v.is_nan() {
+                    *stats = stats.merge(&BucketStats::single(v));
+                }
+            },
+            Operator::Map(f) => emit(t, f(v)),
+            Operator::Rolling { window, buffer, f } => {
+                if *window == 0 {
+                    emit(t, f64::NAN);
+                    return;
+                }
+                if buffer.len() == *window {
+                    buffer.pop_front();
+                }
+                buffer.push_back(v);
+                let value = if buffer.len() < *window { f64::NAN } else { f(buffer.make_contiguous()) };
+                emit(t, value);
+            },
+            Operator::Ewma { alpha, value } => {
+                let next = match *value {
+                    Some(prev) => *alpha * v + (1.0 - *alpha) * prev,
+                    None => v,
+                };
+                *value = Some(next);
+                emit(t, next);
+            },
+        }
+    }
+
+    /// Emit data points which are still kept after the last data point
+    fn finish(&mut self, emit: &mut dyn FnMut(i64, f64)) {
+        match self {
+            Operator::FillGaps { frequency, resolution, start: Some(start), step, prev: Some((pt, pv)), .. }
+                if frequency.step_in(*start, *step, *resolution) == *pt => emit(*pt, *pv),
+            Operator::Resample { aggregation, bucket: Some(b), stats, .. } => {
+                emit(*b, stats.value(*aggregation).unwrap_or(f64::NAN));
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Pass data point through the operators
+fn push(operators: &mut [Operator], t: i64, v: f64, output: &mut dyn FnMut(i64, f64)) {
+    match operators.split_first_mut() {
+        Some((first, rest)) => first.push(t, v, &mut |t, v| push(rest, t, v, output)),
+        None => output(t, v),
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_as_eager() {
+        let ts = TimeSeries::new(vec![0, 7, 20, 35, 40, 41, 70], vec![1.0, 2.0, 4.0, 3.0, 5.0, 6.0, 2.0]);
+        for method in [FillMethod::Ffill, FillMethod::Interpolate, FillMethod::Nan] {
+            let output = ts.pipeline()
+                .fill_gaps(5, method)
+                .map(|v| v * 2.0)
+                .resample(10, Aggregation::Max)
+                .rolling_std(3)
+                .ewma(2)
+                .collect();
+            let expected = ts.upsample(5, method)
+                .resample(10, Aggregation::Max)
+                .rolling_std(3)
+                .ewma(2);
+            assert_eq!(output.index, expected.index);
+            let expected: Vec<f64> = expected.values.iter().map(|v| v * 2.0).collect();
+            assert_eq!(format!("{:?}", output.values), format!("{:?}", expected));
+        }
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        assert!(TimeSeries::empty().pipeline().fill_gaps(5, FillMethod::Ffill).resample(10, Aggregation::Sum).collect().is_empty());
+        let ts = TimeSeries::new(vec![0, 0, 10], vec![1.0, 2.0, 3.0]);
+        assert_eq!(ts.pipeline().fill_gaps(5, FillMethod::Ffill).collect(), ts.upsample(5, FillMethod::Ffill));
+        assert!(ts.pipeline().rolling_mean(5).collect().values.iter().all(|v| v.is_nan()));
+        assert!(ts.pipeline().rolling_mean(0).collect().values.iter().all(|v| v.is_nan()));
+        assert_eq!(ts.pipeline().collect(), ts);
+        assert!(ts.pipeline().fill_gaps(0, FillMethod::Ffill).collect().is_empty());
+        assert!(ts.pipeline().resample(-10, Aggregation::Sum).collect().is_empty());
+    }
+
+    #[test]
+    fn test_provenance() {
+        let ts = TimeSeries::new(vec![0, 10], vec![1.0, 2.0]).with_provenance();
+        let output = ts.pipeline().resample(10, Aggregation::Sum).rolling_mean(2).collect();
+        let expected = ts.resample(10, Aggregation::Sum).rolling_mean(2);
+        assert_eq!(output.provenance, expected.provenance);
+    }
+}
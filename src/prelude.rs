@@ -0,0 +1,29 @@
+//! Replacement of the std prelude in no_std builds
+//!
+//! Collections come from `alloc` and floating point functions from `libm`.
+//! Modules available without std import everything from here.
+//!
+
+pub(crate) use alloc::borrow::ToOwned;
+pub(crate) use alloc::format;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec;
+pub(crate) use alloc::vec::Vec;
+
+
+/// Floating point functions which are defined in std
+pub(crate) trait Float {
+    fn sqrt(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+}
+
+impl Float for f64 {
+    fn sqrt(self) -> f64 { libm::sqrt(self) }
+    fn powi(self, n: i32) -> f64 { libm::pow(self, n as f64) }
+    fn floor(self) -> f64 { libm::floor(self) }
+    fn ceil(self) -> f64 { libm::ceil(self) }
+    fn round(self) -> f64 { libm::round(self) }
+}
@@ -0,0 +1,180 @@
+//! Calendar profiles
+//!
+//! Data points are grouped by calendar component of their timestamp (e.g. hour of the day),
+//! so the profile shows the typical shape of the series, like the average daily load curve.
+//! Subtracting the profile is a lightweight seasonal adjustment.
+//! Components are calculated in UTC (see `calendar` module). Data points with timestamps
+//! out of the datetime range don't belong to any group.
+//!
+
+use crate::TimeSeries;
+use crate::index::DateTimeIndex;
+use crate::nan::NanPolicy;
+use crate::resample::Aggregation;
+
+
+/// Calendar component used for grouping. Groups are numbered from 0:
+///   * HourOfDay - 24 groups, hour 0-23
+///   * DayOfWeek - 7 groups, Monday is 0
+///   * DayOfMonth - 31 groups, the first day of the month is 0
+///   * Month - 12 groups, January is 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Component {
+    HourOfDay,
+    DayOfWeek,
+    DayOfMonth,
+    Month,
+}
+
+/// Data points grouped by calendar component. Created with `TimeSeries::groupby_calendar()`.
+pub struct CalendarGroups<'a> {
+    ts: &'a TimeSeries,
+    component: Component,
+}
+
+
+impl Component {
+
+    /// Number of groups
+    pub fn group_count(&self) -> usize {
+        match self {
+            Component::HourOfDay => 24,
+            Component::DayOfWeek => 7,
+            Component::DayOfMonth => 31,
+            Component::Month => 12,
+        }
+    }
+
+    /// Group of each timestamp in the index. None if the timestamp is out of the datetime range.
+    pub(crate) fn groups(&self, index: &DateTimeIndex) -> Vec<Option<usize>> {
+        let values = match self {
+            Component::HourOfDay => index.hour_of_day(),
+            Component::DayOfWeek => index.day_of_week(),
+            Component::DayOfMonth => index.day_of_month().into_iter().map(|d| d.map(|d| d - 1)).collect(),
+            Component::Month => index.month().into_iter().map(|m| m.map(|m| m - 1)).collect(),
+        };
+        values.into_iter().map(|v| v.map(|v| v as usize)).collect()
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Group data points by calendar component
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::profile::Component;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// // 00:00 and 01:00 on 2 days
+    /// let ts = TimeSeries::new(vec![0, 3_600_000, 86_400_000, 90_000_000], vec![1.0, 5.0, 3.0, 7.0]);
+    /// let profile = ts.groupby_calendar(Component::HourOfDay).aggregate(Aggregation::Mean);
+    /// assert_eq!(profile.len(), 24);
+    /// assert_eq!(profile[..2], [2.0, 6.0]);
+    /// assert!(profile[2].is_nan());
+    /// ```
+    pub fn groupby_calendar(&self, component: Component) -> CalendarGroups<'_> {
+        CalendarGroups { ts: self, component }
+    }
+
+    /// Subtract the mean profile of the calendar component from each data point.
+    /// The result shows anomalies relative to the typical shape of the series.
+    /// Data points out of the datetime range get NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::profile::Component;
+    ///
+    /// // 00:00 and 01:00 on 2 days
+    /// let ts = TimeSeries::new(vec![0, 3_600_000, 86_400_000, 90_000_000], vec![1.0, 5.0, 3.0, 7.0]);
+    /// let anomalies = ts.remove_seasonal_profile(Component::HourOfDay);
+    /// assert_eq!(anomalies.values, vec![-1.0, -1.0, 1.0, 1.0]);
+    /// ```
+    pub fn remove_seasonal_profile(&self, component: Component) -> TimeSeries {
+        let profile = self.groupby_calendar(component).aggregate(Aggregation::Mean);
+        let mut output = self.clone();
+        for (v, group) in output.values.iter_mut().zip(component.groups(&self.index)) {
+            *v = group.map_or(f64::NAN, |group| *v - profile[group]);
+        }
+        output.logged(self, "remove_seasonal_profile", || vec![("component", format!("{:?}", component))])
+    }
+}
+
+impl<'a> CalendarGroups<'a> {
+
+    /// Values of data points in each group
+    pub fn values(&self) -> Vec<Vec<f64>> {
+        let mut groups = vec![vec![]; self.component.group_count()];
+        for (group, &v) in self.component.groups(&self.ts.index).into_iter().zip(&self.ts.values) {
+            if let Some(group) = group {
+                groups[group].push(v);
+            }
+        }
+        groups
+    }
+
+    /// Aggregated value of each group. NaN values are skipped and empty groups get NaN
+    /// (or 0 for Count). WeightedMean is equal to Mean.
+    pub fn aggregate(&self, aggregation: Aggregation) -> Vec<f64> {
+        let empty = if aggregation == Aggregation::Count { 0.0 } else { f64::NAN };
+        self.values().iter()
+            .map(|values| NanPolicy::Skip.aggregate(values, aggregation).unwrap_or(empty))
+            .collect()
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency::DAY;
+
+    #[test]
+    fn test_day_of_week_profile() {
+        // Monday 2020-02-10, two weeks of daily data
+        let monday = 1_581_292_800_000;
+        let index: Vec<i64> = (0..14).map(|d| monday + d * DAY).collect();
+        let values: Vec<f64> = (0..14).map(|d| d as f64).collect();
+        let ts = TimeSeries::new(index, values);
+        let groups = ts.groupby_calendar(Component::DayOfWeek);
+        assert_eq!(groups.aggregate(Aggregation::Mean), vec![3.5, 4.5, 5.5, 6.5, 7.5, 8.5, 9.5]);
+        assert_eq!(groups.aggregate(Aggregation::Count), vec![2.0; 7]);
+        assert_eq!(groups.aggregate(Aggregation::First)[0], 0.0);
+    }
+
+    #[test]
+    fn test_remove_profile_keeps_nan() {
+        let ts = TimeSeries::new(vec![0, DAY, 2 * DAY], vec![1.0, f64::NAN, 3.0]);
+        let output = ts.remove_seasonal_profile(Component::Month);
+        assert_eq!(output.index, ts.index);
+        assert_eq!(output.values[0], -1.0);
+        assert!(output.values[1].is_nan());
+        assert_eq!(output.values[2], 1.0);
+    }
+
+    #[test]
+    fn test_month_profile_skips_nan() {
+        let ts = TimeSeries::new(vec![0, DAY, 40 * DAY], vec![1.0, f64::NAN, 2.0]);
+        let profile = ts.groupby_calendar(Component::Month).aggregate(Aggregation::Sum);
+        assert_eq!(profile[..2], [1.0, 2.0]);
+        assert!(profile[2].is_nan());
+        assert_eq!(ts.groupby_calendar(Component::DayOfMonth).values()[0], vec![1.0]);
+    }
+
+    #[test]
+    fn test_profile_out_of_datetime_range() {
+        let ts = TimeSeries::new(vec![0, DAY, i64::MAX], vec![1.0, 3.0, 100.0]);
+        assert_eq!(ts.groupby_calendar(Component::HourOfDay).values()[0], vec![1.0, 3.0]);
+        let output = ts.remove_seasonal_profile(Component::HourOfDay);
+        assert_eq!(output.values[..2], [-1.0, 1.0]);
+        assert!(output.values[2].is_nan());
+    }
+}
@@ -0,0 +1,125 @@
+//! Progress reporting for long running operations
+//!
+//! Operations with `_with_progress` variant call the callback with the amount of work done
+//! and the total amount, if it is known:
+//!   * `io::csv::read_from_file_with_progress` - Bytes read from the file
+//!   * `TimeSeries::resample_with_progress` - Data points aggregated
+//!   * `backtest::rolling_origin_with_progress` - Folds evaluated
+//!
+//! Callback is called when at least 1% of the work is done (or every 10000 units if the total
+//! is not known) and always when the operation finishes.
+//!
+
+#[cfg(feature = "io")]
+use std::io::{self, Read};
+
+
+/// Callback called with the work done and the total work
+pub type Progress<'a> = &'a dyn Fn(u64, Option<u64>);
+
+/// Reporting step if the total is not known
+const UNKNOWN_TOTAL_STEP: u64 = 10_000;
+
+
+/// Calls the callback only after enough work was done since the last call
+pub(crate) struct Reporter<'a> {
+    callback: Progress<'a>,
+    total: Option<u64>,
+    step: u64,
+    next: u64,
+    done: u64,
+}
+
+/// Reader which reports number of bytes read
+#[cfg(feature = "io")]
+pub(crate) struct ProgressReader<'a, R> {
+    inner: R,
+    reporter: Reporter<'a>,
+    finished: bool,
+}
+
+
+impl<'a> Reporter<'a> {
+
+    pub(crate) fn new(callback: Progress<'a>, total: Option<u64>) -> Reporter<'a> {
+        let step = total.map_or(UNKNOWN_TOTAL_STEP, |t| (t / 100).max(1));
+        Reporter { callback, total, step, next: step, done: 0 }
+    }
+
+    pub(crate) fn update(&mut self, done: u64) {
+        self.done = done;
+        if done >= self.next {
+            (self.callback)(done, self.total);
+            self.next = done + self.step;
+        }
+    }
+
+    /// Report the final amount of work
+    pub(crate) fn finish(&self) {
+        (self.callback)(self.done, self.total);
+    }
+}
+
+#[cfg(feature = "io")]
+impl<'a, R: Read> ProgressReader<'a, R> {
+
+    pub(crate) fn new(inner: R, reporter: Reporter<'a>) -> ProgressReader<'a, R> {
+        ProgressReader { inner, reporter, finished: false }
+    }
+}
+
+#[cfg(feature = "io")]
+impl<R: Read> Read for ProgressReader<'_, R> {
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 {
+            // Reader can be called again after the end of data
+            if !self.finished {
+                self.finished = true;
+                self.reporter.finish();
+            }
+        } else {
+            self.reporter.update(self.reporter.done + n as u64);
+        }
+        Ok(n)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_reporter_step() {
+        let calls = RefCell::new(vec![]);
+        let callback = |done, total| calls.borrow_mut().push((done, total));
+        let mut reporter = Reporter::new(&callback, Some(200));
+        (1..=200).for_each(|i| reporter.update(i));
+        reporter.finish();
+        assert_eq!(calls.borrow().len(), 101);
+        assert_eq!(calls.borrow()[0], (2, Some(200)));
+        assert_eq!(calls.borrow()[100], (200, Some(200)));
+    }
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn test_reader_finishes_once() {
+        let calls = RefCell::new(vec![]);
+        let callback = |done, total| calls.borrow_mut().push((done, total));
+        let data = [0u8; 10];
+        let mut reader = ProgressReader::new(&data[..], Reporter::new(&callback, Some(10)));
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).unwrap();
+        let count = calls.borrow().len();
+        assert_eq!(reader.read(&mut [0; 4]).unwrap(), 0);
+        assert_eq!(reader.read(&mut [0; 4]).unwrap(), 0);
+        assert_eq!(calls.borrow().len(), count);
+        assert_eq!(calls.borrow().last(), Some(&(10, Some(10))));
+    }
+}
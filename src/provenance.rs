@@ -0,0 +1,143 @@
+//! Audit trail of operations applied to the series
+//!
+//! Tracking is disabled by default. After calling `with_provenance()` every transformation
+//! copies the log to its output and appends the applied operation with its parameters.
+//! The log can be serialized with serde.
+//!
+
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::TimeSeries;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Single operation with its parameters
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Operation {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+}
+
+/// List of operations in the order they were applied
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Provenance {
+    pub operations: Vec<Operation>,
+}
+
+
+impl Operation {
+
+    pub fn new(name: &str, params: &[(&str, String)]) -> Operation {
+        let params = params.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+        Operation { name: name.to_owned(), params }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<String> = self.params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        write!(f, "{}({})", self.name, params.join(", "))
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, op) in self.operations.iter().enumerate() {
+            writeln!(f, "{}. {}", i + 1, op)?;
+        }
+        Ok(())
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Start recording operations applied to this series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 5, 10, 15], vec![1.0, 3.0, 2.0, 4.0]).with_provenance();
+    /// let output = ts.diff().resample(10, Aggregation::Mean);
+    /// let log = output.provenance.unwrap().to_string();
+    /// assert_eq!(log, "1. diff()\n2. resample(frequency=Millisecond(10), aggregation=Mean)\n");
+    /// ```
+    pub fn with_provenance(mut self) -> TimeSeries {
+        if self.provenance.is_none() {
+            self.provenance = Some(Provenance::default());
+        }
+        self
+    }
+
+    /// Copy provenance log from the source series and append the operation.
+    /// Log is not changed if the source series doesn't track provenance.
+    /// Parameters are formatted only when the log is recorded.
+    pub(crate) fn logged<'a, P>(mut self, source: &TimeSeries, name: &str, params: P) -> TimeSeries
+        where P: FnOnce() -> Vec<(&'a str, String)>
+    {
+        debug_assert!(self.quality.as_ref().is_none_or(|q| q.len() == self.len()), "{} returned quality with wrong length", name);
+        if let Some(log) = &source.provenance {
+            let mut log = log.clone();
+            log.operations.push(Operation::new(name, &params()));
+            self.provenance = Some(log);
+        }
+        self
+    }
+
+    /// Append the operation which changed this series in place.
+    /// Log is not changed if the series doesn't track provenance.
+    #[cfg(feature = "std")]
+    pub(crate) fn log_inplace<'a, P>(&mut self, name: &str, params: P)
+        where P: FnOnce() -> Vec<(&'a str, String)>
+    {
+        if let Some(log) = &mut self.provenance {
+            log.operations.push(Operation::new(name, &params()));
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_by_default() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        assert!(ts.diff().head(1).provenance.is_none());
+    }
+
+    #[test]
+    fn test_params_formatted_only_when_tracked() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        let output = ts.clone().logged(&ts, "noop", || panic!("params formatted without provenance"));
+        assert!(output.provenance.is_none());
+        let ts = ts.with_provenance();
+        let output = ts.clone().logged(&ts, "noop", || vec![("x", "1".to_string())]);
+        assert_eq!(output.provenance.unwrap().to_string(), "1. noop(x=1)\n");
+    }
+
+    #[test]
+    fn test_source_is_not_modified() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]).with_provenance();
+        let _ = ts.tail(2);
+        assert!(ts.provenance.unwrap().operations.is_empty());
+    }
+
+    #[test]
+    fn test_merge_uses_first_series_log() {
+        let ts1 = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]).with_provenance().detrend();
+        let ts2 = TimeSeries::new(vec![3], vec![3.0]);
+        let merged = ts1.merge(&ts2);
+        let names: Vec<String> = merged.provenance.unwrap().operations.into_iter().map(|op| op.name).collect();
+        assert_eq!(names, vec!["detrend", "merge"]);
+    }
+}
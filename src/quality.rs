@@ -0,0 +1,180 @@
+//! Per-point data quality flags
+//!
+//! Quality is stored as u8 code for each data point. Codes below are predefined,
+//! other values can be used for application specific flags.
+//! Series without quality channel treats all points as GOOD.
+//!
+
+use crate::{TimeSeries, DataPoint};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Value is valid
+pub const GOOD: u8 = 0;
+/// Value is available but may be wrong
+pub const SUSPECT: u8 = 1;
+/// Value was replaced (e.g. interpolated or manually entered)
+pub const SUBSTITUTED: u8 = 2;
+/// Value is missing. The stored value is a placeholder
+pub const MISSING: u8 = 3;
+
+
+impl TimeSeries {
+
+    /// Attach quality codes to the series.
+    /// If there are less codes then data points, then missing codes are set to GOOD.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::quality;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0])
+    ///     .with_quality(vec![quality::GOOD, quality::SUSPECT]);
+    /// assert_eq!(ts.quality_at(1), quality::SUSPECT);
+    /// assert_eq!(ts.quality_at(2), quality::GOOD);
+    /// ```
+    pub fn with_quality(mut self, mut quality: Vec<u8>) -> TimeSeries {
+        quality.resize(self.len(), GOOD);
+        self.quality = Some(quality);
+        self
+    }
+
+    /// Quality code of the nth data point. GOOD if the series has no quality channel.
+    pub fn quality_at(&self, pos: usize) -> u8 {
+        match &self.quality {
+            Some(q) => q[pos],
+            None => GOOD,
+        }
+    }
+
+    /// Keep only data points which quality passes the predicate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::quality;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0])
+    ///     .with_quality(vec![quality::GOOD, quality::MISSING, quality::SUBSTITUTED]);
+    /// let valid = ts.filter_quality(|q| q != quality::MISSING);
+    /// assert_eq!(valid.index.values, vec![1, 3]);
+    /// ```
+    pub fn filter_quality<F>(&self, predicate: F) -> TimeSeries
+    where
+        F: Fn(u8) -> bool {
+
+        let positions: Vec<usize> = (0..self.len()).filter(|&i| predicate(self.quality_at(i))).collect();
+        self.select(&positions).logged(self, "filter_quality", Vec::new)
+    }
+
+    /// Keep only data points which pass the predicate.
+    /// Data points have quality code if the series has quality channel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::quality;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 20.0, 30.0])
+    ///     .with_quality(vec![quality::GOOD, quality::SUSPECT, quality::GOOD]);
+    /// let valid = ts.filter(|dp| dp.value > 10.0 && dp.quality == Some(quality::GOOD));
+    /// assert_eq!(valid.index.values, vec![3]);
+    /// ```
+    pub fn filter<F>(&self, predicate: F) -> TimeSeries
+    where
+        F: Fn(&DataPoint) -> bool {
+
+        let positions: Vec<usize> = self.iter().enumerate()
+            .filter(|(_, dp)| predicate(dp))
+            .map(|(i, _)| i)
+            .collect();
+        self.select(&positions).logged(self, "filter", Vec::new)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flagged() -> TimeSeries {
+        TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 4.0, 8.0])
+            .with_quality(vec![GOOD, SUSPECT, GOOD, MISSING])
+    }
+
+    #[test]
+    fn test_head_tail_keep_quality() {
+        let ts = flagged();
+        assert_eq!(ts.head(2).quality, Some(vec![GOOD, SUSPECT]));
+        assert_eq!(ts.tail(1).quality, Some(vec![MISSING]));
+    }
+
+    #[test]
+    fn test_diff_takes_worst_quality() {
+        assert_eq!(flagged().diff().quality, Some(vec![SUSPECT, SUSPECT, MISSING]));
+    }
+
+    #[test]
+    fn test_merge_keeps_quality() {
+        let ts1 = flagged();
+        let ts2 = TimeSeries::new(vec![0, 5], vec![0.0, 5.0]);
+        let merged = ts1.merge(&ts2);
+        assert_eq!(merged.quality, Some(vec![GOOD, GOOD, SUSPECT, GOOD, MISSING, GOOD]));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_quality_length_invariant() {
+        use crate::ToSeries;
+        use crate::extrapolation::Extrapolation;
+        use crate::index::{Keep, Resolution};
+        use crate::merge::OverlapPolicy;
+        use crate::nan::NanPolicy;
+        use crate::resample::{Aggregation, FillMethod};
+
+        let ts = TimeSeries::new(vec![1000, 2000, 2000, 5000, 9000, 10_000], vec![1.0, f64::NAN, 3.0, 2.0, 8.0, 4.0])
+            .with_quality(vec![GOOD, SUSPECT, MISSING, GOOD, SUBSTITUTED, GOOD]);
+        let other = TimeSeries::new(vec![2000, 6000, 12_000], vec![1.0, 2.0, 3.0]).with_quality(vec![SUSPECT, GOOD, MISSING]);
+        let plain = TimeSeries::new(vec![3000, 4000], vec![5.0, 6.0]);
+        let mut outputs = vec![
+            ts.diff(), ts.rate(1000), ts.derivative(), ts.second_derivative(), ts.head(2), ts.tail(2),
+            ts.truncate_before(2000), ts.sample(3, 1), ts.to_resolution(Resolution::Second), ts.sort_by_time(),
+            ts.drop_duplicates(Keep::First), ts.merge(&other), ts.merge(&plain), plain.merge(&ts),
+            ts.merge_with(&other, Aggregation::Sum), ts.merge_by(&other, |a, b| a + b),
+            ts.resample(4000, Aggregation::Mean), ts.upsample(500, FillMethod::Ffill), ts.align_to(&other, FillMethod::Interpolate),
+            ts.resample_weighted(4000, &other, Aggregation::WeightedMean),
+            ts.rolling_mean(2), ts.rolling_quantile(3, 0.5), ts.ewma(3), ts.asof_join(&other, 1000),
+            ts.drop_repeats(), ts.paa(2), ts.detrend(), ts.median_filter(3), ts.filter(|dp| dp.value > 1.0),
+            ts.filter_quality(|q| q == GOOD), ts.range(2000..9000).to_series(),
+            ts.sample_at(&[0, 1500, 20_000], Extrapolation::Zero).unwrap(),
+            ts.combine(&other, NanPolicy::Skip, |a, b| a + b).unwrap(), &ts + &other, &ts * 2.0,
+            ts.concat(&other, OverlapPolicy::KeepSelf).unwrap(), ts.concat(&other, OverlapPolicy::KeepOther).unwrap(),
+            ts.concat(&other, OverlapPolicy::Merge(Aggregation::Max)).unwrap(), plain.concat(&other, OverlapPolicy::KeepSelf).unwrap(),
+        ];
+        outputs.extend(ts.split_on_gaps(2000));
+        let (before, after) = ts.split_at_time(5000);
+        outputs.extend([before, after]);
+        let mut inplace = ts.clone();
+        inplace += 1.0;
+        inplace.apply_inplace(|v| v * 2.0);
+        outputs.push(inplace);
+        for (i, output) in outputs.iter().enumerate() {
+            assert!(output.quality.as_ref().is_none_or(|q| q.len() == output.len()), "Output {} has wrong quality length", i);
+        }
+    }
+
+    #[test]
+    fn test_no_quality() {
+        let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+        assert_eq!(ts.diff().quality, None);
+        assert_eq!(ts.filter_quality(|q| q == GOOD).len(), 2);
+    }
+}
@@ -0,0 +1,109 @@
+//! Point-wise reduction of many series into one
+//!
+//! Series are first aligned to a common index with `AlignPolicy`, and then
+//! values at each timestamp are aggregated. NaN values are skipped.
+//!
+
+use std::borrow::Cow;
+
+use crate::TimeSeries;
+use crate::nan::NanPolicy;
+use crate::resample::{Aggregation, FillMethod};
+
+
+/// How series are aligned before aggregation
+///   * Union - Timestamps from any series. Series without data point at the timestamp are skipped
+///   * Intersection - Only timestamps present in all series
+///   * Fill(method) - Timestamps from any series. Missing values are filled with the given method
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlignPolicy {
+    Union,
+    Intersection,
+    Fill(FillMethod),
+}
+
+
+/// Aggregate values of all series at each timestamp.
+/// Timestamps where all values are missing get NaN.
+/// All series are converted to the resolution of the first series.
+///
+/// # Example
+///
+/// ```
+/// use timeseries::TimeSeries;
+/// use timeseries::reduce::{aggregate_series, AlignPolicy};
+/// use timeseries::resample::{Aggregation, FillMethod};
+///
+/// let host1 = TimeSeries::new(vec![0, 10, 20], vec![1.0, 2.0, 3.0]);
+/// let host2 = TimeSeries::new(vec![0, 15], vec![10.0, 20.0]);
+/// let series = [host1, host2];
+///
+/// let total = aggregate_series(&series, Aggregation::Sum, AlignPolicy::Union);
+/// assert_eq!(total, TimeSeries::new(vec![0, 10, 15, 20], vec![11.0, 2.0, 20.0, 3.0]));
+///
+/// let total = aggregate_series(&series, Aggregation::Sum, AlignPolicy::Intersection);
+/// assert_eq!(total, TimeSeries::new(vec![0], vec![11.0]));
+///
+/// let total = aggregate_series(&series, Aggregation::Sum, AlignPolicy::Fill(FillMethod::Ffill));
+/// assert_eq!(total, TimeSeries::new(vec![0, 10, 15, 20], vec![11.0, 12.0, 22.0, 23.0]));
+/// ```
+pub fn aggregate_series(series: &[TimeSeries], aggregation: Aggregation, policy: AlignPolicy) -> TimeSeries {
+    let refs: Vec<&TimeSeries> = series.iter().collect();
+    aggregate_refs(&refs, aggregation, policy)
+}
+
+pub(crate) fn aggregate_refs(series: &[&TimeSeries], aggregation: Aggregation, policy: AlignPolicy) -> TimeSeries {
+    let resolution = series.first().map(|ts| ts.index.resolution).unwrap_or_default();
+    let series: Vec<Cow<TimeSeries>> = series.iter().map(|ts| ts.in_resolution(resolution)).collect();
+    let mut index: Vec<i64> = series.iter().flat_map(|ts| ts.index.iter().cloned()).collect();
+    index.sort_unstable();
+    index.dedup();
+    if policy == AlignPolicy::Intersection {
+        index.retain(|t| series.iter().all(|ts| ts.index.values.binary_search(t).is_ok()));
+    }
+    let method = match policy {
+        AlignPolicy::Fill(method) => method,
+        _ => FillMethod::Nan,
+    };
+    let columns: Vec<Vec<f64>> = series.iter().map(|ts| ts.fill_values(&index, method)).collect();
+    let values = (0..index.len())
+        .map(|row| {
+            let row_values: Vec<f64> = columns.iter().map(|c| c[row]).collect();
+            NanPolicy::Skip.aggregate(&row_values, aggregation).unwrap_or(f64::NAN)
+        })
+        .collect();
+    let mut output = TimeSeries::new(index, values);
+    output.index.resolution = resolution;
+    output
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        assert!(aggregate_series(&[], Aggregation::Sum, AlignPolicy::Union).is_empty());
+        let series = [TimeSeries::new(vec![1], vec![1.0]), TimeSeries::empty()];
+        assert!(aggregate_series(&series, Aggregation::Sum, AlignPolicy::Intersection).is_empty());
+    }
+
+    #[test]
+    fn test_mean_min_max() {
+        let series = [TimeSeries::new(vec![1, 2], vec![1.0, 4.0]), TimeSeries::new(vec![1, 2], vec![3.0, f64::NAN])];
+        assert_eq!(aggregate_series(&series, Aggregation::Mean, AlignPolicy::Union).values, vec![2.0, 4.0]);
+        assert_eq!(aggregate_series(&series, Aggregation::Min, AlignPolicy::Union).values, vec![1.0, 4.0]);
+        assert_eq!(aggregate_series(&series, Aggregation::Max, AlignPolicy::Union).values, vec![3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let series = [TimeSeries::new(vec![0, 10], vec![0.0, 10.0]), TimeSeries::new(vec![5], vec![1.0])];
+        let total = aggregate_series(&series, Aggregation::Sum, AlignPolicy::Fill(FillMethod::Interpolate));
+        assert_eq!(total.values, vec![0.0, 6.0, 10.0]);
+    }
+}
@@ -0,0 +1,527 @@
+//! Resampling and grouping series by time periods
+//!
+//! Periods are given by `Frequency`. Plain number of milliseconds can be used
+//! for fixed periods aligned to the epoch.
+//!
+
+use core::ops::Range;
+
+use crate::TimeSeries;
+use crate::extrapolation::Extrapolation;
+use crate::frequency::Frequency;
+use crate::index::DateTimeIndex;
+use crate::nan::{NanError, NanPolicy};
+use crate::progress::{Progress, Reporter};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Function used to calculate single value from all data points in the bucket
+///   * Mean - Arithmetic mean
+///   * WeightedMean - Mean weighted by companion weight series (see `resample_weighted`)
+///   * Sum - Sum of values
+///   * Min - Minimum value
+///   * Max - Maximum value
+///   * First - First value in the bucket
+///   * Last - Last value in the bucket
+///   * Count - Number of data points
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Aggregation {
+    Mean,
+    WeightedMean,
+    Sum,
+    Min,
+    Max,
+    First,
+    Last,
+    Count,
+}
+
+/// How values are filled at new timestamps
+///   * Ffill - Value of the last data point before the timestamp
+///   * Interpolate - Linear interpolation between the surrounding data points
+///   * Nan - f64::NAN
+///
+/// Timestamps which are already in the series keep their value.
+/// Timestamps before the first data point get NaN. Timestamps after the last data point
+/// get NaN, except for Ffill which keeps the last value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillMethod {
+    Ffill,
+    Interpolate,
+    Nan,
+}
+
+
+impl TimeSeries {
+
+    /// Resample series into buckets of the given frequency.
+    /// Buckets are labeled with their start timestamp (see `Frequency::floor`).
+    /// Empty buckets are skipped. WeightedMean without weights is equal to Mean.
+    /// NaN values are ignored and bucket without other values gets NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 5, 10, 15, 25], vec![1.0, 3.0, 2.0, 4.0, 5.0]);
+    /// let expected = TimeSeries::new(vec![0, 10, 20], vec![2.0, 3.0, 5.0]);
+    /// assert_eq!(ts.resample(10, Aggregation::Mean), expected);
+    /// ```
+    pub fn resample<F: Into<Frequency>>(&self, frequency: F, aggregation: Aggregation) -> TimeSeries {
+        let frequency = frequency.into();
+        let weights = vec![1.0; self.len()];
+        self.resample_with_weights(frequency, &weights, aggregation, &|_, _| ())
+            .logged(self, "resample", || vec![("frequency", format!("{:?}", frequency)), ("aggregation", format!("{:?}", aggregation))])
+    }
+
+    /// Resample series and report the number of aggregated data points (see `resample`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 5, 10, 15, 25], vec![1.0, 3.0, 2.0, 4.0, 5.0]);
+    /// let last = Cell::new((0, None));
+    /// let resampled = ts.resample_with_progress(10, Aggregation::Mean, &|done, total| last.set((done, total)));
+    /// assert_eq!(resampled, ts.resample(10, Aggregation::Mean));
+    /// assert_eq!(last.get(), (5, Some(5)));
+    /// ```
+    pub fn resample_with_progress<F>(&self, frequency: F, aggregation: Aggregation, progress: Progress) -> TimeSeries
+    where
+        F: Into<Frequency> {
+
+        let frequency = frequency.into();
+        let weights = vec![1.0; self.len()];
+        self.resample_with_weights(frequency, &weights, aggregation, progress)
+            .logged(self, "resample", || vec![("frequency", format!("{:?}", frequency)), ("aggregation", format!("{:?}", aggregation))])
+    }
+
+    /// Resample series using weights from companion series.
+    /// Weight of each data point is the weight series value at its timestamp
+    /// (last known weight, see `at_with` with `Extrapolation::NearestValue`).
+    /// Weights are only used by `Aggregation::WeightedMean`.
+    /// Bucket with total weight 0 gets NaN value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 5, 10, 15], vec![1.0, 3.0, 2.0, 4.0]);
+    /// let quality = TimeSeries::new(vec![0, 5, 10], vec![3.0, 1.0, 0.0]);
+    /// let resampled = ts.resample_weighted(10, &quality, Aggregation::WeightedMean);
+    /// assert_eq!(resampled.values[0], 1.5);
+    /// assert!(resampled.values[1].is_nan());
+    /// ```
+    pub fn resample_weighted<F>(&self, frequency: F, weights: &TimeSeries, aggregation: Aggregation) -> TimeSeries
+    where
+        F: Into<Frequency> {
+
+        let frequency = frequency.into();
+        let weights = weights.in_resolution(self.index.resolution);
+        let ws: Vec<f64> = self.index.iter()
+            .map(|&ts| weights.at_with(ts, Extrapolation::NearestValue).unwrap_or(0.0))
+            .collect();
+        self.resample_with_weights(frequency, &ws, aggregation, &|_, _| ())
+            .logged(self, "resample_weighted", || vec![("frequency", format!("{:?}", frequency)), ("aggregation", format!("{:?}", aggregation))])
+    }
+
+    /// Resample series with the given NaN policy (see `resample`).
+    /// Bucket without values (after skipping NaN values) gets NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::nan::NanPolicy;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 5, 10], vec![1.0, f64::NAN, 2.0]);
+    /// let resampled = ts.resample_with_policy(10, Aggregation::Mean, NanPolicy::Skip).unwrap();
+    /// assert_eq!(resampled.values, vec![1.0, 2.0]);
+    /// ```
+    pub fn resample_with_policy<F>(&self, frequency: F, aggregation: Aggregation, policy: NanPolicy) -> Result<TimeSeries, NanError>
+    where
+        F: Into<Frequency> {
+
+        policy.check(&self.values)?;
+        let frequency = frequency.into();
+        let (index, values) = self.buckets(frequency).into_iter()
+            .map(|(start, range)| (start, policy.aggregate(&self.values[range], aggregation).unwrap_or(f64::NAN)))
+            .unzip();
+        Ok(self.derived(index, values)
+            .logged(self, "resample_with_policy", || vec![
+                ("frequency", format!("{:?}", frequency)),
+                ("aggregation", format!("{:?}", aggregation)),
+                ("policy", format!("{:?}", policy)),
+            ]))
+    }
+
+    /// Resample series using custom aggregation function.
+    /// The function gets values and timestamps of the data points in each bucket.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 5, 10, 15, 25], vec![1.0, 3.0, 2.0, 4.0, 5.0]);
+    /// // Time of the last data point in each bucket
+    /// let resampled = ts.resample_with(10, |_, index| index[index.len()-1] as f64);
+    /// assert_eq!(resampled.values, vec![5.0, 15.0, 25.0]);
+    /// ```
+    pub fn resample_with<F, G>(&self, frequency: F, f: G) -> TimeSeries
+    where
+        F: Into<Frequency>,
+        G: Fn(&[f64], &[i64]) -> f64 {
+
+        let frequency = frequency.into();
+        let (index, values) = self.buckets(frequency).into_iter()
+            .map(|(start, range)| (start, f(&self.values[range.clone()], &self.index.values[range])))
+            .unzip();
+        self.derived(index, values)
+            .logged(self, "resample_with", || vec![("frequency", format!("{:?}", frequency))])
+    }
+
+    /// Convert series to a denser regular index.
+    /// New index starts at the first data point and steps with the given frequency
+    /// up to the last data point.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::FillMethod;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 20], vec![1.0, 3.0]);
+    /// assert_eq!(ts.upsample(5, FillMethod::Ffill).values, vec![1.0, 1.0, 1.0, 1.0, 3.0]);
+    /// assert_eq!(ts.upsample(5, FillMethod::Interpolate).values, vec![1.0, 1.5, 2.0, 2.5, 3.0]);
+    /// ```
+    pub fn upsample<F: Into<Frequency>>(&self, frequency: F, method: FillMethod) -> TimeSeries {
+        let frequency = frequency.into();
+        let index = match (self.index.first(), self.index.last()) {
+            (Some(start), Some(end)) => DateTimeIndex::date_range_in(start, end, frequency, self.index.resolution),
+            _ => DateTimeIndex::new(vec![]),
+        };
+        let values = self.fill_values(&index.values, method);
+        self.derived(index.values, values)
+            .logged(self, "upsample", || vec![("frequency", format!("{:?}", frequency)), ("method", format!("{:?}", method))])
+    }
+
+    /// Reindex series onto the index of the other series.
+    /// The result has exactly the same index as the other series, so both series
+    /// can be used together in pairwise operations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::FillMethod;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 10, 20], vec![1.0, 2.0, 4.0]);
+    /// let other = TimeSeries::new(vec![5, 10, 15], vec![0.0, 0.0, 0.0]);
+    /// let aligned = ts.align_to(&other, FillMethod::Interpolate);
+    /// assert_eq!(aligned.index, other.index);
+    /// assert_eq!(aligned.values, vec![1.5, 2.0, 3.0]);
+    /// ```
+    pub fn align_to(&self, other: &TimeSeries, method: FillMethod) -> TimeSeries {
+        let values = self.in_resolution(other.index.resolution).fill_values(&other.index.values, method);
+        let mut output = TimeSeries::new(other.index.values.clone(), values);
+        output.index.resolution = other.index.resolution;
+        output.metadata = self.metadata.clone();
+        output.logged(self, "align_to", || vec![("other_len", other.len().to_string()), ("method", format!("{:?}", method))])
+    }
+
+    /// Values at the given timestamps calculated with the fill method
+    pub(crate) fn fill_values(&self, timestamps: &[i64], method: FillMethod) -> Vec<f64> {
+        timestamps.iter().map(|&ts| {
+            let pos = self.index.values.partition_point(|&t| t <= ts);
+            if pos > 0 && self.index[pos-1] == ts {
+                return self.values[pos-1];
+            }
+            match method {
+                FillMethod::Ffill if pos > 0 => self.values[pos-1],
+                FillMethod::Interpolate if pos > 0 && pos < self.len() => {
+                    let (t0, t1) = (self.index[pos-1], self.index[pos]);
+                    let (v0, v1) = (self.values[pos-1], self.values[pos]);
+                    v0 + (v1 - v0) * (ts - t0) as f64 / (t1 - t0) as f64
+                },
+                _ => f64::NAN,
+            }
+        }).collect()
+    }
+
+    /// Split series into groups of data points from the same period.
+    /// Each group is labeled with the start of its period. Empty periods are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::frequency::Frequency;
+    ///
+    /// // 2020-01-15, 2020-01-20, 2020-02-10
+    /// let ts = TimeSeries::new(vec![1_579_046_400_000, 1_579_478_400_000, 1_581_292_800_000], vec![1.0, 2.0, 3.0]);
+    /// let groups = ts.group_by(Frequency::Month);
+    /// assert_eq!(groups.len(), 2);
+    /// // 2020-02-01
+    /// assert_eq!(groups[1].0, 1_580_515_200_000);
+    /// assert_eq!(groups[0].1.values, vec![1.0, 2.0]);
+    /// ```
+    pub fn group_by<F: Into<Frequency>>(&self, frequency: F) -> Vec<(i64, TimeSeries)> {
+        self.buckets(frequency.into()).into_iter()
+            .map(|(start, range)| (start, self.select(&range.collect::<Vec<usize>>())))
+            .collect()
+    }
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(len = self.len(), ?frequency, ?aggregation)))]
+    fn resample_with_weights(&self, frequency: Frequency, weights: &[f64], aggregation: Aggregation, progress: Progress)
+        -> TimeSeries {
+
+        let mut reporter = Reporter::new(progress, Some(self.len() as u64));
+        let (index, values) = self.buckets(frequency).into_iter()
+            .map(|(start, range)| {
+                reporter.update(range.end as u64);
+                (start, aggregate(&self.values[range.clone()], &weights[range], aggregation))
+            })
+            .unzip();
+        reporter.finish();
+        self.derived(index, values)
+    }
+
+    /// Start of each non empty period with the range of data points in it
+    fn buckets(&self, frequency: Frequency) -> Vec<(i64, Range<usize>)> {
+        let mut buckets = vec![];
+        if !frequency.is_valid() {
+            return buckets;
+        }
+        let mut start = 0;
+        while start < self.len() {
+            let bucket = frequency.floor_in(self.index[start], self.index.resolution);
+            let mut end = start + 1;
+            while end < self.len() && frequency.floor_in(self.index[end], self.index.resolution) == bucket {
+                end += 1;
+            }
+            buckets.push((bucket, start..end));
+            start = end;
+        }
+        buckets
+    }
+}
+
+
+/// Aggregate bucket with the default NaN policy. NaN if there are no values.
+fn aggregate(values: &[f64], weights: &[f64], aggregation: Aggregation) -> f64 {
+    match aggregation {
+        Aggregation::WeightedMean => {
+            let (total, sum) = values.iter().zip(weights)
+                .filter(|(v, _)| !v.is_nan())
+                .fold((0.0, 0.0), |(total, sum), (v, w)| (total + w, sum + v * w));
+            if total == 0.0 { f64::NAN } else { sum / total }
+        },
+        _ => NanPolicy::default().aggregate(values, aggregation).unwrap_or(f64::NAN),
+    }
+}
+
+/// Statistics of a group of data points which can be merged with the next group.
+/// Used to answer aggregation queries from pre-aggregated data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct BucketStats {
+    count: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+    first: f64,
+    last: f64,
+}
+
+impl BucketStats {
+
+    pub(crate) fn empty() -> BucketStats {
+        BucketStats { count: 0, sum: 0.0, min: f64::NAN, max: f64::NAN, first: f64::NAN, last: f64::NAN }
+    }
+
+    pub(crate) fn from_values(values: &[f64]) -> BucketStats {
+        values.iter().fold(BucketStats::empty(), |acc, &v| acc.merge(&BucketStats::single(v)))
+    }
+
+    pub(crate) fn single(value: f64) -> BucketStats {
+        BucketStats { count: 1, sum: value, min: value, max: value, first: value, last: value }
+    }
+
+    /// Statistics of both groups. The other group should contain later data points.
+    pub(crate) fn merge(&self, other: &BucketStats) -> BucketStats {
+        if self.count == 0 {
+            return *other;
+        }
+        if other.count == 0 {
+            return *self;
+        }
+        BucketStats {
+            count: self.count + other.count,
+            sum: self.sum + other.sum,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            first: self.first,
+            last: other.last,
+        }
+    }
+
+    /// Aggregated value. None for empty group. WeightedMean is calculated as Mean.
+    pub(crate) fn value(&self, aggregation: Aggregation) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let value = match aggregation {
+            Aggregation::Mean | Aggregation::WeightedMean => self.sum / self.count as f64,
+            Aggregation::Sum => self.sum,
+            Aggregation::Min => self.min,
+            Aggregation::Max => self.max,
+            Aggregation::First => self.first,
+            Aggregation::Last => self.last,
+            Aggregation::Count => self.count as f64,
+        };
+        Some(value)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::Resolution;
+
+    #[test]
+    fn test_resample_empty() {
+        assert!(TimeSeries::empty().resample(10, Aggregation::Sum).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_frequency() {
+        let ts = TimeSeries::new(vec![0, 5, 10], vec![1.0, 2.0, 3.0]);
+        assert!(ts.resample(0, Aggregation::Sum).is_empty());
+        assert!(ts.resample(-10, Aggregation::Sum).is_empty());
+        assert!(ts.upsample(0, FillMethod::Ffill).is_empty());
+        assert!(ts.upsample(-5, FillMethod::Ffill).is_empty());
+    }
+
+    #[test]
+    fn test_negative_timestamps() {
+        let ts = TimeSeries::new(vec![-15, -5, 5], vec![1.0, 2.0, 3.0]);
+        let resampled = ts.resample(10, Aggregation::Count);
+        assert_eq!(resampled, TimeSeries::new(vec![-20, -10, 0], vec![1.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_aggregations() {
+        let ts = TimeSeries::new(vec![0, 1, 2], vec![2.0, 1.0, 3.0]);
+        assert_eq!(ts.resample(10, Aggregation::Sum).values, vec![6.0]);
+        assert_eq!(ts.resample(10, Aggregation::Min).values, vec![1.0]);
+        assert_eq!(ts.resample(10, Aggregation::Max).values, vec![3.0]);
+        assert_eq!(ts.resample(10, Aggregation::First).values, vec![2.0]);
+        assert_eq!(ts.resample(10, Aggregation::Last).values, vec![3.0]);
+        assert_eq!(ts.resample(10, Aggregation::WeightedMean).values, vec![2.0]);
+    }
+
+    #[test]
+    fn test_weights_ignored_by_mean() {
+        let ts = TimeSeries::new(vec![0, 1], vec![2.0, 4.0]);
+        let weights = TimeSeries::new(vec![0, 1], vec![1.0, 0.0]);
+        assert_eq!(ts.resample_weighted(10, &weights, Aggregation::Mean).values, vec![3.0]);
+        assert_eq!(ts.resample_weighted(10, &weights, Aggregation::WeightedMean).values, vec![2.0]);
+    }
+
+    #[test]
+    fn test_resample_with_time_weighted_mean() {
+        let ts = TimeSeries::new(vec![0, 2, 10, 11], vec![1.0, 4.0, 2.0, 8.0]);
+        let resampled = ts.resample_with(10, |values, index| {
+            TimeSeries::new(index.to_vec(), values.to_vec()).time_weighted_mean().unwrap_or(values[0])
+        });
+        assert_eq!(resampled, TimeSeries::new(vec![0, 10], vec![1.0, 2.0]));
+    }
+
+    #[test]
+    fn test_upsample_hourly_to_5_minutes() {
+        let hour = 3_600_000;
+        let ts = TimeSeries::new(vec![0, hour, 2 * hour], vec![0.0, 12.0, 6.0]);
+        let upsampled = ts.upsample(Frequency::Minute(5), FillMethod::Interpolate);
+        assert_eq!(upsampled.len(), 25);
+        assert_eq!(upsampled.values[1], 1.0);
+        assert_eq!(upsampled.values[18], 9.0);
+        let nan = ts.upsample(Frequency::Minute(5), FillMethod::Nan);
+        assert_eq!(nan.values.iter().filter(|v| !v.is_nan()).count(), 3);
+        assert!(TimeSeries::empty().upsample(5, FillMethod::Ffill).is_empty());
+    }
+
+    #[test]
+    fn test_fill_outside_range() {
+        let ts = TimeSeries::new(vec![10, 20], vec![1.0, 2.0]);
+        let values = ts.fill_values(&[5, 25], FillMethod::Ffill);
+        assert!(values[0].is_nan());
+        assert_eq!(values[1], 2.0);
+        assert!(ts.fill_values(&[25], FillMethod::Interpolate)[0].is_nan());
+    }
+
+    #[test]
+    fn test_align_to_then_combine() {
+        let ts = TimeSeries::new(vec![0, 10, 20], vec![1.0, 2.0, 3.0]);
+        let other = TimeSeries::new(vec![-5, 12, 30], vec![1.0, 1.0, 1.0]);
+        let aligned = ts.align_to(&other, FillMethod::Ffill);
+        assert_eq!(aligned.index, other.index);
+        assert!(aligned.values[0].is_nan());
+        assert_eq!(aligned.values[1..], [2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_bucket_stats_same_as_aggregate() {
+        let values = [2.0, 1.0, f64::NAN, 3.0];
+        // Resample skips NaN values
+        let stats = BucketStats::from_values(&values[..2]).merge(&BucketStats::from_values(&values[3..]));
+        for &aggregation in &[Aggregation::Mean, Aggregation::Sum, Aggregation::Min, Aggregation::Max,
+                              Aggregation::First, Aggregation::Last, Aggregation::Count] {
+            assert_eq!(stats.value(aggregation), Some(aggregate(&values, &[1.0; 4], aggregation)));
+        }
+        assert!(BucketStats::from_values(&values).value(Aggregation::Sum).unwrap().is_nan());
+        assert_eq!(BucketStats::empty().value(Aggregation::Count), None);
+    }
+
+    #[test]
+    fn test_monthly_resample() {
+        // 2020-01-31 and 2020-02-29 with two points each
+        let day = 86_400_000;
+        let ts = TimeSeries::new(
+            vec![1_580_428_800_000, 1_580_428_800_000 + day / 2, 1_582_934_400_000, 1_582_934_400_000 + day / 2],
+            vec![1.0, 3.0, 5.0, 7.0]);
+        let resampled = ts.resample(Frequency::Month, Aggregation::Sum);
+        // 2020-01-01 and 2020-02-01
+        assert_eq!(resampled, TimeSeries::new(vec![1_577_836_800_000, 1_580_515_200_000], vec![4.0, 12.0]));
+    }
+
+    #[test]
+    fn test_nanosecond_resolution() {
+        let ms = TimeSeries::new(vec![0, 1_500, 2_000, 4_000], vec![1.0, 3.0, 2.0, 4.0]);
+        let ns = ms.to_resolution(Resolution::Nanosecond);
+        for frequency in [Frequency::Second(1), Frequency::Minute(1), Frequency::Month] {
+            assert_eq!(ns.resample(frequency, Aggregation::Sum), ms.resample(frequency, Aggregation::Sum).to_resolution(Resolution::Nanosecond));
+        }
+        assert_eq!(ns.upsample(Frequency::Second(1), FillMethod::Ffill),
+                   ms.upsample(Frequency::Second(1), FillMethod::Ffill).to_resolution(Resolution::Nanosecond));
+        assert_eq!(ns.group_by(Frequency::Second(2))[1].0, 2_000_000_000);
+        // Steps shorter than one second don't repeat timestamps
+        let seconds = ms.to_resolution(Resolution::Second);
+        assert_eq!(seconds.upsample(500, FillMethod::Ffill).index.values, vec![0, 1, 2, 3, 4]);
+        let aligned = ms.align_to(&ns, FillMethod::Nan);
+        assert_eq!(aligned.index, ns.index);
+        assert_eq!(aligned.values, ms.values);
+    }
+}
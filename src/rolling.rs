@@ -0,0 +1,510 @@
+//! Moving window statistics
+//!
+//! Windows are defined by the number of data points and end at the current data point.
+//! Data points without full window get NaN value, so the output has the same index as the input.
+//!
+//! Time based windows (`rolling_time`) contain data points from the last `window` milliseconds
+//! up to and including the current data point. They are better suited for irregular series.
+//!
+
+use core::cmp::{Ordering, Reverse};
+use alloc::collections::BinaryHeap;
+
+use crate::TimeSeries;
+use crate::nan::{NanError, NanPolicy};
+use crate::resample::Aggregation;
+use crate::stats::{nan_mean, nan_std};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+impl TimeSeries {
+
+    /// Apply function to each window of the given size.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 3.0, 4.0]);
+    /// let sums = ts.rolling_apply(2, |xs| xs.iter().sum());
+    /// assert!(sums.values[0].is_nan());
+    /// assert_eq!(sums.values[1..], [3.0, 5.0, 7.0]);
+    /// ```
+    pub fn rolling_apply<F>(&self, window: usize, f: F) -> TimeSeries
+    where
+        F: Fn(&[f64]) -> f64 {
+
+        self.rolling(window, f).logged(self, "rolling_apply", || vec![("window", window.to_string())])
+    }
+
+    /// Moving average. NaN values are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 6.0]);
+    /// assert_eq!(ts.rolling_mean(2).values[1..], [1.5, 4.0]);
+    /// ```
+    pub fn rolling_mean(&self, window: usize) -> TimeSeries {
+        self.rolling(window, nan_mean).logged(self, "rolling_mean", || vec![("window", window.to_string())])
+    }
+
+    /// Moving sample standard deviation. NaN values are ignored.
+    pub fn rolling_std(&self, window: usize) -> TimeSeries {
+        self.rolling(window, nan_std)
+            .logged(self, "rolling_std", || vec![("window", window.to_string())])
+    }
+
+    /// Moving minimum. NaN values are ignored.
+    pub fn rolling_min(&self, window: usize) -> TimeSeries {
+        self.rolling(window, |xs| NanPolicy::default().aggregate(xs, Aggregation::Min).unwrap_or(f64::NAN))
+            .logged(self, "rolling_min", || vec![("window", window.to_string())])
+    }
+
+    /// Moving maximum. NaN values are ignored.
+    pub fn rolling_max(&self, window: usize) -> TimeSeries {
+        self.rolling(window, |xs| NanPolicy::default().aggregate(xs, Aggregation::Max).unwrap_or(f64::NAN))
+            .logged(self, "rolling_max", || vec![("window", window.to_string())])
+    }
+
+    /// Moving aggregation with the given NaN policy.
+    /// Window without values (after skipping NaN values) gets NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::nan::NanPolicy;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, f64::NAN, 3.0]);
+    /// let sums = ts.rolling_aggregate(2, Aggregation::Sum, NanPolicy::Skip).unwrap();
+    /// assert_eq!(sums.values[1..], [1.0, 3.0]);
+    /// ```
+    pub fn rolling_aggregate(&self, window: usize, aggregation: Aggregation, policy: NanPolicy) -> Result<TimeSeries, NanError> {
+        policy.check(&self.values)?;
+        Ok(self.rolling(window, |xs| policy.aggregate(xs, aggregation).unwrap_or(f64::NAN))
+            .logged(self, "rolling_aggregate", || vec![
+                ("window", window.to_string()),
+                ("aggregation", format!("{:?}", aggregation)),
+                ("policy", format!("{:?}", policy)),
+            ]))
+    }
+
+    /// Moving quantile with linear interpolation between data points.
+    /// q should be in the range [0, 1]. NaN values are ignored.
+    ///
+    /// Windows are kept in two heaps, so each step takes O(log window) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![4.0, 1.0, 3.0, 2.0]);
+    /// assert_eq!(ts.rolling_quantile(3, 0.25).values[2..], [2.0, 1.5]);
+    /// ```
+    pub fn rolling_quantile(&self, window: usize, q: f64) -> TimeSeries {
+        let q = q.clamp(0.0, 1.0);
+        let mut output = self.clone();
+        output.values = if window == 0 || window > self.len() {
+            vec![f64::NAN; self.len()]
+        } else {
+            let mut heaps = QuantileHeaps::new(q);
+            let mut values = Vec::with_capacity(self.len());
+            for (pos, &v) in self.values.iter().enumerate() {
+                heaps.insert(v, pos);
+                if pos >= window {
+                    heaps.remove(self.values[pos - window], pos - window);
+                }
+                values.push(if pos + 1 < window { f64::NAN } else { heaps.quantile() });
+            }
+            values
+        };
+        output.logged(self, "rolling_quantile", || vec![("window", window.to_string()), ("q", q.to_string())])
+    }
+
+    /// Moving median. NaN values are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 100.0, 2.0, 3.0]);
+    /// assert_eq!(ts.rolling_median(3).values[2..], [2.0, 3.0]);
+    /// ```
+    pub fn rolling_median(&self, window: usize) -> TimeSeries {
+        self.rolling_quantile(window, 0.5)
+    }
+
+    /// Exponentially weighted moving average with smoothing factor `2 / (span + 1)`.
+    /// The first output value is equal to the first data point.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 4.0, 4.0]);
+    /// assert_eq!(ts.ewma(3).values, vec![1.0, 2.5, 3.25]);
+    /// ```
+    pub fn ewma(&self, span: usize) -> TimeSeries {
+        let alpha = 2.0 / (span as f64 + 1.0);
+        let mut output = self.clone();
+        output.values = ewma(&self.values, alpha);
+        output.logged(self, "ewma", || vec![("span", span.to_string())])
+    }
+
+    fn rolling<F>(&self, window: usize, f: F) -> TimeSeries
+    where
+        F: Fn(&[f64]) -> f64 {
+
+        let mut output = self.clone();
+        output.values = if window == 0 || window > self.len() {
+            vec![f64::NAN; self.len()]
+        } else {
+            let mut values = vec![f64::NAN; window - 1];
+            values.extend(self.values.windows(window).map(f));
+            values
+        };
+        output
+    }
+}
+
+
+/// Time based moving windows. Created with `TimeSeries::rolling_time()`.
+///   * window - Window duration in index units (milliseconds by default)
+pub struct TimeWindows<'a> {
+    ts: &'a TimeSeries,
+    window: i64,
+}
+
+impl TimeSeries {
+
+    /// Time based windows which contain data points with timestamps in `(t - window, t]`
+    /// for each data point timestamp `t`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1000, 5000, 6000], vec![1.0, 2.0, 3.0, 4.0]);
+    /// assert_eq!(ts.rolling_time(2000).mean().values, vec![1.0, 1.5, 3.0, 3.5]);
+    /// ```
+    pub fn rolling_time(&self, window: i64) -> TimeWindows<'_> {
+        TimeWindows { ts: self, window }
+    }
+}
+
+impl<'a> TimeWindows<'a> {
+
+    /// Apply function to timestamps and values of each window.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// // Time weighted average, where each value lasts until the next data point
+    /// let ts = TimeSeries::new(vec![0, 1000, 4000], vec![1.0, 3.0, 5.0]);
+    /// let weighted = ts.rolling_time(5000).apply(|index, values| {
+    ///     let total: f64 = index.windows(2).zip(values).map(|(t, v)| (t[1] - t[0]) as f64 * v).sum();
+    ///     total / (index[index.len() - 1] - index[0]) as f64
+    /// });
+    /// assert_eq!(weighted.values[2], 2.5);
+    /// ```
+    pub fn apply<F>(&self, f: F) -> TimeSeries
+    where
+        F: Fn(&[i64], &[f64]) -> f64 {
+
+        self.windows(f).logged(self.ts, "rolling_time_apply", || vec![("window", self.window.to_string())])
+    }
+
+    /// Moving average. NaN values are ignored.
+    pub fn mean(&self) -> TimeSeries {
+        self.windows(|_, xs| nan_mean(xs)).logged(self.ts, "rolling_time_mean", || vec![("window", self.window.to_string())])
+    }
+
+    /// Moving sum. NaN values are ignored.
+    pub fn sum(&self) -> TimeSeries {
+        self.windows(|_, xs| NanPolicy::default().aggregate(xs, Aggregation::Sum).unwrap_or(f64::NAN)).logged(self.ts, "rolling_time_sum", || vec![("window", self.window.to_string())])
+    }
+
+    /// Number of data points in each window
+    pub fn count(&self) -> TimeSeries {
+        self.windows(|index, _| index.len() as f64).logged(self.ts, "rolling_time_count", || vec![("window", self.window.to_string())])
+    }
+
+    fn windows<F>(&self, f: F) -> TimeSeries
+    where
+        F: Fn(&[i64], &[f64]) -> f64 {
+
+        let index = &self.ts.index.values;
+        let mut output = self.ts.clone();
+        let mut start = 0;
+        for (end, &t) in index.iter().enumerate() {
+            while index[start] <= t.saturating_sub(self.window) && start < end {
+                start += 1;
+            }
+            output.values[end] = f(&index[start..=end], &self.ts.values[start..=end]);
+        }
+        output
+    }
+}
+
+
+/// Value with its position in the series. Position makes each entry unique.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    value: f64,
+    pos: usize,
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.total_cmp(&other.value).then(self.pos.cmp(&other.pos))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+/// Sliding window split into lower and upper heaps.
+/// The lower heap holds the entries up to the quantile rank.
+/// Removed entries are deleted lazily, when they get to the top of the heap.
+/// Entries are removed in the order of positions, so all entries before `start` are removed.
+/// Heap with more removed entries than live ones is purged, so heaps stay O(window).
+struct QuantileHeaps {
+    q: f64,
+    lower: BinaryHeap<Entry>,
+    upper: BinaryHeap<Reverse<Entry>>,
+    lower_len: usize,
+    upper_len: usize,
+    start: usize,
+}
+
+impl QuantileHeaps {
+
+    fn new(q: f64) -> QuantileHeaps {
+        QuantileHeaps {
+            q,
+            lower: BinaryHeap::new(),
+            upper: BinaryHeap::new(),
+            lower_len: 0,
+            upper_len: 0,
+            start: 0,
+        }
+    }
+
+    fn insert(&mut self, value: f64, pos: usize) {
+        if value.is_nan() {
+            return;
+        }
+        let entry = Entry { value, pos };
+        if self.lower_top().is_some_and(|top| entry < top) {
+            self.lower.push(entry);
+            self.lower_len += 1;
+        } else {
+            self.upper.push(Reverse(entry));
+            self.upper_len += 1;
+        }
+        self.rebalance();
+    }
+
+    /// Remove the oldest entry in the window
+    fn remove(&mut self, value: f64, pos: usize) {
+        if !value.is_nan() {
+            // The entry is not removed yet, so it can still be the top of the lower heap
+            let entry = Entry { value, pos };
+            if self.lower_top().is_some_and(|top| entry <= top) {
+                self.lower_len -= 1;
+            } else {
+                self.upper_len -= 1;
+            }
+        }
+        self.start = pos + 1;
+        self.rebalance();
+        self.purge();
+    }
+
+    fn quantile(&mut self) -> f64 {
+        let n = self.lower_len + self.upper_len;
+        if n == 0 {
+            return f64::NAN;
+        }
+        let rank = self.q * (n - 1) as f64;
+        let low = self.lower_top().map_or(f64::NAN, |e| e.value);
+        let frac = rank - rank.floor();
+        if frac > 0.0 {
+            let high = self.upper_top().map_or(f64::NAN, |e| e.value);
+            low + frac * (high - low)
+        } else {
+            low
+        }
+    }
+
+    fn rebalance(&mut self) {
+        let n = self.lower_len + self.upper_len;
+        let target = if n == 0 { 0 } else { (self.q * (n - 1) as f64).floor() as usize + 1 };
+        while self.lower_len > target {
+            let entry = self.lower_top().unwrap();
+            self.lower.pop();
+            self.lower_len -= 1;
+            self.upper.push(Reverse(entry));
+            self.upper_len += 1;
+        }
+        while self.lower_len < target {
+            let entry = self.upper_top().unwrap();
+            self.upper.pop();
+            self.upper_len -= 1;
+            self.lower.push(entry);
+            self.lower_len += 1;
+        }
+    }
+
+    /// Drop removed entries from the heap where they outnumber the live ones
+    fn purge(&mut self) {
+        let start = self.start;
+        if self.lower.len() > 2 * self.lower_len + 1 {
+            self.lower.retain(|e| e.pos >= start);
+        }
+        if self.upper.len() > 2 * self.upper_len + 1 {
+            self.upper.retain(|e| e.0.pos >= start);
+        }
+    }
+
+    /// Largest entry in the lower heap after dropping removed entries
+    fn lower_top(&mut self) -> Option<Entry> {
+        while self.lower.peek().is_some_and(|e| e.pos < self.start) {
+            self.lower.pop();
+        }
+        self.lower.peek().cloned()
+    }
+
+    /// Smallest entry in the upper heap after dropping removed entries
+    fn upper_top(&mut self) -> Option<Entry> {
+        while self.upper.peek().is_some_and(|e| e.0.pos < self.start) {
+            self.upper.pop();
+        }
+        self.upper.peek().map(|e| e.0)
+    }
+}
+
+
+/// Exponentially weighted mean of values
+pub(crate) fn ewma(values: &[f64], alpha: f64) -> Vec<f64> {
+    let mut output = Vec::with_capacity(values.len());
+    for &v in values {
+        let next = match output.last() {
+            Some(prev) => alpha * v + (1.0 - alpha) * prev,
+            None => v,
+        };
+        output.push(next);
+    }
+    output
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_time_irregular() {
+        let ts = TimeSeries::new(vec![0, 10, 11, 12, 30], vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(ts.rolling_time(10).count().values, vec![1.0, 1.0, 2.0, 3.0, 1.0]);
+        assert_eq!(ts.rolling_time(10).sum().values, vec![1.0, 2.0, 5.0, 9.0, 5.0]);
+        assert_eq!(ts.rolling_time(0).count().values, vec![1.0; 5]);
+        assert!(TimeSeries::empty().rolling_time(10).mean().is_empty());
+    }
+
+    #[test]
+    fn test_window_too_big() {
+        let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+        let output = ts.rolling_mean(3);
+        assert_eq!(output.index, ts.index);
+        assert!(output.values.iter().all(|v| v.is_nan()));
+        assert!(ts.rolling_mean(0).values.iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn test_min_max_std() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![3.0, 1.0, 2.0, 5.0]);
+        assert_eq!(ts.rolling_min(2).values[1..], [1.0, 1.0, 2.0]);
+        assert_eq!(ts.rolling_max(3).values[2..], [3.0, 5.0]);
+        assert_eq!(ts.rolling_std(3).values[2], 1.0);
+    }
+
+    fn naive_quantile(xs: &[f64], q: f64) -> f64 {
+        let mut xs: Vec<f64> = xs.iter().cloned().filter(|v| !v.is_nan()).collect();
+        if xs.is_empty() {
+            return f64::NAN;
+        }
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let rank = q * (xs.len() - 1) as f64;
+        let low = xs[rank.floor() as usize];
+        let high = xs[rank.ceil() as usize];
+        low + (rank - rank.floor()) * (high - low)
+    }
+
+    #[test]
+    fn test_quantile_heaps_size() {
+        let window = 10;
+        let values: Vec<f64> = (0..10_000).map(|i| ((i * 7919) % 1000) as f64).collect();
+        let mut heaps = QuantileHeaps::new(0.5);
+        for (pos, &v) in values.iter().enumerate() {
+            heaps.insert(v, pos);
+            if pos >= window {
+                heaps.remove(values[pos - window], pos - window);
+            }
+            heaps.quantile();
+            assert!(heaps.lower.len() + heaps.upper.len() <= 3 * window + 2);
+        }
+    }
+
+    #[test]
+    fn test_quantile_same_as_sorting() {
+        let values: Vec<f64> = (0..500)
+            .map(|i| if i % 37 == 0 { f64::NAN } else { ((i * 7919) % 101) as f64 })
+            .collect();
+        let ts = TimeSeries::new((0..500).collect(), values);
+        for &window in &[1, 2, 7, 50] {
+            for &q in &[0.0, 0.1, 0.5, 0.9, 1.0] {
+                let expected = ts.rolling(window, |xs| naive_quantile(xs, q));
+                let output = ts.rolling_quantile(window, q);
+                for (a, b) in output.values.iter().zip(&expected.values) {
+                    assert!(a == b || (a.is_nan() && b.is_nan()), "window={} q={}: {} != {}", window, q, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_median_all_nan_window() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![f64::NAN, f64::NAN, 1.0, 3.0]);
+        let output = ts.rolling_median(2);
+        assert!(output.values[1].is_nan());
+        assert_eq!(output.values[2..], [1.0, 2.0]);
+        assert!(ts.rolling_median(5).values.iter().all(|v| v.is_nan()));
+    }
+}
@@ -0,0 +1,203 @@
+//! Pre-aggregated series at multiple resolutions
+//!
+//! Rollup keeps statistics of each period (for example each minute, hour and day).
+//! Aggregation over a long time range uses the largest periods which fit into the range,
+//! and only the edges of the range are calculated from finer periods or from raw data points.
+//! The result is the same as calculated from raw data points.
+//!
+
+use crate::{TimeSeries, DataPoint};
+use crate::frequency::Frequency;
+use crate::index::Resolution;
+use crate::resample::{Aggregation, BucketStats};
+
+
+/// Series with pre-aggregated levels
+#[derive(Clone, Debug)]
+pub struct Rollup {
+    resolution: Resolution,
+    index: Vec<i64>,
+    values: Vec<f64>,
+    levels: Vec<Level>,
+}
+
+/// Statistics for each non empty period of the given frequency
+#[derive(Clone, Debug)]
+struct Level {
+    frequency: Frequency,
+    resolution: Resolution,
+    starts: Vec<i64>,
+    ends: Vec<i64>,
+    stats: Vec<BucketStats>,
+}
+
+
+impl Rollup {
+
+    /// Create rollup with levels for the given frequencies.
+    /// Frequencies should be ordered from the finest to the coarsest.
+    /// Invalid frequencies (see `Frequency::is_valid`) are skipped.
+    /// Periods are calculated in the index resolution of the series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::frequency::Frequency;
+    /// use timeseries::resample::Aggregation;
+    /// use timeseries::rollup::Rollup;
+    ///
+    /// let index = (0..10_000).map(|i| i * 1000).collect();
+    /// let values = (0..10_000).map(|i| (i % 100) as f64).collect();
+    /// let rollup = Rollup::new(&TimeSeries::new(index, values), &[Frequency::Minute(1), Frequency::Hour(1)]);
+    /// assert_eq!(rollup.query(0, 7_200_000, Aggregation::Max), Some(99.0));
+    /// assert_eq!(rollup.query(1500, 4500, Aggregation::Sum), Some(9.0));
+    /// ```
+    pub fn new(ts: &TimeSeries, frequencies: &[Frequency]) -> Rollup {
+        let resolution = ts.index.resolution;
+        let mut rollup = Rollup {
+            resolution,
+            index: vec![],
+            values: vec![],
+            levels: frequencies.iter()
+                .filter(|frequency| frequency.is_valid())
+                .map(|&frequency| Level { frequency, resolution, starts: vec![], ends: vec![], stats: vec![] })
+                .collect(),
+        };
+        ts.iter().for_each(|dp| { rollup.push(dp); });
+        rollup
+    }
+
+    /// Append data point and update all levels.
+    /// Data point is ignored (and false is returned) if it is not after the last data point.
+    pub fn push(&mut self, dp: DataPoint) -> bool {
+        if self.index.last().is_some_and(|&last| dp.timestamp <= last) {
+            return false;
+        }
+        for level in &mut self.levels {
+            level.push(dp.timestamp, dp.value);
+        }
+        self.index.push(dp.timestamp);
+        self.values.push(dp.value);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Aggregate data points in the time range [start, end).
+    /// None if there are no data points in the range.
+    pub fn query(&self, start: i64, end: i64, aggregation: Aggregation) -> Option<f64> {
+        self.stats(self.levels.len(), start, end).value(aggregation)
+    }
+
+    /// Pre-aggregated series for the given frequency, or None if there is no such level
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::frequency::Frequency;
+    /// use timeseries::resample::Aggregation;
+    /// use timeseries::rollup::Rollup;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 5, 10], vec![1.0, 3.0, 5.0]);
+    /// let rollup = Rollup::new(&ts, &[Frequency::Millisecond(10)]);
+    /// let level = rollup.level(Frequency::Millisecond(10), Aggregation::Mean).unwrap();
+    /// assert_eq!(level, ts.resample(10, Aggregation::Mean));
+    /// ```
+    pub fn level(&self, frequency: Frequency, aggregation: Aggregation) -> Option<TimeSeries> {
+        let level = self.levels.iter().find(|level| level.frequency == frequency)?;
+        let values = level.stats.iter().map(|s| s.value(aggregation).unwrap_or(f64::NAN)).collect();
+        let mut ts = TimeSeries::new(level.starts.clone(), values);
+        ts.index.resolution = self.resolution;
+        Some(ts)
+    }
+
+    /// Statistics of the range using levels up to the given one (0 means raw data points)
+    fn stats(&self, levels: usize, start: i64, end: i64) -> BucketStats {
+        if start >= end {
+            return BucketStats::empty();
+        }
+        if levels == 0 {
+            let from = self.index.partition_point(|&t| t < start);
+            let to = self.index.partition_point(|&t| t < end);
+            return BucketStats::from_values(&self.values[from..to]);
+        }
+        let level = &self.levels[levels - 1];
+        let first = level.starts.partition_point(|&t| t < start);
+        let last = level.ends.partition_point(|&t| t <= end);
+        if first >= last {
+            return self.stats(levels - 1, start, end);
+        }
+        let middle = level.stats[first..last].iter().fold(BucketStats::empty(), |acc, s| acc.merge(s));
+        self.stats(levels - 1, start, level.starts[first])
+            .merge(&middle)
+            .merge(&self.stats(levels - 1, level.ends[last - 1], end))
+    }
+}
+
+impl Level {
+
+    fn push(&mut self, timestamp: i64, value: f64) {
+        let start = self.frequency.floor_in(timestamp, self.resolution);
+        if self.starts.last() == Some(&start) {
+            let last = self.stats.len() - 1;
+            self.stats[last] = self.stats[last].merge(&BucketStats::single(value));
+        } else {
+            self.starts.push(start);
+            self.ends.push(self.frequency.step_in(start, 1, self.resolution));
+            self.stats.push(BucketStats::single(value));
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(ts: &TimeSeries, start: i64, end: i64, aggregation: Aggregation) -> Option<f64> {
+        let part: Vec<f64> = ts.iter().filter(|dp| start <= dp.timestamp && dp.timestamp < end).map(|dp| dp.value).collect();
+        BucketStats::from_values(&part).value(aggregation)
+    }
+
+    #[test]
+    fn test_same_as_raw_data() {
+        let index = (0..5000).map(|i| i * 7919 % 1000 + i * 1000).collect();
+        let values = (0..5000).map(|i| ((i * 31) % 97) as f64).collect();
+        let ts = TimeSeries::new(index, values);
+        let rollup = Rollup::new(&ts, &[Frequency::Second(10), Frequency::Minute(1), Frequency::Minute(15)]);
+        let aggregations = [Aggregation::Sum, Aggregation::Min, Aggregation::Max, Aggregation::First,
+                            Aggregation::Last, Aggregation::Count];
+        for &(start, end) in &[(0, 5_000_000), (12_345, 4_321_000), (61_000, 62_000), (3_000, 3_001), (10, 5)] {
+            for &aggregation in &aggregations {
+                assert_eq!(rollup.query(start, end, aggregation), raw(&ts, start, end, aggregation));
+            }
+        }
+    }
+
+    #[test]
+    fn test_push() {
+        let mut rollup = Rollup::new(&TimeSeries::empty(), &[Frequency::Millisecond(10)]);
+        assert!(rollup.is_empty());
+        assert!(rollup.push(DataPoint::new(5, 1.0)));
+        assert!(rollup.push(DataPoint::new(15, 2.0)));
+        assert!(!rollup.push(DataPoint::new(15, 3.0)));
+        assert_eq!(rollup.len(), 2);
+        assert_eq!(rollup.query(0, 20, Aggregation::Mean), Some(1.5));
+        assert_eq!(rollup.query(20, 30, Aggregation::Mean), None);
+        assert!(rollup.level(Frequency::Hour(1), Aggregation::Mean).is_none());
+        let rollup = Rollup::new(&TimeSeries::new(vec![1, 2], vec![1.0, 2.0]), &[Frequency::Millisecond(0)]);
+        assert!(rollup.level(Frequency::Millisecond(0), Aggregation::Mean).is_none());
+        assert_eq!(rollup.query(0, 10, Aggregation::Sum), Some(3.0));
+    }
+}
@@ -0,0 +1,144 @@
+//! Aggregation of data points in a time range
+//!
+//! Single query can be calculated directly from the series in O(n) time.
+//! For many queries on the same series build a `SegmentTree` once,
+//! and then each query takes O(log n) time.
+//!
+
+use crate::TimeSeries;
+use crate::resample::{Aggregation, BucketStats};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Precomputed statistics of the series ranges
+#[derive(Clone, Debug)]
+pub struct SegmentTree {
+    index: Vec<i64>,
+    size: usize,
+    /// Nodes in the heap layout. Leaves start at `size`.
+    nodes: Vec<BucketStats>,
+}
+
+
+impl SegmentTree {
+
+    /// Build tree for the given series
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    /// use timeseries::segment_tree::SegmentTree;
+    ///
+    /// let ts = TimeSeries::new(vec![10, 20, 30, 40], vec![4.0, 1.0, 3.0, 2.0]);
+    /// let tree = SegmentTree::new(&ts);
+    /// assert_eq!(tree.query(15, 40, Aggregation::Max), Some(3.0));
+    /// assert_eq!(tree.query(15, 40, Aggregation::Sum), Some(4.0));
+    /// assert_eq!(tree.query(50, 60, Aggregation::Min), None);
+    /// ```
+    pub fn new(ts: &TimeSeries) -> SegmentTree {
+        let size = ts.len().next_power_of_two();
+        let mut nodes = vec![BucketStats::empty(); 2 * size];
+        for (i, &v) in ts.values.iter().enumerate() {
+            nodes[size + i] = BucketStats::single(v);
+        }
+        for i in (1..size).rev() {
+            nodes[i] = nodes[2 * i].merge(&nodes[2 * i + 1]);
+        }
+        SegmentTree { index: ts.index.values.clone(), size, nodes }
+    }
+
+    /// Aggregate data points in the time range [start, end).
+    /// None if there are no data points in the range.
+    pub fn query(&self, start: i64, end: i64, aggregation: Aggregation) -> Option<f64> {
+        let from = self.index.partition_point(|&t| t < start);
+        let to = self.index.partition_point(|&t| t < end);
+        self.stats(from, to.max(from)).value(aggregation)
+    }
+
+    /// Statistics of data points at positions [from, to)
+    fn stats(&self, from: usize, to: usize) -> BucketStats {
+        // Left and right parts are collected separately to keep the order of data points
+        let mut left = BucketStats::empty();
+        let mut right = BucketStats::empty();
+        let mut lo = from + self.size;
+        let mut hi = to + self.size;
+        while lo < hi {
+            if lo % 2 == 1 {
+                left = left.merge(&self.nodes[lo]);
+                lo += 1;
+            }
+            if hi % 2 == 1 {
+                hi -= 1;
+                right = self.nodes[hi].merge(&right);
+            }
+            lo /= 2;
+            hi /= 2;
+        }
+        left.merge(&right)
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Aggregate data points in the time range [start, end).
+    /// None if there are no data points in the range.
+    /// Use `SegmentTree` for many queries on the same series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![10, 20, 30], vec![1.0, 2.0, 6.0]);
+    /// assert_eq!(ts.range_query(10, 30, Aggregation::Mean), Some(1.5));
+    /// ```
+    pub fn range_query(&self, start: i64, end: i64, aggregation: Aggregation) -> Option<f64> {
+        let (range, _) = self.index.slice_range(start, end);
+        BucketStats::from_values(&self.values[range]).value(aggregation)
+    }
+
+    /// Build segment tree for fast range queries
+    pub fn segment_tree(&self) -> SegmentTree {
+        SegmentTree::new(self)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_same_as_scan() {
+        let index: Vec<i64> = (0..37).map(|i| i * 10).collect();
+        let values = (0..37).map(|i| ((i * 17) % 11) as f64).collect();
+        let ts = TimeSeries::new(index, values);
+        let tree = ts.segment_tree();
+        let aggregations = [Aggregation::Sum, Aggregation::Min, Aggregation::Max, Aggregation::First,
+                            Aggregation::Last, Aggregation::Count, Aggregation::Mean];
+        for start in (-10..380).step_by(7) {
+            for end in (start..390).step_by(13) {
+                for &aggregation in &aggregations {
+                    assert_eq!(tree.query(start, end, aggregation), ts.range_query(start, end, aggregation));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree = TimeSeries::empty().segment_tree();
+        assert_eq!(tree.query(0, 10, Aggregation::Count), None);
+        let ts = TimeSeries::new(vec![1], vec![5.0]);
+        assert_eq!(ts.segment_tree().query(0, 10, Aggregation::Last), Some(5.0));
+        assert_eq!(ts.range_query(5, 0, Aggregation::Count), None);
+    }
+}
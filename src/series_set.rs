@@ -0,0 +1,375 @@
+//! Collection of series identified by labels
+//!
+//! Each series in the set has a unique set of labels, like `{host="a", metric="cpu"}`.
+//! Series can be selected with label matchers and processed together.
+//!
+
+use std::collections::BTreeMap;
+
+use crate::{TimeSeries, DataPoint};
+use crate::quality::GOOD;
+use crate::reduce::{aggregate_refs, AlignPolicy};
+use crate::resample::Aggregation;
+
+
+/// Label names and values identifying a series
+pub type Labels = BTreeMap<String, String>;
+
+/// Condition on a single label
+///   * Equal - Label has the given value
+///   * NotEqual - Label is missing or has a different value
+///   * OneOf - Label has one of the given values
+///   * Exists - Label is present with any value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Matcher {
+    Equal(String, String),
+    NotEqual(String, String),
+    OneOf(String, Vec<String>),
+    Exists(String),
+}
+
+/// Series keyed by labels
+#[derive(Clone, Debug, Default)]
+pub struct SeriesSet {
+    series: BTreeMap<Labels, TimeSeries>,
+    retention: Option<i64>,
+}
+
+
+/// Create labels from (name, value) pairs
+///
+/// # Example
+///
+/// ```
+/// use timeseries::series_set::labels;
+///
+/// let labels = labels(&[("host", "a"), ("metric", "cpu")]);
+/// assert_eq!(labels["host"], "a");
+/// ```
+pub fn labels(pairs: &[(&str, &str)]) -> Labels {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+
+impl Matcher {
+
+    /// Label equal to the value
+    pub fn eq(name: &str, value: &str) -> Matcher {
+        Matcher::Equal(name.to_owned(), value.to_owned())
+    }
+
+    /// Label missing or not equal to the value
+    pub fn ne(name: &str, value: &str) -> Matcher {
+        Matcher::NotEqual(name.to_owned(), value.to_owned())
+    }
+
+    /// Check if labels satisfy this condition
+    pub fn matches(&self, labels: &Labels) -> bool {
+        match self {
+            Matcher::Equal(name, value) => labels.get(name) == Some(value),
+            Matcher::NotEqual(name, value) => labels.get(name) != Some(value),
+            Matcher::OneOf(name, values) => labels.get(name).is_some_and(|v| values.contains(v)),
+            Matcher::Exists(name) => labels.contains_key(name),
+        }
+    }
+}
+
+
+impl SeriesSet {
+
+    /// Create empty set
+    pub fn new() -> SeriesSet {
+        SeriesSet::default()
+    }
+
+    /// Keep data points not older then retention (in index units) before the last data point of each series.
+    /// Old points are dropped when series is inserted or new data point is pushed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    /// use timeseries::series_set::{SeriesSet, labels};
+    ///
+    /// let mut set = SeriesSet::with_retention(10);
+    /// for t in &[0, 5, 10, 15] {
+    ///     set.push(labels(&[("host", "a")]), DataPoint::new(*t, 1.0));
+    /// }
+    /// assert_eq!(set.get(&labels(&[("host", "a")])).unwrap().index.values, vec![5, 10, 15]);
+    /// ```
+    pub fn with_retention(retention: i64) -> SeriesSet {
+        SeriesSet { series: BTreeMap::new(), retention: Some(retention) }
+    }
+
+    /// Number of series
+    pub fn len(&self) -> usize {
+        self.series.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// Add series. Returns the previous series with the same labels.
+    /// Data points older then the retention are dropped.
+    pub fn insert(&mut self, labels: Labels, mut ts: TimeSeries) -> Option<TimeSeries> {
+        apply_retention(self.retention, &mut ts);
+        self.series.insert(labels, ts)
+    }
+
+    /// Series with exactly the given labels
+    pub fn get(&self, labels: &Labels) -> Option<&TimeSeries> {
+        self.series.get(labels)
+    }
+
+    /// Append data point to the series with the given labels. New series is created if needed.
+    /// Data point older then the last one is merged into the series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::DataPoint;
+    /// use timeseries::series_set::{SeriesSet, labels};
+    ///
+    /// let mut set = SeriesSet::new();
+    /// set.push(labels(&[("host", "a")]), DataPoint::new(1, 1.0));
+    /// set.push(labels(&[("host", "a")]), DataPoint::new(2, 2.0));
+    /// set.push(labels(&[("host", "b")]), DataPoint::new(1, 5.0));
+    /// assert_eq!(set.len(), 2);
+    /// assert_eq!(set.get(&labels(&[("host", "a")])).unwrap().values, vec![1.0, 2.0]);
+    /// ```
+    pub fn push(&mut self, labels: Labels, dp: DataPoint) {
+        let ts = self.series.entry(labels).or_insert_with(TimeSeries::empty);
+        if ts.index.last().is_none_or(|t| t < dp.timestamp) {
+            ts.index.values.push(dp.timestamp);
+            ts.values.push(dp.value);
+            match (&mut ts.quality, dp.quality) {
+                (Some(quality), q) => quality.push(q.unwrap_or(GOOD)),
+                (None, Some(q)) => {
+                    let mut quality = vec![GOOD; ts.values.len() - 1];
+                    quality.push(q);
+                    ts.quality = Some(quality);
+                },
+                (None, None) => (),
+            }
+        } else {
+            let mut point = TimeSeries::from_datapoints(vec![dp]);
+            point.index.resolution = ts.index.resolution;
+            *ts = ts.merge(&point);
+        }
+        apply_retention(self.retention, ts);
+    }
+
+    /// Drop data points older then the timestamp. Series left without data points are removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::series_set::{SeriesSet, labels};
+    ///
+    /// let mut set = SeriesSet::new();
+    /// set.insert(labels(&[("host", "a")]), TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]));
+    /// set.insert(labels(&[("host", "b")]), TimeSeries::new(vec![1], vec![5.0]));
+    /// set.truncate_before(2);
+    /// assert_eq!(set.len(), 1);
+    /// assert_eq!(set.get(&labels(&[("host", "a")])).unwrap().values, vec![2.0, 3.0]);
+    /// ```
+    pub fn truncate_before(&mut self, timestamp: i64) {
+        for ts in self.series.values_mut() {
+            if ts.index.first().is_some_and(|first| first < timestamp) {
+                *ts = ts.truncate_before(timestamp);
+            }
+        }
+        self.series.retain(|_, ts| !ts.is_empty());
+    }
+
+    /// Iterate over labels and series
+    pub fn iter(&self) -> impl Iterator<Item = (&Labels, &TimeSeries)> {
+        self.series.iter()
+    }
+
+    /// Series with labels matching all conditions
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::series_set::{SeriesSet, Matcher, labels};
+    ///
+    /// let mut set = SeriesSet::new();
+    /// set.insert(labels(&[("host", "a"), ("metric", "cpu")]), TimeSeries::new(vec![1], vec![1.0]));
+    /// set.insert(labels(&[("host", "b"), ("metric", "cpu")]), TimeSeries::new(vec![1], vec![2.0]));
+    /// set.insert(labels(&[("host", "a"), ("metric", "mem")]), TimeSeries::new(vec![1], vec![3.0]));
+    /// let cpu = set.select(&[Matcher::eq("metric", "cpu"), Matcher::ne("host", "b")]);
+    /// assert_eq!(cpu.len(), 1);
+    /// ```
+    pub fn select(&self, matchers: &[Matcher]) -> SeriesSet {
+        let series = self.series.iter()
+            .filter(|(labels, _)| matchers.iter().all(|m| m.matches(labels)))
+            .map(|(labels, ts)| (labels.clone(), ts.clone()))
+            .collect();
+        SeriesSet { series, retention: self.retention }
+    }
+
+    /// Apply function to each series
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::series_set::{SeriesSet, labels};
+    ///
+    /// let mut set = SeriesSet::new();
+    /// set.insert(labels(&[("host", "a")]), TimeSeries::new(vec![1, 2], vec![1.0, 4.0]));
+    /// let diffs = set.apply(|ts| ts.diff());
+    /// assert_eq!(diffs.get(&labels(&[("host", "a")])).unwrap().values, vec![3.0]);
+    /// ```
+    pub fn apply<F: Fn(&TimeSeries) -> TimeSeries>(&self, f: F) -> SeriesSet {
+        let series = self.series.iter()
+            .map(|(labels, ts)| (labels.clone(), f(ts)))
+            .collect();
+        SeriesSet { series, retention: self.retention }
+    }
+
+    /// Point-wise aggregation across all series.
+    /// The result has timestamps from all series. NaN and missing values are skipped.
+    /// Use `reduce::aggregate_series` for other alignment policies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    /// use timeseries::series_set::{SeriesSet, labels};
+    ///
+    /// let mut set = SeriesSet::new();
+    /// set.insert(labels(&[("host", "a")]), TimeSeries::new(vec![1, 2], vec![1.0, 2.0]));
+    /// set.insert(labels(&[("host", "b")]), TimeSeries::new(vec![2, 3], vec![5.0, 6.0]));
+    /// let total = set.aggregate(Aggregation::Sum);
+    /// assert_eq!(total, TimeSeries::new(vec![1, 2, 3], vec![1.0, 7.0, 6.0]));
+    /// ```
+    pub fn aggregate(&self, aggregation: Aggregation) -> TimeSeries {
+        let series: Vec<&TimeSeries> = self.series.values().collect();
+        aggregate_refs(&series, aggregation, AlignPolicy::Union)
+    }
+
+    /// Aggregate series with the same values of the given labels
+    /// (like `sum by (host)` in PromQL). Other labels are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::resample::Aggregation;
+    /// use timeseries::series_set::{SeriesSet, labels};
+    ///
+    /// let mut set = SeriesSet::new();
+    /// set.insert(labels(&[("dc", "eu"), ("host", "a")]), TimeSeries::new(vec![1], vec![1.0]));
+    /// set.insert(labels(&[("dc", "eu"), ("host", "b")]), TimeSeries::new(vec![1], vec![2.0]));
+    /// set.insert(labels(&[("dc", "us"), ("host", "c")]), TimeSeries::new(vec![1], vec![5.0]));
+    /// let by_dc = set.aggregate_by(&["dc"], Aggregation::Sum);
+    /// assert_eq!(by_dc.len(), 2);
+    /// assert_eq!(by_dc.get(&labels(&[("dc", "eu")])).unwrap().values, vec![3.0]);
+    /// ```
+    pub fn aggregate_by(&self, names: &[&str], aggregation: Aggregation) -> SeriesSet {
+        let mut groups: BTreeMap<Labels, SeriesSet> = BTreeMap::new();
+        for (labels, ts) in &self.series {
+            let key = labels.iter()
+                .filter(|(name, _)| names.contains(&name.as_str()))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            groups.entry(key).or_default().insert(labels.clone(), ts.clone());
+        }
+        let series = groups.into_iter()
+            .map(|(key, group)| (key, group.aggregate(aggregation)))
+            .collect();
+        SeriesSet { series, retention: self.retention }
+    }
+}
+
+/// Drop data points older then retention before the last data point
+fn apply_retention(retention: Option<i64>, ts: &mut TimeSeries) {
+    if let (Some(retention), Some(last)) = (retention, ts.index.last()) {
+        let timestamp = last.saturating_sub(retention);
+        if ts.index.first().is_some_and(|first| first < timestamp) {
+            *ts = ts.truncate_before(timestamp);
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matchers() {
+        let l = labels(&[("host", "a"), ("metric", "cpu")]);
+        assert!(Matcher::eq("host", "a").matches(&l));
+        assert!(!Matcher::ne("host", "a").matches(&l));
+        assert!(Matcher::ne("dc", "eu").matches(&l));
+        assert!(Matcher::OneOf("host".to_owned(), vec!["b".to_owned(), "a".to_owned()]).matches(&l));
+        assert!(!Matcher::Exists("dc".to_owned()).matches(&l));
+    }
+
+    #[test]
+    fn test_push_out_of_order() {
+        let mut set = SeriesSet::new();
+        let l = labels(&[("host", "a")]);
+        set.push(l.clone(), DataPoint::new(5, 5.0));
+        set.push(l.clone(), DataPoint::new(1, 1.0));
+        assert_eq!(set.get(&l).unwrap().index.values, vec![1, 5]);
+    }
+
+    #[test]
+    fn test_retention_keeps_late_points_in_range() {
+        let mut set = SeriesSet::with_retention(10);
+        let l = labels(&[("host", "a")]);
+        set.push(l.clone(), DataPoint::new(20, 2.0));
+        set.push(l.clone(), DataPoint::new(5, 0.5));
+        set.push(l.clone(), DataPoint::new(12, 1.2));
+        assert_eq!(set.get(&l).unwrap().index.values, vec![12, 20]);
+    }
+
+    #[test]
+    fn test_push_quality() {
+        let mut set = SeriesSet::new();
+        let key = labels(&[("host", "a")]);
+        set.push(key.clone(), DataPoint::new(1, 1.0));
+        set.push(key.clone(), DataPoint::new(3, 3.0).with_quality(2));
+        set.push(key.clone(), DataPoint::new(4, 4.0));
+        set.push(key.clone(), DataPoint::new(2, 2.0).with_quality(1));
+        let ts = set.get(&key).unwrap();
+        assert_eq!(ts.index.values, vec![1, 2, 3, 4]);
+        assert_eq!(ts.quality, Some(vec![0, 1, 2, 0]));
+    }
+
+    #[test]
+    fn test_insert_applies_retention() {
+        let mut set = SeriesSet::with_retention(10);
+        let l = labels(&[("host", "a")]);
+        set.insert(l.clone(), TimeSeries::new(vec![0, 5, 10, 15], vec![1.0; 4]));
+        assert_eq!(set.get(&l).unwrap().index.values, vec![5, 10, 15]);
+    }
+
+    #[test]
+    fn test_aggregate_empty() {
+        let set = SeriesSet::new();
+        assert!(set.aggregate(Aggregation::Sum).is_empty());
+        assert!(set.aggregate_by(&["host"], Aggregation::Sum).is_empty());
+    }
+
+    #[test]
+    fn test_aggregate_skips_nan() {
+        let mut set = SeriesSet::new();
+        set.insert(labels(&[("host", "a")]), TimeSeries::new(vec![1, 2], vec![f64::NAN, 2.0]));
+        set.insert(labels(&[("host", "b")]), TimeSeries::new(vec![2], vec![4.0]));
+        let mean = set.aggregate(Aggregation::Mean);
+        assert!(mean.values[0].is_nan());
+        assert_eq!(mean.values[1], 3.0);
+    }
+}
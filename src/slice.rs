@@ -0,0 +1,351 @@
+//! Borrowed view of the series in the timestamp range
+//!
+//! `TimeSeries::range` selects data points without copying. Use `to_series` to get an owned copy.
+//! Series can also be indexed by position (`ts[pos]`), which returns the value.
+//!
+//! `TimeSeries::from_slices` creates the same view over buffers owned by someone else
+//! (e.g. Arrow arrays or memory mapped files), so they can be used without copying.
+//! `CowSeries` borrows or owns its buffers, so it can be cloned and passed around
+//! without copying borrowed data until it is modified.
+//!
+
+use alloc::borrow::Cow;
+use core::ops::{Bound, Index, RangeBounds};
+
+use crate::{TimeSeries, DataPoint, ToSeries};
+use crate::builder::BuildError;
+use crate::index::{DateTimeIndex, Resolution};
+use crate::metadata::Metadata;
+
+
+/// Borrowed data points of the series
+///   * index - Timestamps
+///   * values - Values
+///   * quality - Optional quality codes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeSeriesSlice<'a> {
+    pub index: &'a [i64],
+    pub values: &'a [f64],
+    pub quality: Option<&'a [u8]>,
+    resolution: Resolution,
+    metadata: Option<&'a Metadata>,
+}
+
+/// Series with borrowed or owned data.
+/// Cloning borrowed series doesn't copy the data.
+/// Use `Cow::to_mut` on the fields to modify them (borrowed data is copied first).
+///   * index - Timestamps
+///   * values - Values
+///   * quality - Optional quality codes
+///   * resolution - Unit of the timestamps
+///   * metadata - Name, unit and tags
+#[derive(Clone, Debug, PartialEq)]
+pub struct CowSeries<'a> {
+    pub index: Cow<'a, [i64]>,
+    pub values: Cow<'a, [f64]>,
+    pub quality: Option<Cow<'a, [u8]>>,
+    pub resolution: Resolution,
+    pub metadata: Cow<'a, Metadata>,
+}
+
+
+impl TimeSeries {
+
+    /// Data points with timestamps in the range
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{TimeSeries, DataPoint};
+    ///
+    /// let ts = TimeSeries::new(vec![10, 20, 30, 40], vec![1.0, 2.0, 3.0, 4.0]);
+    /// assert_eq!(ts.range(15..40).values, &[2.0, 3.0]);
+    /// assert_eq!(ts.range(20..=40).len(), 3);
+    /// assert_eq!(ts.range(..20).nth(0), Some(DataPoint::new(10, 1.0)));
+    /// assert_eq!(ts[1], 2.0);
+    /// ```
+    pub fn range<R: RangeBounds<i64>>(&self, range: R) -> TimeSeriesSlice<'_> {
+        let (from, to) = bounds(&self.index.values, range);
+        TimeSeriesSlice {
+            index: &self.index.values[from..to],
+            values: &self.values[from..to],
+            quality: self.quality.as_ref().map(|q| &q[from..to]),
+            resolution: self.index.resolution,
+            metadata: Some(&self.metadata),
+        }
+    }
+
+    /// Borrowed series over existing buffers. Data is not copied.
+    /// Timestamps should be sorted and in milliseconds (see `TimeSeriesSlice::with_resolution`).
+    /// Error if the buffers have different lengths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::{TimeSeries, ToSeries};
+    ///
+    /// let index = [10, 20, 30, 40];
+    /// let values = [1.0, 2.0, 3.0, 4.0];
+    /// let slice = TimeSeries::from_slices(&index, &values).unwrap();
+    /// assert_eq!(slice.range(20..).values, &[2.0, 3.0, 4.0]);
+    /// assert_eq!(slice.to_series(), TimeSeries::new(index.to_vec(), values.to_vec()));
+    /// assert!(TimeSeries::from_slices(&index, &values[..2]).is_err());
+    /// ```
+    pub fn from_slices<'a>(index: &'a [i64], values: &'a [f64]) -> Result<TimeSeriesSlice<'a>, BuildError> {
+        if index.len() != values.len() {
+            return Err(BuildError::LengthMismatch { index: index.len(), values: values.len() });
+        }
+        Ok(TimeSeriesSlice {
+            index,
+            values,
+            quality: None,
+            resolution: Resolution::Millisecond,
+            metadata: None,
+        })
+    }
+}
+
+impl<'a> TimeSeriesSlice<'a> {
+
+    /// Set quality codes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of codes is different from the number of data points.
+    pub fn with_quality(mut self, quality: &'a [u8]) -> Self {
+        assert_eq!(quality.len(), self.len(), "Quality codes should have the same length as values");
+        self.quality = Some(quality);
+        self
+    }
+
+    /// Set unit of the timestamps
+    pub fn with_resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Set metadata which is copied by `to_series`
+    pub fn with_metadata(mut self, metadata: &'a Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Unit of the timestamps
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Data points with timestamps in the range. Data is not copied
+    pub fn range<R: RangeBounds<i64>>(&self, range: R) -> TimeSeriesSlice<'a> {
+        let (from, to) = bounds(self.index, range);
+        TimeSeriesSlice {
+            index: &self.index[from..to],
+            values: &self.values[from..to],
+            quality: self.quality.map(|q| &q[from..to]),
+            ..*self
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Return nth element of the slice
+    pub fn nth(&self, pos: usize) -> Option<DataPoint> {
+        if pos < self.len() {
+            Some(DataPoint { timestamp: self.index[pos], value: self.values[pos], quality: self.quality.map(|q| q[pos]) })
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over data points
+    pub fn iter(&self) -> impl Iterator<Item = DataPoint> + 'a {
+        let slice = *self;
+        (0..self.len()).filter_map(move |pos| slice.nth(pos))
+    }
+}
+
+impl<'a> ToSeries for TimeSeriesSlice<'a> {
+    /// Copy data points into owned series. Resolution and metadata are kept.
+    fn to_series(&self) -> TimeSeries {
+        let mut output = TimeSeries::new(self.index.to_vec(), self.values.to_vec());
+        output.index = output.index.with_resolution(self.resolution);
+        output.quality = self.quality.map(|q| q.to_vec());
+        output.metadata = self.metadata.cloned().unwrap_or_default();
+        output
+    }
+}
+
+impl<'a> From<TimeSeriesSlice<'a>> for CowSeries<'a> {
+    /// Borrow data of the slice
+    fn from(slice: TimeSeriesSlice<'a>) -> CowSeries<'a> {
+        CowSeries {
+            index: Cow::Borrowed(slice.index),
+            values: Cow::Borrowed(slice.values),
+            quality: slice.quality.map(Cow::Borrowed),
+            resolution: slice.resolution,
+            metadata: slice.metadata.map_or_else(|| Cow::Owned(Metadata::default()), Cow::Borrowed),
+        }
+    }
+}
+
+impl From<TimeSeries> for CowSeries<'static> {
+    /// Take ownership of the series data. Data is not copied
+    fn from(ts: TimeSeries) -> CowSeries<'static> {
+        CowSeries {
+            resolution: ts.index.resolution,
+            index: Cow::Owned(ts.index.values),
+            values: Cow::Owned(ts.values),
+            quality: ts.quality.map(Cow::Owned),
+            metadata: Cow::Owned(ts.metadata),
+        }
+    }
+}
+
+impl<'a> CowSeries<'a> {
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Borrowed view of all data points. Data is not copied
+    pub fn as_slice(&self) -> TimeSeriesSlice<'_> {
+        TimeSeriesSlice {
+            index: &self.index,
+            values: &self.values,
+            quality: self.quality.as_deref(),
+            resolution: self.resolution,
+            metadata: Some(&self.metadata),
+        }
+    }
+
+    /// Convert into owned series. Only borrowed data is copied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::slice::CowSeries;
+    ///
+    /// let index = [10, 20, 30];
+    /// let values = [1.0, 2.0, 3.0];
+    /// let borrowed = CowSeries::from(TimeSeries::from_slices(&index, &values).unwrap());
+    /// let mut copy = borrowed.clone();
+    /// copy.values.to_mut()[0] = 5.0;
+    /// assert_eq!(borrowed.values[0], 1.0);
+    /// assert_eq!(copy.into_series(), TimeSeries::new(vec![10, 20, 30], vec![5.0, 2.0, 3.0]));
+    /// ```
+    pub fn into_series(self) -> TimeSeries {
+        let index = DateTimeIndex::new(self.index.into_owned()).with_resolution(self.resolution);
+        TimeSeries {
+            index,
+            values: self.values.into_owned(),
+            quality: self.quality.map(Cow::into_owned),
+            provenance: None,
+            metadata: self.metadata.into_owned(),
+        }
+    }
+}
+
+impl<'a> ToSeries for CowSeries<'a> {
+    /// Copy data points into owned series
+    fn to_series(&self) -> TimeSeries {
+        self.as_slice().to_series()
+    }
+}
+
+/// Positions of the first timestamp in the range and the first timestamp after the range
+fn bounds<R: RangeBounds<i64>>(index: &[i64], range: R) -> (usize, usize) {
+    let from = match range.start_bound() {
+        Bound::Included(&start) => index.partition_point(|&t| t < start),
+        Bound::Excluded(&start) => index.partition_point(|&t| t <= start),
+        Bound::Unbounded => 0,
+    };
+    let to = match range.end_bound() {
+        Bound::Included(&end) => index.partition_point(|&t| t <= end),
+        Bound::Excluded(&end) => index.partition_point(|&t| t < end),
+        Bound::Unbounded => index.len(),
+    }.max(from);
+    (from, to)
+}
+
+impl Index<usize> for TimeSeries {
+    type Output = f64;
+
+    /// Value at the given position
+    fn index(&self, pos: usize) -> &Self::Output {
+        &self.values[pos]
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_bounds() {
+        let ts = TimeSeries::new(vec![10, 20, 30], vec![1.0, 2.0, 3.0]).with_quality(vec![0, 1, 2]).with_name("x");
+        assert_eq!(ts.range(..).len(), 3);
+        assert!(ts.range(40..).is_empty());
+        assert!(ts.range((Bound::Included(30), Bound::Excluded(20))).is_empty());
+        assert_eq!(ts.range((Bound::Excluded(10), Bound::Unbounded)).index, &[20, 30]);
+        let slice = ts.range(20..);
+        assert_eq!(slice.quality, Some(&[1u8, 2][..]));
+        assert_eq!(slice.iter().map(|dp| dp.value).collect::<Vec<f64>>(), vec![2.0, 3.0]);
+        let copy = slice.to_series();
+        assert_eq!(copy.metadata.name.as_deref(), Some("x"));
+        assert_eq!(copy.quality, Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_from_slices() {
+        let index = vec![10, 20, 30];
+        let values = vec![1.0, 2.0, 3.0, 4.0];
+        let quality = vec![0, 1, 2];
+        let slice = TimeSeries::from_slices(&index, &values[..3]).unwrap().with_quality(&quality).with_resolution(Resolution::Second);
+        assert_eq!(slice.len(), 3);
+        assert!(core::ptr::eq(slice.values, &values[..3]));
+        let sub = slice.range(15..=20);
+        assert_eq!(sub.values, &[2.0]);
+        assert_eq!(sub.quality, Some(&[1u8][..]));
+        let copy = sub.to_series();
+        assert_eq!(copy.index.resolution, Resolution::Second);
+        assert_eq!(copy.quality, Some(vec![1]));
+        assert_eq!(TimeSeries::from_slices(&index, &values), Err(BuildError::LengthMismatch { index: 3, values: 4 }));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_quality_length_mismatch() {
+        let _ = TimeSeries::from_slices(&[1, 2], &[1.0, 2.0]).unwrap().with_quality(&[0]);
+    }
+
+    #[test]
+    fn test_cow_series() {
+        let index = vec![10, 20];
+        let values = vec![1.0, 2.0];
+        let ts = TimeSeries::new(index.clone(), values.clone()).with_name("x").with_quality(vec![0, 1]);
+        let borrowed = CowSeries::from(ts.range(..));
+        assert!(matches!(borrowed.clone().values, Cow::Borrowed(_)));
+        assert_eq!(borrowed.to_series(), ts);
+        let owned = CowSeries::from(ts.clone());
+        let ptr = owned.values.as_ptr();
+        let copy = owned.into_series();
+        assert_eq!(copy.values.as_ptr(), ptr);
+        assert_eq!(copy, ts);
+        assert_eq!(CowSeries::from(TimeSeries::from_slices(&index, &values).unwrap()).into_series().metadata, Metadata::default());
+    }
+}
@@ -0,0 +1,168 @@
+//! Smoothing filters with centered windows
+//!
+//! Unlike moving window statistics (see `rolling` module), windows are centered on the data point,
+//! so the filters don't shift peaks in time. Output has the same index as the input.
+//! Window size is the number of data points and has to be odd.
+//!
+
+use crate::TimeSeries;
+use crate::linalg::{Matrix, invert, mul, transpose};
+
+
+impl TimeSeries {
+
+    /// Savitzky-Golay filter. Each value is replaced by the value of polynomial of the given order
+    /// fitted (with least squares) to the window around it. Data points closer to the edges than
+    /// half of the window use the polynomial fitted to the first (or last) full window.
+    /// All values are NaN if the window is even, not larger than polyorder or larger than the series.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use assert_approx_eq::assert_approx_eq;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![0.0, 1.0, 4.0, 9.0, 16.0]);
+    /// let smooth = ts.savgol_filter(5, 2);
+    /// for (a, b) in smooth.values.iter().zip(&ts.values) {
+    ///     assert_approx_eq!(a, b);
+    /// }
+    /// let noisy = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![0.0, 0.0, 5.0, 0.0, 0.0]);
+    /// assert_approx_eq!(noisy.savgol_filter(5, 1).values[2], 1.0);
+    /// ```
+    pub fn savgol_filter(&self, window: usize, polyorder: usize) -> TimeSeries {
+        let params = || vec![("window", window.to_string()), ("polyorder", polyorder.to_string())];
+        let mut output = self.clone();
+        if window.is_multiple_of(2) || window <= polyorder || window > self.len() {
+            output.values = vec![f64::NAN; self.len()];
+            return output.logged(self, "savgol_filter", params);
+        }
+        let half = window / 2;
+        let fit = Polyfit::new(half, polyorder);
+        let center = fit.weights(0.0);
+        output.values = (0..self.len()).map(|pos| {
+            if pos < half {
+                dot(&fit.weights(pos as f64 - half as f64), &self.values[..window])
+            } else if pos + half >= self.len() {
+                let start = self.len() - window;
+                dot(&fit.weights((pos - start) as f64 - half as f64), &self.values[start..])
+            } else {
+                dot(&center, &self.values[pos - half..=pos + half])
+            }
+        }).collect();
+        output.logged(self, "savgol_filter", params)
+    }
+
+    /// Median filter. NaN values are ignored.
+    /// Windows of data points close to the edges are cut to the available data points.
+    /// All values are NaN if the window is even.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 9.0, 2.0, 3.0, 10.0]);
+    /// assert_eq!(ts.median_filter(3).values, vec![5.0, 2.0, 3.0, 3.0, 6.5]);
+    /// ```
+    pub fn median_filter(&self, window: usize) -> TimeSeries {
+        let mut output = self.clone();
+        if window.is_multiple_of(2) {
+            output.values = vec![f64::NAN; self.len()];
+            return output.logged(self, "median_filter", || vec![("window", window.to_string())]);
+        }
+        let half = window / 2;
+        // Trailing median ending at pos + half is the centered median at pos
+        let trailing = self.rolling_median(window).values;
+        output.values = (0..self.len()).map(|pos| {
+            if pos >= half && pos + half < self.len() {
+                trailing[pos + half]
+            } else {
+                median(&self.values[pos.saturating_sub(half)..self.len().min(pos + half + 1)])
+            }
+        }).collect();
+        output.logged(self, "median_filter", || vec![("window", window.to_string())])
+    }
+}
+
+
+/// Least squares polynomial fit over window positions -half..=half
+struct Polyfit {
+    half: usize,
+    /// Pseudo inverse of the design matrix: (A^T A)^-1 A^T. Row j gives coefficient of x^j
+    pinv: Matrix,
+}
+
+impl Polyfit {
+
+    fn new(half: usize, order: usize) -> Polyfit {
+        // Positions are scaled to [-1, 1] to keep the normal equations well conditioned
+        let xs: Vec<f64> = (0..=2 * half).map(|i| (i as f64 - half as f64) / half.max(1) as f64).collect();
+        let design: Matrix = xs.iter().map(|x| (0..=order).map(|j| x.powi(j as i32)).collect()).collect();
+        let design_t = transpose(&design);
+        // Normal matrix of distinct positions is positive definite when order < window
+        let inverse = invert(mul(&design_t, &design)).expect("Polynomial order should be smaller than the window");
+        let pinv = mul(&inverse, &design_t);
+        Polyfit { half, pinv }
+    }
+
+    /// Weights of window values which give fitted polynomial value at the offset from the window center
+    fn weights(&self, offset: f64) -> Vec<f64> {
+        let x = offset / self.half.max(1) as f64;
+        (0..self.pinv[0].len())
+            .map(|i| self.pinv.iter().enumerate().map(|(j, row)| row[i] * x.powi(j as i32)).sum())
+            .collect()
+    }
+}
+
+fn dot(xs: &[f64], ys: &[f64]) -> f64 {
+    xs.iter().zip(ys).map(|(x, y)| x * y).sum()
+}
+
+fn median(xs: &[f64]) -> f64 {
+    let mut xs: Vec<f64> = xs.iter().cloned().filter(|v| !v.is_nan()).collect();
+    if xs.is_empty() {
+        return f64::NAN;
+    }
+    xs.sort_by(|a, b| a.total_cmp(b));
+    let mid = xs.len() / 2;
+    if xs.len() % 2 == 1 { xs[mid] } else { (xs[mid - 1] + xs[mid]) / 2.0 }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_savgol_known_coefficients() {
+        // Classic 5 point quadratic smoothing weights: (-3, 12, 17, 12, -3) / 35
+        let fit = Polyfit::new(2, 2);
+        let expected = [-3.0, 12.0, 17.0, 12.0, -3.0];
+        for (w, e) in fit.weights(0.0).iter().zip(&expected) {
+            assert_approx_eq!(w, e / 35.0);
+        }
+    }
+
+    #[test]
+    fn test_savgol_invalid_window() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        assert!(ts.savgol_filter(2, 1).values.iter().all(|v| v.is_nan()));
+        assert!(ts.savgol_filter(3, 3).values.iter().all(|v| v.is_nan()));
+        assert!(ts.savgol_filter(5, 2).values.iter().all(|v| v.is_nan()));
+        assert_eq!(ts.savgol_filter(1, 0).values, ts.values);
+    }
+
+    #[test]
+    fn test_median_filter_keeps_step() {
+        let ts = TimeSeries::new((0..8).collect(), vec![0.0, 0.0, 0.0, 0.0, 5.0, 5.0, 5.0, 5.0]);
+        assert_eq!(ts.median_filter(5).values, ts.values);
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, f64::NAN, 3.0]);
+        assert_eq!(ts.median_filter(3).values, vec![1.0, 2.0, 3.0]);
+        assert!(ts.median_filter(4).values.iter().all(|v| v.is_nan()));
+    }
+}
@@ -0,0 +1,125 @@
+//! Frequency analysis of regularly sampled series
+//!
+//! Enabled with the `fft` feature.
+//!
+
+use rustfft::FftPlanner;
+use rustfft::num_complex::Complex;
+
+use crate::TimeSeries;
+use crate::stats::mean;
+
+
+/// Power spectrum of the series
+///   * frequencies - Frequency in cycles per millisecond
+///   * power - Spectral power for each frequency
+#[derive(Clone, Debug, PartialEq)]
+pub struct Periodogram {
+    pub frequencies: Vec<f64>,
+    pub power: Vec<f64>,
+}
+
+impl Periodogram {
+
+    /// Period length (in milliseconds) for each frequency
+    pub fn periods(&self) -> Vec<f64> {
+        self.frequencies.iter().map(|f| 1.0 / f).collect()
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Calculate periodogram with FFT.
+    /// Series is expected to be regularly sampled. Sample rate is inferred from the index.
+    /// The mean is removed before transformation, so zero frequency is not included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let values = (0..8).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+    /// let ts = TimeSeries::new((0..8).map(|i| i * 10).collect(), values);
+    /// let periodogram = ts.periodogram();
+    /// assert_eq!(periodogram.frequencies.len(), 4);
+    /// assert_eq!(periodogram.frequencies[3], 0.05);
+    /// ```
+    pub fn periodogram(&self) -> Periodogram {
+        let n = self.len();
+        let dt = self.index.infer_sample_rate();
+        if n < 2 || dt <= 0 {
+            return Periodogram { frequencies: vec![], power: vec![] };
+        }
+        let m = mean(&self.values);
+        let mut buffer: Vec<Complex<f64>> = self.values.iter().map(|&v| Complex::new(v - m, 0.0)).collect();
+        let fft = FftPlanner::new().plan_fft_forward(n);
+        fft.process(&mut buffer);
+        let frequencies = (1..=n/2).map(|k| k as f64 / (n as f64 * dt as f64)).collect();
+        let power = buffer[1..=n/2].iter().map(|c| c.norm_sqr() / n as f64).collect();
+        Periodogram { frequencies, power }
+    }
+
+    /// Length (in milliseconds) of the strongest periodic component.
+    /// Returns None if the series is too short or constant, or if it contains NaN values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// // Daily pattern in hourly data
+    /// let values = (0..24*7).map(|h| (h as f64 * std::f64::consts::PI / 12.0).sin()).collect();
+    /// let index = (0..24*7).map(|h| h * 3_600_000).collect();
+    /// let ts = TimeSeries::new(index, values);
+    /// assert_eq!(ts.dominant_period(), Some(24 * 3_600_000));
+    /// ```
+    pub fn dominant_period(&self) -> Option<i64> {
+        let periodogram = self.periodogram();
+        let (pos, power) = periodogram.power.iter().enumerate()
+            .filter(|(_, p)| !p.is_nan())
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        if *power <= f64::EPSILON {
+            None
+        } else {
+            Some((1.0 / periodogram.frequencies[pos]).round() as i64)
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant() {
+        let ts = TimeSeries::new((0..10).collect(), vec![3.0; 10]);
+        assert_eq!(ts.dominant_period(), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let ts = TimeSeries::empty();
+        assert!(ts.periodogram().power.is_empty());
+        assert_eq!(ts.dominant_period(), None);
+    }
+
+    #[test]
+    fn test_nan() {
+        let mut values: Vec<f64> = (0..24).map(|h| (h as f64 * std::f64::consts::PI / 6.0).sin()).collect();
+        values[5] = f64::NAN;
+        let ts = TimeSeries::new((0..24).collect(), values);
+        assert_eq!(ts.dominant_period(), None);
+    }
+
+    #[test]
+    fn test_weekly_with_trend_noise() {
+        let values = (0..70).map(|d| 5.0 * ((d % 7) as f64) + 0.01 * d as f64).collect();
+        let ts = TimeSeries::new((0..70).map(|d| d * 86_400_000).collect(), values);
+        assert_eq!(ts.dominant_period(), Some(7 * 86_400_000));
+    }
+}
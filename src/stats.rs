@@ -0,0 +1,592 @@
+//! Statistics calculated over series values
+//!
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::TimeSeries;
+use crate::index::Resolution;
+use crate::nan::{NanError, NanPolicy};
+use crate::resample::Aggregation;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+
+/// Largest precision of `value_counts`. 10^308 is the largest finite power of 10.
+const MAX_PRECISION: i32 = 308;
+
+/// Distribution of values in equal width bins
+///   * edges - Bin edges (number of bins + 1). The last bin includes its right edge
+///   * counts - Number of values in each bin
+///   * nan_count - Number of NaN (and infinite) values which are not included in bins
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+    pub nan_count: usize,
+}
+
+/// Number of occurrences of each distinct value
+///   * values - Distinct (rounded) values with their counts, sorted by value
+///   * nan_count - Number of NaN values
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValueCounts {
+    pub values: Vec<(f64, usize)>,
+    pub nan_count: usize,
+}
+
+/// Overview of the series returned by `describe`.
+/// Value statistics skip NaN values and are NaN if there are no values.
+///   * count - Number of values which are not NaN
+///   * missing - Number of NaN values
+///   * mean, std (sample), min, q25, median, q75, max - Distribution of values
+///   * start, end - First and last timestamp
+///   * freq - Most common time step between data points
+///   * resolution - Resolution of the timestamps
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub missing: usize,
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub q25: f64,
+    pub median: f64,
+    pub q75: f64,
+    pub max: f64,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub freq: Option<i64>,
+    pub resolution: Resolution,
+}
+
+
+impl TimeSeries {
+
+    /// Summary of values and timestamps in a single call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1000, 2000, 3000, 4000, 5000], vec![1.0, 2.0, f64::NAN, 3.0, 4.0, 5.0]);
+    /// let summary = ts.describe();
+    /// assert_eq!(summary.count, 5);
+    /// assert_eq!(summary.missing, 1);
+    /// assert_eq!(summary.median, 3.0);
+    /// assert_eq!(summary.q75, 4.0);
+    /// assert_eq!(summary.freq, Some(1000));
+    /// println!("{}", summary);
+    /// ```
+    pub fn describe(&self) -> Summary {
+        let mut sorted: Vec<f64> = self.values.iter().cloned().filter(|v| !v.is_nan()).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let count = sorted.len();
+        Summary {
+            count,
+            missing: self.len() - count,
+            mean: if count == 0 { f64::NAN } else { mean(&sorted) },
+            std: covariance(&sorted, &sorted).map_or(f64::NAN, |v| v.sqrt()),
+            min: sorted.first().cloned().unwrap_or(f64::NAN),
+            q25: quantile(&sorted, 0.25),
+            median: quantile(&sorted, 0.5),
+            q75: quantile(&sorted, 0.75),
+            max: sorted.last().cloned().unwrap_or(f64::NAN),
+            start: self.index.first(),
+            end: self.index.last(),
+            freq: if self.len() > 1 { Some(self.index.infer_sample_rate()) } else { None },
+            resolution: self.index.resolution,
+        }
+    }
+
+    /// Arithmetic mean of series values. NaN values are ignored.
+    /// None if there are no values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 3.0, 6.0]);
+    /// assert_eq!(ts.mean(), Some(3.0));
+    /// ```
+    pub fn mean(&self) -> Option<f64> {
+        NanPolicy::default().aggregate(&self.values, Aggregation::Mean)
+    }
+
+    /// Aggregate all values with the given NaN policy.
+    /// None if there are no values (after skipping NaN values).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::nan::NanPolicy;
+    /// use timeseries::resample::Aggregation;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, f64::NAN, 3.0]);
+    /// assert_eq!(ts.aggregate(Aggregation::Mean, NanPolicy::Skip), Ok(Some(2.0)));
+    /// assert!(ts.aggregate(Aggregation::Mean, NanPolicy::Propagate).unwrap().unwrap().is_nan());
+    /// assert!(ts.aggregate(Aggregation::Mean, NanPolicy::Error).is_err());
+    /// ```
+    pub fn aggregate(&self, aggregation: Aggregation, policy: NanPolicy) -> Result<Option<f64>, NanError> {
+        policy.check(&self.values)?;
+        Ok(policy.aggregate(&self.values, aggregation))
+    }
+
+    /// Mean where each value is weighted by the time until the next data point.
+    /// The last data point has no duration, so it is only used if it is the only one.
+    /// Data points followed by the same timestamp have no duration either.
+    /// If all data points have the same timestamp, then it is the plain mean.
+    /// None if series is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 1000, 4000], vec![10.0, 2.0, 100.0]);
+    /// assert_eq!(ts.time_weighted_mean(), Some(4.0));
+    /// ```
+    pub fn time_weighted_mean(&self) -> Option<f64> {
+        match self.len() {
+            0 => None,
+            1 => Some(self.values[0]),
+            n => {
+                let duration = (self.index[n-1] - self.index[0]) as f64;
+                if duration == 0.0 {
+                    return Some(self.values.iter().sum::<f64>() / n as f64);
+                }
+                let total: f64 = (0..n-1)
+                    .filter(|&i| self.index[i+1] != self.index[i])
+                    .map(|i| self.values[i] * (self.index[i+1] - self.index[i]) as f64)
+                    .sum();
+                Some(total / duration)
+            }
+        }
+    }
+
+    /// Sample standard deviation of series values. NaN values are ignored.
+    /// None if there are less then 2 values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+    /// assert_eq!(ts.std(), Some(1.0));
+    /// ```
+    pub fn std(&self) -> Option<f64> {
+        let values = NanPolicy::default().filter(&self.values);
+        covariance(&values, &values).map(|v| v.sqrt())
+    }
+
+    /// Minimum value. NaN values are ignored. None if there are no values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![2.0, 1.0, 3.0]);
+    /// assert_eq!(ts.min(), Some(1.0));
+    /// ```
+    pub fn min(&self) -> Option<f64> {
+        NanPolicy::default().aggregate(&self.values, Aggregation::Min)
+    }
+
+    /// Maximum value. NaN values are ignored. None if there are no values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3], vec![2.0, 1.0, 3.0]);
+    /// assert_eq!(ts.max(), Some(3.0));
+    /// ```
+    pub fn max(&self) -> Option<f64> {
+        NanPolicy::default().aggregate(&self.values, Aggregation::Max)
+    }
+
+    /// Calculate histogram with n equal width bins between min and max value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![0.0, 1.0, f64::NAN, 3.0, 4.0]);
+    /// let hist = ts.histogram(2);
+    /// assert_eq!(hist.edges, vec![0.0, 2.0, 4.0]);
+    /// assert_eq!(hist.counts, vec![2, 2]);
+    /// assert_eq!(hist.nan_count, 1);
+    /// ```
+    pub fn histogram(&self, n_bins: usize) -> Histogram {
+        let finite: Vec<f64> = self.values.iter().cloned().filter(|v| v.is_finite()).collect();
+        let nan_count = self.len() - finite.len();
+        if finite.is_empty() || n_bins == 0 {
+            return Histogram { edges: vec![], counts: vec![], nan_count };
+        }
+        let mut min = finite.iter().cloned().fold(f64::INFINITY, f64::min);
+        let mut max = finite.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if min == max {
+            min -= 0.5;
+            max += 0.5;
+        }
+        let width = (max - min) / n_bins as f64;
+        let edges = (0..=n_bins).map(|i| min + width * i as f64).collect();
+        let mut counts = vec![0; n_bins];
+        for v in finite {
+            let bin = ((v - min) / width).floor() as usize;
+            counts[bin.min(n_bins - 1)] += 1;
+        }
+        Histogram { edges, counts, nan_count }
+    }
+
+    /// Count occurrences of values rounded to the given number of decimal digits.
+    /// Precision is clamped to [-308, 308], so the rounding scale stays finite and non zero.
+    /// Values which can't be scaled without overflow are counted as they are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.02, 0.5, 0.98, f64::NAN]);
+    /// let counts = ts.value_counts(1);
+    /// assert_eq!(counts.values, vec![(0.5, 1), (1.0, 2)]);
+    /// assert_eq!(counts.nan_count, 1);
+    /// ```
+    pub fn value_counts(&self, precision: i32) -> ValueCounts {
+        let scale = 10f64.powi(precision.clamp(-MAX_PRECISION, MAX_PRECISION));
+        let mut rounded: Vec<f64> = self.values.iter()
+            .filter(|v| !v.is_nan())
+            .map(|&v| {
+                let r = (v * scale).round() / scale;
+                if r.is_finite() { r } else { v }
+            })
+            .collect();
+        let nan_count = self.len() - rounded.len();
+        rounded.sort_by(f64::total_cmp);
+        let mut values: Vec<(f64, usize)> = vec![];
+        for v in rounded {
+            match values.last_mut() {
+                Some(last) if last.0 == v => last.1 += 1,
+                _ => values.push((v, 1)),
+            }
+        }
+        ValueCounts { values, nan_count }
+    }
+
+    /// Sample covariance between 2 series.
+    /// Only data points with timestamps present in both series are used.
+    /// Returns None if there are less then 2 common points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts1 = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 3.0, 4.0]);
+    /// let ts2 = TimeSeries::new(vec![2, 3, 4, 5], vec![4.0, 6.0, 8.0, 10.0]);
+    /// assert_eq!(ts1.cov(&ts2), Some(2.0));
+    /// ```
+    pub fn cov(&self, other: &TimeSeries) -> Option<f64> {
+        let (xs, ys) = self.common_values(other);
+        covariance(&xs, &ys)
+    }
+
+    /// Pearson correlation coefficient between 2 series.
+    /// Only data points with timestamps present in both series are used.
+    /// Returns None if there are less then 2 common points or one of the series is constant.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use assert_approx_eq::assert_approx_eq;
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts1 = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 3.0, 4.0]);
+    /// let ts2 = TimeSeries::new(vec![1, 2, 3, 4], vec![8.0, 6.0, 4.0, 2.0]);
+    /// assert_approx_eq!(ts1.corr(&ts2).unwrap(), -1.0);
+    /// ```
+    pub fn corr(&self, other: &TimeSeries) -> Option<f64> {
+        let (xs, ys) = self.common_values(other);
+        pearson(&xs, &ys)
+    }
+
+    /// Spearman rank correlation coefficient between 2 series.
+    /// Ties get the average rank.
+    /// Only data points with timestamps present in both series are used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use assert_approx_eq::assert_approx_eq;
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts1 = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 3.0, 4.0]);
+    /// let ts2 = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 4.0, 9.0, 16.0]);
+    /// assert_approx_eq!(ts1.spearman_corr(&ts2).unwrap(), 1.0);
+    /// ```
+    pub fn spearman_corr(&self, other: &TimeSeries) -> Option<f64> {
+        let (xs, ys) = self.common_values(other);
+        pearson(&ranks(&xs), &ranks(&ys))
+    }
+
+    /// Values from both series at timestamps which exist in both of them.
+    /// Timestamps of the other series are converted to the resolution of this series.
+    pub(crate) fn common_values(&self, other: &TimeSeries) -> (Vec<f64>, Vec<f64>) {
+        let other = &*other.in_resolution(self.index.resolution);
+        let mut xs = vec![];
+        let mut ys = vec![];
+        let mut pos1 = 0;
+        let mut pos2 = 0;
+        while pos1 < self.len() && pos2 < other.len() {
+            match self.index[pos1].cmp(&other.index[pos2]) {
+                Ordering::Less => pos1 += 1,
+                Ordering::Greater => pos2 += 1,
+                Ordering::Equal => {
+                    xs.push(self.values[pos1]);
+                    ys.push(other.values[pos2]);
+                    pos1 += 1;
+                    pos2 += 1;
+                }
+            }
+        }
+        (xs, ys)
+    }
+}
+
+
+pub(crate) fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+/// Mean with the default NaN policy. NaN if there are no values.
+pub(crate) fn nan_mean(xs: &[f64]) -> f64 {
+    NanPolicy::default().aggregate(xs, Aggregation::Mean).unwrap_or(f64::NAN)
+}
+
+/// Sample standard deviation with the default NaN policy. NaN if there are less then 2 values.
+pub(crate) fn nan_std(xs: &[f64]) -> f64 {
+    let xs = NanPolicy::default().filter(xs);
+    covariance(&xs, &xs).map_or(f64::NAN, |v| v.sqrt())
+}
+
+/// Quantile with linear interpolation between sorted values
+pub(crate) fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let pos = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+pub(crate) fn covariance(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() < 2 {
+        return None;
+    }
+    let mx = mean(xs);
+    let my = mean(ys);
+    let sum: f64 = xs.iter().zip(ys).map(|(x, y)| (x - mx) * (y - my)).sum();
+    Some(sum / (xs.len() - 1) as f64)
+}
+
+pub(crate) fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let cov = covariance(xs, ys)?;
+    let sx = covariance(xs, xs)?.sqrt();
+    let sy = covariance(ys, ys)?.sqrt();
+    if sx == 0.0 || sy == 0.0 {
+        None
+    } else {
+        Some(cov / (sx * sy))
+    }
+}
+
+/// Quantile function of the standard normal distribution (Acklam's approximation).
+/// Relative error is below 1.15e-9.
+#[cfg(feature = "std")]
+pub(crate) fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02,
+                         1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+    const B: [f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02,
+                         6.680131188771972e+01, -1.328068155288572e+01];
+    const C: [f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00,
+                         -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+    const D: [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00,
+                         3.754408661907416e+00];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    }
+}
+
+/// Rank of each value (starting from 1). Equal values get the average rank.
+fn ranks(xs: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..xs.len()).collect();
+    order.sort_by(|&a, &b| xs[a].partial_cmp(&xs[b]).unwrap_or(Ordering::Equal));
+    let mut output = vec![0.0; xs.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && xs[order[end]] == xs[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        order[start..end].iter().for_each(|&i| output[i] = rank);
+        start = end;
+    }
+    output
+}
+
+impl fmt::Display for Summary {
+    /// One statistic per line, with formatted timestamps
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "count    {}", self.count)?;
+        writeln!(f, "missing  {}", self.missing)?;
+        let stats = [("mean", self.mean), ("std", self.std), ("min", self.min), ("25%", self.q25),
+                     ("50%", self.median), ("75%", self.q75), ("max", self.max)];
+        for (name, value) in stats.iter() {
+            writeln!(f, "{:<8} {}", name, value)?;
+        }
+        let format = |t: Option<i64>| t.map_or("-".to_string(), |t| self.resolution.format_timestamp(t));
+        writeln!(f, "start    {}", format(self.start))?;
+        writeln!(f, "end      {}", format(self.end))?;
+        write!(f, "freq     {}", self.freq.map_or("-".to_string(), |d| self.resolution.format_duration(d)))
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_empty() {
+        let ts = TimeSeries::empty();
+        assert_eq!(ts.mean(), None);
+        assert_eq!(ts.std(), None);
+        assert_eq!(ts.min(), None);
+        assert_eq!(ts.max(), None);
+        assert_eq!(ts.time_weighted_mean(), None);
+        let summary = ts.describe();
+        assert_eq!(summary.count, 0);
+        assert!(summary.median.is_nan());
+        assert_eq!(summary.freq, None);
+    }
+
+    #[test]
+    fn test_describe_display() {
+        let ts = TimeSeries::new(vec![0, 3_600_000, 7_200_000], vec![1.0, 3.0, 2.0]);
+        let text = ts.describe().to_string();
+        assert!(text.contains("50%      2\n"));
+        assert!(text.contains("end      1970-01-01 02:00:00\n"));
+        assert!(text.ends_with("freq     1h"));
+    }
+
+    #[test]
+    fn test_inverse_normal_cdf() {
+        assert_approx_eq!(inverse_normal_cdf(0.5), 0.0);
+        assert_approx_eq!(inverse_normal_cdf(0.75), 0.6744897502, 1e-8);
+        assert_approx_eq!(inverse_normal_cdf(0.01), -2.3263478740, 1e-8);
+        assert_approx_eq!(inverse_normal_cdf(0.99), 2.3263478740, 1e-8);
+    }
+
+    #[test]
+    fn test_time_weighted_mean_single_point() {
+        let ts = TimeSeries::new(vec![5], vec![3.0]);
+        assert_eq!(ts.time_weighted_mean(), Some(3.0));
+    }
+
+    #[test]
+    fn test_min_max_with_nan() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![2.0, f64::NAN, 3.0]);
+        assert_eq!(ts.min(), Some(2.0));
+        assert_eq!(ts.max(), Some(3.0));
+    }
+
+    #[test]
+    fn test_histogram_constant() {
+        let ts = TimeSeries::new(vec![1, 2], vec![3.0, 3.0]);
+        let hist = ts.histogram(2);
+        assert_eq!(hist.edges, vec![2.5, 3.0, 3.5]);
+        assert_eq!(hist.counts, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_histogram_only_nan() {
+        let ts = TimeSeries::new(vec![1, 2], vec![f64::NAN, f64::INFINITY]);
+        let hist = ts.histogram(5);
+        assert!(hist.counts.is_empty());
+        assert_eq!(hist.nan_count, 2);
+    }
+
+    #[test]
+    fn test_ranks_with_ties() {
+        assert_eq!(ranks(&[10.0, 20.0, 10.0, 30.0]), vec![1.5, 3.0, 1.5, 4.0]);
+    }
+
+    #[test]
+    fn test_corr_no_common_points() {
+        let ts1 = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        let ts2 = TimeSeries::new(vec![4, 5, 6], vec![1.0, 2.0, 3.0]);
+        assert_eq!(ts1.corr(&ts2), None);
+        assert_eq!(ts1.cov(&ts2), None);
+    }
+
+    #[test]
+    fn test_corr_constant() {
+        let ts1 = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        let ts2 = TimeSeries::new(vec![1, 2, 3], vec![5.0, 5.0, 5.0]);
+        assert_eq!(ts1.corr(&ts2), None);
+    }
+
+    #[test]
+    fn test_corr() {
+        let ts1 = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.0, 3.0, 5.0, 4.0]);
+        let ts2 = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![2.0, 1.0, 4.0, 3.0, 5.0]);
+        assert_approx_eq!(ts1.corr(&ts2).unwrap(), 0.6);
+        assert_approx_eq!(ts1.spearman_corr(&ts2).unwrap(), 0.6);
+    }
+
+    #[test]
+    fn test_corr_mixed_resolution() {
+        let ts1 = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 4.0]);
+        let mut ts2 = TimeSeries::new(vec![1_000_000, 2_000_000, 3_000_000], vec![2.0, 4.0, 8.0]);
+        ts2.index.resolution = Resolution::Nanosecond;
+        assert_approx_eq!(ts1.corr(&ts2).unwrap(), 1.0);
+        assert_approx_eq!(ts2.cov(&ts1).unwrap(), ts2.cov(&ts2).unwrap() / 2.0);
+        assert_approx_eq!(ts1.spearman_corr(&ts2).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_value_counts_extreme_precision() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![0.0, 1.5, 1e300, f64::NAN]);
+        let counts = ts.value_counts(400);
+        assert_eq!(counts.values, vec![(0.0, 1), (1.5, 1), (1e300, 1)]);
+        assert_eq!(counts.nan_count, 1);
+        assert_eq!(ts.value_counts(-400).values, vec![(0.0, 3)]);
+        assert_eq!(ts.value_counts(i32::MAX).values, counts.values);
+    }
+}
@@ -0,0 +1,196 @@
+//! Preparing series for supervised learning
+//!
+//! Values at previous data points (lags) are used as features and the value
+//! `horizon` data points ahead as the target. Lags and horizon are counted in data points,
+//! so the series should be regularly sampled.
+//!
+//! Train/test splits never shuffle data points. Training data is always older then test data.
+//! `TimeSeriesSplit` gives cross-validation folds with expanding training window.
+//!
+
+use crate::TimeSeries;
+
+
+/// Feature matrix with targets
+///   * index - Timestamp of the last known value in each row
+///   * x - Value at each lag, one row per sample
+///   * y - Target value for each row
+#[derive(Clone, Debug, PartialEq)]
+pub struct SupervisedData {
+    pub index: Vec<i64>,
+    pub x: Vec<Vec<f64>>,
+    pub y: Vec<f64>,
+}
+
+/// Cross-validation with expanding window.
+/// Data points are divided into n_splits + 1 equal parts. Each fold tests one part
+/// and trains on all data points before it (except for the gap).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeSeriesSplit {
+    n_splits: usize,
+    gap: usize,
+}
+
+
+impl TimeSeriesSplit {
+
+    pub fn new(n_splits: usize) -> TimeSeriesSplit {
+        TimeSeriesSplit { n_splits, gap: 0 }
+    }
+
+    /// Number of data points skipped between training and test data
+    pub fn gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Training and test series for each fold.
+    /// Folds without training data are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    /// use timeseries::supervised::TimeSeriesSplit;
+    ///
+    /// let ts = TimeSeries::new((0..8).collect(), vec![0.0; 8]);
+    /// let folds = TimeSeriesSplit::new(3).split(&ts);
+    /// assert_eq!(folds.len(), 3);
+    /// assert_eq!(folds[0].0.index.values, vec![0, 1]);
+    /// assert_eq!(folds[0].1.index.values, vec![2, 3]);
+    /// assert_eq!(folds[2].0.index.values, vec![0, 1, 2, 3, 4, 5]);
+    /// assert_eq!(folds[2].1.index.values, vec![6, 7]);
+    /// ```
+    pub fn split(&self, ts: &TimeSeries) -> Vec<(TimeSeries, TimeSeries)> {
+        let test_size = ts.len() / (self.n_splits + 1);
+        if test_size == 0 {
+            return vec![];
+        }
+        let params = || vec![("n_splits", self.n_splits.to_string()), ("gap", self.gap.to_string())];
+        (0..self.n_splits)
+            .map(|k| ts.len() - (self.n_splits - k) * test_size)
+            .filter(|&start| start > self.gap)
+            .map(|start| {
+                let train: Vec<usize> = (0..start - self.gap).collect();
+                let test: Vec<usize> = (start..start + test_size).collect();
+                (ts.select(&train).logged(ts, "time_series_split", params),
+                 ts.select(&test).logged(ts, "time_series_split", params))
+            })
+            .collect()
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Build lagged feature matrix. Lag 0 is the value at the row timestamp.
+    /// Rows at the start without all lags and rows at the end without target are skipped.
+    /// NaN values are copied as they are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    /// let data = ts.to_supervised(&[0, 2], 1);
+    /// assert_eq!(data.index, vec![3, 4]);
+    /// assert_eq!(data.x, vec![vec![3.0, 1.0], vec![4.0, 2.0]]);
+    /// assert_eq!(data.y, vec![4.0, 5.0]);
+    /// ```
+    pub fn to_supervised(&self, lags: &[usize], horizon: usize) -> SupervisedData {
+        let first = lags.iter().cloned().max().unwrap_or(0);
+        let rows = first..self.len().saturating_sub(horizon);
+        SupervisedData {
+            index: rows.clone().map(|i| self.index[i]).collect(),
+            x: rows.clone().map(|i| lags.iter().map(|lag| self.values[i - lag]).collect()).collect(),
+            y: rows.map(|i| self.values[i + horizon]).collect(),
+        }
+    }
+
+    /// Split into the first fraction of data points and the rest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    /// let (train, test) = ts.split_at_fraction(0.8);
+    /// assert_eq!(train.index.values, vec![1, 2, 3, 4]);
+    /// assert_eq!(test.index.values, vec![5]);
+    /// ```
+    pub fn split_at_fraction(&self, fraction: f64) -> (TimeSeries, TimeSeries) {
+        let pos = ((self.len() as f64 * fraction).round().max(0.0) as usize).min(self.len());
+        let head: Vec<usize> = (0..pos).collect();
+        let tail: Vec<usize> = (pos..self.len()).collect();
+        let params = || vec![("fraction", fraction.to_string())];
+        (self.select(&head).logged(self, "split_at_fraction", params),
+         self.select(&tail).logged(self, "split_at_fraction", params))
+    }
+
+    /// Split into training and test data, where the test data are the last test_ratio of data points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![1.0, 2.0, 3.0, 4.0]);
+    /// let (train, test) = ts.split_train_test(0.25);
+    /// assert_eq!(train.len(), 3);
+    /// assert_eq!(test.index.values, vec![4]);
+    /// ```
+    pub fn split_train_test(&self, test_ratio: f64) -> (TimeSeries, TimeSeries) {
+        self.split_at_fraction(1.0 - test_ratio)
+    }
+
+    /// Split into training data before the timestamp and test data from the timestamp on.
+    /// Same as `split_at_time`.
+    pub fn split_by_date(&self, timestamp: i64) -> (TimeSeries, TimeSeries) {
+        self.split_at_time(timestamp)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_too_short() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        assert!(ts.to_supervised(&[3], 0).x.is_empty());
+        assert!(ts.to_supervised(&[1], 5).y.is_empty());
+        assert!(TimeSeries::empty().to_supervised(&[0], 0).index.is_empty());
+    }
+
+    #[test]
+    fn test_split_at_fraction_bounds() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        assert_eq!(ts.split_at_fraction(1.5).0, ts);
+        assert!(ts.split_at_fraction(-1.0).0.is_empty());
+        assert_eq!(ts.split_by_date(3).1.index.values, vec![3]);
+    }
+
+    #[test]
+    fn test_time_series_split_gap() {
+        let ts = TimeSeries::new((0..6).collect(), vec![0.0; 6]);
+        let folds = TimeSeriesSplit::new(2).gap(2).split(&ts);
+        assert_eq!(folds.len(), 1);
+        assert_eq!(folds[0].0.index.values, vec![0, 1]);
+        assert_eq!(folds[0].1.index.values, vec![4, 5]);
+        assert!(TimeSeriesSplit::new(10).split(&ts).is_empty());
+    }
+
+    #[test]
+    fn test_no_lags() {
+        let ts = TimeSeries::new(vec![1, 2, 3], vec![1.0, 2.0, 3.0]);
+        let data = ts.to_supervised(&[], 1);
+        assert_eq!(data.x, vec![Vec::<f64>::new(), vec![]]);
+        assert_eq!(data.y, vec![2.0, 3.0]);
+    }
+}
@@ -0,0 +1,111 @@
+//! Dimensionality reduction for similarity search and pattern mining
+//!
+//!   * PAA (Piecewise Aggregate Approximation) - mean of each of n equal size segments
+//!   * SAX (Symbolic Aggregate approXimation) - PAA of z-normalized series mapped to letters.
+//!     Breakpoints divide the standard normal distribution into equal probability regions,
+//!     so each letter is equally likely
+//!
+
+use crate::TimeSeries;
+use crate::stats::{inverse_normal_cdf, mean};
+
+
+/// Standard deviation below which the series is treated as constant during z-normalization
+const FLAT_STD: f64 = 1e-8;
+
+
+impl TimeSeries {
+
+    /// Piecewise aggregate approximation.
+    /// Data points are split into n segments of (almost) equal size and each segment is replaced
+    /// by its mean at the timestamp of its first data point.
+    /// If the series has less than n data points, then each data point is a segment.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2, 3, 4, 5, 6], vec![1.0, 3.0, 5.0, 7.0, 9.0, 11.0]);
+    /// let paa = ts.paa(3);
+    /// assert_eq!(paa.index.values, vec![1, 3, 5]);
+    /// assert_eq!(paa.values, vec![2.0, 6.0, 10.0]);
+    /// ```
+    pub fn paa(&self, n_segments: usize) -> TimeSeries {
+        let n = n_segments.min(self.len());
+        let bounds: Vec<usize> = (0..=n).map(|i| i * self.len() / n.max(1)).collect();
+        let index = bounds.windows(2).map(|b| self.index[b[0]]).collect();
+        let values = bounds.windows(2).map(|b| mean(&self.values[b[0]..b[1]])).collect();
+        self.derived(index, values).logged(self, "paa", || vec![("n_segments", n_segments.to_string())])
+    }
+
+    /// Symbolic aggregate approximation.
+    /// Series is z-normalized, reduced with `paa` and each segment is mapped to a letter
+    /// starting from `a`. Alphabet size is limited to 2..=26.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new((0..8).collect(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+    /// assert_eq!(ts.sax(4, 4), "abcd");
+    /// assert_eq!(ts.sax(2, 3), "ac");
+    /// ```
+    pub fn sax(&self, n_segments: usize, alphabet_size: usize) -> String {
+        let alphabet_size = alphabet_size.clamp(2, 26);
+        let breakpoints: Vec<f64> = (1..alphabet_size)
+            .map(|i| inverse_normal_cdf(i as f64 / alphabet_size as f64))
+            .collect();
+        self.z_normalized().paa(n_segments).values.iter()
+            .map(|&v| {
+                let symbol = breakpoints.iter().filter(|&&b| b <= v).count();
+                (b'a' + symbol as u8) as char
+            })
+            .collect()
+    }
+
+    /// Values with zero mean and unit standard deviation. Constant series becomes all zeros.
+    fn z_normalized(&self) -> TimeSeries {
+        let mut ts = self.clone();
+        if self.is_empty() {
+            return ts;
+        }
+        let m = mean(&self.values);
+        let std = (self.values.iter().map(|v| (v - m) * (v - m)).sum::<f64>() / self.len() as f64).sqrt();
+        ts.values = if std < FLAT_STD {
+            vec![0.0; self.len()]
+        } else {
+            self.values.iter().map(|v| (v - m) / std).collect()
+        };
+        ts
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paa_uneven_segments() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4, 5], vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let paa = ts.paa(2);
+        assert_eq!(paa.index.values, vec![1, 3]);
+        assert_eq!(paa.values, vec![1.5, 4.0]);
+        assert_eq!(ts.paa(10), ts);
+        assert!(ts.paa(0).is_empty());
+        assert!(TimeSeries::empty().paa(3).is_empty());
+    }
+
+    #[test]
+    fn test_sax_flat() {
+        let ts = TimeSeries::new(vec![1, 2, 3, 4], vec![5.0; 4]);
+        assert_eq!(ts.sax(2, 3), "bb");
+        assert_eq!(ts.sax(2, 4), "cc");
+        assert_eq!(TimeSeries::empty().sax(4, 4), "");
+    }
+}
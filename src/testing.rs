@@ -0,0 +1,120 @@
+//! Arbitrary series for property based tests with proptest (requires `testing` feature)
+//!
+//! `any::<TimeSeries>()` and `any::<DateTimeIndex>()` generate valid instances.
+//! `any_with::<TimeSeries>(Shape::Adversarial)` generates edge cases which are allowed
+//! by the constructors but break the usual assumptions: unsorted and duplicated timestamps,
+//! extreme timestamps and non finite values.
+//!
+//! # Example
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use timeseries::TimeSeries;
+//! use timeseries::testing::Shape;
+//!
+//! proptest! {
+//!     fn sorted_is_monotonic(ts in any_with::<TimeSeries>(Shape::Adversarial)) {
+//!         prop_assert!(ts.sort_by_time().index.is_monotonic());
+//!     }
+//! }
+//! # sorted_is_monotonic();
+//! ```
+//!
+
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::strategy::BoxedStrategy;
+
+use crate::TimeSeries;
+use crate::index::DateTimeIndex;
+
+
+/// Maximum number of generated data points
+const MAX_LEN: usize = 100;
+/// Valid timestamps start within this distance from the epoch (about 30 years in milliseconds)
+const MAX_START: i64 = 1_000_000_000_000;
+/// Largest gap between valid timestamps
+const MAX_DELTA: i64 = 1_000_000;
+
+
+/// Kind of generated instances
+///   * Valid - Strictly increasing timestamps and finite values
+///   * Adversarial - Unsorted or duplicated timestamps, extreme timestamps, NaN and infinite values
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Shape {
+    #[default]
+    Valid,
+    Adversarial,
+}
+
+
+impl Arbitrary for DateTimeIndex {
+    type Parameters = Shape;
+    type Strategy = BoxedStrategy<DateTimeIndex>;
+
+    fn arbitrary_with(shape: Shape) -> Self::Strategy {
+        timestamps(shape).prop_map(DateTimeIndex::new).boxed()
+    }
+}
+
+impl Arbitrary for TimeSeries {
+    type Parameters = Shape;
+    type Strategy = BoxedStrategy<TimeSeries>;
+
+    fn arbitrary_with(shape: Shape) -> Self::Strategy {
+        timestamps(shape)
+            .prop_flat_map(move |index| {
+                let n = index.len();
+                (Just(index), vec(values(shape), n))
+            })
+            .prop_map(|(index, values)| TimeSeries::new(index, values))
+            .boxed()
+    }
+}
+
+fn timestamps(shape: Shape) -> BoxedStrategy<Vec<i64>> {
+    match shape {
+        Shape::Valid => (-MAX_START..MAX_START, vec(1..MAX_DELTA, 0..MAX_LEN))
+            .prop_map(|(start, deltas)| {
+                deltas.iter().scan(start, |t, delta| { let current = *t; *t += delta; Some(current) }).collect()
+            })
+            .boxed(),
+        Shape::Adversarial => prop_oneof![
+            // Small range gives many duplicates
+            vec(-10i64..10, 0..MAX_LEN),
+            vec(-10i64..10, 0..MAX_LEN).prop_map(|mut ts| { ts.sort_unstable(); ts }),
+            vec(prop_oneof![Just(i64::MIN), Just(i64::MAX), Just(0i64), any::<i64>()], 0..MAX_LEN),
+        ].boxed(),
+    }
+}
+
+fn values(shape: Shape) -> BoxedStrategy<f64> {
+    match shape {
+        Shape::Valid => (-1e6..1e6).boxed(),
+        Shape::Adversarial => proptest::num::f64::ANY.boxed(),
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_valid_series(ts in any::<TimeSeries>()) {
+            prop_assert!(ts.index.is_monotonic());
+            prop_assert!(ts.index.is_unique());
+            prop_assert!(ts.values.iter().all(|v| v.is_finite()));
+        }
+
+        #[test]
+        fn test_adversarial_series(ts in any_with::<TimeSeries>(Shape::Adversarial)) {
+            prop_assert_eq!(ts.index.len(), ts.values.len());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Trend estimation
+//!
+
+use crate::TimeSeries;
+use crate::stats::mean;
+
+
+/// Linear regression of value against time: value = slope * timestamp + intercept
+///   * slope - Change of value per millisecond
+///   * intercept - Value at timestamp 0
+///   * r2 - Coefficient of determination (R²) of the fit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearTrend {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r2: f64,
+}
+
+impl LinearTrend {
+
+    /// Trend value at the given timestamp
+    pub fn predict(&self, timestamp: i64) -> f64 {
+        self.slope * timestamp as f64 + self.intercept
+    }
+}
+
+
+impl TimeSeries {
+
+    /// Fit linear trend with least squares method.
+    /// Returns None if the series has less then 2 distinct timestamps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 10, 20, 30], vec![1.0, 2.0, 3.0, 4.0]);
+    /// let trend = ts.linear_trend().unwrap();
+    /// assert_eq!(trend.slope, 0.1);
+    /// assert_eq!(trend.intercept, 1.0);
+    /// assert_eq!(trend.r2, 1.0);
+    /// ```
+    pub fn linear_trend(&self) -> Option<LinearTrend> {
+        if self.len() < 2 {
+            return None;
+        }
+        // Timestamps are centered to keep precision for epoch based values
+        let xs: Vec<f64> = self.index.iter().map(|&t| t as f64).collect();
+        let mx = mean(&xs);
+        let my = mean(&self.values);
+        let sxx: f64 = xs.iter().map(|x| (x - mx) * (x - mx)).sum();
+        if sxx == 0.0 {
+            return None;
+        }
+        let sxy: f64 = xs.iter().zip(&self.values).map(|(x, y)| (x - mx) * (y - my)).sum();
+        let slope = sxy / sxx;
+        let intercept = my - slope * mx;
+        let ss_tot: f64 = self.values.iter().map(|y| (y - my) * (y - my)).sum();
+        let ss_res: f64 = xs.iter().zip(&self.values)
+            .map(|(x, y)| y - (my + slope * (x - mx)))
+            .map(|e| e * e)
+            .sum();
+        let r2 = if ss_tot == 0.0 { 1.0 } else { 1.0 - ss_res / ss_tot };
+        Some(LinearTrend { slope, intercept, r2 })
+    }
+
+    /// Subtract fitted linear trend from the series.
+    /// If trend can't be calculated then the series is returned unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![0, 10, 20, 30], vec![1.0, 2.0, 3.0, 4.0]);
+    /// assert_eq!(ts.detrend().values, vec![0.0, 0.0, 0.0, 0.0]);
+    /// ```
+    pub fn detrend(&self) -> TimeSeries {
+        match self.linear_trend() {
+            Some(trend) => {
+                let mut output = self.clone();
+                output.values = self.iter().map(|dp| dp.value - trend.predict(dp.timestamp)).collect();
+                output.logged(self, "detrend", Vec::new)
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_single_point() {
+        let ts = TimeSeries::new(vec![1], vec![1.0]);
+        assert_eq!(ts.linear_trend(), None);
+        assert_eq!(ts.detrend(), ts);
+    }
+
+    #[test]
+    fn test_epoch_timestamps() {
+        let start = 1_600_000_000_000;
+        let index = (0..100).map(|i| start + i * 60_000).collect();
+        let values = (0..100).map(|i| 5.0 + 2.0 * i as f64).collect();
+        let ts = TimeSeries::new(index, values);
+        let trend = ts.linear_trend().unwrap();
+        assert_approx_eq!(trend.slope * 60_000.0, 2.0);
+        assert_approx_eq!(trend.predict(start), 5.0, 1e-3);
+        assert_approx_eq!(trend.r2, 1.0);
+    }
+
+    #[test]
+    fn test_noisy_r2() {
+        let ts = TimeSeries::new(vec![0, 1, 2, 3], vec![0.0, 2.0, 1.0, 3.0]);
+        let trend = ts.linear_trend().unwrap();
+        assert_approx_eq!(trend.slope, 0.8);
+        assert_approx_eq!(trend.r2, 0.64);
+    }
+}
@@ -0,0 +1,53 @@
+//! JavaScript interop for WebAssembly builds
+//!
+//! Series are passed to JavaScript as a pair of Float64Arrays.
+//! Timestamps are converted to f64, which is exact for milliseconds up to year 285 000.
+//! The crate can be built for `wasm32-unknown-unknown` with:
+//!
+//! ```text
+//! cargo build --target wasm32-unknown-unknown --no-default-features --features wasm
+//! ```
+//!
+
+use js_sys::Float64Array;
+
+use crate::TimeSeries;
+
+
+impl TimeSeries {
+
+    /// Copy index and values into JavaScript arrays
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use timeseries::TimeSeries;
+    ///
+    /// let ts = TimeSeries::new(vec![1, 2], vec![1.0, 2.0]);
+    /// let (index, values) = ts.to_js_arrays();
+    /// assert_eq!(values.length(), 2);
+    /// ```
+    pub fn to_js_arrays(&self) -> (Float64Array, Float64Array) {
+        let index: Vec<f64> = self.index.iter().map(|&t| t as f64).collect();
+        (Float64Array::from(&index[..]), Float64Array::from(&self.values[..]))
+    }
+
+    /// Create series from JavaScript arrays.
+    /// Timestamps are truncated to whole milliseconds.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use js_sys::Float64Array;
+    /// use timeseries::TimeSeries;
+    ///
+    /// let index = Float64Array::from(&[1.0, 2.0][..]);
+    /// let values = Float64Array::from(&[5.0, 6.0][..]);
+    /// let ts = TimeSeries::from_js_arrays(&index, &values);
+    /// assert_eq!(ts.len(), 2);
+    /// ```
+    pub fn from_js_arrays(index: &Float64Array, values: &Float64Array) -> TimeSeries {
+        let index = index.to_vec().iter().map(|&t| t as i64).collect();
+        TimeSeries::new(index, values.to_vec())
+    }
+}